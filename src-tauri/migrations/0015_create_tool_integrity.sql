@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS custom_library_tool_integrity (
+  tool_id TEXT PRIMARY KEY,
+  -- 'ok' or 'failed' from the most recent verification run.
+  status TEXT NOT NULL,
+  failed_files INTEGER NOT NULL DEFAULT 0,
+  checked_at INTEGER NOT NULL,
+  FOREIGN KEY (tool_id) REFERENCES custom_library_tools(id) ON DELETE CASCADE
+);
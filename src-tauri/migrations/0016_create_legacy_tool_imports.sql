@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS custom_library_legacy_imports (
+  -- Ids from the legacy `tools` registry table; rows are kept even if the library tool is
+  -- later deleted so the one-time migration never re-imports it.
+  legacy_tool_id TEXT PRIMARY KEY,
+  tool_id TEXT NOT NULL,
+  imported_at INTEGER NOT NULL
+);
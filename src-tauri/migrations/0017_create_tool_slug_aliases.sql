@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS custom_library_tool_slug_aliases (
+  -- Previous slugs of renamed tools, kept so old deep links still resolve.
+  slug TEXT PRIMARY KEY,
+  tool_id TEXT NOT NULL,
+  created_at INTEGER NOT NULL,
+  FOREIGN KEY (tool_id) REFERENCES custom_library_tools(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_custom_library_tool_slug_aliases_tool
+  ON custom_library_tool_slug_aliases(tool_id);
@@ -0,0 +1 @@
+ALTER TABLE custom_library_tools ADD COLUMN icon_rel_path TEXT;
@@ -0,0 +1,2 @@
+-- Filled for existing versions by the version hash backfill that runs with this migration.
+ALTER TABLE custom_library_tool_versions ADD COLUMN version_hash TEXT;
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS custom_library_tool_views (
+  -- One row per tool that has been opened; tools never viewed have no row.
+  tool_id TEXT PRIMARY KEY,
+  last_viewed_at INTEGER NOT NULL,
+  FOREIGN KEY (tool_id) REFERENCES custom_library_tools(id) ON DELETE CASCADE
+);
@@ -0,0 +1,3 @@
+-- NULL max_versions means unlimited; NULL version_limit_mode means the default mode.
+ALTER TABLE custom_library_tools ADD COLUMN max_versions INTEGER;
+ALTER TABLE custom_library_tools ADD COLUMN version_limit_mode TEXT;
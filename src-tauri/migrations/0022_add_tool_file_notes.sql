@@ -0,0 +1 @@
+ALTER TABLE custom_library_tool_files ADD COLUMN note TEXT;
@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use tauri::AppHandle;
+
+use crate::secrets;
+use crate::tools::db::{now_epoch_millis, open_pool};
+
+/// Bumped when the backup shape changes incompatibly.
+const CREDENTIAL_BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// Credential metadata for moving to another machine. Secret values stay in the OS keyring
+/// and are never part of this type.
+#[derive(Debug)]
+pub enum CredentialsError {
+    Validation(String),
+    Database(String),
+}
+
+pub type CredentialsResult<T> = Result<T, CredentialsError>;
+
+impl Display for CredentialsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Validation(message) | Self::Database(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for CredentialsError {}
+
+impl From<sqlx::Error> for CredentialsError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Database(format!("Database operation failed: {value}"))
+    }
+}
+
+impl From<crate::tools::error::ToolsError> for CredentialsError {
+    fn from(value: crate::tools::error::ToolsError) -> Self {
+        Self::Database(value.user_message())
+    }
+}
+
+/// Lets commands use `?` directly; the frontend only sees the message.
+impl From<CredentialsError> for String {
+    fn from(value: CredentialsError) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialMetadataBackup {
+    pub format_version: u32,
+    pub exported_at: i64,
+    pub credentials: Vec<CredentialMetadata>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialMetadata {
+    pub id: String,
+    pub provider: String,
+    pub label: String,
+    pub created_at: i64,
+    pub last_used_at: Option<i64>,
+    /// Tools whose auth settings reference this credential; informational on import.
+    #[serde(default)]
+    pub tools: Vec<CredentialToolLink>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialToolLink {
+    pub tool_id: String,
+    pub tool_name: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialImportReport {
+    pub imported: Vec<String>,
+    /// Ids that already had a metadata row; the existing row is left untouched.
+    pub skipped: Vec<String>,
+    /// Ids with no secret in this machine's keyring, so the user must re-enter them.
+    pub needs_secret: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn credentials_export_metadata(
+    app: AppHandle,
+) -> Result<CredentialMetadataBackup, String> {
+    let pool = open_pool(&app).await.map_err(CredentialsError::from)?;
+    let backup = export_metadata(&pool).await?;
+    debug!(
+        "credentials: exported metadata for {} credentials",
+        backup.credentials.len()
+    );
+    Ok(backup)
+}
+
+#[tauri::command]
+pub async fn credentials_import_metadata(
+    app: AppHandle,
+    backup: CredentialMetadataBackup,
+) -> Result<CredentialImportReport, String> {
+    let pool = open_pool(&app).await.map_err(CredentialsError::from)?;
+    let mut report = import_metadata(&pool, &backup).await?;
+    report.needs_secret = backup
+        .credentials
+        .iter()
+        .filter(|credential| {
+            !matches!(
+                secrets::read_internal_secret(credential.id.trim()),
+                Ok(Some(_))
+            )
+        })
+        .map(|credential| credential.id.trim().to_string())
+        .collect();
+    debug!(
+        "credentials: imported {} metadata rows, skipped {}, {} need secrets",
+        report.imported.len(),
+        report.skipped.len(),
+        report.needs_secret.len()
+    );
+    Ok(report)
+}
+
+/// The `tools` and `credentials` tables come from the plugin migrations registered in `lib.rs`.
+pub async fn export_metadata(pool: &SqlitePool) -> CredentialsResult<CredentialMetadataBackup> {
+    let mut tools_by_credential = HashMap::<String, Vec<CredentialToolLink>>::new();
+    let tool_rows = sqlx::query("SELECT id, config_json FROM tools ORDER BY id ASC")
+        .fetch_all(pool)
+        .await?;
+    for row in tool_rows {
+        let tool_id: String = row.get("id");
+        let Ok(config) = serde_json::from_str::<serde_json::Value>(row.get("config_json")) else {
+            continue;
+        };
+        let Some(credential_id) = config
+            .get("credentialRefId")
+            .and_then(|value| value.as_str())
+            .filter(|value| !value.trim().is_empty())
+        else {
+            continue;
+        };
+        tools_by_credential
+            .entry(credential_id.trim().to_string())
+            .or_default()
+            .push(CredentialToolLink {
+                tool_name: config
+                    .get("name")
+                    .and_then(|value| value.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                tool_id,
+            });
+    }
+
+    let rows = sqlx::query(
+        "SELECT id, provider, label, created_at, last_used_at
+      FROM credentials
+      ORDER BY label COLLATE NOCASE ASC, id ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+    let credentials = rows
+        .into_iter()
+        .map(|row| {
+            let id: String = row.get("id");
+            CredentialMetadata {
+                tools: tools_by_credential.remove(&id).unwrap_or_default(),
+                id,
+                provider: row.get("provider"),
+                label: row.get("label"),
+                created_at: row.get("created_at"),
+                last_used_at: row.get("last_used_at"),
+            }
+        })
+        .collect();
+
+    Ok(CredentialMetadataBackup {
+        format_version: CREDENTIAL_BACKUP_FORMAT_VERSION,
+        exported_at: now_epoch_millis()?,
+        credentials,
+    })
+}
+
+/// Recreates missing metadata rows in one transaction; existing ids are reported as skipped.
+pub async fn import_metadata(
+    pool: &SqlitePool,
+    backup: &CredentialMetadataBackup,
+) -> CredentialsResult<CredentialImportReport> {
+    if backup.format_version != CREDENTIAL_BACKUP_FORMAT_VERSION {
+        return Err(CredentialsError::Validation(format!(
+            "Unsupported credential backup format version {}.",
+            backup.format_version
+        )));
+    }
+    for credential in &backup.credentials {
+        for (field, value) in [
+            ("id", &credential.id),
+            ("provider", &credential.provider),
+            ("label", &credential.label),
+        ] {
+            if value.trim().is_empty() {
+                return Err(CredentialsError::Validation(format!(
+                    "Credential {field} is required."
+                )));
+            }
+        }
+    }
+
+    let mut report = CredentialImportReport::default();
+    let mut tx = pool.begin().await?;
+    for credential in &backup.credentials {
+        let id = credential.id.trim();
+        let result = sqlx::query(
+            "INSERT OR IGNORE INTO credentials (id, provider, label, created_at, last_used_at)
+        VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .bind(id)
+        .bind(credential.provider.trim())
+        .bind(credential.label.trim())
+        .bind(credential.created_at)
+        .bind(credential.last_used_at)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            report.skipped.push(id.to_string());
+        } else {
+            report.imported.push(id.to_string());
+        }
+    }
+    tx.commit().await?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        for sql_batch in [
+            include_str!("../migrations/0001_create_tools.sql"),
+            include_str!("../migrations/0004_create_credentials.sql"),
+        ] {
+            sqlx::raw_sql(sql_batch).execute(&pool).await.unwrap();
+        }
+        pool
+    }
+
+    #[test]
+    fn export_lists_tool_links_and_import_recreates_missing_rows() {
+        tauri::async_runtime::block_on(async {
+            let source = create_test_pool().await;
+            sqlx::query(
+                "INSERT INTO credentials (id, provider, label, created_at, last_used_at)
+          VALUES ('cred_1', 'web', 'Survey API', 10, 20), ('cred_2', 'web', 'Archive', 30, NULL)",
+            )
+            .execute(&source)
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO tools (id, tool_type, category, tags, status, config_json, created_at, updated_at)
+          VALUES ('tool_1', 'rest_api', 'general', '[]', 'active',
+                  '{\"name\":\"Survey Lookup\",\"credentialRefId\":\"cred_1\"}', 1, 1)",
+            )
+            .execute(&source)
+            .await
+            .unwrap();
+
+            let backup = export_metadata(&source).await.unwrap();
+            let json = serde_json::to_value(&backup).unwrap();
+            assert_eq!(json["credentials"][0]["label"], "Archive");
+            assert_eq!(
+                json["credentials"][1]["tools"][0]["toolName"],
+                "Survey Lookup"
+            );
+            assert!(!json.to_string().to_lowercase().contains("secret"));
+
+            let target = create_test_pool().await;
+            sqlx::query(
+                "INSERT INTO credentials (id, provider, label, created_at)
+          VALUES ('cred_2', 'web', 'Archive (existing)', 5)",
+            )
+            .execute(&target)
+            .await
+            .unwrap();
+            let report = import_metadata(&target, &backup).await.unwrap();
+            assert_eq!(report.imported, vec!["cred_1"]);
+            assert_eq!(report.skipped, vec!["cred_2"]);
+
+            let label: String =
+                sqlx::query_scalar("SELECT label FROM credentials WHERE id = 'cred_2'")
+                    .fetch_one(&target)
+                    .await
+                    .unwrap();
+            assert_eq!(label, "Archive (existing)");
+        });
+    }
+}
@@ -1,8 +1,12 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
 
-use super::db::{self, HelpCreatePageInput, HelpPageRecord, HelpPageSummary, HelpUpdatePageInput};
+use super::db::{
+    self, HelpBrokenLink, HelpCreatePageInput, HelpPageRecord, HelpPageSummary, HelpSearchOrder,
+    HelpSearchResult, HelpUpdatePageInput,
+};
 use super::error::HelpResult;
+use super::render;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -23,6 +27,14 @@ pub struct HelpUpdatePageRequest {
     pub content_md: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelpRenderedPage {
+    #[serde(flatten)]
+    pub page: HelpPageRecord,
+    pub content_html: String,
+}
+
 #[tauri::command]
 pub async fn help_list_pages(app: AppHandle) -> Result<Vec<HelpPageSummary>, String> {
     run(async {
@@ -32,6 +44,19 @@ pub async fn help_list_pages(app: AppHandle) -> Result<Vec<HelpPageSummary>, Str
     .await
 }
 
+#[tauri::command]
+pub async fn help_search_pages(
+    app: AppHandle,
+    query: String,
+    order: Option<HelpSearchOrder>,
+) -> Result<Vec<HelpSearchResult>, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        db::search_pages(&pool, &query, order.unwrap_or_default()).await
+    })
+    .await
+}
+
 #[tauri::command]
 pub async fn help_get_page(app: AppHandle, slug: String) -> Result<HelpPageRecord, String> {
     run(async {
@@ -41,6 +66,17 @@ pub async fn help_get_page(app: AppHandle, slug: String) -> Result<HelpPageRecor
     .await
 }
 
+#[tauri::command]
+pub async fn help_render_page(app: AppHandle, slug: String) -> Result<HelpRenderedPage, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let page = db::get_page(&pool, slug.trim()).await?;
+        let content_html = render::render_page_html(&page.slug, page.updated_at, &page.content_md);
+        Ok(HelpRenderedPage { page, content_html })
+    })
+    .await
+}
+
 #[tauri::command]
 pub async fn help_create_page(
     app: AppHandle,
@@ -97,6 +133,15 @@ pub async fn help_delete_page(app: AppHandle, slug: String) -> Result<(), String
     .await
 }
 
+#[tauri::command]
+pub async fn help_check_links(app: AppHandle) -> Result<Vec<HelpBrokenLink>, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        db::check_links(&pool).await
+    })
+    .await
+}
+
 #[tauri::command]
 pub async fn app_state_get(app: AppHandle, key: String) -> Result<Option<String>, String> {
     run(async {
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
@@ -5,6 +6,7 @@ use sqlx::{Row, SqlitePool};
 use tauri::AppHandle;
 
 use super::error::{HelpError, HelpResult};
+use super::render::internal_link_targets;
 
 const BUILTIN_WELCOME_KEY: &str = "welcome_dismissed";
 const BUILTIN_DEVELOPER_MODE_KEY: &str = "developer_mode";
@@ -81,6 +83,23 @@ pub struct HelpPageSummary {
     pub updated_at: i64,
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelpSearchResult {
+    #[serde(flatten)]
+    pub page: HelpPageSummary,
+    /// Higher is a better match; zero when results are in category order.
+    pub score: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HelpSearchOrder {
+    #[default]
+    Relevance,
+    Category,
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HelpPageRecord {
@@ -95,6 +114,13 @@ pub struct HelpPageRecord {
     pub updated_at: i64,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelpBrokenLink {
+    pub source_slug: String,
+    pub broken_target: String,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HelpCreatePageInput {
@@ -149,6 +175,86 @@ pub async fn list_pages(pool: &SqlitePool) -> HelpResult<Vec<HelpPageSummary>> {
         .collect())
 }
 
+/// Matches pages whose title, category, or content contains `query`.
+///
+/// Relevance order puts exact title matches first and lifts custom pages above builtin ones;
+/// category order returns the matches in the same order as `list_pages`.
+pub async fn search_pages(
+    pool: &SqlitePool,
+    query: &str,
+    order: HelpSearchOrder,
+) -> HelpResult<Vec<HelpSearchResult>> {
+    let needle = validate_required("query", query, 200)?.to_lowercase();
+    let pattern = format!(
+        "%{}%",
+        needle
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+    let rows = sqlx::query(
+        "SELECT id, slug, title, category, sort_order, content_md, is_builtin, updated_at
+         FROM help_pages
+         WHERE title LIKE ?1 ESCAPE '\\'
+            OR category LIKE ?1 ESCAPE '\\'
+            OR content_md LIKE ?1 ESCAPE '\\'
+         ORDER BY category COLLATE NOCASE ASC, sort_order ASC, title COLLATE NOCASE ASC",
+    )
+    .bind(&pattern)
+    .fetch_all(pool)
+    .await?;
+
+    let mut results = rows
+        .into_iter()
+        .map(|row| {
+            let page = HelpPageSummary {
+                id: row.get("id"),
+                slug: row.get("slug"),
+                title: row.get("title"),
+                category: row.get("category"),
+                sort_order: row.get("sort_order"),
+                is_builtin: row.get::<i64, _>("is_builtin") == 1,
+                updated_at: row.get("updated_at"),
+            };
+            let score = match order {
+                HelpSearchOrder::Relevance => {
+                    relevance_score(&page, &row.get::<String, _>("content_md"), &needle)
+                }
+                HelpSearchOrder::Category => 0,
+            };
+            HelpSearchResult { page, score }
+        })
+        .collect::<Vec<_>>();
+
+    if order == HelpSearchOrder::Relevance {
+        // Stable sort keeps category order as the tie-breaker.
+        results.sort_by_key(|result| std::cmp::Reverse(result.score));
+    }
+    Ok(results)
+}
+
+fn relevance_score(page: &HelpPageSummary, content_md: &str, needle: &str) -> i64 {
+    let title = page.title.to_lowercase();
+    let mut score = if title == needle {
+        100
+    } else if title.starts_with(needle) {
+        60
+    } else if title.contains(needle) {
+        40
+    } else {
+        0
+    };
+    if page.category.to_lowercase().contains(needle) {
+        score += 15;
+    }
+    let content_hits = content_md.to_lowercase().matches(needle).count() as i64;
+    score += content_hits.min(10) * 2;
+    if !page.is_builtin {
+        score += 30;
+    }
+    score
+}
+
 pub async fn get_page(pool: &SqlitePool, slug: &str) -> HelpResult<HelpPageRecord> {
     let normalized_slug = normalize_slug(slug)?;
     let row = sqlx::query(
@@ -264,6 +370,35 @@ pub async fn delete_page(pool: &SqlitePool, slug: &str) -> HelpResult<()> {
     Ok(())
 }
 
+/// Lists links to help slugs that no page has, ordered by source page then target.
+pub async fn check_links(pool: &SqlitePool) -> HelpResult<Vec<HelpBrokenLink>> {
+    let rows = sqlx::query("SELECT slug, content_md FROM help_pages ORDER BY slug ASC")
+        .fetch_all(pool)
+        .await?;
+    let known_slugs = rows
+        .iter()
+        .map(|row| row.get::<String, _>("slug"))
+        .collect::<HashSet<_>>();
+
+    let mut broken = Vec::new();
+    for row in &rows {
+        let source_slug: String = row.get("slug");
+        let mut targets = internal_link_targets(row.get("content_md"));
+        targets.sort();
+        targets.dedup();
+        broken.extend(
+            targets
+                .into_iter()
+                .filter(|target| !known_slugs.contains(target))
+                .map(|broken_target| HelpBrokenLink {
+                    source_slug: source_slug.clone(),
+                    broken_target,
+                }),
+        );
+    }
+    Ok(broken)
+}
+
 pub async fn app_state_get(pool: &SqlitePool, key: &str) -> HelpResult<Option<String>> {
     let normalized_key = normalize_state_key(key)?;
     let row = sqlx::query("SELECT value FROM app_state WHERE key = ?1")
@@ -494,6 +629,47 @@ mod tests {
         });
     }
 
+    #[test]
+    fn search_ranks_custom_pages_and_exact_titles_first() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            seed_builtin_pages_if_needed(&pool).await.unwrap();
+            create_page(
+                &pool,
+                HelpCreatePageInput {
+                    slug: "tools-checklist".to_string(),
+                    title: "Tools Checklist".to_string(),
+                    category: "Internal".to_string(),
+                    sort_order: None,
+                    content_md: "Use the shared tools library for pen tables.".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+
+            let ranked = search_pages(&pool, "tools", HelpSearchOrder::Relevance)
+                .await
+                .unwrap();
+            assert_eq!(ranked[0].page.slug, "tools-checklist");
+            assert!(ranked.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+            let exact = search_pages(&pool, "tools library", HelpSearchOrder::Relevance)
+                .await
+                .unwrap();
+            assert_eq!(exact[0].page.slug, "tools-library");
+
+            let listed = search_pages(&pool, "tools", HelpSearchOrder::Category)
+                .await
+                .unwrap();
+            assert_eq!(listed.len(), ranked.len());
+            assert!(listed.iter().all(|result| result.score == 0));
+            assert!(search_pages(&pool, "100%_", HelpSearchOrder::Relevance)
+                .await
+                .unwrap()
+                .is_empty());
+        });
+    }
+
     #[test]
     fn builtin_pages_are_read_only_without_developer_mode() {
         tauri::async_runtime::block_on(async {
@@ -520,4 +696,36 @@ mod tests {
                 .contains("read-only unless Developer Mode is enabled"));
         });
     }
+
+    #[test]
+    fn check_links_reports_targets_without_pages() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            seed_builtin_pages_if_needed(&pool).await.unwrap();
+            assert!(check_links(&pool).await.unwrap().is_empty());
+
+            create_page(
+                &pool,
+                HelpCreatePageInput {
+                    slug: "plot-styles".to_string(),
+                    title: "Plot Styles".to_string(),
+                    category: "Internal".to_string(),
+                    sort_order: None,
+                    content_md: "See [setup](help://pen-tables), [start](quick-start), \
+                                 [again](pen-tables#widths) and [site](https://example.com)."
+                        .to_string(),
+                },
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(
+                check_links(&pool).await.unwrap(),
+                vec![HelpBrokenLink {
+                    source_slug: "plot-styles".to_string(),
+                    broken_target: "pen-tables".to_string(),
+                }]
+            );
+        });
+    }
 }
@@ -1,3 +1,4 @@
 pub mod commands;
 mod db;
 mod error;
+mod render;
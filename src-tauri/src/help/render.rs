@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
+
+const HELP_LINK_SCHEME: &str = "help://";
+
+struct CachedRender {
+    updated_at: i64,
+    html: String,
+}
+
+fn render_cache() -> &'static Mutex<HashMap<String, CachedRender>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, CachedRender>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Returns sanitized HTML for a page, reusing the cached render while `updated_at` is unchanged.
+pub fn render_page_html(slug: &str, updated_at: i64, content_md: &str) -> String {
+    if let Ok(cache) = render_cache().lock() {
+        if let Some(cached) = cache.get(slug) {
+            if cached.updated_at == updated_at {
+                return cached.html.clone();
+            }
+        }
+    }
+
+    let rendered = render_markdown(content_md);
+    if let Ok(mut cache) = render_cache().lock() {
+        cache.insert(
+            slug.to_string(),
+            CachedRender {
+                updated_at,
+                html: rendered.clone(),
+            },
+        );
+    }
+    rendered
+}
+
+pub fn render_markdown(content_md: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES;
+    let mut unsafe_html = String::with_capacity(content_md.len() * 3 / 2);
+    html::push_html(&mut unsafe_html, Parser::new_ext(content_md, options));
+
+    ammonia::Builder::default()
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .add_tags(["input"])
+        .clean(&unsafe_html)
+        .to_string()
+}
+
+/// Slugs linked from a page, either as `help://slug` or as a bare `slug` destination.
+/// Fragments and query strings are dropped; external URLs and file paths are ignored.
+pub fn internal_link_targets(content_md: &str) -> Vec<String> {
+    let mut targets = Vec::new();
+    for event in Parser::new_ext(content_md, Options::ENABLE_FOOTNOTES) {
+        let Event::Start(Tag::Link { dest_url, .. }) = event else {
+            continue;
+        };
+        let destination = dest_url.trim();
+        let (candidate, explicit) = match destination.strip_prefix(HELP_LINK_SCHEME) {
+            Some(rest) => (rest, true),
+            None => (destination, false),
+        };
+        let slug = candidate
+            .split(['#', '?'])
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches('/')
+            .to_ascii_lowercase();
+        let looks_like_slug = !slug.is_empty()
+            && slug
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric() || character == '-');
+        if (explicit && !slug.is_empty()) || looks_like_slug {
+            targets.push(slug);
+        }
+    }
+    targets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_markdown_and_strips_unsafe_markup() {
+        let html = render_markdown(
+            "# Title\n\n<script>alert(1)</script>\n\n[link](javascript:alert(1)) **bold**",
+        );
+
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(html.contains("<strong>bold</strong>"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("javascript:"));
+    }
+
+    #[test]
+    fn cache_is_invalidated_when_updated_at_changes() {
+        let slug = format!("render-cache-{}", uuid::Uuid::new_v4());
+        let first = render_page_html(&slug, 1, "first");
+        let cached = render_page_html(&slug, 1, "ignored while unchanged");
+        let refreshed = render_page_html(&slug, 2, "second");
+
+        assert_eq!(first, cached);
+        assert!(refreshed.contains("second"));
+    }
+
+    #[test]
+    fn collects_internal_link_targets_only() {
+        let targets = internal_link_targets(
+            "[Start](help://Quick-Start#steps) [Tools](tools-library) \
+             [Site](https://example.com) [Doc](notes.md) [Top](#top) [Old](help://old_slug)",
+        );
+
+        assert_eq!(targets, vec!["quick-start", "tools-library", "old_slug"]);
+    }
+}
@@ -1,3 +1,4 @@
+mod credentials;
 mod help;
 mod secrets;
 mod tools;
@@ -90,6 +91,31 @@ fn sql_migrations() -> Vec<tauri_plugin_sql::Migration> {
             sql: include_str!("../migrations/0014_create_help_center.sql"),
             kind: tauri_plugin_sql::MigrationKind::Up,
         },
+        tauri_plugin_sql::Migration {
+            version: 15,
+            description: "create_tool_integrity",
+            sql: include_str!("../migrations/0015_create_tool_integrity.sql"),
+            kind: tauri_plugin_sql::MigrationKind::Up,
+        },
+        tauri_plugin_sql::Migration {
+            version: 16,
+            description: "create_legacy_tool_imports",
+            sql: include_str!("../migrations/0016_create_legacy_tool_imports.sql"),
+            kind: tauri_plugin_sql::MigrationKind::Up,
+        },
+        tauri_plugin_sql::Migration {
+            version: 17,
+            description: "create_tool_slug_aliases",
+            sql: include_str!("../migrations/0017_create_tool_slug_aliases.sql"),
+            kind: tauri_plugin_sql::MigrationKind::Up,
+        },
+        // 18 and 19 add columns to library tables; only tools::db applies those.
+        tauri_plugin_sql::Migration {
+            version: 20,
+            description: "create_tool_views",
+            sql: include_str!("../migrations/0020_create_tool_views.sql"),
+            kind: tauri_plugin_sql::MigrationKind::Up,
+        },
     ]
 }
 
@@ -100,22 +126,78 @@ pub fn run() {
             secrets::secret_set,
             secrets::secret_get,
             secrets::secret_delete,
+            credentials::credentials_export_metadata,
+            credentials::credentials_import_metadata,
             tools::commands::tools_list,
+            tools::commands::tools_list_grouped,
+            tools::commands::tools_tag_cooccurrence,
             tools::commands::tool_get,
+            tools::commands::tool_get_by_slug,
+            tools::commands::tool_rename_slug,
+            tools::commands::tool_update,
+            tools::commands::tool_record_view,
+            tools::commands::tool_list_versions_semver,
+            tools::commands::tool_get_latest_instructions,
+            tools::commands::tool_list_distinct_files,
+            tools::commands::tool_preview_text_head,
             tools::commands::tool_create,
             tools::commands::tool_add_version,
+            tools::commands::tool_add_version_from,
+            tools::commands::tool_set_icon,
+            tools::commands::tool_request_delete,
             tools::commands::tool_delete,
             tools::commands::tool_delete_version,
+            tools::commands::tool_rename_version,
+            tools::commands::tool_prune_versions,
+            tools::commands::tool_file_set_note,
+            tools::commands::tool_file_get_note,
+            tools::commands::tool_rename_file,
+            tools::commands::tool_set_version_limit,
             tools::commands::tool_export_zip,
+            tools::commands::tools_export_many,
+            tools::commands::tool_export_bundle,
+            tools::commands::tool_export_install_package,
+            tools::commands::tools_export_catalog,
+            tools::commands::tool_export_full_zip,
+            tools::commands::tool_export_full_history,
             tools::commands::tool_export_zip_payload,
             tools::commands::tool_preview_import_zip_payload,
+            tools::commands::tool_import_bundle_zip,
+            tools::commands::tool_inspect_archive,
+            tools::commands::tool_import_diff,
             tools::commands::tool_import_zip,
+            tools::commands::tool_verify_zip,
             tools::commands::tool_import_zip_payload,
+            tools::commands::tool_verify_integrity,
+            tools::commands::tools_verify_all,
+            tools::commands::tools_verify_storage,
+            tools::commands::tools_migrate_legacy_db,
+            tools::commands::tools_storage_root,
+            tools::commands::tools_storage_report,
+            tools::commands::tools_storage_usage,
+            tools::commands::tool_storage_usage,
+            tools::commands::tools_gc,
+            tools::commands::tools_set_encryption,
+            tools::commands::tools_get_allowed_extensions,
+            tools::commands::tools_set_allowed_extensions,
+            tools::commands::tools_get_metadata_defaults,
+            tools::commands::tools_set_metadata_defaults,
+            tools::commands::tools_audit_paths,
+            tools::commands::tools_find_duplicate_file_names,
+            tools::commands::tools_normalize_all,
+            tools::commands::tools_checkpoint_db,
+            tools::commands::tools_cleanup_report,
+            tools::commands::tools_find_missing_files,
+            tools::commands::tools_migrate_storage,
+            tools::commands::tools_cancel_storage_migration,
             help::commands::help_list_pages,
+            help::commands::help_search_pages,
             help::commands::help_get_page,
+            help::commands::help_render_page,
             help::commands::help_create_page,
             help::commands::help_update_page,
             help::commands::help_delete_page,
+            help::commands::help_check_links,
             help::commands::app_state_get,
             help::commands::app_state_set
         ])
@@ -135,6 +217,10 @@ pub fn run() {
                     .level(log_level)
                     .build(),
             )?;
+            tools::commands::reconcile_cleanup_ledger_on_startup(app.handle().clone());
+            tools::commands::report_duplicate_file_names_on_startup(app.handle().clone());
+            tools::commands::load_allowed_extensions_on_startup(app.handle().clone());
+            tools::commands::purge_quarantine_on_startup(app.handle().clone());
             Ok(())
         });
 
@@ -9,6 +9,21 @@ fn build_entry(credential_id: &str) -> Result<keyring::Entry, String> {
         .map_err(|error| format!("failed to build keyring entry: {error}"))
 }
 
+/// Reads a secret the app manages itself; `None` when nothing is stored yet.
+pub(crate) fn read_internal_secret(credential_id: &str) -> Result<Option<String>, String> {
+    match build_entry(credential_id)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(error) => Err(format!("failed to read secret: {error}")),
+    }
+}
+
+pub(crate) fn store_internal_secret(credential_id: &str, secret_value: &str) -> Result<(), String> {
+    build_entry(credential_id)?
+        .set_password(secret_value)
+        .map_err(|error| format!("failed to store secret: {error}"))
+}
+
 #[tauri::command]
 pub async fn secret_set(credential_id: String, secret_value: String) -> Result<(), String> {
     let entry = build_entry(&credential_id)?;
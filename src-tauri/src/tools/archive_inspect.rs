@@ -0,0 +1,643 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use serde::Serialize;
+
+use super::cleanup_ledger::TrackedArtifacts;
+use super::error::{ToolsError, ToolsResult};
+use super::settings::ContentCheckMode;
+use super::storage::{assert_safe_archive_path, check_magic_bytes, sanitize_filename, sha256_hex};
+use super::zip::{
+    create_temp_dir, decode_manifest_bytes, extract_entry_within_limit, resolve_archive_root,
+    sniff_archive_format, tar_error, zip_error, ArchiveFormat, ExtractionLimits, ToolArchiveIndex,
+    ToolExportManifest, CHECKSUMS_FILE_NAME, LINEAGE_FILE_NAME, TOOL_INDEX_FILE_NAME,
+};
+
+/// Archives with more entries than this are refused rather than listed.
+pub const MAX_INSPECTED_ENTRIES: usize = 10_000;
+/// Files an archive may hold beside the ones its manifest declares.
+const METADATA_FILE_NAMES: &[&str] = &[
+    "manifest.json",
+    "instructions.md",
+    CHECKSUMS_FILE_NAME,
+    TOOL_INDEX_FILE_NAME,
+    LINEAGE_FILE_NAME,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ArchiveEntryKind {
+    File,
+    Directory,
+    Symlink,
+    /// Hard links, devices, FIFOs and other tar entry types.
+    Other,
+}
+
+/// What an entry's bytes look like, regardless of its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DetectedType {
+    Empty,
+    Text,
+    Script,
+    Zip,
+    Gzip,
+    Pdf,
+    Dwg,
+    Png,
+    WindowsExecutable,
+    ElfExecutable,
+    MachOExecutable,
+    Binary,
+}
+
+impl DetectedType {
+    fn detect(bytes: &[u8]) -> Self {
+        const MACH_O_MAGIC: &[&[u8]] = &[
+            b"\xfe\xed\xfa\xce",
+            b"\xfe\xed\xfa\xcf",
+            b"\xce\xfa\xed\xfe",
+            b"\xcf\xfa\xed\xfe",
+        ];
+        match bytes {
+            [] => Self::Empty,
+            [b'M', b'Z', ..] => Self::WindowsExecutable,
+            [0x7f, b'E', b'L', b'F', ..] => Self::ElfExecutable,
+            _ if MACH_O_MAGIC.iter().any(|magic| bytes.starts_with(magic)) => Self::MachOExecutable,
+            [b'#', b'!', ..] => Self::Script,
+            [b'P', b'K', 0x03, 0x04, ..] | [b'P', b'K', 0x05, 0x06, ..] => Self::Zip,
+            [0x1f, 0x8b, ..] => Self::Gzip,
+            _ if bytes.starts_with(b"%PDF") => Self::Pdf,
+            _ if bytes.starts_with(b"AC1") => Self::Dwg,
+            _ if bytes.starts_with(b"\x89PNG\r\n\x1a\n") => Self::Png,
+            _ if !bytes.contains(&0) && std::str::from_utf8(bytes).is_ok() => Self::Text,
+            _ => Self::Binary,
+        }
+    }
+
+    fn is_executable(self) -> bool {
+        matches!(
+            self,
+            Self::Script | Self::WindowsExecutable | Self::ElfExecutable | Self::MachOExecutable
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntryReport {
+    /// Entry path as stored in the archive, with `\` separators shown as `/`.
+    pub path: String,
+    pub kind: ArchiveEntryKind,
+    pub size_bytes: u64,
+    /// Zip only; tar.gz compresses the archive as a whole.
+    pub compressed_size_bytes: Option<u64>,
+    /// Set for files written to the sandbox; flagged entries are listed but never extracted.
+    pub detected_type: Option<DetectedType>,
+    pub sha256: Option<String>,
+    pub extracted: bool,
+    pub flags: Vec<String>,
+}
+
+impl ArchiveEntryReport {
+    fn new(path: String, kind: ArchiveEntryKind, size_bytes: u64) -> Self {
+        Self {
+            path,
+            kind,
+            size_bytes,
+            compressed_size_bytes: None,
+            detected_type: None,
+            sha256: None,
+            extracted: false,
+            flags: Vec::new(),
+        }
+    }
+}
+
+/// Every entry of an archive, including those the importer would reject.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveInventory {
+    pub format: ArchiveFormat,
+    pub entries: Vec<ArchiveEntryReport>,
+    pub file_count: usize,
+    pub total_size_bytes: u64,
+    pub flagged_count: usize,
+    /// Archive-level findings, such as a missing manifest or declared files that are absent.
+    pub warnings: Vec<String>,
+}
+
+/// Extracts `archive_path` into a throwaway sandbox and reports every entry. Unlike import,
+/// unsafe entries are flagged and skipped instead of failing the whole archive; only the
+/// extracted-size limit stops inspection. The sandbox is removed before returning.
+pub fn inspect_archive(
+    archive_path: &str,
+    limits: &ExtractionLimits,
+) -> ToolsResult<ArchiveInventory> {
+    let archive_path = PathBuf::from(archive_path.trim());
+    if !archive_path.is_file() {
+        return Err(ToolsError::Zip("Archive path is invalid.".to_string()));
+    }
+    let format = sniff_archive_format(&archive_path)?;
+    debug!(
+        "custom-tools: inspecting archive path={} format={format:?}",
+        archive_path.to_string_lossy()
+    );
+
+    let sandbox = create_temp_dir("tool-inspect")?;
+    let cleanup = TrackedArtifacts::track("archive inspection", vec![sandbox.clone()]);
+    let result = (|| -> ToolsResult<ArchiveInventory> {
+        let mut entries = match format {
+            ArchiveFormat::Zip => inspect_zip_entries(&archive_path, &sandbox, limits)?,
+            ArchiveFormat::TarGz => inspect_tar_gz_entries(&archive_path, &sandbox, limits)?,
+        };
+        flag_duplicate_paths(&mut entries);
+        let warnings = flag_undeclared_entries(&mut entries, &sandbox);
+
+        let files = entries
+            .iter()
+            .filter(|entry| entry.kind == ArchiveEntryKind::File);
+        Ok(ArchiveInventory {
+            format,
+            file_count: files.clone().count(),
+            total_size_bytes: files.map(|entry| entry.size_bytes).sum(),
+            flagged_count: entries
+                .iter()
+                .filter(|entry| !entry.flags.is_empty())
+                .count(),
+            entries,
+            warnings,
+        })
+    })();
+
+    cleanup.settle(&result);
+    let inventory = result?;
+    debug!(
+        "custom-tools: inspected archive entries={} flagged={}",
+        inventory.entries.len(),
+        inventory.flagged_count
+    );
+    Ok(inventory)
+}
+
+fn inspect_zip_entries(
+    archive_path: &Path,
+    sandbox: &Path,
+    limits: &ExtractionLimits,
+) -> ToolsResult<Vec<ArchiveEntryReport>> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(archive_path)?)
+        .map_err(|error| zip_error("read", error))?;
+    check_entry_count(archive.len())?;
+
+    let mut remaining = limits.max_total_bytes;
+    let mut entries = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let (mut report, encrypted) = {
+            let entry = archive
+                .by_index_raw(index)
+                .map_err(|error| zip_error("read", error))?;
+            let path = entry.name().replace('\\', "/");
+            let kind = if entry.is_symlink() {
+                ArchiveEntryKind::Symlink
+            } else if entry.is_dir() {
+                ArchiveEntryKind::Directory
+            } else {
+                ArchiveEntryKind::File
+            };
+            let mut report = ArchiveEntryReport::new(path, kind, entry.size());
+            report.compressed_size_bytes = Some(entry.compressed_size());
+            (report, entry.encrypted())
+        };
+
+        if report.kind == ArchiveEntryKind::Symlink {
+            report
+                .flags
+                .push("Symbolic link; the importer rejects archives with links.".to_string());
+        } else if encrypted {
+            report
+                .flags
+                .push("Encrypted; its contents cannot be inspected.".to_string());
+        } else if limits.exceeds_ratio(
+            report.size_bytes,
+            report.compressed_size_bytes.unwrap_or_default(),
+        ) {
+            report.flags.push(format!(
+                "Expands to more than {} times its compressed size; the importer treats it as a zip bomb.",
+                limits.max_compression_ratio
+            ));
+        } else if let Some(target) = safe_target(&mut report, sandbox)? {
+            if report.kind == ArchiveEntryKind::File {
+                let mut entry = archive
+                    .by_index(index)
+                    .map_err(|error| zip_error("read", error))?;
+                extract_entry_within_limit(
+                    &mut entry,
+                    &target,
+                    &report.path,
+                    limits,
+                    &mut remaining,
+                )?;
+            }
+            report.extracted = true;
+        }
+        flag_entry_content(&mut report, sandbox)?;
+        entries.push(report);
+    }
+    Ok(entries)
+}
+
+fn inspect_tar_gz_entries(
+    archive_path: &Path,
+    sandbox: &Path,
+    limits: &ExtractionLimits,
+) -> ToolsResult<Vec<ArchiveEntryReport>> {
+    let mut archive =
+        tar::Archive::new(flate2::read::GzDecoder::new(fs::File::open(archive_path)?));
+
+    let mut remaining = limits.max_total_bytes;
+    let mut entries = Vec::new();
+    for entry in archive
+        .entries()
+        .map_err(|error| tar_error("read", error))?
+    {
+        let mut entry = entry.map_err(|error| tar_error("read", error))?;
+        let path = String::from_utf8_lossy(&entry.path_bytes()).replace('\\', "/");
+        let path = path.trim_start_matches("./").to_string();
+        let size_bytes = entry.header().size().unwrap_or(0);
+        let entry_type = entry.header().entry_type();
+        let kind = match entry_type {
+            tar::EntryType::XGlobalHeader => continue,
+            tar::EntryType::Directory if path.trim_end_matches('/').is_empty() => continue,
+            tar::EntryType::Directory => ArchiveEntryKind::Directory,
+            tar::EntryType::Regular | tar::EntryType::Continuous => ArchiveEntryKind::File,
+            tar::EntryType::Symlink => ArchiveEntryKind::Symlink,
+            _ => ArchiveEntryKind::Other,
+        };
+        check_entry_count(entries.len() + 1)?;
+
+        let mut report = ArchiveEntryReport::new(path, kind, size_bytes);
+        match kind {
+            ArchiveEntryKind::Symlink | ArchiveEntryKind::Other => {
+                let target = entry
+                    .link_name_bytes()
+                    .map(|target| String::from_utf8_lossy(&target).to_string());
+                report.flags.push(match (kind, target) {
+                    (ArchiveEntryKind::Symlink, Some(target)) => format!(
+                        "Symbolic link to {target}; the importer rejects archives with links."
+                    ),
+                    (_, Some(target)) => {
+                        format!("Hard link to {target}; the importer rejects archives with links.")
+                    }
+                    _ => format!(
+                        "Special {entry_type:?} entry; the importer only accepts files and folders."
+                    ),
+                });
+            }
+            ArchiveEntryKind::File | ArchiveEntryKind::Directory => {
+                if let Some(target) = safe_target(&mut report, sandbox)? {
+                    if kind == ArchiveEntryKind::File {
+                        extract_entry_within_limit(
+                            &mut entry,
+                            &target,
+                            &report.path,
+                            limits,
+                            &mut remaining,
+                        )?;
+                    }
+                    report.extracted = true;
+                }
+            }
+        }
+        flag_entry_content(&mut report, sandbox)?;
+        entries.push(report);
+    }
+    Ok(entries)
+}
+
+fn check_entry_count(count: usize) -> ToolsResult<()> {
+    if count > MAX_INSPECTED_ENTRIES {
+        return Err(ToolsError::Zip(format!(
+            "The archive has more than {MAX_INSPECTED_ENTRIES} entries; inspection stopped."
+        )));
+    }
+    Ok(())
+}
+
+/// Resolves where a file or folder entry lands in the sandbox, creating the folders it needs.
+/// Entries whose path could escape the sandbox are flagged and yield `None`.
+fn safe_target(report: &mut ArchiveEntryReport, sandbox: &Path) -> ToolsResult<Option<PathBuf>> {
+    let relative = report.path.trim_end_matches('/');
+    if let Err(error) = assert_safe_archive_path(relative) {
+        report.flags.push(format!(
+            "{}; the entry could be written outside the extraction folder.",
+            error.user_message().trim_end_matches('.')
+        ));
+        return Ok(None);
+    }
+    let target = sandbox.join(relative);
+    let folder = match report.kind {
+        ArchiveEntryKind::Directory => Some(target.as_path()),
+        _ => target.parent(),
+    };
+    if let Some(folder) = folder {
+        if folder.is_file() {
+            report
+                .flags
+                .push("Its path passes through a file entry; not extracted.".to_string());
+            return Ok(None);
+        }
+        fs::create_dir_all(folder)?;
+    }
+    Ok(Some(target))
+}
+
+/// Windows folders ignore case, so paths differing only in case collide on extraction.
+fn flag_duplicate_paths(entries: &mut [ArchiveEntryReport]) {
+    let mut seen = HashSet::new();
+    for entry in entries {
+        let key = entry.path.trim_end_matches('/').to_lowercase();
+        if !seen.insert(key) {
+            entry.flags.push(
+                "Duplicate path; on extraction it overwrites an earlier entry with the same name."
+                    .to_string(),
+            );
+        }
+    }
+}
+
+/// Hashes and sniffs an extracted file, then flags names and content the importer would refuse
+/// or that deserve a second look. Runs right after extraction, before a later entry with the
+/// same path can overwrite the file.
+fn flag_entry_content(entry: &mut ArchiveEntryReport, sandbox: &Path) -> ToolsResult<()> {
+    if entry.kind == ArchiveEntryKind::Directory {
+        return Ok(());
+    }
+    let name = entry
+        .path
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    if entry
+        .path
+        .split('/')
+        .any(|segment| segment.starts_with('.') && segment != "." && segment != "..")
+        || entry.path.split('/').any(|segment| segment == "__MACOSX")
+    {
+        entry
+            .flags
+            .push("Hidden or operating-system metadata file.".to_string());
+    }
+    if entry.kind != ArchiveEntryKind::File || !entry.extracted {
+        return Ok(());
+    }
+
+    let bytes = fs::read(sandbox.join(&entry.path))?;
+    let detected = DetectedType::detect(&bytes);
+    entry.size_bytes = bytes.len() as u64;
+    entry.detected_type = Some(detected);
+    entry.sha256 = Some(sha256_hex(&bytes));
+
+    if detected.is_executable() {
+        entry.flags.push(format!(
+            "Executable content ({detected:?}); do not run it without reviewing it."
+        ));
+    }
+    if METADATA_FILE_NAMES.contains(&name.as_str()) {
+        return Ok(());
+    }
+    if let Err(error) = sanitize_filename(&name) {
+        entry.flags.push(error.user_message());
+    }
+    if let Some(message) = check_magic_bytes(&name, &bytes, ContentCheckMode::Warn)? {
+        entry.flags.push(message);
+    }
+    Ok(())
+}
+
+/// Flags entries that no manifest in the archive accounts for, which import rejects. Returns
+/// archive-level warnings, including declared files that are missing.
+fn flag_undeclared_entries(entries: &mut [ArchiveEntryReport], sandbox: &Path) -> Vec<String> {
+    let root = match resolve_archive_root(sandbox) {
+        Ok(root) => root,
+        Err(error) => {
+            return vec![format!(
+                "{} The importer would reject this archive.",
+                error.user_message()
+            )]
+        }
+    };
+    let prefix = root
+        .strip_prefix(sandbox)
+        .map(|relative| relative.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_default();
+    let join = |folder: &str, name: &str| {
+        [prefix.as_str(), folder, name]
+            .iter()
+            .filter(|part| !part.is_empty())
+            .map(|part| part.trim_matches('/'))
+            .collect::<Vec<_>>()
+            .join("/")
+    };
+
+    let mut warnings = Vec::new();
+    let mut version_folders = vec![String::new()];
+    let mut declared = HashSet::new();
+    if root.join(TOOL_INDEX_FILE_NAME).is_file() {
+        declared.insert(join("", TOOL_INDEX_FILE_NAME));
+        declared.insert(join("", LINEAGE_FILE_NAME));
+        match read_json::<ToolArchiveIndex>(&root.join(TOOL_INDEX_FILE_NAME)) {
+            Ok(index) => {
+                version_folders = index
+                    .versions
+                    .into_iter()
+                    .map(|version| version.path)
+                    .collect();
+            }
+            Err(error) => {
+                warnings.push(format!("{TOOL_INDEX_FILE_NAME} is not valid: {error}"));
+                version_folders.clear();
+            }
+        }
+    }
+
+    for folder in &version_folders {
+        for name in ["manifest.json", "instructions.md", CHECKSUMS_FILE_NAME] {
+            declared.insert(join(folder, name));
+        }
+        let manifest_path = join(folder, "manifest.json");
+        match read_json::<ToolExportManifest>(&sandbox.join(&manifest_path)) {
+            Ok(manifest) => {
+                let relative_paths = manifest
+                    .files
+                    .into_iter()
+                    .map(|file| file.relative_path)
+                    .chain(manifest.assets.into_iter().map(|asset| asset.relative_path));
+                for relative_path in relative_paths {
+                    let path = join(folder, &relative_path);
+                    let present = entries
+                        .iter()
+                        .any(|entry| entry.path == path && entry.kind == ArchiveEntryKind::File);
+                    if !present {
+                        warnings.push(format!(
+                            "{manifest_path} declares {path}, which is not in the archive."
+                        ));
+                    }
+                    declared.insert(path);
+                }
+            }
+            Err(error) => warnings.push(format!("{manifest_path} is not valid: {error}")),
+        }
+    }
+
+    for entry in entries {
+        if entry.kind != ArchiveEntryKind::Directory && !declared.contains(&entry.path) {
+            entry
+                .flags
+                .push("Not declared in the manifest; the importer rejects it.".to_string());
+        }
+    }
+    warnings
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let bytes = fs::read(path).map_err(|error| error.to_string())?;
+    let raw = decode_manifest_bytes(bytes).map_err(|error| error.user_message())?;
+    serde_json::from_str(&raw).map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut writer = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        for (name, content) in entries {
+            writer
+                .start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    fn manifest(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let files = files
+            .iter()
+            .map(|(name, bytes)| {
+                serde_json::json!({
+                    "originalName": name,
+                    "sha256": sha256_hex(bytes),
+                    "sizeBytes": bytes.len(),
+                    "relativePath": format!("files/{name}"),
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_vec(&serde_json::json!({
+            "tool": {
+                "name": "CAD Toolset",
+                "slug": "cad-toolset",
+                "description": "",
+                "category": "cad",
+                "tags": [],
+            },
+            "version": { "version": "1.0.0", "changelogMd": null },
+            "files": files,
+        }))
+        .unwrap()
+    }
+
+    fn limits() -> ExtractionLimits {
+        ExtractionLimits::for_import(&Default::default())
+    }
+
+    #[test]
+    fn inventory_lists_declared_files_and_flags_everything_else() {
+        let dir = create_temp_dir("inspect-test").unwrap();
+        let archive = dir.join("tool.zip");
+        write_zip(
+            &archive,
+            &[
+                ("manifest.json", &manifest(&[("layfix.lsp", b"(princ)")])),
+                ("instructions.md", b"# Install"),
+                ("files/layfix.lsp", b"(princ)"),
+                ("files/setup.exe", b"MZ\x90\x00"),
+                ("files/drawing.dwg", b"not a drawing"),
+                ("../escape.txt", b"outside"),
+                (".hidden/notes.txt", b"notes"),
+                ("files/LAYFIX.lsp", b"(princ)"),
+            ],
+        );
+
+        let inventory = inspect_archive(archive.to_str().unwrap(), &limits()).unwrap();
+        let flags = |path: &str| {
+            inventory
+                .entries
+                .iter()
+                .find(|entry| entry.path == path)
+                .unwrap()
+                .flags
+                .join(" | ")
+        };
+
+        assert_eq!(inventory.format, ArchiveFormat::Zip);
+        assert_eq!(inventory.entries.len(), 8);
+        assert_eq!(inventory.flagged_count, 5);
+        assert!(inventory.warnings.is_empty());
+        assert_eq!(flags("manifest.json"), "");
+        assert_eq!(flags("files/layfix.lsp"), "");
+        let lisp = &inventory.entries[2];
+        assert_eq!(lisp.detected_type, Some(DetectedType::Text));
+        assert_eq!(
+            lisp.sha256.as_deref(),
+            Some(sha256_hex(b"(princ)").as_str())
+        );
+
+        let exe = flags("files/setup.exe");
+        assert!(exe.contains("Executable content"));
+        assert!(exe.contains("Unsupported file extension .exe"));
+        assert!(exe.contains("Not declared in the manifest"));
+        assert!(flags("files/drawing.dwg").contains("does not look like a .dwg file"));
+        let escape = &inventory.entries[5];
+        assert!(!escape.extracted);
+        assert!(escape.flags[0].contains("Unsafe zip entry path"));
+        assert!(!dir.join("escape.txt").exists());
+        assert!(flags(".hidden/notes.txt").contains("Hidden"));
+        assert!(flags("files/LAYFIX.lsp").contains("Duplicate path"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn inventory_warns_about_missing_manifest_and_declared_files() {
+        let dir = create_temp_dir("inspect-test").unwrap();
+        let no_manifest = dir.join("loose.zip");
+        write_zip(&no_manifest, &[("readme.txt", b"hello")]);
+        let inventory = inspect_archive(no_manifest.to_str().unwrap(), &limits()).unwrap();
+        assert_eq!(inventory.flagged_count, 0);
+        assert!(inventory.warnings[0].contains("manifest.json was not found"));
+
+        let missing = dir.join("missing.zip");
+        write_zip(
+            &missing,
+            &[(
+                "cad-toolset/manifest.json",
+                &manifest(&[("layfix.lsp", b"(princ)")]),
+            )],
+        );
+        let inventory = inspect_archive(missing.to_str().unwrap(), &limits()).unwrap();
+        assert_eq!(
+            inventory.warnings,
+            vec![
+                "cad-toolset/manifest.json declares cad-toolset/files/layfix.lsp, which is not in the archive."
+            ]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,245 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use tauri::AppHandle;
+use uuid::{Builder, Uuid};
+
+use super::cleanup_ledger::TrackedArtifacts;
+use super::db::ExportVersionContext;
+use super::error::{ToolsError, ToolsResult};
+use super::storage::{ensure_free_space, sanitize_filename, sha256_hex};
+use super::zip::{estimate_export_size_bytes, read_verified_export_file};
+
+pub const PACKAGE_CONTENTS_FILE_NAME: &str = "PackageContents.xml";
+const BUNDLE_EXTENSION: &str = "bundle";
+const CONTENTS_DIR: &str = "Contents";
+const HELP_DIR: &str = "Help";
+/// Extensions AutoCAD loads itself when they are listed as bundle components.
+const LOADABLE_EXTENSIONS: &[&str] = &["lsp", "vlx", "fas", "cuix"];
+
+/// Writes `<destination>.bundle` with `PackageContents.xml` and the version files under `Contents/`.
+pub fn export_tool_version_bundle(
+    app: &AppHandle,
+    context: &ExportVersionContext,
+    destination_path: &str,
+) -> ToolsResult<PathBuf> {
+    let destination = normalize_bundle_destination(destination_path)?;
+    if destination.exists() {
+        return Err(ToolsError::Conflict(format!(
+            "{} already exists.",
+            destination.to_string_lossy()
+        )));
+    }
+    let parent = destination
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    fs::create_dir_all(&parent)?;
+    ensure_free_space(
+        &parent,
+        estimate_export_size_bytes(context),
+        "bundle export",
+    )?;
+
+    debug!(
+        "custom-tools: bundle export start tool_id={} version_id={} destination={}",
+        context.version.tool_id,
+        context.version.id,
+        destination.to_string_lossy()
+    );
+
+    // Build next to the destination so the final rename never crosses volumes.
+    let staging = parent.join(format!(".tool-bundle-{}", Uuid::new_v4()));
+    let cleanup = TrackedArtifacts::track("bundle export", vec![staging.clone()]);
+    let result = (|| -> ToolsResult<()> {
+        let contents_dir = staging.join(CONTENTS_DIR);
+        let help_dir = contents_dir.join(HELP_DIR);
+        fs::create_dir_all(&help_dir)?;
+
+        fs::write(
+            staging.join(PACKAGE_CONTENTS_FILE_NAME),
+            build_package_contents_xml(context)?,
+        )?;
+        fs::write(
+            help_dir.join("instructions.md"),
+            context.version.instructions_md.as_bytes(),
+        )?;
+
+        for file in &context.files {
+            let (sanitized, bytes) = read_verified_export_file(app, context, file)?;
+            fs::write(contents_dir.join(sanitized), bytes)?;
+        }
+
+        fs::rename(&staging, &destination)?;
+        Ok(())
+    })();
+
+    cleanup.settle(&result);
+    result?;
+    debug!(
+        "custom-tools: bundle export success tool_id={} version_id={}",
+        context.version.tool_id, context.version.id
+    );
+    Ok(destination)
+}
+
+pub fn build_package_contents_xml(context: &ExportVersionContext) -> ToolsResult<String> {
+    let tool = &context.tool;
+    let version = &context.version;
+    let mut components = String::new();
+    for file in &context.files {
+        let sanitized = sanitize_filename(&file.original_name)?;
+        let extension = Path::new(&sanitized)
+            .extension()
+            .and_then(|value| value.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if !LOADABLE_EXTENSIONS.contains(&extension.as_str()) {
+            continue;
+        }
+        let startup = if extension == "cuix" {
+            ""
+        } else {
+            " LoadOnAutoCADStartup=\"True\""
+        };
+        components.push_str(&format!(
+            "    <ComponentEntry AppName=\"{}\" Version=\"{}\" ModuleName=\"./{CONTENTS_DIR}/{}\"{startup} />\n",
+            escape_xml(&tool.name),
+            escape_xml(&version.version),
+            escape_xml(&sanitized),
+        ));
+    }
+
+    Ok(format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+<ApplicationPackage SchemaVersion=\"1.0\" AutodeskProduct=\"AutoCAD\" ProductType=\"Application\" \
+Name=\"{name}\" Description=\"{description}\" AppVersion=\"{app_version}\" \
+ProductCode=\"{product_code}\" UpgradeCode=\"{upgrade_code}\">\n\
+  <CompanyDetails Name=\"{name}\" />\n\
+  <Components Description=\"{category}\">\n\
+    <RuntimeRequirements OS=\"Win64\" Platform=\"AutoCAD*\" />\n\
+{components}  </Components>\n\
+</ApplicationPackage>\n",
+        name = escape_xml(&tool.name),
+        description = escape_xml(&tool.description),
+        app_version = escape_xml(&version.version),
+        category = escape_xml(&tool.category),
+        product_code = derived_guid(&format!("product:{}:{}", tool.slug, version.version)),
+        upgrade_code = derived_guid(&format!("upgrade:{}", tool.slug)),
+    ))
+}
+
+fn normalize_bundle_destination(raw: &str) -> ToolsResult<PathBuf> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Err(ToolsError::Validation(
+            "Destination path is required.".to_string(),
+        ));
+    }
+
+    let mut destination = PathBuf::from(trimmed);
+    let has_bundle_extension = destination
+        .extension()
+        .is_some_and(|value| value.eq_ignore_ascii_case(BUNDLE_EXTENSION));
+    if !has_bundle_extension {
+        let mut name = destination
+            .file_name()
+            .ok_or_else(|| ToolsError::Validation("Destination path is invalid.".to_string()))?
+            .to_os_string();
+        name.push(".");
+        name.push(BUNDLE_EXTENSION);
+        destination.set_file_name(name);
+    }
+    Ok(destination)
+}
+
+/// AutoCAD keys upgrades on UpgradeCode, so it is derived from the slug to stay stable across exports.
+fn derived_guid(seed: &str) -> String {
+    let digest = sha256_hex(format!("korda-tools:{seed}").as_bytes());
+    let mut bytes = [0u8; 16];
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digest[index * 2..index * 2 + 2], 16).unwrap_or_default();
+    }
+    let guid = Builder::from_custom_bytes(bytes).into_uuid();
+    format!("{{{}}}", guid.hyphenated().to_string().to_ascii_uppercase())
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\t' => escaped.push_str("&#9;"),
+            other if other.is_control() => {}
+            other => escaped.push(other),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::db::test_export_context;
+    use super::*;
+
+    fn context(version: &str) -> ExportVersionContext {
+        let mut context =
+            test_export_context(&[("layers.lsp", b""), ("menu.cuix", b""), ("readme.pdf", b"")]);
+        context.tool.name = "Layer <Tools> & Co".to_string();
+        context.tool.slug = "layer-tools".to_string();
+        context.tool.description = "Line one\nline \"two\"".to_string();
+        context.version.version = version.to_string();
+        context
+    }
+
+    #[test]
+    fn package_contents_lists_loadable_files_and_escapes_metadata() {
+        let xml = build_package_contents_xml(&context("1.2.0")).unwrap();
+
+        assert!(xml.contains("Name=\"Layer &lt;Tools&gt; &amp; Co\""));
+        assert!(xml.contains("Description=\"Line one&#10;line &quot;two&quot;\""));
+        assert!(xml.contains("ModuleName=\"./Contents/layers.lsp\" LoadOnAutoCADStartup=\"True\""));
+        assert!(xml.contains("ModuleName=\"./Contents/menu.cuix\" />"));
+        assert!(!xml.contains("readme.pdf"));
+    }
+
+    #[test]
+    fn upgrade_code_is_stable_across_versions() {
+        let first = build_package_contents_xml(&context("1.0.0")).unwrap();
+        let second = build_package_contents_xml(&context("2.0.0")).unwrap();
+        let attribute = |xml: &str, name: &str| {
+            let start = xml.find(&format!("{name}=\"")).unwrap() + name.len() + 2;
+            xml[start..start + 38].to_string()
+        };
+
+        assert_eq!(
+            attribute(&first, "UpgradeCode"),
+            attribute(&second, "UpgradeCode")
+        );
+        assert_ne!(
+            attribute(&first, "ProductCode"),
+            attribute(&second, "ProductCode")
+        );
+    }
+
+    #[test]
+    fn destination_gains_bundle_extension() {
+        assert_eq!(
+            normalize_bundle_destination("deploy/LayerTools").unwrap(),
+            PathBuf::from("deploy/LayerTools.bundle")
+        );
+        assert_eq!(
+            normalize_bundle_destination("deploy/LayerTools.BUNDLE").unwrap(),
+            PathBuf::from("deploy/LayerTools.BUNDLE")
+        );
+        assert!(normalize_bundle_destination("  ").is_err());
+    }
+}
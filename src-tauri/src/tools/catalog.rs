@@ -0,0 +1,160 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::db::CatalogEntry;
+use super::error::{ToolsError, ToolsResult};
+
+const CSV_HEADER: [&str; 10] = [
+    "name",
+    "slug",
+    "description",
+    "category",
+    "tags",
+    "versionCount",
+    "latestVersion",
+    "totalSizeBytes",
+    "createdAt",
+    "updatedAt",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CatalogFormat {
+    Csv,
+    Json,
+}
+
+impl CatalogFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Writes the catalog to `destination`, adding the format's extension when none is given.
+/// The file is written beside the destination first so readers never see a partial report.
+pub fn export_catalog(
+    entries: &[CatalogEntry],
+    format: CatalogFormat,
+    destination_path: &str,
+) -> ToolsResult<PathBuf> {
+    let trimmed = destination_path.trim();
+    if trimmed.is_empty() {
+        return Err(ToolsError::Validation(
+            "Destination path is required.".to_string(),
+        ));
+    }
+    let mut destination = PathBuf::from(trimmed);
+    if destination.extension().is_none() {
+        destination.set_extension(format.extension());
+    }
+    let parent = destination
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    fs::create_dir_all(&parent)?;
+
+    let staging = parent.join(format!(".tool-catalog-{}", Uuid::new_v4()));
+    let result = fs::write(&staging, render_catalog(entries, format)?)
+        .and_then(|_| fs::rename(&staging, &destination));
+    if let Err(error) = result {
+        let _ = fs::remove_file(&staging);
+        return Err(error.into());
+    }
+    Ok(destination)
+}
+
+pub fn render_catalog(entries: &[CatalogEntry], format: CatalogFormat) -> ToolsResult<String> {
+    match format {
+        CatalogFormat::Json => serde_json::to_string_pretty(entries)
+            .map_err(|error| ToolsError::Io(format!("Failed to serialize tool catalog: {error}"))),
+        CatalogFormat::Csv => {
+            let mut output = csv_row(CSV_HEADER.iter().map(|field| field.to_string()));
+            for entry in entries {
+                output.push_str(&csv_row([
+                    entry.name.clone(),
+                    entry.slug.clone(),
+                    entry.description.clone(),
+                    entry.category.clone(),
+                    entry.tags.join("; "),
+                    entry.version_count.to_string(),
+                    entry.latest_version.clone().unwrap_or_default(),
+                    entry.total_size_bytes.to_string(),
+                    entry.created_at.to_string(),
+                    entry.updated_at.to_string(),
+                ]));
+            }
+            Ok(output)
+        }
+    }
+}
+
+/// RFC 4180 row: fields holding separators, quotes, or line breaks are quoted with quotes doubled.
+fn csv_row(fields: impl IntoIterator<Item = String>) -> String {
+    let mut row = fields
+        .into_iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    row.push_str("\r\n");
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(description: &str) -> CatalogEntry {
+        CatalogEntry {
+            name: "Layer Tools".to_string(),
+            slug: "layer-tools".to_string(),
+            description: description.to_string(),
+            category: "cad".to_string(),
+            tags: vec!["autocad".to_string(), "layers".to_string()],
+            version_count: 2,
+            latest_version: Some("1.1.0".to_string()),
+            total_size_bytes: 2048,
+            created_at: 1,
+            updated_at: 2,
+        }
+    }
+
+    #[test]
+    fn csv_quotes_fields_with_separators_quotes_and_newlines() {
+        let csv = render_catalog(
+            &[entry("Sets \"current\" layer,\nthen purges")],
+            CatalogFormat::Csv,
+        )
+        .unwrap();
+        let lines = csv.split("\r\n").collect::<Vec<_>>();
+
+        assert_eq!(lines[0], CSV_HEADER.join(","));
+        assert_eq!(
+            lines[1],
+            "Layer Tools,layer-tools,\"Sets \"\"current\"\" layer,\nthen purges\",cad,autocad; layers,2,1.1.0,2048,1,2"
+        );
+        assert_eq!(lines[2], "");
+    }
+
+    #[test]
+    fn json_uses_camel_case_fields() {
+        let json = render_catalog(&[entry("plain")], CatalogFormat::Json).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed[0]["versionCount"], 2);
+        assert_eq!(parsed[0]["latestVersion"], "1.1.0");
+        assert_eq!(parsed[0]["tags"][1], "layers");
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::db::now_epoch_millis;
+use super::error::ToolsResult;
+
+const MARKER_EXTENSION: &str = "json";
+/// Reclaimed operations kept for the report; older entries are dropped first.
+const MAX_REPORTED_OPERATIONS: usize = 200;
+
+/// One in-flight operation's artifacts, written to the ledger directory before the work starts
+/// and removed once it settles. A marker still present at startup belongs to an operation that
+/// never finished.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LedgerMarker {
+    operation: String,
+    started_at: i64,
+    paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReclaimedOperation {
+    pub operation: String,
+    pub started_at: i64,
+    pub reclaimed_at: i64,
+    /// True when the artifacts were left by an earlier run and removed at startup.
+    pub at_startup: bool,
+    pub paths: Vec<String>,
+    pub bytes: u64,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupReport {
+    pub operations: Vec<ReclaimedOperation>,
+    pub reclaimed_bytes: u64,
+    /// Leftovers found at startup but kept because startup cleanup is set to report only.
+    pub pending: Vec<ReclaimedOperation>,
+}
+
+fn ledger_dir() -> &'static OnceLock<PathBuf> {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    &DIR
+}
+
+fn report() -> &'static Mutex<CleanupReport> {
+    static REPORT: OnceLock<Mutex<CleanupReport>> = OnceLock::new();
+    REPORT.get_or_init(|| Mutex::new(CleanupReport::default()))
+}
+
+fn in_flight() -> &'static Mutex<HashMap<Uuid, Vec<PathBuf>>> {
+    static IN_FLIGHT: OnceLock<Mutex<HashMap<Uuid, Vec<PathBuf>>>> = OnceLock::new();
+    IN_FLIGHT.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn lock_in_flight() -> std::sync::MutexGuard<'static, HashMap<Uuid, Vec<PathBuf>>> {
+    in_flight()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Paths of operations in this process that are tracked but not yet kept or reclaimed. Their
+/// files may already sit in storage while the rows that claim them are still uncommitted.
+pub fn in_flight_paths() -> Vec<PathBuf> {
+    lock_in_flight().values().flatten().cloned().collect()
+}
+
+fn lock_report() -> std::sync::MutexGuard<'static, CleanupReport> {
+    // The report is diagnostic only; a poisoned lock still holds usable entries.
+    report()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Points the ledger at `dir` and returns the markers already there. Call once at startup,
+/// before any tracked operation can run, so the snapshot holds only leftovers.
+pub fn init(dir: PathBuf) -> ToolsResult<Vec<PathBuf>> {
+    fs::create_dir_all(&dir)?;
+    let stale = stale_markers(&dir)?;
+    let _ = ledger_dir().set(dir);
+    Ok(stale)
+}
+
+/// Everything reclaimed since launch, newest last.
+pub fn current_report() -> CleanupReport {
+    lock_report().clone()
+}
+
+/// Removes (or, when `remove` is false, only lists) the artifacts named by leftover markers,
+/// and records the outcome in the report. Paths in `referenced` are left alone: markers are
+/// written before any file, so an operation that committed but crashed before settling still
+/// names files its rows now own.
+pub fn reconcile(markers: &[PathBuf], remove: bool, referenced: &[PathBuf]) {
+    for marker_path in markers {
+        let marker = match fs::read(marker_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<LedgerMarker>(&bytes).ok())
+        {
+            Some(marker) => marker,
+            None => {
+                warn!(
+                    "custom-tools: dropping unreadable cleanup marker {}",
+                    marker_path.to_string_lossy()
+                );
+                let _ = fs::remove_file(marker_path);
+                continue;
+            }
+        };
+
+        if !remove {
+            let entry = describe(&marker, true, referenced);
+            if !entry.paths.is_empty() {
+                lock_report().pending.push(entry);
+            }
+            continue;
+        }
+
+        let entry = reclaim(&marker, true, referenced);
+        let _ = fs::remove_file(marker_path);
+        record(entry);
+    }
+}
+
+/// Temp directories and written files owned by one operation. Dropping it without calling
+/// [`TrackedArtifacts::keep`] or [`TrackedArtifacts::settle`] treats the operation as failed:
+/// the paths are deleted and reported.
+#[derive(Debug)]
+pub struct TrackedArtifacts {
+    id: Uuid,
+    marker: LedgerMarker,
+    marker_path: Option<PathBuf>,
+    settled: bool,
+}
+
+impl TrackedArtifacts {
+    pub fn track(operation: &str, paths: Vec<PathBuf>) -> Self {
+        Self::track_in(ledger_dir().get().map(PathBuf::as_path), operation, paths)
+    }
+
+    fn track_in(dir: Option<&Path>, operation: &str, paths: Vec<PathBuf>) -> Self {
+        let marker = LedgerMarker {
+            operation: operation.to_string(),
+            started_at: now_epoch_millis().unwrap_or_default(),
+            paths,
+        };
+        // Without a ledger directory (tests, or a failed init) cleanup still runs in-process;
+        // only crash recovery is lost.
+        let marker_path = dir.and_then(|dir| {
+            let path = dir.join(format!("{}.{MARKER_EXTENSION}", Uuid::new_v4()));
+            let written = serde_json::to_vec(&marker)
+                .ok()
+                .and_then(|bytes| fs::write(&path, bytes).ok());
+            if written.is_none() {
+                warn!(
+                    "custom-tools: could not write cleanup marker for {}",
+                    marker.operation
+                );
+            }
+            written.map(|_| path)
+        });
+        let id = Uuid::new_v4();
+        lock_in_flight().insert(id, marker.paths.clone());
+        Self {
+            id,
+            marker,
+            marker_path,
+            settled: false,
+        }
+    }
+
+    /// The operation succeeded and its artifacts are now owned elsewhere (e.g. by DB rows).
+    pub fn keep(mut self) {
+        self.settled = true;
+        self.release();
+    }
+
+    /// Scratch artifacts: deleted either way, but only reported when `result` is an error.
+    pub fn settle<T, E>(mut self, result: &Result<T, E>) {
+        if result.is_ok() {
+            self.settled = true;
+            for path in &self.marker.paths {
+                let _ = remove_path(path);
+            }
+            self.release();
+        }
+    }
+
+    /// Ends tracking: the operation leaves the in-flight set and its marker is removed.
+    fn release(&mut self) {
+        lock_in_flight().remove(&self.id);
+        if let Some(path) = self.marker_path.take() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+impl Drop for TrackedArtifacts {
+    fn drop(&mut self) {
+        if self.settled {
+            return;
+        }
+        let entry = reclaim(&self.marker, false, &[]);
+        self.release();
+        if !entry.paths.is_empty() {
+            debug!(
+                "custom-tools: reclaimed {} path(s) from failed {}",
+                entry.paths.len(),
+                entry.operation
+            );
+        }
+        record(entry);
+    }
+}
+
+fn stale_markers(dir: &Path) -> ToolsResult<Vec<PathBuf>> {
+    let mut markers = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == MARKER_EXTENSION)
+        })
+        .collect::<Vec<_>>();
+    markers.sort();
+    Ok(markers)
+}
+
+fn describe(marker: &LedgerMarker, at_startup: bool, referenced: &[PathBuf]) -> ReclaimedOperation {
+    let existing = marker
+        .paths
+        .iter()
+        .filter(|path| path.exists() && !referenced.contains(path))
+        .collect::<Vec<_>>();
+    ReclaimedOperation {
+        operation: marker.operation.clone(),
+        started_at: marker.started_at,
+        reclaimed_at: now_epoch_millis().unwrap_or_default(),
+        at_startup,
+        paths: existing
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect(),
+        bytes: existing.iter().map(|path| path_size(path)).sum(),
+        errors: Vec::new(),
+    }
+}
+
+fn reclaim(marker: &LedgerMarker, at_startup: bool, referenced: &[PathBuf]) -> ReclaimedOperation {
+    let mut entry = describe(marker, at_startup, referenced);
+    for path in &entry.paths {
+        if let Err(error) = remove_path(Path::new(path)) {
+            entry.errors.push(format!("{path}: {error}"));
+        }
+    }
+    entry
+}
+
+fn record(entry: ReclaimedOperation) {
+    if entry.paths.is_empty() {
+        return;
+    }
+    let mut report = lock_report();
+    report.reclaimed_bytes += entry.bytes;
+    report.operations.push(entry);
+    let overflow = report
+        .operations
+        .len()
+        .saturating_sub(MAX_REPORTED_OPERATIONS);
+    report.operations.drain(..overflow);
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(error),
+    }
+}
+
+fn path_size(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| path_size(&entry.path()))
+                    .sum()
+            })
+            .unwrap_or_default(),
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::zip::create_temp_dir;
+    use super::*;
+
+    #[test]
+    fn dropped_artifacts_are_deleted_and_settled_ones_are_not_reported() {
+        let root = create_temp_dir("cleanup-ledger-drop").unwrap();
+        let ledger = root.join("ledger");
+        fs::create_dir_all(&ledger).unwrap();
+        let scratch = root.join("scratch");
+        fs::create_dir_all(&scratch).unwrap();
+        fs::write(scratch.join("part.bin"), b"12345").unwrap();
+        let stored = root.join("stored.lsp");
+        fs::write(&stored, b"(princ)").unwrap();
+
+        let failed = TrackedArtifacts::track_in(
+            Some(&ledger),
+            "cleanup ledger drop test",
+            vec![scratch.clone()],
+        );
+        assert_eq!(stale_markers(&ledger).unwrap().len(), 1);
+        drop(failed);
+        assert!(!scratch.exists());
+        assert!(stale_markers(&ledger).unwrap().is_empty());
+        let reported = current_report()
+            .operations
+            .into_iter()
+            .find(|entry| entry.operation == "cleanup ledger drop test")
+            .unwrap();
+        assert_eq!(reported.bytes, 5);
+        assert!(!reported.at_startup);
+
+        let kept = TrackedArtifacts::track_in(Some(&ledger), "kept", vec![stored.clone()]);
+        assert!(in_flight_paths().contains(&stored));
+        kept.keep();
+        assert!(!in_flight_paths().contains(&stored));
+        assert!(stored.exists());
+        assert!(stale_markers(&ledger).unwrap().is_empty());
+
+        fs::create_dir_all(&scratch).unwrap();
+        let settled = TrackedArtifacts::track_in(Some(&ledger), "settled", vec![scratch.clone()]);
+        settled.settle(&Ok::<(), ()>(()));
+        assert!(!scratch.exists());
+        assert!(!current_report()
+            .operations
+            .iter()
+            .any(|entry| entry.operation == "settled"));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn reconcile_removes_leftovers_of_operations_that_never_finished() {
+        let root = create_temp_dir("cleanup-ledger-reconcile").unwrap();
+        let ledger = root.join("ledger");
+        fs::create_dir_all(&ledger).unwrap();
+        let staging = root.join("tool-import-staging");
+        fs::create_dir_all(&staging).unwrap();
+        fs::write(staging.join("manifest.json"), b"{}").unwrap();
+        let orphan = root.join("orphan.lsp");
+        fs::write(&orphan, b"(princ)").unwrap();
+        let committed = root.join("committed.lsp");
+        fs::write(&committed, b"(princ 1)").unwrap();
+        fs::write(ledger.join("garbage.json"), b"not json").unwrap();
+
+        // Simulates a process killed mid-import: the marker outlives the operation.
+        std::mem::forget(TrackedArtifacts::track_in(
+            Some(&ledger),
+            "cleanup ledger reconcile test",
+            vec![
+                staging.clone(),
+                orphan.clone(),
+                committed.clone(),
+                root.join("never-created"),
+            ],
+        ));
+        let markers = stale_markers(&ledger).unwrap();
+        assert_eq!(markers.len(), 2);
+
+        let referenced = vec![committed.clone()];
+        reconcile(&markers, false, &referenced);
+        assert!(staging.exists() && orphan.exists());
+        assert!(current_report()
+            .pending
+            .iter()
+            .any(|entry| entry.operation == "cleanup ledger reconcile test"));
+
+        reconcile(&stale_markers(&ledger).unwrap(), true, &referenced);
+        assert!(!staging.exists() && !orphan.exists());
+        assert!(committed.exists());
+        assert!(stale_markers(&ledger).unwrap().is_empty());
+        let reclaimed = current_report()
+            .operations
+            .into_iter()
+            .find(|entry| entry.operation == "cleanup ledger reconcile test")
+            .unwrap();
+        assert!(reclaimed.at_startup);
+        assert_eq!(reclaimed.paths.len(), 2);
+        assert_eq!(reclaimed.bytes, 2 + 7);
+        assert!(reclaimed.errors.is_empty());
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -1,23 +1,57 @@
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use base64::Engine;
-use log::debug;
+use log::{debug, warn};
 use serde::{Deserialize, Serialize};
-use tauri::AppHandle;
+use tauri::{AppHandle, Emitter, Manager, Window};
 use uuid::Uuid;
 
+use super::archive_inspect::{self, ArchiveInventory};
+use super::bundle;
+use super::catalog::{self, CatalogFormat};
+use super::cleanup_ledger::{self, CleanupReport, TrackedArtifacts};
 use super::db::{
-    self, FileRecordInsert, ToolDetail, ToolListFilters, ToolMetadataInput, ToolSummary,
-    VersionInsertInput,
+    self, DistinctToolFile, DuplicateFileName, FileRecordInsert, LatestInstructions,
+    NormalizationReport, PrunedVersion, TagCooccurrence, ToolCategoryGroup, ToolDetail,
+    ToolListFilters, ToolListOrder, ToolListPage, ToolMetadataInput, ToolVersionDetail,
+    VersionInsertInput, VersionLimitMode, WalCheckpoint,
 };
+use super::delete_tokens;
+use super::derived_version;
+use super::encryption::StorageCipher;
 use super::error::{ToolsError, ToolsResult};
+use super::import_plans::{self, ToolImportDiff};
+use super::install_package;
+use super::integrity::{
+    self, IntegrityIssue, IntegrityProgress, IntegrityReport, MissingStoredFile, PathAuditReport,
+    INTEGRITY_PROGRESS_EVENT, STORAGE_SCAN_PROGRESS_EVENT,
+};
+use super::legacy::{self, LegacyMigratedTool, LegacyMigrationReport, LegacySkippedTool};
+use super::relocation::{self, StorageMigrationReport};
+use super::settings::{
+    self, ContentCheckMode, StartupCleanupMode, ToolMetadataDefaults, ToolsSettings,
+};
 use super::storage::{
-    self, delete_tool_folder, delete_version_folder, remove_written_files, sha256_hex,
-    stage_inbound_files, write_staged_files, FileLimits, InboundToolFile,
+    self, build_stored_rel_path, check_json_content, check_magic_bytes, delete_tool_folder,
+    delete_version_folder, ensure_within_quota, sanitize_filename, stage_decoded_files,
+    stage_inbound_files, stage_tool_icon, write_staged_files, DecodedToolFile, FileLimits,
+    InboundToolFile,
+};
+use super::usage::{self, GcReport, StorageUsage};
+use super::zip::{
+    self, ArchiveFormat, BareFileDetails, CompressionLevel, ExportOptions, ExportSummary,
+    ExtractionLimits, ImportOptions, ToolImportPreview,
 };
-use super::zip;
 
 const DEFAULT_INITIAL_VERSION: &str = "1.0.0";
+const TAG_COOCCURRENCE_LIMIT: i64 = 20;
+const CLEANUP_LEDGER_DIR: &str = "cleanup-ledger";
+/// Highest size limit a payload import may ask for; imported files are held in memory.
+const MAX_IMPORT_LIMIT_OVERRIDE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+/// How long the extraction folders of failed imports are kept for inspection.
+const QUARANTINE_RETENTION: Duration = Duration::from_secs(14 * 24 * 60 * 60);
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -29,6 +63,15 @@ pub struct ToolCreateRequest {
     pub files: Vec<InboundToolFile>,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolBySlug {
+    #[serde(flatten)]
+    pub detail: ToolDetail,
+    /// The slug that was requested when it only matched through a rename alias.
+    pub redirected_from: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ToolAddVersionRequest {
@@ -44,6 +87,45 @@ pub struct ToolAddVersionRequest {
 pub struct ZipPayloadRequest {
     pub file_name: String,
     pub data_base64: String,
+    #[serde(default)]
+    pub trust_manifest: bool,
+    #[serde(default)]
+    pub verify_checksums: bool,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Raises or lowers the per-file size ceiling for this import.
+    #[serde(default)]
+    pub max_file_size_bytes: Option<u64>,
+    /// Raises or lowers the whole-version size ceiling for this import.
+    #[serde(default)]
+    pub max_total_size_bytes: Option<u64>,
+}
+
+impl ZipPayloadRequest {
+    fn import_options(&self, settings: &ToolsSettings) -> ToolsResult<ImportOptions> {
+        let defaults = FileLimits::default();
+        let limits = FileLimits {
+            max_file_size_bytes: self
+                .max_file_size_bytes
+                .unwrap_or(defaults.max_file_size_bytes),
+            max_total_size_bytes: self
+                .max_total_size_bytes
+                .unwrap_or(defaults.max_total_size_bytes),
+            ..defaults
+        };
+        let in_range = |limit: u64| (1..=MAX_IMPORT_LIMIT_OVERRIDE_BYTES).contains(&limit);
+        if !in_range(limits.max_file_size_bytes) || !in_range(limits.max_total_size_bytes) {
+            return Err(ToolsError::Validation(format!(
+                "Import size limits must be greater than zero and at most {} MB.",
+                MAX_IMPORT_LIMIT_OVERRIDE_BYTES / (1024 * 1024)
+            )));
+        }
+        Ok(ImportOptions {
+            password: self.password.clone(),
+            limits,
+            ..import_options(settings, self.trust_manifest, self.verify_checksums)
+        })
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -51,60 +133,104 @@ pub struct ZipPayloadRequest {
 pub struct ToolCreateResult {
     pub tool_id: String,
     pub version_id: String,
+    pub warnings: Vec<String>,
+    /// Versions removed to stay within the tool's version limit.
+    pub pruned_versions: Vec<PrunedVersion>,
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ToolImportResult {
-    pub tool_id: String,
-    pub version_id: String,
-    pub created_tool: bool,
+pub struct StorageReport {
+    pub root: String,
+    pub used_bytes: u64,
+    pub quota_bytes: Option<u64>,
+    pub remaining_bytes: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ExportZipPayload {
-    pub file_name: String,
-    pub data_base64: String,
+pub struct ToolImportResult {
+    pub tool_id: String,
+    pub version_id: String,
+    pub created_tool: bool,
+    pub warnings: Vec<String>,
+    /// Wall time from the start of the command, including archive extraction.
+    pub duration_ms: Option<u64>,
+    pub total_bytes: Option<u64>,
+    pub file_count: Option<usize>,
+    pub pruned_versions: Vec<PrunedVersion>,
+    /// Version labels imported, oldest first; several for whole-tool archives.
+    pub imported_versions: Vec<String>,
+    /// Versions of a whole-tool archive that already existed and were left alone.
+    pub skipped_versions: Vec<String>,
 }
 
+/// Outcome for one tool folder of a bundle; exactly one of `result` and `error` is set.
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ToolImportPreview {
-    pub tool_name: String,
-    pub slug: String,
-    pub version: String,
-    pub files: Vec<ToolImportPreviewFile>,
-    pub total_size_bytes: u64,
-    pub warnings: Vec<String>,
+pub struct ToolBundleImportResult {
+    pub folder: String,
+    pub result: Option<ToolImportResult>,
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ToolImportPreviewFile {
-    pub original_name: String,
-    pub size_bytes: u64,
-    pub sha256: String,
+pub struct ExportZipPayload {
+    pub file_name: String,
+    pub data_base64: String,
 }
 
+/// Lists tools matching the filters. `page` (from 1) and `page_size` select one page; the
+/// result's `total` counts every match.
+// Each argument is a separate key of the frontend's invoke payload.
+#[allow(clippy::too_many_arguments)]
 #[tauri::command]
 pub async fn tools_list(
     app: AppHandle,
     query: Option<String>,
     category: Option<String>,
     tag: Option<String>,
-) -> Result<Vec<ToolSummary>, String> {
+    order_by: Option<ToolListOrder>,
+    quality_issues: Option<bool>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+) -> Result<ToolListPage, String> {
     run(async {
         let pool = db::open_pool(&app).await?;
-        db::list_tools(
+        let mut listed = db::list_tools_page(
             &pool,
             ToolListFilters {
                 query,
                 category,
                 tag,
+                order_by: order_by.unwrap_or_default(),
+                quality_issues: quality_issues.unwrap_or(false),
             },
+            page,
+            page_size,
         )
-        .await
+        .await?;
+        for tool in &mut listed.tools {
+            tool.icon_data_base64 = read_icon_base64(&app, tool.icon_rel_path.as_deref());
+        }
+        Ok(listed)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tools_list_grouped(
+    app: AppHandle,
+    filters: Option<ToolListFilters>,
+) -> Result<Vec<ToolCategoryGroup>, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let mut groups = db::list_tools_grouped(&pool, filters.unwrap_or_default()).await?;
+        for tool in groups.iter_mut().flat_map(|group| group.tools.iter_mut()) {
+            tool.icon_data_base64 = read_icon_base64(&app, tool.icon_rel_path.as_deref());
+        }
+        Ok(groups)
     })
     .await
 }
@@ -113,7 +239,150 @@ pub async fn tools_list(
 pub async fn tool_get(app: AppHandle, tool_id: String) -> Result<ToolDetail, String> {
     run(async {
         let pool = db::open_pool(&app).await?;
-        db::get_tool_detail(&pool, tool_id.trim()).await
+        let mut detail = db::get_tool_detail(&pool, tool_id.trim()).await?;
+        detail.icon_data_base64 = read_icon_base64(&app, detail.icon_rel_path.as_deref());
+        Ok(detail)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tool_get_by_slug(app: AppHandle, slug: String) -> Result<ToolBySlug, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let found = db::find_tool_by_slug(&pool, slug.trim())
+            .await?
+            .ok_or_else(|| ToolsError::NotFound("Tool not found.".to_string()))?;
+        let mut detail = db::get_tool_detail(&pool, &found.tool_id).await?;
+        detail.icon_data_base64 = read_icon_base64(&app, detail.icon_rel_path.as_deref());
+        Ok(ToolBySlug {
+            detail,
+            redirected_from: found.redirected.then(|| slug.trim().to_string()),
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tool_rename_slug(
+    app: AppHandle,
+    tool_id: String,
+    new_slug: String,
+) -> Result<String, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let slug = db::rename_tool_slug(&pool, tool_id.trim(), &new_slug).await?;
+        debug!(
+            "custom-tools: tool {} now uses slug {}",
+            tool_id.trim(),
+            slug
+        );
+        Ok(slug)
+    })
+    .await
+}
+
+/// Edits a tool's name, description, category, tags and optionally its slug.
+#[tauri::command]
+pub async fn tool_update(
+    app: AppHandle,
+    tool_id: String,
+    metadata: ToolMetadataInput,
+) -> Result<ToolDetail, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let mut detail = db::update_tool_metadata(&pool, tool_id.trim(), metadata).await?;
+        detail.icon_data_base64 = read_icon_base64(&app, detail.icon_rel_path.as_deref());
+        debug!("custom-tools: updated metadata of tool {}", detail.id);
+        Ok(detail)
+    })
+    .await
+}
+
+/// Records that the tool was opened so `tools_list` can order by recent views.
+#[tauri::command]
+pub async fn tool_record_view(app: AppHandle, tool_id: String) -> Result<i64, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        db::record_tool_view(&pool, tool_id.trim()).await
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tool_list_versions_semver(
+    app: AppHandle,
+    tool_id: String,
+) -> Result<Vec<ToolVersionDetail>, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        db::list_versions_semver(&pool, tool_id.trim()).await
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tool_get_latest_instructions(
+    app: AppHandle,
+    tool_id: String,
+) -> Result<LatestInstructions, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        db::get_latest_instructions(&pool, tool_id.trim()).await
+    })
+    .await
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextFilePreview {
+    pub original_name: String,
+    pub lines: Vec<String>,
+    pub truncated: bool,
+    pub size_bytes: i64,
+}
+
+/// Returns the first `max_lines` lines of a stored text file without reading all of it.
+#[tauri::command]
+pub async fn tool_preview_text_head(
+    app: AppHandle,
+    version_id: String,
+    original_name: String,
+    max_lines: Option<usize>,
+) -> Result<TextFilePreview, String> {
+    run(async {
+        let max_lines = max_lines.unwrap_or(storage::TEXT_PREVIEW_MAX_LINES);
+        if max_lines == 0 {
+            return Err(ToolsError::Validation(
+                "max_lines must be at least 1.".to_string(),
+            ));
+        }
+        let pool = db::open_pool(&app).await?;
+        let file = db::find_version_file(&pool, version_id.trim(), &original_name).await?;
+        let head = storage::read_stored_text_head(
+            &storage::resolve_stored_path(&app, &file.stored_rel_path)?,
+            max_lines,
+        )?;
+        Ok(TextFilePreview {
+            original_name: file.original_name,
+            lines: head.lines,
+            truncated: head.truncated,
+            size_bytes: file.size_bytes,
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tool_list_distinct_files(
+    app: AppHandle,
+    tool_id: String,
+) -> Result<Vec<DistinctToolFile>, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let tool_id = tool_id.trim();
+        db::get_tool_detail(&pool, tool_id).await?;
+        db::list_distinct_files(&pool, tool_id).await
     })
     .await
 }
@@ -127,13 +396,17 @@ pub async fn tool_create(
         let pool = db::open_pool(&app).await?;
         let tool_id = Uuid::new_v4().to_string();
         let version_id = Uuid::new_v4().to_string();
+        let settings = settings::load_settings(&pool).await?;
 
         let staged =
             stage_inbound_files(&tool_id, &version_id, request.files, &FileLimits::default())?;
-        let written = write_staged_files(&app, &staged)?;
+        let warnings = staged_content_warnings(&settings, &staged)?;
+        enforce_library_quota(&pool, &settings, &staged).await?;
         let file_rows = to_db_file_rows(&staged);
+        let cleanup = TrackedArtifacts::track("tool create", planned_paths(&app, &file_rows)?);
+        write_staged_files(&app, &staged, storage_cipher(&settings)?.as_ref(), &[])?;
 
-        let db_result = db::create_tool_with_version(
+        db::create_tool_with_version(
             &pool,
             &tool_id,
             &version_id,
@@ -147,16 +420,14 @@ pub async fn tool_create(
             },
             &file_rows,
         )
-        .await;
-
-        if let Err(error) = db_result {
-            remove_written_files(&written);
-            return Err(error);
-        }
+        .await?;
+        cleanup.keep();
 
         Ok(ToolCreateResult {
             tool_id,
             version_id,
+            warnings,
+            pruned_versions: Vec::new(),
         })
     })
     .await
@@ -175,12 +446,22 @@ pub async fn tool_add_version(
         }
 
         let version_id = Uuid::new_v4().to_string();
+        let settings = settings::load_settings(&pool).await?;
         let staged =
             stage_inbound_files(&tool_id, &version_id, request.files, &FileLimits::default())?;
-        let written = write_staged_files(&app, &staged)?;
+        let warnings = staged_content_warnings(&settings, &staged)?;
+        enforce_library_quota(&pool, &settings, &staged).await?;
+        let existing = db::list_stored_files_for_tool(&pool, &tool_id).await?;
         let file_rows = to_db_file_rows(&staged);
+        let cleanup = TrackedArtifacts::track("version add", planned_paths(&app, &file_rows)?);
+        write_staged_files(
+            &app,
+            &staged,
+            storage_cipher(&settings)?.as_ref(),
+            &existing,
+        )?;
 
-        let db_result = db::add_version_with_files(
+        let pruned_versions = db::add_version_with_files(
             &pool,
             &tool_id,
             &version_id,
@@ -191,26 +472,162 @@ pub async fn tool_add_version(
             },
             &file_rows,
         )
-        .await;
+        .await?;
+        cleanup.keep();
+        delete_pruned_folders(&app, &tool_id, &pruned_versions)?;
+
+        Ok(ToolCreateResult {
+            tool_id,
+            version_id,
+            warnings,
+            pruned_versions,
+        })
+    })
+    .await
+}
 
-        if let Err(error) = db_result {
-            remove_written_files(&written);
-            return Err(error);
+/// Creates a version from `base_version_id`'s files with `changed_files` applied and
+/// `removed_names` dropped. Unchanged files are copied on disk instead of being uploaded again;
+/// the result is an ordinary version that does not depend on the base.
+#[tauri::command]
+pub async fn tool_add_version_from(
+    app: AppHandle,
+    tool_id: String,
+    base_version_id: String,
+    changed_files: Vec<InboundToolFile>,
+    removed_names: Vec<String>,
+    instructions: String,
+    version: String,
+) -> Result<ToolCreateResult, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let tool_id = tool_id.trim().to_string();
+        let base = db::get_export_context(&pool, base_version_id.trim()).await?;
+        if base.version.tool_id != tool_id {
+            return Err(ToolsError::NotFound(
+                "Base version not found for this tool.".to_string(),
+            ));
         }
 
+        let version_id = Uuid::new_v4().to_string();
+        let settings = settings::load_settings(&pool).await?;
+        let planned = derived_version::plan_derived_files(
+            &tool_id,
+            &version_id,
+            &base.files,
+            changed_files,
+            &removed_names,
+            &FileLimits::default(),
+        )?;
+        let warnings = staged_content_warnings(&settings, &planned.staged)?;
+        enforce_library_quota_bytes(&pool, &settings, planned.total_bytes()).await?;
+        let existing = db::list_stored_files_for_tool(&pool, &tool_id).await?;
+        let file_rows = planned.file_rows();
+        let cleanup =
+            TrackedArtifacts::track("derived version add", planned_paths(&app, &file_rows)?);
+        write_staged_files(
+            &app,
+            &planned.staged,
+            storage_cipher(&settings)?.as_ref(),
+            &existing,
+        )?;
+        derived_version::copy_carried_files(&app, &planned.carried)?;
+
+        let pruned_versions = db::add_version_with_files(
+            &pool,
+            &tool_id,
+            &version_id,
+            VersionInsertInput {
+                version,
+                changelog_md: None,
+                instructions_md: instructions,
+            },
+            &file_rows,
+        )
+        .await?;
+        cleanup.keep();
+        delete_pruned_folders(&app, &tool_id, &pruned_versions)?;
+        debug!(
+            "custom-tools: added version {} from base {} ({} uploaded, {} carried)",
+            version_id,
+            base.version.id,
+            planned.staged.len(),
+            planned.carried.len()
+        );
+
         Ok(ToolCreateResult {
             tool_id,
             version_id,
+            warnings,
+            pruned_versions,
         })
     })
     .await
 }
 
 #[tauri::command]
-pub async fn tool_delete(app: AppHandle, tool_id: String) -> Result<(), String> {
+pub async fn tool_set_icon(
+    app: AppHandle,
+    tool_id: String,
+    file: InboundToolFile,
+) -> Result<ToolDetail, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let tool_id = tool_id.trim();
+        let icon = stage_tool_icon(tool_id, file)?;
+        let icon_path = storage::resolve_icon_path(&app, &icon.stored_rel_path)?;
+        if let Some(parent) = icon_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write to a sibling temp file so a failed update keeps the old icon intact.
+        let temp_path = icon_path.with_extension("tmp");
+        fs::write(&temp_path, &icon.bytes)?;
+        let previous = match db::set_tool_icon(&pool, tool_id, &icon.stored_rel_path).await {
+            Ok(previous) => previous,
+            Err(error) => {
+                let _ = fs::remove_file(&temp_path);
+                return Err(error);
+            }
+        };
+        fs::rename(&temp_path, &icon_path)?;
+
+        if let Some(previous) = previous.filter(|value| value != &icon.stored_rel_path) {
+            if let Ok(previous_path) = storage::resolve_icon_path(&app, &previous) {
+                let _ = fs::remove_file(previous_path);
+            }
+        }
+        debug!("custom-tools: set icon for tool {}", tool_id);
+
+        let mut detail = db::get_tool_detail(&pool, tool_id).await?;
+        detail.icon_data_base64 = read_icon_base64(&app, detail.icon_rel_path.as_deref());
+        Ok(detail)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tool_request_delete(app: AppHandle, tool_id: String) -> Result<String, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        db::ensure_tool_exists(&pool, tool_id.trim()).await?;
+        Ok(delete_tokens::issue_delete_token(tool_id.trim()))
+    })
+    .await
+}
+
+/// Deletes the tool and its files. `confirm_token` must come from `tool_request_delete` for the
+/// same tool, so a misrouted call cannot remove a different tool.
+#[tauri::command]
+pub async fn tool_delete(
+    app: AppHandle,
+    tool_id: String,
+    confirm_token: String,
+) -> Result<(), String> {
     run(async {
         let pool = db::open_pool(&app).await?;
         let trimmed_tool_id = tool_id.trim();
+        delete_tokens::redeem_delete_token(trimmed_tool_id, &confirm_token)?;
         db::delete_tool(&pool, trimmed_tool_id).await?;
         debug!("custom-tools: deleted tool {}", trimmed_tool_id);
         delete_tool_folder(&app, trimmed_tool_id)
@@ -219,9 +636,14 @@ pub async fn tool_delete(app: AppHandle, tool_id: String) -> Result<(), String>
 }
 
 #[tauri::command]
-pub async fn tool_delete_version(app: AppHandle, tool_version_id: String) -> Result<(), String> {
+pub async fn tool_delete_version(
+    app: AppHandle,
+    tool_id: String,
+    tool_version_id: String,
+) -> Result<(), String> {
     run(async {
         let pool = db::open_pool(&app).await?;
+        let trimmed_tool_id = tool_id.trim();
         let trimmed_version_id = tool_version_id.trim();
         if trimmed_version_id.is_empty() {
             return Err(ToolsError::Validation(
@@ -229,159 +651,1316 @@ pub async fn tool_delete_version(app: AppHandle, tool_version_id: String) -> Res
             ));
         }
 
-        let tool_id = db::delete_tool_version(&pool, trimmed_version_id).await?;
+        db::delete_version(&pool, trimmed_tool_id, trimmed_version_id).await?;
         debug!(
             "custom-tools: deleted version {} for tool {}",
-            trimmed_version_id, tool_id
+            trimmed_version_id, trimmed_tool_id
         );
-        delete_version_folder(&app, &tool_id, trimmed_version_id)
+        delete_version_folder(&app, trimmed_tool_id, trimmed_version_id)
     })
     .await
 }
 
+/// Corrects a version's label; returns the label as stored.
 #[tauri::command]
-pub async fn tool_export_zip(
+pub async fn tool_rename_version(
     app: AppHandle,
-    tool_version_id: String,
-    destination_path: String,
-) -> Result<(), String> {
+    tool_id: String,
+    version_id: String,
+    new_version: String,
+) -> Result<String, String> {
     run(async {
+        let pool = db::open_pool(&app).await?;
+        let label =
+            db::rename_version(&pool, tool_id.trim(), version_id.trim(), &new_version).await?;
         debug!(
-            "custom-tools: exporting version {} to {}",
-            tool_version_id.trim(),
-            destination_path.trim()
+            "custom-tools: version {} of tool {} is now {}",
+            version_id.trim(),
+            tool_id.trim(),
+            label
         );
-        let pool = db::open_pool(&app).await?;
-        let context = db::get_export_context(&pool, tool_version_id.trim()).await?;
-        let result = zip::export_tool_version_zip(&app, &context, destination_path.trim());
-        if result.is_ok() {
-            debug!(
-                "custom-tools: exported version {} successfully",
-                tool_version_id.trim()
-            );
-        }
-        result
+        Ok(label)
     })
     .await
 }
 
 #[tauri::command]
-pub async fn tool_export_zip_payload(
+pub async fn tool_prune_versions(
     app: AppHandle,
-    tool_version_id: String,
-) -> Result<ExportZipPayload, String> {
+    tool_id: String,
+    keep: usize,
+) -> Result<Vec<PrunedVersion>, String> {
     run(async {
-        debug!(
-            "custom-tools: exporting payload for version {}",
-            tool_version_id.trim()
-        );
         let pool = db::open_pool(&app).await?;
-        let context = db::get_export_context(&pool, tool_version_id.trim()).await?;
-
-        let temp_zip_path =
-            std::env::temp_dir().join(format!("tool-export-{}.zip", Uuid::new_v4()));
-        zip::export_tool_version_zip(&app, &context, temp_zip_path.to_string_lossy().as_ref())?;
-
-        let bytes = fs::read(&temp_zip_path)?;
-        let _ = fs::remove_file(&temp_zip_path);
-
-        let file_name = format!(
-            "{}-{}.zip",
-            context.tool.slug,
-            context.version.version.replace('.', "_")
+        let trimmed_tool_id = tool_id.trim();
+        let pruned = db::prune_versions(&pool, trimmed_tool_id, keep).await?;
+        debug!(
+            "custom-tools: pruned {} versions for tool {} keeping {}",
+            pruned.len(),
+            trimmed_tool_id,
+            keep
         );
-
-        Ok(ExportZipPayload {
-            file_name,
-            data_base64: base64::engine::general_purpose::STANDARD.encode(bytes),
-        })
+        for version in &pruned {
+            delete_version_folder(&app, trimmed_tool_id, &version.id)?;
+        }
+        Ok(pruned)
     })
     .await
 }
 
 #[tauri::command]
-pub async fn tool_preview_import_zip_payload(
-    payload: ZipPayloadRequest,
-) -> Result<ToolImportPreview, String> {
+pub async fn tools_tag_cooccurrence(
+    app: AppHandle,
+    tag: String,
+) -> Result<Vec<TagCooccurrence>, String> {
     run(async {
-        debug!(
-            "custom-tools: preview import payload {}",
-            payload.file_name.trim()
-        );
-        let parsed = zip::import_tool_zip_payload(&payload.file_name, &payload.data_base64)?;
-        let slug =
-            parsed.metadata.slug.clone().ok_or_else(|| {
-                ToolsError::Validation("Manifest tool.slug is required.".to_string())
-            })?;
-
-        let mut total_size_bytes = 0u64;
-        let files = parsed
-            .files
-            .iter()
-            .map(|file| {
-                let size_bytes = file.bytes.len() as u64;
-                total_size_bytes += size_bytes;
-                ToolImportPreviewFile {
-                    original_name: file.original_name.clone(),
-                    size_bytes,
-                    sha256: sha256_hex(&file.bytes),
-                }
-            })
-            .collect::<Vec<_>>();
-
-        Ok(ToolImportPreview {
-            tool_name: parsed.metadata.name,
-            slug,
-            version: parsed.version.version,
-            files,
-            total_size_bytes,
-            warnings: Vec::new(),
-        })
+        let pool = db::open_pool(&app).await?;
+        db::tag_cooccurrence(&pool, &tag, TAG_COOCCURRENCE_LIMIT).await
     })
     .await
 }
 
 #[tauri::command]
-pub async fn tool_import_zip_payload(
+pub async fn tool_file_set_note(
     app: AppHandle,
-    payload: ZipPayloadRequest,
-) -> Result<ToolImportResult, String> {
+    tool_version_id: String,
+    file_id: String,
+    note: Option<String>,
+) -> Result<Option<String>, String> {
     run(async {
-        debug!(
-            "custom-tools: importing payload {}",
-            payload.file_name.trim()
-        );
-        let parsed = zip::import_tool_zip_payload(&payload.file_name, &payload.data_base64)?;
-        let result = import_parsed_archive(&app, parsed).await?;
-        debug!(
-            "custom-tools: imported payload into tool {}, version {}, created_tool={}",
-            result.tool_id, result.version_id, result.created_tool
-        );
-        Ok(result)
+        let pool = db::open_pool(&app).await?;
+        db::set_file_note(
+            &pool,
+            tool_version_id.trim(),
+            file_id.trim(),
+            note.as_deref(),
+        )
+        .await
     })
     .await
 }
 
+/// Renames a stored file, moving it to the stored path of the sanitized new name. The stored
+/// bytes and hash are unchanged.
 #[tauri::command]
-pub async fn tool_import_zip(app: AppHandle, zip_path: String) -> Result<ToolImportResult, String> {
+pub async fn tool_rename_file(
+    app: AppHandle,
+    file_id: String,
+    new_name: String,
+) -> Result<String, String> {
     run(async {
-        debug!("custom-tools: importing zip {}", zip_path.trim());
-        let parsed = zip::import_tool_zip(zip_path.trim())?;
-        let result = import_parsed_archive(&app, parsed).await?;
-        debug!(
-            "custom-tools: imported zip into tool {}, version {}, created_tool={}",
-            result.tool_id, result.version_id, result.created_tool
-        );
-        Ok(result)
-    })
-    .await
+        let pool = db::open_pool(&app).await?;
+        let record = db::get_file_path_record(&pool, file_id.trim()).await?;
+        let sanitized = sanitize_filename(&new_name)?;
+        let stored_rel_path =
+            build_stored_rel_path(&record.tool_id, &record.tool_version_id, &sanitized)?;
+        if sanitized == record.original_name && stored_rel_path == record.stored_rel_path {
+            return Ok(sanitized);
+        }
+
+        let base_dir = storage::storage_base_dir(&app)?;
+        db::rename_file(
+            &pool,
+            &record.file_id,
+            &sanitized,
+            &stored_rel_path,
+            || storage::move_stored_file_in(&base_dir, &record.stored_rel_path, &stored_rel_path),
+            || {
+                let _ = storage::move_stored_file_in(
+                    &base_dir,
+                    &stored_rel_path,
+                    &record.stored_rel_path,
+                );
+            },
+        )
+        .await?;
+        debug!(
+            "custom-tools: renamed file {} from {} to {}",
+            record.file_id, record.original_name, sanitized
+        );
+        Ok(sanitized)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tool_file_get_note(
+    app: AppHandle,
+    tool_version_id: String,
+    file_id: String,
+) -> Result<Option<String>, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        db::get_file_note(&pool, tool_version_id.trim(), file_id.trim()).await
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tool_set_version_limit(
+    app: AppHandle,
+    tool_id: String,
+    max_versions: Option<i64>,
+    mode: Option<VersionLimitMode>,
+) -> Result<(), String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let trimmed_tool_id = tool_id.trim();
+        db::set_version_limit(
+            &pool,
+            trimmed_tool_id,
+            max_versions,
+            mode.unwrap_or_default(),
+        )
+        .await?;
+        debug!(
+            "custom-tools: set version limit for tool {} to {:?}",
+            trimmed_tool_id, max_versions
+        );
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tool_export_zip(
+    app: AppHandle,
+    tool_version_id: String,
+    destination_path: String,
+    include_checksums: Option<bool>,
+    compression: Option<CompressionLevel>,
+    format: Option<ArchiveFormat>,
+    password: Option<String>,
+) -> Result<ExportSummary, String> {
+    run(async {
+        debug!(
+            "custom-tools: exporting version {} to {}",
+            tool_version_id.trim(),
+            destination_path.trim()
+        );
+        let pool = db::open_pool(&app).await?;
+        let settings = settings::load_settings(&pool).await?;
+        let context = db::get_export_context(&pool, tool_version_id.trim()).await?;
+        let result = zip::export_tool_version_zip(
+            &app,
+            &context,
+            destination_path.trim(),
+            &ExportOptions {
+                include_checksums: include_checksums.unwrap_or(false),
+                compression: compression.unwrap_or_default(),
+                format: format.unwrap_or_default(),
+                password,
+                exported_at: None,
+                staging_root: settings.staging_root.clone(),
+            },
+            None,
+        );
+        if result.is_ok() {
+            debug!(
+                "custom-tools: exported version {} successfully",
+                tool_version_id.trim()
+            );
+        }
+        result
+    })
+    .await
+}
+
+/// Exports several versions into one directory, naming each archive from `name_template`.
+/// Every destination is resolved and checked before anything is written.
+#[tauri::command]
+pub async fn tools_export_many(
+    app: AppHandle,
+    version_ids: Vec<String>,
+    directory: String,
+    name_template: Option<String>,
+    include_checksums: Option<bool>,
+    force: Option<bool>,
+) -> Result<Vec<String>, String> {
+    run(async {
+        let directory = directory.trim();
+        if directory.is_empty() {
+            return Err(ToolsError::Validation(
+                "Export directory is required.".to_string(),
+            ));
+        }
+        let template = name_template
+            .as_deref()
+            .filter(|template| !template.trim().is_empty())
+            .unwrap_or(zip::DEFAULT_EXPORT_NAME_TEMPLATE);
+        let date = zip::utc_date((db::now_epoch_millis()? / 1000).max(0) as u64);
+        let pool = db::open_pool(&app).await?;
+        let settings = settings::load_settings(&pool).await?;
+
+        let mut exports = Vec::with_capacity(version_ids.len());
+        let mut seen = std::collections::HashSet::new();
+        for version_id in &version_ids {
+            let context = db::get_export_context(&pool, version_id.trim()).await?;
+            let destination =
+                zip::resolve_export_destination(Path::new(directory), template, &context, &date)?;
+            if !seen.insert(destination.clone()) {
+                return Err(ToolsError::Conflict(format!(
+                    "Name template gives more than one export the name {}.",
+                    destination.to_string_lossy()
+                )));
+            }
+            if destination.exists() && !force.unwrap_or(false) {
+                return Err(ToolsError::Conflict(format!(
+                    "{} already exists. Export again with force to overwrite it.",
+                    destination.to_string_lossy()
+                )));
+            }
+            exports.push((context, destination));
+        }
+
+        let options = ExportOptions {
+            include_checksums: include_checksums.unwrap_or(false),
+            staging_root: settings.staging_root,
+            ..ExportOptions::default()
+        };
+        let mut written = Vec::with_capacity(exports.len());
+        for (context, destination) in &exports {
+            let destination = destination.to_string_lossy().to_string();
+            zip::export_tool_version_zip(&app, context, &destination, &options, None)?;
+            written.push(destination);
+        }
+        debug!(
+            "custom-tools: exported {} versions to {}",
+            written.len(),
+            directory
+        );
+        Ok(written)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tool_export_bundle(
+    app: AppHandle,
+    tool_version_id: String,
+    destination_path: String,
+) -> Result<String, String> {
+    run(async {
+        debug!(
+            "custom-tools: exporting bundle for version {} to {}",
+            tool_version_id.trim(),
+            destination_path.trim()
+        );
+        let pool = db::open_pool(&app).await?;
+        let context = db::get_export_context(&pool, tool_version_id.trim()).await?;
+        let bundle_path =
+            bundle::export_tool_version_bundle(&app, &context, destination_path.trim())?;
+        Ok(bundle_path.to_string_lossy().to_string())
+    })
+    .await
+}
+
+/// Exports a zip with the version files plus a PowerShell installer for AutoCAD workstations.
+#[tauri::command]
+pub async fn tool_export_install_package(
+    app: AppHandle,
+    tool_version_id: String,
+    destination_path: String,
+) -> Result<String, String> {
+    run(async {
+        debug!(
+            "custom-tools: exporting install package for version {} to {}",
+            tool_version_id.trim(),
+            destination_path.trim()
+        );
+        let pool = db::open_pool(&app).await?;
+        let settings = settings::load_settings(&pool).await?;
+        let context = db::get_export_context(&pool, tool_version_id.trim()).await?;
+        let package_path = install_package::export_install_package(
+            &app,
+            &context,
+            destination_path.trim(),
+            settings.staging_root.as_deref(),
+        )?;
+        Ok(package_path.to_string_lossy().to_string())
+    })
+    .await
+}
+
+/// Writes one row per tool (CSV or JSON) for reporting outside the app.
+#[tauri::command]
+pub async fn tools_export_catalog(
+    app: AppHandle,
+    format: CatalogFormat,
+    destination_path: String,
+) -> Result<String, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let entries = db::list_catalog_entries(&pool).await?;
+        let path = catalog::export_catalog(&entries, format, &destination_path)?;
+        debug!(
+            "custom-tools: exported catalog of {} tools to {}",
+            entries.len(),
+            path.to_string_lossy()
+        );
+        Ok(path.to_string_lossy().to_string())
+    })
+    .await
+}
+
+/// Exports every version of a tool into one archive; see `zip::export_tool_zip` for the layout.
+#[tauri::command]
+pub async fn tool_export_full_zip(
+    app: AppHandle,
+    tool_id: String,
+    destination_path: String,
+    include_checksums: Option<bool>,
+    compression: Option<CompressionLevel>,
+    format: Option<ArchiveFormat>,
+    password: Option<String>,
+) -> Result<(), String> {
+    run(async {
+        debug!(
+            "custom-tools: exporting all versions of tool {} to {}",
+            tool_id.trim(),
+            destination_path.trim()
+        );
+        let pool = db::open_pool(&app).await?;
+        let settings = settings::load_settings(&pool).await?;
+        let context = db::get_full_export_context(&pool, tool_id.trim()).await?;
+        zip::export_tool_zip(
+            &app,
+            &context,
+            destination_path.trim(),
+            &ExportOptions {
+                include_checksums: include_checksums.unwrap_or(false),
+                compression: compression.unwrap_or_default(),
+                format: format.unwrap_or_default(),
+                password,
+                exported_at: None,
+                staging_root: settings.staging_root.clone(),
+            },
+            false,
+        )
+    })
+    .await
+}
+
+/// Exports a tool's whole history: every version plus `lineage.json`, which records version
+/// order, creation times and the files each version changed. Imports like any whole-tool archive.
+#[tauri::command]
+pub async fn tool_export_full_history(
+    app: AppHandle,
+    tool_id: String,
+    destination_path: String,
+) -> Result<(), String> {
+    run(async {
+        debug!(
+            "custom-tools: exporting history of tool {} to {}",
+            tool_id.trim(),
+            destination_path.trim()
+        );
+        let pool = db::open_pool(&app).await?;
+        let settings = settings::load_settings(&pool).await?;
+        let context = db::get_full_export_context(&pool, tool_id.trim()).await?;
+        zip::export_tool_zip(
+            &app,
+            &context,
+            destination_path.trim(),
+            &ExportOptions {
+                include_checksums: true,
+                staging_root: settings.staging_root,
+                ..ExportOptions::default()
+            },
+            true,
+        )
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tool_export_zip_payload(
+    app: AppHandle,
+    window: Window,
+    tool_version_id: String,
+    include_checksums: Option<bool>,
+    compression: Option<CompressionLevel>,
+    format: Option<ArchiveFormat>,
+    password: Option<String>,
+) -> Result<ExportZipPayload, String> {
+    run(async {
+        debug!(
+            "custom-tools: exporting payload for version {}",
+            tool_version_id.trim()
+        );
+        let pool = db::open_pool(&app).await?;
+        let settings = settings::load_settings(&pool).await?;
+        let context = db::get_export_context(&pool, tool_version_id.trim()).await?;
+        let format = format.unwrap_or_default();
+
+        let temp_zip_path = zip::resolve_staging_root(settings.staging_root.as_deref()).join(
+            format!("tool-export-{}.{}", Uuid::new_v4(), format.extension()),
+        );
+        zip::export_tool_version_zip(
+            &app,
+            &context,
+            temp_zip_path.to_string_lossy().as_ref(),
+            &ExportOptions {
+                include_checksums: include_checksums.unwrap_or(false),
+                compression: compression.unwrap_or_default(),
+                format,
+                password,
+                exported_at: None,
+                staging_root: settings.staging_root.clone(),
+            },
+            Some(&|progress| {
+                let _ = window.emit(zip::EXPORT_PROGRESS_EVENT, progress);
+            }),
+        )?;
+
+        let bytes = fs::read(&temp_zip_path)?;
+        let _ = fs::remove_file(&temp_zip_path);
+
+        let file_name = format!(
+            "{}-{}.{}",
+            context.tool.slug,
+            context.version.version.replace('.', "_"),
+            format.extension()
+        );
+
+        Ok(ExportZipPayload {
+            file_name,
+            data_base64: base64::engine::general_purpose::STANDARD.encode(bytes),
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tool_preview_import_zip_payload(
+    app: AppHandle,
+    payload: ZipPayloadRequest,
+) -> Result<ToolImportPreview, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let settings = settings::load_settings(&pool).await?;
+        debug!(
+            "custom-tools: preview import payload {}",
+            payload.file_name.trim()
+        );
+        // Lists every broken file at once; committing the plan still fails until they are fixed.
+        let options = ImportOptions {
+            collect_file_problems: true,
+            ..payload.import_options(&settings)?
+        };
+        let (mut preview, parsed) =
+            zip::verify_tool_zip_payload(&payload.file_name, &payload.data_base64, &options)?;
+        for file in &parsed.files {
+            preview.warnings.extend(content_warnings(
+                &settings,
+                &file.original_name,
+                &file.bytes,
+            )?);
+        }
+        preview.token = Some(import_plans::store_import_plan(parsed));
+        Ok(preview)
+    })
+    .await
+}
+
+/// Runs the import's manifest, size and hash checks on an archive without staging files or
+/// touching the database.
+#[tauri::command]
+pub async fn tool_verify_zip(
+    app: AppHandle,
+    zip_path: String,
+    trust_manifest: Option<bool>,
+    verify_checksums: Option<bool>,
+    password: Option<String>,
+) -> Result<ToolImportPreview, String> {
+    run(async {
+        debug!("custom-tools: verifying zip {}", zip_path.trim());
+        let pool = db::open_pool(&app).await?;
+        let settings = settings::load_settings(&pool).await?;
+        let options = ImportOptions {
+            password,
+            ..import_options(
+                &settings,
+                trust_manifest.unwrap_or(false),
+                verify_checksums.unwrap_or(false),
+            )
+        };
+        zip::verify_tool_zip(zip_path.trim(), &options)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tool_import_diff(
+    app: AppHandle,
+    token: String,
+    version_id: Option<String>,
+) -> Result<ToolImportDiff, String> {
+    run(async {
+        let parsed = import_plans::import_plan(&token)?;
+        let slug = parsed.metadata.slug.clone().unwrap_or_default();
+        let pool = db::open_pool(&app).await?;
+        let tool_id = db::find_tool_by_slug(&pool, &slug)
+            .await?
+            .map(|found| found.tool_id)
+            .ok_or_else(|| {
+                ToolsError::NotFound(format!("No existing tool uses the slug {slug}."))
+            })?;
+
+        let version_id = match version_id {
+            Some(version_id) => version_id,
+            None => {
+                db::get_latest_instructions(&pool, &tool_id)
+                    .await?
+                    .version_id
+            }
+        };
+        let existing = db::get_export_context(&pool, &version_id).await?;
+        if existing.version.tool_id != tool_id {
+            return Err(ToolsError::Validation(format!(
+                "Version {} does not belong to {slug}.",
+                existing.version.version
+            )));
+        }
+
+        let diff = import_plans::diff_import(&parsed, &existing);
+        debug!(
+            "custom-tools: import diff tool_id={} version_id={} added={} removed={} changed={}",
+            diff.tool_id,
+            diff.version_id,
+            diff.added.len(),
+            diff.removed.len(),
+            diff.changed.len()
+        );
+        Ok(diff)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tool_import_zip_payload(
+    app: AppHandle,
+    window: Window,
+    payload: ZipPayloadRequest,
+) -> Result<ToolImportResult, String> {
+    let started = Instant::now();
+    run(async {
+        debug!(
+            "custom-tools: importing payload {}",
+            payload.file_name.trim()
+        );
+        let pool = db::open_pool(&app).await?;
+        let settings = settings::load_settings(&pool).await?;
+        let options = payload.import_options(&settings)?;
+        let archives = zip::import_tool_zip_payload(
+            &payload.file_name,
+            &payload.data_base64,
+            &options,
+            Some(&|progress| {
+                let _ = window.emit(zip::IMPORT_PROGRESS_EVENT, progress);
+            }),
+        )?;
+        let result =
+            import_parsed_archives(&app, &pool, archives, &options.limits, started).await?;
+        debug!(
+            "custom-tools: imported payload into tool {}, version {}, created_tool={} files={} bytes={} duration_ms={}",
+            result.tool_id,
+            result.version_id,
+            result.created_tool,
+            result.file_count.unwrap_or_default(),
+            result.total_bytes.unwrap_or_default(),
+            result.duration_ms.unwrap_or_default()
+        );
+        Ok(result)
+    })
+    .await
+}
+
+/// Imports every tool of a bundle archive. Tools import independently, so a failed tool is
+/// reported in its entry and the others still land.
+#[tauri::command]
+pub async fn tool_import_bundle_zip(
+    app: AppHandle,
+    zip_path: String,
+    trust_manifest: Option<bool>,
+    verify_checksums: Option<bool>,
+    password: Option<String>,
+) -> Result<Vec<ToolBundleImportResult>, String> {
+    let started = Instant::now();
+    run(async {
+        debug!("custom-tools: importing bundle zip {}", zip_path.trim());
+        let pool = db::open_pool(&app).await?;
+        let settings = settings::load_settings(&pool).await?;
+        let options = ImportOptions {
+            password,
+            ..import_options(
+                &settings,
+                trust_manifest.unwrap_or(false),
+                verify_checksums.unwrap_or(false),
+            )
+        };
+        let tools = zip::import_tool_bundle_zip(zip_path.trim(), &options)?;
+
+        let mut results = Vec::with_capacity(tools.len());
+        for tool in tools {
+            let outcome = match tool.versions {
+                Ok(archives) => {
+                    import_parsed_archives(&app, &pool, archives, &options.limits, started).await
+                }
+                Err(error) => Err(error),
+            };
+            results.push(match outcome {
+                Ok(result) => ToolBundleImportResult {
+                    folder: tool.folder,
+                    result: Some(result),
+                    error: None,
+                },
+                Err(error) => {
+                    warn!(
+                        "custom-tools: bundle tool {} failed to import: {}",
+                        tool.folder,
+                        error.user_message()
+                    );
+                    ToolBundleImportResult {
+                        folder: tool.folder,
+                        result: None,
+                        error: Some(error.user_message()),
+                    }
+                }
+            });
+        }
+        debug!(
+            "custom-tools: imported bundle zip tools={} failed={} duration_ms={}",
+            results.len(),
+            results.iter().filter(|entry| entry.error.is_some()).count(),
+            started.elapsed().as_millis()
+        );
+        checkpoint_after_bulk_write(&pool).await;
+        Ok(results)
+    })
+    .await
+}
+
+/// Lists everything inside an archive, including entries import would reject, without
+/// importing it.
+#[tauri::command]
+pub async fn tool_inspect_archive(
+    app: AppHandle,
+    zip_path: String,
+) -> Result<ArchiveInventory, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let settings = settings::load_settings(&pool).await?;
+        let limits = ExtractionLimits::for_import(&import_options(&settings, false, false));
+        archive_inspect::inspect_archive(&zip_path, &limits)
+    })
+    .await
+}
+
+// Each argument is a separate key of the frontend's invoke payload.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn tool_import_zip(
+    app: AppHandle,
+    zip_path: String,
+    trust_manifest: Option<bool>,
+    verify_checksums: Option<bool>,
+    password: Option<String>,
+    allow_bare_file: Option<bool>,
+    bare_file: Option<BareFileDetails>,
+    quarantine_on_failure: Option<bool>,
+) -> Result<ToolImportResult, String> {
+    let started = Instant::now();
+    run(async {
+        debug!("custom-tools: importing zip {}", zip_path.trim());
+        let pool = db::open_pool(&app).await?;
+        let settings = settings::load_settings(&pool).await?;
+        let quarantine_root = if quarantine_on_failure.unwrap_or(false) {
+            Some(storage::quarantine_root(&app)?)
+        } else {
+            None
+        };
+        let options = ImportOptions {
+            password,
+            allow_bare_file: allow_bare_file.unwrap_or(false),
+            bare_file,
+            quarantine_root,
+            ..import_options(
+                &settings,
+                trust_manifest.unwrap_or(false),
+                verify_checksums.unwrap_or(false),
+            )
+        };
+        let archives = zip::import_tool_zip(
+            zip_path.trim(),
+            &options,
+            None,
+        )?;
+        let result =
+            import_parsed_archives(&app, &pool, archives, &options.limits, started).await?;
+        debug!(
+            "custom-tools: imported zip into tool {}, version {}, created_tool={} files={} bytes={} duration_ms={}",
+            result.tool_id,
+            result.version_id,
+            result.created_tool,
+            result.file_count.unwrap_or_default(),
+            result.total_bytes.unwrap_or_default(),
+            result.duration_ms.unwrap_or_default()
+        );
+        Ok(result)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tool_verify_integrity(
+    app: AppHandle,
+    tool_id: String,
+) -> Result<IntegrityReport, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let tool_id = tool_id.trim();
+        db::get_tool_detail(&pool, tool_id).await?;
+        verify_tool(&pool, &storage::storage_base_dir(&app)?, tool_id).await
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tools_verify_all(app: AppHandle) -> Result<Vec<IntegrityReport>, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let base_dir = storage::storage_base_dir(&app)?;
+        let tool_ids = db::list_tool_ids(&pool).await?;
+
+        let mut reports = Vec::with_capacity(tool_ids.len());
+        for (index, tool_id) in tool_ids.iter().enumerate() {
+            reports.push(verify_tool(&pool, &base_dir, tool_id).await?);
+            let _ = app.emit(
+                INTEGRITY_PROGRESS_EVENT,
+                IntegrityProgress {
+                    processed: index + 1,
+                    total: tool_ids.len(),
+                    tool_id: tool_id.clone(),
+                },
+            );
+        }
+
+        debug!(
+            "custom-tools: verified {} tools, {} failed",
+            reports.len(),
+            reports
+                .iter()
+                .filter(|report| !report.failures.is_empty())
+                .count()
+        );
+        Ok(reports)
+    })
+    .await
+}
+
+/// Re-hashes every stored file in the library and lists the ones that are missing, unreadable
+/// or no longer match their recorded size and hash. Set `emit_progress` to receive
+/// `STORAGE_SCAN_PROGRESS_EVENT` while the scan runs.
+#[tauri::command]
+pub async fn tools_verify_storage(
+    app: AppHandle,
+    emit_progress: Option<bool>,
+) -> Result<Vec<IntegrityIssue>, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let files = db::list_stored_files(&pool).await?;
+        let emit_progress = emit_progress.unwrap_or(false);
+        let issues =
+            integrity::scan_stored_files(&storage::storage_base_dir(&app)?, &files, &|progress| {
+                if emit_progress {
+                    let _ = app.emit(STORAGE_SCAN_PROGRESS_EVENT, progress);
+                }
+            });
+        debug!(
+            "custom-tools: storage scan checked {} files, {} issues",
+            files.len(),
+            issues.len()
+        );
+        Ok(issues)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tools_migrate_legacy_db(app: AppHandle) -> Result<LegacyMigrationReport, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let settings = settings::load_settings(&pool).await?;
+        let mut report = LegacyMigrationReport::default();
+
+        for row in legacy::list_pending_legacy_tools(&pool).await? {
+            let import = match legacy::to_library_import(&row) {
+                Ok(import) => import,
+                Err(reason) => {
+                    report.skipped.push(LegacySkippedTool {
+                        legacy_tool_id: row.id,
+                        reason,
+                    });
+                    continue;
+                }
+            };
+
+            let name = import.metadata.name.clone();
+            match import_legacy_tool(&app, &pool, &settings, import).await {
+                Ok(tool_id) => {
+                    legacy::record_legacy_import(&pool, &row.id, &tool_id).await?;
+                    report.migrated.push(LegacyMigratedTool {
+                        legacy_tool_id: row.id,
+                        tool_id,
+                        name,
+                    });
+                }
+                Err(error) => report.skipped.push(LegacySkippedTool {
+                    legacy_tool_id: row.id,
+                    reason: error.user_message(),
+                }),
+            }
+        }
+
+        debug!(
+            "custom-tools: legacy migration migrated={} skipped={}",
+            report.migrated.len(),
+            report.skipped.len()
+        );
+        if !report.migrated.is_empty() {
+            checkpoint_after_bulk_write(&pool).await;
+        }
+        Ok(report)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tools_storage_root(app: AppHandle) -> Result<String, String> {
+    run(async {
+        Ok(storage::storage_base_dir(&app)?
+            .to_string_lossy()
+            .to_string())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tools_audit_paths(app: AppHandle) -> Result<PathAuditReport, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let rows = db::list_file_paths(&pool).await?;
+        let report = integrity::audit_stored_paths(&rows);
+        debug!(
+            "custom-tools: path audit checked {} files, {} issues",
+            report.checked_files,
+            report.issues.len()
+        );
+        Ok(report)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tools_find_duplicate_file_names(
+    app: AppHandle,
+) -> Result<Vec<DuplicateFileName>, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        db::find_duplicate_file_names(&pool).await
+    })
+    .await
+}
+
+/// Logs versions holding several files with the same name so they can be repaired. Runs once
+/// in the background at startup; failures are logged rather than blocking launch.
+pub fn report_duplicate_file_names_on_startup(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let result = async {
+            let pool = db::open_pool(&app).await?;
+            db::find_duplicate_file_names(&pool).await
+        }
+        .await;
+        match result {
+            Ok(duplicates) => {
+                for duplicate in duplicates {
+                    warn!(
+                        "custom-tools: version {} has {} files named {}",
+                        duplicate.tool_version_id, duplicate.file_count, duplicate.original_name
+                    );
+                }
+            }
+            Err(error) => warn!(
+                "custom-tools: duplicate file name check failed: {}",
+                error.user_message()
+            ),
+        }
+    });
+}
+
+/// Applies the extensions stored under `tools.extra_extensions` at startup. Until it finishes,
+/// and if the stored list is unreadable, only the built-in extensions are accepted.
+pub fn load_allowed_extensions_on_startup(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let result = async {
+            let pool = db::open_pool(&app).await?;
+            storage::set_allowed_extensions(&settings::load_extra_extensions(&pool).await?)
+        }
+        .await;
+        match result {
+            Ok(extra) => debug!("custom-tools: extra file extensions {:?}", extra),
+            Err(error) => warn!(
+                "custom-tools: stored file extensions ignored: {}",
+                error.user_message()
+            ),
+        }
+    });
+}
+
+/// Removes quarantined import folders older than `QUARANTINE_RETENTION`.
+pub fn purge_quarantine_on_startup(app: AppHandle) {
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = storage::quarantine_root(&app)
+            .and_then(|root| zip::purge_quarantine(&root, QUARANTINE_RETENTION));
+        match result {
+            Ok(0) => {}
+            Ok(removed) => debug!("custom-tools: purged {removed} quarantined import(s)"),
+            Err(error) => warn!(
+                "custom-tools: quarantine purge failed: {}",
+                error.user_message()
+            ),
+        }
+    });
+}
+
+/// Reconciles the cleanup ledger: artifacts from operations an earlier run never finished are
+/// removed, or only reported when `tools.startup_cleanup` is `report`. The leftover markers are
+/// snapshotted before returning so operations started afterwards are never mistaken for them.
+pub fn reconcile_cleanup_ledger_on_startup(app: AppHandle) {
+    let stale = match app.path().app_config_dir() {
+        Ok(dir) => cleanup_ledger::init(dir.join(CLEANUP_LEDGER_DIR)),
+        Err(error) => Err(ToolsError::Io(format!(
+            "Failed to resolve app config directory: {error}"
+        ))),
+    };
+    let stale = match stale {
+        Ok(stale) => stale,
+        Err(error) => {
+            warn!(
+                "custom-tools: cleanup ledger unavailable: {}",
+                error.user_message()
+            );
+            return;
+        }
+    };
+    if stale.is_empty() {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let (mode, referenced) = async {
+            let pool = db::open_pool(&app).await?;
+            let mode = settings::load_settings(&pool).await?.startup_cleanup;
+            let base_dir = storage::storage_base_dir(&app)?;
+            let referenced = referenced_rel_paths(&pool)
+                .await?
+                .iter()
+                .filter_map(|rel_path| storage::resolve_stored_path_in(&base_dir, rel_path).ok())
+                .collect::<Vec<_>>();
+            Ok::<_, ToolsError>((mode, referenced))
+        }
+        .await
+        .unwrap_or_else(|error| {
+            warn!(
+                "custom-tools: startup cleanup falls back to report only: {}",
+                error.user_message()
+            );
+            (StartupCleanupMode::Report, Vec::new())
+        });
+        cleanup_ledger::reconcile(&stale, mode == StartupCleanupMode::Remove, &referenced);
+        let report = cleanup_ledger::current_report();
+        debug!(
+            "custom-tools: startup cleanup markers={} reclaimed_bytes={} pending={}",
+            stale.len(),
+            report.reclaimed_bytes,
+            report.pending.len()
+        );
+    });
+}
+
+/// Stored file and icon paths that library rows reference.
+async fn referenced_rel_paths(pool: &sqlx::SqlitePool) -> ToolsResult<Vec<String>> {
+    let mut referenced = db::list_stored_files(pool)
+        .await?
+        .into_iter()
+        .map(|file| file.stored_rel_path)
+        .collect::<Vec<_>>();
+    referenced.extend(db::list_icon_paths(pool).await?);
+    Ok(referenced)
+}
+
+/// What the cleanup ledger reclaimed since launch, including leftovers of earlier runs.
+#[tauri::command]
+pub async fn tools_cleanup_report() -> Result<CleanupReport, String> {
+    Ok(cleanup_ledger::current_report())
+}
+
+/// Re-normalizes every tool's slug and tags. Runs as a dry run unless `apply` is true.
+#[tauri::command]
+pub async fn tools_normalize_all(
+    app: AppHandle,
+    apply: Option<bool>,
+) -> Result<NormalizationReport, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let report = db::normalize_all_tools(&pool, apply.unwrap_or(false)).await?;
+        debug!(
+            "custom-tools: normalization applied={} slug_changes={} tag_changes={} skipped={}",
+            report.applied,
+            report.slug_changes.len(),
+            report.tag_changes.len(),
+            report.skipped.len()
+        );
+        if report.applied {
+            checkpoint_after_bulk_write(&pool).await;
+        }
+        Ok(report)
+    })
+    .await
+}
+
+/// Admin command that checkpoints the WAL on demand, e.g. right before a file-level backup.
+#[tauri::command]
+pub async fn tools_checkpoint_db(app: AppHandle) -> Result<WalCheckpoint, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let checkpoint = db::checkpoint_wal(&pool).await?;
+        debug!(
+            "custom-tools: wal checkpoint busy={} wal_frames={} checkpointed_frames={}",
+            checkpoint.busy, checkpoint.wal_frames, checkpoint.checkpointed_frames
+        );
+        Ok(checkpoint)
+    })
+    .await
+}
+
+/// Lists file rows whose stored file no longer exists, e.g. after an external cleanup.
+#[tauri::command]
+pub async fn tools_find_missing_files(app: AppHandle) -> Result<Vec<MissingStoredFile>, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let rows = db::list_file_paths(&pool).await?;
+        let missing = integrity::find_missing_files(&storage::storage_base_dir(&app)?, &rows);
+        debug!(
+            "custom-tools: missing file scan checked {} files, {} missing",
+            rows.len(),
+            missing.len()
+        );
+        Ok(missing)
+    })
+    .await
+}
+
+/// Turns encryption at rest on or off for files written from now on. Enabling it first makes sure
+/// the keyring can hold the storage key; existing files stay readable either way.
+#[tauri::command]
+pub async fn tools_set_encryption(app: AppHandle, enabled: bool) -> Result<(), String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        if enabled {
+            let cipher = StorageCipher::load_or_create()?;
+            cipher.decrypt(&cipher.encrypt(b"keyring check")?)?;
+        }
+        settings::write_setting(
+            &pool,
+            settings::ENCRYPT_AT_REST_KEY,
+            if enabled { "true" } else { "false" },
+        )
+        .await?;
+        debug!("custom-tools: encryption at rest enabled={}", enabled);
+        Ok(())
+    })
+    .await
+}
+
+/// Lists every file extension the library accepts, built-in ones first.
+#[tauri::command]
+pub async fn tools_get_allowed_extensions() -> Result<Vec<String>, String> {
+    run(async { Ok(storage::allowed_extensions()) }).await
+}
+
+/// Replaces the extensions accepted on top of the built-in set and returns the full list.
+/// Executables and shell scripts are refused.
+#[tauri::command]
+pub async fn tools_set_allowed_extensions(
+    app: AppHandle,
+    extensions: Vec<String>,
+) -> Result<Vec<String>, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let extra = storage::normalize_extra_extensions(&extensions)?;
+        let stored = serde_json::to_string(&extra)
+            .map_err(|error| ToolsError::Validation(format!("Invalid file extensions: {error}")))?;
+        settings::write_setting(&pool, settings::EXTRA_EXTENSIONS_KEY, &stored).await?;
+        storage::set_allowed_extensions(&extra)?;
+        debug!("custom-tools: extra file extensions set to {:?}", extra);
+        Ok(storage::allowed_extensions())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tools_get_metadata_defaults(app: AppHandle) -> Result<ToolMetadataDefaults, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        settings::load_metadata_defaults(&pool).await
+    })
+    .await
+}
+
+/// Sets the category and tags applied to new tools that leave them empty. Pass no category and
+/// an empty list to clear both.
+#[tauri::command]
+pub async fn tools_set_metadata_defaults(
+    app: AppHandle,
+    category: Option<String>,
+    tags: Vec<String>,
+) -> Result<ToolMetadataDefaults, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let defaults = db::set_metadata_defaults(&pool, category, tags).await?;
+        debug!(
+            "custom-tools: metadata defaults category={:?} tags={}",
+            defaults.category,
+            defaults.tags.len()
+        );
+        Ok(defaults)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tools_storage_report(app: AppHandle) -> Result<StorageReport, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let settings = settings::load_settings(&pool).await?;
+        let used_bytes = db::total_stored_bytes(&pool).await?;
+        Ok(StorageReport {
+            root: storage::storage_base_dir(&app)?
+                .to_string_lossy()
+                .to_string(),
+            used_bytes,
+            quota_bytes: settings.library_quota_bytes,
+            remaining_bytes: settings
+                .library_quota_bytes
+                .map(|quota| quota.saturating_sub(used_bytes)),
+        })
+    })
+    .await
+}
+
+/// Measures what the library occupies on disk and flags files whose size disagrees with the
+/// library records, including unrecorded and missing files.
+#[tauri::command]
+pub async fn tools_storage_usage(app: AppHandle) -> Result<StorageUsage, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let records = db::list_stored_files(&pool).await?;
+        usage::measure_storage(&storage::storage_base_dir(&app)?, &records)
+    })
+    .await
+}
+
+/// Removes files under the tools root that no library row references, such as leftovers from
+/// failed writes. Files younger than `usage::GC_MIN_AGE` are kept. With `dry_run` the report
+/// only lists what would be removed.
+#[tauri::command]
+pub async fn tools_gc(app: AppHandle, dry_run: Option<bool>) -> Result<GcReport, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        // Taken before the rows are read: an operation that settles in between is then either
+        // still in this set or already committed.
+        let in_flight = cleanup_ledger::in_flight_paths();
+        let referenced = referenced_rel_paths(&pool).await?;
+        let report = usage::collect_garbage(
+            &storage::storage_base_dir(&app)?,
+            &referenced,
+            &in_flight,
+            usage::GC_MIN_AGE,
+            dry_run.unwrap_or(false),
+        )?;
+        debug!(
+            "custom-tools: storage gc dry_run={} orphans={} reclaimed_bytes={}",
+            report.dry_run,
+            report.removed_paths.len(),
+            report.reclaimed_bytes
+        );
+        Ok(report)
+    })
+    .await
+}
+
+/// `tools_storage_usage` for a single tool's folder.
+#[tauri::command]
+pub async fn tool_storage_usage(app: AppHandle, tool_id: String) -> Result<StorageUsage, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let tool_id = tool_id.trim();
+        db::get_tool_detail(&pool, tool_id).await?;
+        let records = db::list_stored_files_for_tool(&pool, tool_id).await?;
+        usage::measure_tool_storage(&storage::storage_base_dir(&app)?, tool_id, &records)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tools_migrate_storage(
+    app: AppHandle,
+    new_root: String,
+) -> Result<StorageMigrationReport, String> {
+    run(async {
+        let pool = db::open_pool(&app).await?;
+        let files = db::list_stored_files(&pool).await?;
+        let icon_paths = db::list_icon_paths(&pool).await?;
+        relocation::migrate_storage_root(&app, &new_root, &files, &icon_paths)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn tools_cancel_storage_migration() -> Result<(), String> {
+    relocation::request_cancel();
+    Ok(())
+}
+
+/// Keeps the WAL bounded after operations that write many rows. The work is already committed,
+/// so a failed checkpoint is only logged; SQLite retries at the next automatic checkpoint.
+async fn checkpoint_after_bulk_write(pool: &sqlx::SqlitePool) {
+    match db::checkpoint_wal(pool).await {
+        Ok(checkpoint) if checkpoint.busy => {
+            warn!("custom-tools: wal checkpoint after bulk write was blocked by another connection")
+        }
+        Ok(checkpoint) => debug!(
+            "custom-tools: wal checkpoint after bulk write checkpointed_frames={}",
+            checkpoint.checkpointed_frames
+        ),
+        Err(error) => warn!(
+            "custom-tools: wal checkpoint after bulk write failed: {}",
+            error.user_message()
+        ),
+    }
 }
 
 async fn import_parsed_archive(
     app: &AppHandle,
+    pool: &sqlx::SqlitePool,
     parsed: zip::ParsedImportArchive,
+    limits: &FileLimits,
+    started: Instant,
 ) -> ToolsResult<ToolImportResult> {
-    let pool = db::open_pool(app).await?;
+    parsed.ensure_no_file_problems()?;
+    let settings = settings::load_settings(pool).await?;
 
     let slug = parsed
         .metadata
@@ -389,75 +1968,277 @@ async fn import_parsed_archive(
         .clone()
         .ok_or_else(|| ToolsError::Validation("Manifest tool.slug is required.".to_string()))?;
 
-    let existing_tool_id = db::find_tool_id_by_slug(&pool, &slug).await?;
-    let (tool_id, version_id, created_tool) = if let Some(tool_id) = existing_tool_id {
-        if db::find_version_id(&pool, &tool_id, &parsed.version.version)
-            .await?
-            .is_some()
-        {
-            return Err(ToolsError::Conflict(
-                "A tool with this slug and version already exists. Import aborted.".to_string(),
-            ));
-        }
+    let version_label = parsed.version.version.clone();
+    let (mut tx, target) = db::begin_import(pool, &slug, &version_label).await?;
+    let notes = parsed
+        .files
+        .iter()
+        .filter_map(|file| Some((file.original_name.clone(), file.note.clone()?)))
+        .collect::<std::collections::HashMap<_, _>>();
+    let staged = stage_decoded_files(
+        &target.tool_id,
+        &target.version_id,
+        to_decoded_files(parsed.files),
+        limits,
+    )?;
+    let mut warnings = parsed.warnings;
+    warnings.extend(staged_content_warnings(&settings, &staged)?);
+    enforce_library_quota(pool, &settings, &staged).await?;
+    let existing = db::list_stored_files_for_tool(&mut *tx, &target.tool_id).await?;
+    let mut file_rows = to_db_file_rows(&staged);
+    for row in &mut file_rows {
+        row.note = notes.get(&row.original_name).cloned();
+    }
+    let cleanup = TrackedArtifacts::track("tool import", planned_paths(app, &file_rows)?);
+    write_staged_files(app, &staged, storage_cipher(&settings)?.as_ref(), &existing)?;
+    let pruned_versions =
+        db::commit_import(tx, &target, parsed.metadata, parsed.version, &file_rows).await?;
+    cleanup.keep();
+    delete_pruned_folders(app, &target.tool_id, &pruned_versions)?;
 
-        let version_id = Uuid::new_v4().to_string();
-        let staged = stage_inbound_files(
-            &tool_id,
-            &version_id,
-            to_inbound_files(parsed.files),
-            &FileLimits::default(),
-        )?;
-        let written = write_staged_files(app, &staged)?;
-        let db_result = db::add_version_with_files(
-            &pool,
-            &tool_id,
-            &version_id,
-            parsed.version,
-            &to_db_file_rows(&staged),
-        )
-        .await;
+    Ok(ToolImportResult {
+        tool_id: target.tool_id,
+        version_id: target.version_id,
+        created_tool: target.created_tool,
+        warnings,
+        duration_ms: Some(started.elapsed().as_millis() as u64),
+        total_bytes: Some(staged.iter().map(|file| file.size_bytes).sum()),
+        file_count: Some(staged.len()),
+        pruned_versions,
+        imported_versions: vec![version_label],
+        skipped_versions: Vec::new(),
+    })
+}
+
+/// Imports each parsed version in order. A single-version archive behaves exactly like
+/// `import_parsed_archive`; for whole-tool archives, versions the tool already has are skipped,
+/// so re-running an interrupted import picks up where it stopped.
+async fn import_parsed_archives(
+    app: &AppHandle,
+    pool: &sqlx::SqlitePool,
+    mut archives: Vec<zip::ParsedImportArchive>,
+    limits: &FileLimits,
+    started: Instant,
+) -> ToolsResult<ToolImportResult> {
+    if archives.len() == 1 {
+        return import_parsed_archive(app, pool, archives.remove(0), limits, started).await;
+    }
 
-        if let Err(error) = db_result {
-            remove_written_files(&written);
-            return Err(error);
+    let mut combined: Option<ToolImportResult> = None;
+    let mut skipped_versions = Vec::new();
+    for parsed in archives {
+        let slug = parsed.metadata.slug.clone().unwrap_or_default();
+        if let Some(tool_id) = db::find_tool_id_by_slug(pool, &slug).await? {
+            if db::find_version_id(pool, &tool_id, &parsed.version.version)
+                .await?
+                .is_some()
+            {
+                skipped_versions.push(parsed.version.version);
+                continue;
+            }
         }
 
-        (tool_id, version_id, false)
-    } else {
-        let tool_id = Uuid::new_v4().to_string();
-        let version_id = Uuid::new_v4().to_string();
-        let staged = stage_inbound_files(
-            &tool_id,
-            &version_id,
-            to_inbound_files(parsed.files),
-            &FileLimits::default(),
-        )?;
-        let written = write_staged_files(app, &staged)?;
-        let db_result = db::create_tool_with_version(
-            &pool,
-            &tool_id,
-            &version_id,
-            parsed.metadata,
-            parsed.version,
-            &to_db_file_rows(&staged),
+        let result = import_parsed_archive(app, pool, parsed, limits, started).await?;
+        combined = Some(match combined {
+            None => result,
+            Some(mut total) => {
+                total.version_id = result.version_id;
+                total.created_tool |= result.created_tool;
+                total.warnings.extend(result.warnings);
+                total.duration_ms = result.duration_ms;
+                total.total_bytes = Some(
+                    total.total_bytes.unwrap_or_default() + result.total_bytes.unwrap_or_default(),
+                );
+                total.file_count = Some(
+                    total.file_count.unwrap_or_default() + result.file_count.unwrap_or_default(),
+                );
+                total.pruned_versions.extend(result.pruned_versions);
+                total.imported_versions.extend(result.imported_versions);
+                total
+            }
+        });
+    }
+
+    let mut result = combined.ok_or_else(|| {
+        ToolsError::Conflict(
+            "Every version in the archive already exists. Import skipped.".to_string(),
         )
-        .await;
+    })?;
+    result.skipped_versions = skipped_versions;
+    Ok(result)
+}
 
-        if let Err(error) = db_result {
-            remove_written_files(&written);
-            return Err(error);
-        }
+fn delete_pruned_folders(
+    app: &AppHandle,
+    tool_id: &str,
+    pruned: &[PrunedVersion],
+) -> ToolsResult<()> {
+    if !pruned.is_empty() {
+        debug!(
+            "custom-tools: version limit pruned {} versions for tool {}",
+            pruned.len(),
+            tool_id
+        );
+    }
+    for version in pruned {
+        delete_version_folder(app, tool_id, &version.id)?;
+    }
+    Ok(())
+}
 
-        (tool_id, version_id, true)
-    };
+async fn import_legacy_tool(
+    app: &AppHandle,
+    pool: &sqlx::SqlitePool,
+    settings: &ToolsSettings,
+    import: legacy::LegacyToolImport,
+) -> ToolsResult<String> {
+    let tool_id = Uuid::new_v4().to_string();
+    let version_id = Uuid::new_v4().to_string();
+    let staged = stage_decoded_files(
+        &tool_id,
+        &version_id,
+        vec![DecodedToolFile {
+            original_name: import.file_name,
+            mime: Some("application/json".to_string()),
+            bytes: import.file_bytes,
+            sha256: None,
+        }],
+        &FileLimits::default(),
+    )?;
+    enforce_library_quota(pool, settings, &staged).await?;
+    let file_rows = to_db_file_rows(&staged);
+    let cleanup = TrackedArtifacts::track("legacy migration", planned_paths(app, &file_rows)?);
+    write_staged_files(app, &staged, storage_cipher(settings)?.as_ref(), &[])?;
 
-    Ok(ToolImportResult {
-        tool_id,
-        version_id,
-        created_tool,
+    db::create_tool_with_version(
+        pool,
+        &tool_id,
+        &version_id,
+        import.metadata,
+        import.version,
+        &file_rows,
+    )
+    .await?;
+    cleanup.keep();
+
+    Ok(tool_id)
+}
+
+async fn verify_tool(
+    pool: &sqlx::SqlitePool,
+    base_dir: &std::path::Path,
+    tool_id: &str,
+) -> ToolsResult<IntegrityReport> {
+    let files = db::list_stored_files_for_tool(pool, tool_id).await?;
+    let failures = integrity::verify_stored_files(base_dir, &files);
+    let checked_at = db::record_integrity_result(pool, tool_id, failures.len()).await?;
+
+    Ok(IntegrityReport {
+        tool_id: tool_id.to_string(),
+        status: if failures.is_empty() { "ok" } else { "failed" }.to_string(),
+        checked_files: files.len(),
+        failures,
+        checked_at,
     })
 }
 
+fn read_icon_base64(app: &AppHandle, icon_rel_path: Option<&str>) -> Option<String> {
+    let path = storage::resolve_icon_path(app, icon_rel_path?).ok()?;
+    let bytes = fs::read(path).ok()?;
+    Some(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+async fn enforce_library_quota(
+    pool: &sqlx::SqlitePool,
+    settings: &ToolsSettings,
+    staged: &[storage::StagedToolFile],
+) -> ToolsResult<()> {
+    let incoming_bytes = staged.iter().map(|file| file.size_bytes).sum::<u64>();
+    enforce_library_quota_bytes(pool, settings, incoming_bytes).await
+}
+
+async fn enforce_library_quota_bytes(
+    pool: &sqlx::SqlitePool,
+    settings: &ToolsSettings,
+    incoming_bytes: u64,
+) -> ToolsResult<()> {
+    if settings.library_quota_bytes.is_none() {
+        return Ok(());
+    }
+
+    let used_bytes = db::total_stored_bytes(pool).await?;
+    ensure_within_quota(used_bytes, incoming_bytes, settings.library_quota_bytes)
+}
+
+/// Strict manifest parsing is relaxed to warnings for archives the caller marks as trusted, so
+/// exports from a newer trusted build still import.
+fn import_options(
+    settings: &ToolsSettings,
+    trust_manifest: bool,
+    verify_checksums: bool,
+) -> ImportOptions {
+    let unknown_fields = match settings.manifest_unknown_fields {
+        ContentCheckMode::Reject if trust_manifest => ContentCheckMode::Warn,
+        mode => mode,
+    };
+    ImportOptions {
+        trust_manifest,
+        verify_checksums,
+        unknown_fields,
+        max_compression_ratio: settings.max_compression_ratio,
+        max_archive_size_bytes: None,
+        password: None,
+        limits: FileLimits::default(),
+        collect_file_problems: false,
+        allow_bare_file: false,
+        bare_file: None,
+        staging_root: settings.staging_root.clone(),
+        quarantine_root: None,
+    }
+}
+
+fn storage_cipher(settings: &ToolsSettings) -> ToolsResult<Option<StorageCipher>> {
+    settings
+        .encrypt_at_rest
+        .then(StorageCipher::load_or_create)
+        .transpose()
+}
+
+fn staged_content_warnings(
+    settings: &ToolsSettings,
+    staged: &[storage::StagedToolFile],
+) -> ToolsResult<Vec<String>> {
+    let mut warnings = Vec::new();
+    for file in staged {
+        warnings.extend(content_warnings(
+            settings,
+            &file.original_name,
+            &file.bytes,
+        )?);
+    }
+    Ok(warnings)
+}
+
+fn content_warnings(
+    settings: &ToolsSettings,
+    original_name: &str,
+    bytes: &[u8],
+) -> ToolsResult<Vec<String>> {
+    let checks = [
+        check_json_content(original_name, bytes, settings.json_validation)?,
+        check_magic_bytes(original_name, bytes, settings.magic_byte_validation)?,
+    ];
+    Ok(checks.into_iter().flatten().collect())
+}
+
+/// Where `rows` will be stored, tracked before the first byte is written so a crash mid-write
+/// still leaves a ledger entry for every partial file.
+fn planned_paths(app: &AppHandle, rows: &[FileRecordInsert]) -> ToolsResult<Vec<PathBuf>> {
+    rows.iter()
+        .map(|row| storage::resolve_stored_path(app, &row.stored_rel_path))
+        .collect()
+}
+
 fn to_db_file_rows(staged: &[storage::StagedToolFile]) -> Vec<FileRecordInsert> {
     staged
         .iter()
@@ -467,17 +2248,19 @@ fn to_db_file_rows(staged: &[storage::StagedToolFile]) -> Vec<FileRecordInsert>
             sha256: file.sha256.clone(),
             size_bytes: file.size_bytes as i64,
             mime: file.mime.clone(),
+            note: None,
         })
         .collect()
 }
 
-fn to_inbound_files(files: Vec<zip::ImportFileBytes>) -> Vec<InboundToolFile> {
+fn to_decoded_files(files: Vec<zip::ImportFileBytes>) -> Vec<DecodedToolFile> {
     files
         .into_iter()
-        .map(|file| InboundToolFile {
+        .map(|file| DecodedToolFile {
             original_name: file.original_name,
             mime: file.mime,
-            data_base64: base64::engine::general_purpose::STANDARD.encode(file.bytes),
+            bytes: file.bytes,
+            sha256: Some(file.sha256),
         })
         .collect()
 }
@@ -1,14 +1,102 @@
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
-use sqlx::{Row, SqlitePool};
+use sqlx::{Row, Sqlite, SqliteConnection, SqliteExecutor, SqlitePool, Transaction};
 use tauri::{AppHandle, Manager};
+use unicode_normalization::UnicodeNormalization;
 use uuid::Uuid;
 
 use super::error::{ToolsError, ToolsResult};
-use super::storage::{normalize_stored_rel_path, sanitize_filename};
+use super::settings::{self, ToolMetadataDefaults};
+use super::storage::{normalize_stored_rel_path, sanitize_filename, version_content_hash};
 
 pub const DB_FILE_NAME: &str = "korda_tools.db";
+pub const MAX_FILE_NOTE_CHARS: usize = 2_000;
+
+struct LibraryMigration {
+    version: i64,
+    description: &'static str,
+    sql: &'static str,
+    /// Data fill that SQL alone cannot express, run in the migration's transaction.
+    backfill: Option<LibraryBackfill>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum LibraryBackfill {
+    VersionHashes,
+}
+
+/// Library tables are created here rather than by the SQL plugin, so they are tracked separately.
+const LIBRARY_MIGRATIONS: &[LibraryMigration] = &[
+    LibraryMigration {
+        version: 12,
+        description: "create_custom_tool_library",
+        sql: include_str!("../../migrations/0012_create_custom_tool_library.sql"),
+        backfill: None,
+    },
+    LibraryMigration {
+        version: 13,
+        description: "harden_custom_tool_library",
+        sql: include_str!("../../migrations/0013_harden_custom_tool_library.sql"),
+        backfill: None,
+    },
+    // Library settings live in the shared app_state table.
+    LibraryMigration {
+        version: 14,
+        description: "create_help_center",
+        sql: include_str!("../../migrations/0014_create_help_center.sql"),
+        backfill: None,
+    },
+    LibraryMigration {
+        version: 15,
+        description: "create_tool_integrity",
+        sql: include_str!("../../migrations/0015_create_tool_integrity.sql"),
+        backfill: None,
+    },
+    LibraryMigration {
+        version: 16,
+        description: "create_legacy_tool_imports",
+        sql: include_str!("../../migrations/0016_create_legacy_tool_imports.sql"),
+        backfill: None,
+    },
+    LibraryMigration {
+        version: 17,
+        description: "create_tool_slug_aliases",
+        sql: include_str!("../../migrations/0017_create_tool_slug_aliases.sql"),
+        backfill: None,
+    },
+    LibraryMigration {
+        version: 18,
+        description: "add_tool_icons",
+        sql: include_str!("../../migrations/0018_add_tool_icons.sql"),
+        backfill: None,
+    },
+    LibraryMigration {
+        version: 19,
+        description: "add_tool_version_hashes",
+        sql: include_str!("../../migrations/0019_add_tool_version_hashes.sql"),
+        backfill: Some(LibraryBackfill::VersionHashes),
+    },
+    LibraryMigration {
+        version: 20,
+        description: "create_tool_views",
+        sql: include_str!("../../migrations/0020_create_tool_views.sql"),
+        backfill: None,
+    },
+    LibraryMigration {
+        version: 21,
+        description: "add_tool_version_limits",
+        sql: include_str!("../../migrations/0021_add_tool_version_limits.sql"),
+        backfill: None,
+    },
+    LibraryMigration {
+        version: 22,
+        description: "add_tool_file_notes",
+        sql: include_str!("../../migrations/0022_add_tool_file_notes.sql"),
+        backfill: None,
+    },
+];
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -16,17 +104,53 @@ pub struct ToolMetadataInput {
     pub name: String,
     pub slug: Option<String>,
     pub description: String,
+    /// Falls back to the configured default category when empty.
+    #[serde(default)]
     pub category: String,
+    /// Falls back to the configured default tags when empty.
     #[serde(default)]
     pub tags: Vec<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ToolListFilters {
     pub query: Option<String>,
     pub category: Option<String>,
     pub tag: Option<String>,
+    #[serde(default)]
+    pub order_by: ToolListOrder,
+    /// Only tools with at least one `ToolQualityIssue`.
+    #[serde(default)]
+    pub quality_issues: bool,
+}
+
+/// Categories that mean nobody picked one; `general` is what legacy imports and the UI default to.
+const PLACEHOLDER_CATEGORIES: &[&str] = &["general", "uncategorized"];
+
+/// What counts as blank text for `quality_issues`, in Rust and in the SQL filter alike. SQLite's
+/// `trim` strips only the characters it is given, so the set is spelled out for both.
+const BLANK_CHARS: [char; 6] = [' ', '\t', '\n', '\u{b}', '\u{c}', '\r'];
+
+fn is_blank(value: &str) -> bool {
+    value.trim_matches(BLANK_CHARS).is_empty()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ToolQualityIssue {
+    MissingDescription,
+    MissingTags,
+    PlaceholderCategory,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ToolListOrder {
+    #[default]
+    Updated,
+    /// Most recently opened first; tools that were never opened follow in `Updated` order.
+    RecentlyViewed,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -38,9 +162,22 @@ pub struct ToolSummary {
     pub description: String,
     pub category: String,
     pub tags: Vec<String>,
+    pub icon_rel_path: Option<String>,
+    pub icon_data_base64: Option<String>,
+    pub integrity: Option<IntegrityStatus>,
     pub created_at: i64,
     pub updated_at: i64,
+    pub last_viewed_at: Option<i64>,
     pub latest_version: Option<VersionSummary>,
+    pub quality_issues: Vec<ToolQualityIssue>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityStatus {
+    pub status: String,
+    pub failed_files: i64,
+    pub checked_at: i64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -62,6 +199,8 @@ pub struct ToolFileDetail {
     pub size_bytes: i64,
     pub mime: Option<String>,
     pub created_at: i64,
+    /// Reviewer annotation explaining why the file exists.
+    pub note: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -72,6 +211,8 @@ pub struct ToolVersionDetail {
     pub version: String,
     pub changelog_md: Option<String>,
     pub instructions_md: String,
+    /// Digest of the file hashes, instructions, and changelog; equal hashes mean equal content.
+    pub version_hash: Option<String>,
     pub created_at: i64,
     pub files: Vec<ToolFileDetail>,
 }
@@ -85,11 +226,125 @@ pub struct ToolDetail {
     pub description: String,
     pub category: String,
     pub tags: Vec<String>,
+    pub icon_rel_path: Option<String>,
+    pub icon_data_base64: Option<String>,
     pub created_at: i64,
     pub updated_at: i64,
+    /// Most versions the tool may keep; `None` means unlimited.
+    pub max_versions: Option<i64>,
+    pub version_limit_mode: VersionLimitMode,
     pub versions: Vec<ToolVersionDetail>,
 }
 
+/// What happens when adding a version would exceed a tool's `max_versions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VersionLimitMode {
+    #[default]
+    Reject,
+    /// Drop the oldest versions so the new one fits.
+    Prune,
+}
+
+impl VersionLimitMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Reject => "reject",
+            Self::Prune => "prune",
+        }
+    }
+
+    fn from_column(value: Option<String>) -> Self {
+        match value.as_deref() {
+            Some("prune") => Self::Prune,
+            _ => Self::Reject,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatestInstructions {
+    pub version_id: String,
+    pub version: String,
+    pub instructions_md: String,
+    pub changelog_md: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DistinctToolFile {
+    pub sha256: String,
+    pub original_name: String,
+    pub size_bytes: i64,
+    pub versions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrunedVersion {
+    pub id: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagCooccurrence {
+    pub tag: String,
+    /// Number of tools carrying both this tag and the queried one.
+    pub tool_count: i64,
+}
+
+/// One reporting row per tool for catalog exports.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogEntry {
+    pub name: String,
+    pub slug: String,
+    pub description: String,
+    pub category: String,
+    pub tags: Vec<String>,
+    pub version_count: i64,
+    pub latest_version: Option<String>,
+    pub total_size_bytes: u64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NormalizationReport {
+    pub applied: bool,
+    pub checked_tools: usize,
+    pub slug_changes: Vec<SlugChange>,
+    pub tag_changes: Vec<TagChange>,
+    pub skipped: Vec<NormalizationSkip>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlugChange {
+    pub tool_id: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagChange {
+    pub tool_id: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// A tool whose stored tags cannot be normalized automatically, e.g. a tag over the length limit.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NormalizationSkip {
+    pub tool_id: String,
+    pub reason: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct VersionInsertInput {
     pub version: String,
@@ -104,6 +359,52 @@ pub struct FileRecordInsert {
     pub sha256: String,
     pub size_bytes: i64,
     pub mime: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SlugMatch {
+    pub tool_id: String,
+    /// True when the lookup matched a previous slug rather than the current one.
+    pub redirected: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportTarget {
+    pub tool_id: String,
+    pub version_id: String,
+    pub created_tool: bool,
+}
+
+struct ValidatedMetadata {
+    name: String,
+    requested_slug: String,
+    description: String,
+    category: String,
+    tags: Vec<String>,
+}
+
+struct ValidatedVersion {
+    version: String,
+    instructions_md: String,
+    changelog_md: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StoredFileRecord {
+    pub original_name: String,
+    pub stored_rel_path: String,
+    pub sha256: String,
+    pub size_bytes: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FilePathRecord {
+    pub file_id: String,
+    pub tool_id: String,
+    pub tool_version_id: String,
+    pub original_name: String,
+    pub stored_rel_path: String,
 }
 
 #[derive(Debug, Clone)]
@@ -113,6 +414,13 @@ pub struct ExportVersionContext {
     pub files: Vec<ToolFileDetail>,
 }
 
+/// Every version of a tool, oldest first, for whole-tool archives.
+#[derive(Debug, Clone)]
+pub struct FullExportContext {
+    pub tool: ToolMetadataExport,
+    pub versions: Vec<ExportVersionContext>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ToolMetadataExport {
     pub name: String,
@@ -127,10 +435,53 @@ pub struct VersionExport {
     pub id: String,
     pub tool_id: String,
     pub version: String,
+    pub created_at: i64,
     pub changelog_md: Option<String>,
     pub instructions_md: String,
 }
 
+/// Version 1.0.0 of the "CAD Toolset" fixture tool holding `files`; tests override the fields
+/// they exercise.
+#[cfg(test)]
+pub(crate) fn test_export_context(files: &[(&str, &[u8])]) -> ExportVersionContext {
+    ExportVersionContext {
+        tool: ToolMetadataExport {
+            name: "CAD Toolset".to_string(),
+            slug: "cad-toolset".to_string(),
+            description: "CAD helpers".to_string(),
+            category: "cad".to_string(),
+            tags: Vec::new(),
+        },
+        version: VersionExport {
+            id: "version-1".to_string(),
+            tool_id: "tool-1".to_string(),
+            version: "1.0.0".to_string(),
+            created_at: 0,
+            changelog_md: None,
+            instructions_md: "# install".to_string(),
+        },
+        files: files
+            .iter()
+            .map(|(name, bytes)| test_export_file("version-1", name, bytes))
+            .collect(),
+    }
+}
+
+/// A file row of the fixture tool, stored under `version_id` and hashed from `bytes`.
+#[cfg(test)]
+pub(crate) fn test_export_file(version_id: &str, name: &str, bytes: &[u8]) -> ToolFileDetail {
+    ToolFileDetail {
+        id: format!("{version_id}-{name}"),
+        original_name: name.to_string(),
+        stored_rel_path: format!("tools/tool-1/{version_id}/files/{name}"),
+        sha256: super::storage::sha256_hex(bytes),
+        size_bytes: bytes.len() as i64,
+        mime: None,
+        created_at: 0,
+        note: None,
+    }
+}
+
 pub async fn open_pool(app: &AppHandle) -> ToolsResult<SqlitePool> {
     let app_config_dir = app.path().app_config_dir().map_err(|error| {
         ToolsError::Io(format!("Failed to resolve app config directory: {error}"))
@@ -138,69 +489,321 @@ pub async fn open_pool(app: &AppHandle) -> ToolsResult<SqlitePool> {
     std::fs::create_dir_all(&app_config_dir)?;
 
     let db_path = app_config_dir.join(DB_FILE_NAME);
-    let db_url = format!("sqlite:{}", db_path.to_string_lossy());
-    let pool = SqlitePool::connect(&db_url).await.map_err(|error| {
+    // WAL lets readers run alongside a writer; `checkpoint_wal` folds it back for backups.
+    let options = sqlx::sqlite::SqliteConnectOptions::new()
+        .filename(&db_path)
+        .create_if_missing(true)
+        .foreign_keys(true)
+        .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal);
+    let pool = SqlitePool::connect_with(options).await.map_err(|error| {
         ToolsError::Database(format!("Failed to open SQLite database: {error}"))
     })?;
 
-    sqlx::query("PRAGMA foreign_keys = ON")
-        .execute(&pool)
-        .await?;
-    execute_batch(
-        &pool,
-        include_str!("../../migrations/0012_create_custom_tool_library.sql"),
-    )
-    .await?;
-    execute_batch(
-        &pool,
-        include_str!("../../migrations/0013_harden_custom_tool_library.sql"),
-    )
-    .await?;
+    apply_library_migrations(&pool, LIBRARY_MIGRATIONS).await?;
 
     Ok(pool)
 }
 
+/// Result of `PRAGMA wal_checkpoint(TRUNCATE)`. Outside WAL mode SQLite reports `-1` for both
+/// frame counts and there is nothing to checkpoint.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalCheckpoint {
+    /// Another connection held the database, so the WAL could not be fully copied back.
+    pub busy: bool,
+    pub wal_frames: i64,
+    pub checkpointed_frames: i64,
+}
+
+/// Copies the WAL back into the database file and truncates it, so the `.db` file alone is a
+/// current copy for backup tools that copy it directly.
+pub async fn checkpoint_wal(pool: &SqlitePool) -> ToolsResult<WalCheckpoint> {
+    let row = sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .fetch_one(pool)
+        .await?;
+    Ok(WalCheckpoint {
+        busy: row.try_get::<i64, _>(0)? != 0,
+        wal_frames: row.try_get(1)?,
+        checkpointed_frames: row.try_get(2)?,
+    })
+}
+
 pub async fn list_tools(
     pool: &SqlitePool,
     filters: ToolListFilters,
 ) -> ToolsResult<Vec<ToolSummary>> {
-    let rows = sqlx::query(
-        "SELECT id, name, slug, description, category, created_at, updated_at
-      FROM custom_library_tools
-      ORDER BY updated_at DESC, name COLLATE NOCASE ASC",
-    )
-    .fetch_all(pool)
-    .await?;
+    Ok(query_tools(pool, &filters, None).await?.0)
+}
+
+/// Largest `page_size` `list_tools_page` accepts.
+pub const MAX_TOOL_PAGE_SIZE: usize = 200;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolListPage {
+    pub tools: Vec<ToolSummary>,
+    /// Tools matching the filters across all pages.
+    pub total: usize,
+    pub page: usize,
+    /// `None` when the whole list was returned as one page.
+    pub page_size: Option<usize>,
+}
+
+/// One page of `list_tools`, counting from page 1. Paging applies after filtering, so `total`
+/// counts every matching tool. Without `page_size` the whole list is a single page.
+pub async fn list_tools_page(
+    pool: &SqlitePool,
+    filters: ToolListFilters,
+    page: Option<usize>,
+    page_size: Option<usize>,
+) -> ToolsResult<ToolListPage> {
+    let page = page.unwrap_or(1);
+    if page == 0 {
+        return Err(ToolsError::Validation("page starts at 1.".to_string()));
+    }
+    if page_size.is_some_and(|size| size == 0 || size > MAX_TOOL_PAGE_SIZE) {
+        return Err(ToolsError::Validation(format!(
+            "page_size must be between 1 and {MAX_TOOL_PAGE_SIZE}."
+        )));
+    }
+
+    let range = match page_size {
+        Some(size) => Some(((page - 1).saturating_mul(size), size)),
+        None if page == 1 => None,
+        // Past the single page: an empty page, but still the full count.
+        None => Some((usize::MAX, 1)),
+    };
+    let (tools, total) = query_tools(pool, &filters, range).await?;
+    Ok(ToolListPage {
+        tools,
+        total,
+        page,
+        page_size,
+    })
+}
+
+/// Runs the list query with every filter in SQL, so tags and versions are only fetched for
+/// tools that match. `range` is `(offset, limit)`; returns the tools and the match count.
+async fn query_tools(
+    pool: &SqlitePool,
+    filters: &ToolListFilters,
+    range: Option<(usize, usize)>,
+) -> ToolsResult<(Vec<ToolSummary>, usize)> {
+    let order_by = match filters.order_by {
+        // The id tiebreak keeps the order, and therefore pages, stable between calls.
+        ToolListOrder::Updated => "t.updated_at DESC, t.name COLLATE NOCASE ASC, t.id ASC",
+        ToolListOrder::RecentlyViewed => {
+            "v.last_viewed_at IS NULL, v.last_viewed_at DESC, t.updated_at DESC, t.name COLLATE NOCASE ASC, t.id ASC"
+        }
+    };
+    let placeholders = PLACEHOLDER_CATEGORIES
+        .iter()
+        .map(|category| format!("'{category}'"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let blank_chars = format!(
+        "char({})",
+        BLANK_CHARS
+            .iter()
+            .map(|character| u32::from(*character).to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    // Mirrors `quality_issues`: blank description, no non-blank tag, or a placeholder category.
+    let sql = format!(
+        "WITH latest AS (
+        SELECT tool_id, version,
+          ROW_NUMBER() OVER (PARTITION BY tool_id ORDER BY created_at DESC, rowid DESC) AS recency
+        FROM custom_library_tool_versions
+      )
+      SELECT t.id, t.name, t.slug, t.description, t.category, t.icon_rel_path,
+          t.created_at, t.updated_at, v.last_viewed_at,
+          i.status AS integrity_status, i.failed_files, i.checked_at,
+          COUNT(*) OVER () AS total_count
+      FROM custom_library_tools t
+      LEFT JOIN custom_library_tool_integrity i ON i.tool_id = t.id
+      LEFT JOIN custom_library_tool_views v ON v.tool_id = t.id
+      LEFT JOIN latest lv ON lv.tool_id = t.id AND lv.recency = 1
+      WHERE (?1 IS NULL OR t.category = ?1 COLLATE NOCASE)
+        AND (?2 IS NULL OR EXISTS (
+          SELECT 1 FROM custom_library_tool_tags g
+          WHERE g.tool_id = t.id AND g.tag = ?2 COLLATE NOCASE
+        ))
+        AND (?3 IS NULL
+          OR t.name LIKE ?3 ESCAPE '\\'
+          OR t.slug LIKE ?3 ESCAPE '\\'
+          OR t.description LIKE ?3 ESCAPE '\\'
+          OR t.category LIKE ?3 ESCAPE '\\'
+          OR lv.version LIKE ?3 ESCAPE '\\'
+          OR EXISTS (
+            SELECT 1 FROM custom_library_tool_tags g
+            WHERE g.tool_id = t.id AND g.tag LIKE ?3 ESCAPE '\\'
+          ))
+        AND (?4 = 0
+          OR trim(t.description, {blank_chars}) = ''
+          OR trim(t.category, {blank_chars}) = ''
+          OR lower(trim(t.category, {blank_chars})) IN ({placeholders})
+          OR NOT EXISTS (
+            SELECT 1 FROM custom_library_tool_tags g
+            WHERE g.tool_id = t.id AND trim(g.tag, {blank_chars}) <> ''
+          ))
+      ORDER BY {order_by}
+      LIMIT ?5 OFFSET ?6"
+    );
+
+    let category = filters
+        .category
+        .as_deref()
+        .map(str::trim)
+        .filter(|category| !category.is_empty());
+    let tag = filters
+        .tag
+        .as_deref()
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty());
+    let query_pattern = filters
+        .query
+        .as_deref()
+        .map(str::trim)
+        .filter(|query| !query.is_empty())
+        .map(|query| format!("%{}%", escape_like(query)));
+    let fetch = |offset: usize, limit: Option<usize>| {
+        sqlx::query(&sql)
+            .bind(category)
+            .bind(tag)
+            .bind(query_pattern.clone())
+            .bind(filters.quality_issues)
+            .bind(limit.map_or(-1, |limit| limit.min(i64::MAX as usize) as i64))
+            .bind(offset.min(i64::MAX as usize) as i64)
+            .fetch_all(pool)
+    };
 
+    let (offset, limit) = match range {
+        Some((offset, limit)) => (offset, Some(limit)),
+        None => (0, None),
+    };
+    let rows = fetch(offset, limit).await?;
+    let total = match rows.first() {
+        Some(row) => row.get::<i64, _>("total_count") as usize,
+        None if offset > 0 => fetch(0, Some(1))
+            .await?
+            .first()
+            .map_or(0, |row| row.get::<i64, _>("total_count") as usize),
+        None => 0,
+    };
+
+    let tool_ids = rows
+        .iter()
+        .map(|row| row.get::<String, _>("id"))
+        .collect::<Vec<_>>();
+    let mut tags_by_tool = fetch_tags_for_tools(pool, &tool_ids).await?;
+    let mut latest_versions = fetch_latest_versions(pool, &tool_ids).await?;
     let mut summaries = Vec::with_capacity(rows.len());
     for row in rows {
         let tool_id: String = row.get("id");
-        let tags = fetch_tags(pool, &tool_id).await?;
-        let latest_version = fetch_latest_version(pool, &tool_id).await?;
+        let tags = tags_by_tool.remove(&tool_id).unwrap_or_default();
+        let latest_version = latest_versions.remove(&tool_id);
 
-        let summary = ToolSummary {
+        let mut summary = ToolSummary {
             id: tool_id,
             name: row.get("name"),
             slug: row.get("slug"),
             description: row.get("description"),
             category: row.get("category"),
             tags,
+            icon_rel_path: row.get("icon_rel_path"),
+            icon_data_base64: None,
+            integrity: row
+                .get::<Option<String>, _>("integrity_status")
+                .map(|status| IntegrityStatus {
+                    status,
+                    failed_files: row.get("failed_files"),
+                    checked_at: row.get("checked_at"),
+                }),
             created_at: row.get("created_at"),
             updated_at: row.get("updated_at"),
+            last_viewed_at: row.get("last_viewed_at"),
             latest_version,
+            quality_issues: Vec::new(),
         };
+        summary.quality_issues = quality_issues(&summary);
+        summaries.push(summary);
+    }
 
-        if matches_filters(&summary, &filters) {
-            summaries.push(summary);
+    Ok((summaries, total))
+}
+
+/// Escapes `LIKE` wildcards so user input only ever matches literally (with `ESCAPE '\'`).
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        if matches!(character, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+    escaped
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCategoryGroup {
+    pub category: String,
+    pub tools: Vec<ToolSummary>,
+}
+
+/// `list_tools` grouped by category. Categories that differ only in case share a group named
+/// after the first spelling listed; groups are ordered case-insensitively and keep the list
+/// order within each group.
+pub async fn list_tools_grouped(
+    pool: &SqlitePool,
+    filters: ToolListFilters,
+) -> ToolsResult<Vec<ToolCategoryGroup>> {
+    Ok(group_tools_by_category(list_tools(pool, filters).await?))
+}
+
+fn group_tools_by_category(tools: Vec<ToolSummary>) -> Vec<ToolCategoryGroup> {
+    let mut groups: Vec<ToolCategoryGroup> = Vec::new();
+    let mut positions = HashMap::<String, usize>::new();
+    for tool in tools {
+        let key = tool.category.to_lowercase();
+        match positions.get(&key) {
+            Some(&position) => groups[position].tools.push(tool),
+            None => {
+                positions.insert(key, groups.len());
+                groups.push(ToolCategoryGroup {
+                    category: tool.category.clone(),
+                    tools: vec![tool],
+                });
+            }
         }
     }
+    groups.sort_by_cached_key(|group| group.category.to_lowercase());
+    groups
+}
 
-    Ok(summaries)
+/// Stamps the tool as viewed now and returns the timestamp; only the latest view is kept.
+pub async fn record_tool_view(pool: &SqlitePool, tool_id: &str) -> ToolsResult<i64> {
+    let viewed_at = now_epoch_millis()?;
+    let result = sqlx::query(
+        "INSERT INTO custom_library_tool_views (tool_id, last_viewed_at)
+         SELECT id, ?2 FROM custom_library_tools WHERE id = ?1
+         ON CONFLICT(tool_id) DO UPDATE SET last_viewed_at = excluded.last_viewed_at",
+    )
+    .bind(tool_id)
+    .bind(viewed_at)
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ToolsError::NotFound("Tool not found.".to_string()));
+    }
+    Ok(viewed_at)
 }
 
 pub async fn get_tool_detail(pool: &SqlitePool, tool_id: &str) -> ToolsResult<ToolDetail> {
     let row = sqlx::query(
-        "SELECT id, name, slug, description, category, created_at, updated_at
+        "SELECT id, name, slug, description, category, icon_rel_path, created_at, updated_at,
+            max_versions, version_limit_mode
       FROM custom_library_tools
       WHERE id = ?1",
     )
@@ -215,7 +818,7 @@ pub async fn get_tool_detail(pool: &SqlitePool, tool_id: &str) -> ToolsResult<To
         "SELECT id
       FROM custom_library_tool_versions
       WHERE tool_id = ?1
-      ORDER BY created_at DESC",
+      ORDER BY created_at DESC, rowid DESC",
     )
     .bind(tool_id)
     .fetch_all(pool)
@@ -234,12 +837,76 @@ pub async fn get_tool_detail(pool: &SqlitePool, tool_id: &str) -> ToolsResult<To
         description: row.get("description"),
         category: row.get("category"),
         tags,
+        icon_rel_path: row.get("icon_rel_path"),
+        icon_data_base64: None,
         created_at: row.get("created_at"),
         updated_at: row.get("updated_at"),
+        max_versions: row.get("max_versions"),
+        version_limit_mode: VersionLimitMode::from_column(row.get("version_limit_mode")),
         versions,
     })
 }
 
+/// Versions ordered by semantic version, highest first. Labels that are not semver (a leading
+/// `v` is allowed) follow, newest upload first, so non-semver tools keep their usual history.
+pub async fn list_versions_semver(
+    pool: &SqlitePool,
+    tool_id: &str,
+) -> ToolsResult<Vec<ToolVersionDetail>> {
+    ensure_tool_exists(pool, tool_id).await?;
+    let version_ids = sqlx::query_scalar::<_, String>(
+        "SELECT id
+      FROM custom_library_tool_versions
+      WHERE tool_id = ?1
+      ORDER BY created_at DESC, rowid DESC",
+    )
+    .bind(tool_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut versions = Vec::with_capacity(version_ids.len());
+    for version_id in version_ids {
+        versions.push(get_version_detail(pool, &version_id).await?);
+    }
+    sort_versions_semver(&mut versions);
+    Ok(versions)
+}
+
+fn sort_versions_semver(versions: &mut [ToolVersionDetail]) {
+    // Stable sort: unparseable labels keep the newest-first order they arrived in.
+    versions.sort_by_cached_key(|version| std::cmp::Reverse(parse_semver(&version.version)));
+}
+
+fn parse_semver(label: &str) -> Option<semver::Version> {
+    let trimmed = label.trim();
+    let unprefixed = trimmed.strip_prefix(['v', 'V']).unwrap_or(trimmed);
+    semver::Version::parse(unprefixed).ok()
+}
+
+pub async fn get_latest_instructions(
+    pool: &SqlitePool,
+    tool_id: &str,
+) -> ToolsResult<LatestInstructions> {
+    let row = sqlx::query(
+        "SELECT id, version, instructions_md, changelog_md
+      FROM custom_library_tool_versions
+      WHERE tool_id = ?1
+      ORDER BY created_at DESC, rowid DESC
+      LIMIT 1",
+    )
+    .bind(tool_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| ToolsError::NotFound("Tool not found or has no versions.".to_string()))?;
+
+    Ok(LatestInstructions {
+        version_id: row.get("id"),
+        version: row.get("version"),
+        instructions_md: row.get("instructions_md"),
+        changelog_md: row.get("changelog_md"),
+    })
+}
+
 pub async fn create_tool_with_version(
     pool: &SqlitePool,
     tool_id: &str,
@@ -249,57 +916,13 @@ pub async fn create_tool_with_version(
     files: &[FileRecordInsert],
 ) -> ToolsResult<(String, String)> {
     let now = now_epoch_millis()?;
-    let name = validate_required("name", &metadata.name, 120)?;
-    let description = validate_required("description", &metadata.description, 8_000)?;
-    let category = validate_required("category", &metadata.category, 120)?;
-    let normalized_tags = normalize_tags(&metadata.tags)?;
-    let requested_slug = metadata
-        .slug
-        .as_ref()
-        .map(|value| value.trim().to_string())
-        .filter(|value| !value.is_empty())
-        .unwrap_or_else(|| slugify(&name));
-    let slug = resolve_unique_slug(pool, &requested_slug, None).await?;
-    let version_label = validate_required("version", &version.version, 80)?;
-    let instructions = validate_required("instructions", &version.instructions_md, 512 * 1024)?;
-    let changelog = normalize_optional_text(version.changelog_md, 512 * 1024)?;
+    let defaults = settings::load_metadata_defaults(pool).await?;
+    let metadata = validate_metadata(apply_metadata_defaults(metadata, &defaults))?;
+    let version = validate_version(version)?;
 
     let mut tx = pool.begin().await?;
-    sqlx::query(
-    "INSERT INTO custom_library_tools (id, name, slug, description, category, created_at, updated_at)
-      VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-  )
-  .bind(tool_id)
-  .bind(&name)
-  .bind(&slug)
-  .bind(&description)
-  .bind(&category)
-  .bind(now)
-  .bind(now)
-  .execute(&mut *tx)
-  .await?;
-
-    for tag in normalized_tags {
-        sqlx::query("INSERT INTO custom_library_tool_tags (tool_id, tag) VALUES (?1, ?2)")
-            .bind(tool_id)
-            .bind(tag)
-            .execute(&mut *tx)
-            .await?;
-    }
-
-    sqlx::query(
-    "INSERT INTO custom_library_tool_versions (id, tool_id, version, changelog_md, instructions_md, created_at)
-      VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-  )
-  .bind(version_id)
-  .bind(tool_id)
-  .bind(&version_label)
-  .bind(changelog)
-  .bind(&instructions)
-  .bind(now)
-  .execute(&mut *tx)
-  .await?;
-
+    insert_tool(&mut tx, tool_id, &metadata, now).await?;
+    insert_version(&mut tx, tool_id, version_id, &version, files, now).await?;
     insert_files(&mut tx, version_id, files, now).await?;
 
     tx.commit().await?;
@@ -312,54 +935,286 @@ pub async fn add_version_with_files(
     version_id: &str,
     version: VersionInsertInput,
     files: &[FileRecordInsert],
-) -> ToolsResult<String> {
+) -> ToolsResult<Vec<PrunedVersion>> {
     let now = now_epoch_millis()?;
+    let version = validate_version(version)?;
 
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
     let tool_exists = sqlx::query("SELECT 1 FROM custom_library_tools WHERE id = ?1")
         .bind(tool_id)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *tx)
         .await?
         .is_some();
     if !tool_exists {
         return Err(ToolsError::NotFound("Tool not found.".to_string()));
     }
 
-    let version_label = validate_required("version", &version.version, 80)?;
-    let instructions = validate_required("instructions", &version.instructions_md, 512 * 1024)?;
-    let changelog = normalize_optional_text(version.changelog_md, 512 * 1024)?;
-
-    let existing_version = find_version_id(pool, tool_id, &version_label).await?;
-    if existing_version.is_some() {
+    if find_version_id(&mut *tx, tool_id, &version.version)
+        .await?
+        .is_some()
+    {
         return Err(ToolsError::Conflict(
             "Version already exists for this tool.".to_string(),
         ));
     }
 
-    let mut tx = pool.begin().await?;
+    insert_version(&mut tx, tool_id, version_id, &version, files, now).await?;
+    insert_files(&mut tx, version_id, files, now).await?;
+    let pruned = enforce_version_limit(&mut tx, tool_id).await?;
+    touch_tool(&mut tx, tool_id, now).await?;
 
-    sqlx::query(
-    "INSERT INTO custom_library_tool_versions (id, tool_id, version, changelog_md, instructions_md, created_at)
-      VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-  )
-  .bind(version_id)
-  .bind(tool_id)
-  .bind(&version_label)
-  .bind(changelog)
-  .bind(&instructions)
-  .bind(now)
-  .execute(&mut *tx)
-  .await?;
+    tx.commit().await?;
+    Ok(pruned)
+}
 
-    insert_files(&mut tx, version_id, files, now).await?;
+/// Takes the write lock up front and resolves where an archive lands, so the slug and
+/// version checks cannot race a concurrent import before `commit_import` runs.
+pub async fn begin_import<'a>(
+    pool: &'a SqlitePool,
+    slug: &str,
+    version: &str,
+) -> ToolsResult<(Transaction<'a, Sqlite>, ImportTarget)> {
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
 
-    sqlx::query("UPDATE custom_library_tools SET updated_at = ?2 WHERE id = ?1")
-        .bind(tool_id)
-        .bind(now)
-        .execute(&mut *tx)
-        .await?;
+    let target = match find_tool_id_by_slug(&mut *tx, slug).await? {
+        Some(tool_id) => {
+            if find_version_id(&mut *tx, &tool_id, version)
+                .await?
+                .is_some()
+            {
+                return Err(ToolsError::Conflict(
+                    "A tool with this slug and version already exists. Import aborted.".to_string(),
+                ));
+            }
+            ImportTarget {
+                tool_id,
+                version_id: Uuid::new_v4().to_string(),
+                created_tool: false,
+            }
+        }
+        None => ImportTarget {
+            tool_id: Uuid::new_v4().to_string(),
+            version_id: Uuid::new_v4().to_string(),
+            created_tool: true,
+        },
+    };
+
+    Ok((tx, target))
+}
+
+pub async fn commit_import(
+    mut tx: Transaction<'_, Sqlite>,
+    target: &ImportTarget,
+    metadata: ToolMetadataInput,
+    version: VersionInsertInput,
+    files: &[FileRecordInsert],
+) -> ToolsResult<Vec<PrunedVersion>> {
+    let now = now_epoch_millis()?;
+    let version = validate_version(version)?;
+
+    if target.created_tool {
+        let metadata = validate_metadata(metadata)?;
+        insert_tool(&mut tx, &target.tool_id, &metadata, now).await?;
+    } else {
+        touch_tool(&mut tx, &target.tool_id, now).await?;
+    }
+    insert_version(
+        &mut tx,
+        &target.tool_id,
+        &target.version_id,
+        &version,
+        files,
+        now,
+    )
+    .await?;
+    insert_files(&mut tx, &target.version_id, files, now).await?;
+    let pruned = if target.created_tool {
+        Vec::new()
+    } else {
+        enforce_version_limit(&mut tx, &target.tool_id).await?
+    };
 
     tx.commit().await?;
-    Ok(version_id.to_string())
+    Ok(pruned)
+}
+
+pub async fn set_version_limit(
+    pool: &SqlitePool,
+    tool_id: &str,
+    max_versions: Option<i64>,
+    mode: VersionLimitMode,
+) -> ToolsResult<()> {
+    if max_versions.is_some_and(|limit| limit < 1) {
+        return Err(ToolsError::Validation(
+            "max_versions must be a positive integer.".to_string(),
+        ));
+    }
+    let result = sqlx::query(
+        "UPDATE custom_library_tools
+      SET max_versions = ?2, version_limit_mode = ?3, updated_at = ?4
+      WHERE id = ?1",
+    )
+    .bind(tool_id)
+    .bind(max_versions)
+    .bind(mode.as_str())
+    .bind(now_epoch_millis()?)
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ToolsError::NotFound("Tool not found.".to_string()));
+    }
+    Ok(())
+}
+
+/// Runs after a version is inserted: rejects the insert or prunes the oldest versions when the
+/// tool now has more than `max_versions`. Lowering a limit does not prune until the next add.
+async fn enforce_version_limit(
+    conn: &mut SqliteConnection,
+    tool_id: &str,
+) -> ToolsResult<Vec<PrunedVersion>> {
+    let row = sqlx::query(
+        "SELECT t.max_versions, t.version_limit_mode,
+            (SELECT COUNT(*) FROM custom_library_tool_versions v WHERE v.tool_id = t.id)
+              AS version_count
+      FROM custom_library_tools t
+      WHERE t.id = ?1",
+    )
+    .bind(tool_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let Some(max_versions) = row.get::<Option<i64>, _>("max_versions") else {
+        return Ok(Vec::new());
+    };
+    if row.get::<i64, _>("version_count") <= max_versions {
+        return Ok(Vec::new());
+    }
+
+    match VersionLimitMode::from_column(row.get("version_limit_mode")) {
+        VersionLimitMode::Reject => Err(ToolsError::Conflict(format!(
+            "This tool keeps at most {max_versions} version(s). Delete or prune older versions first."
+        ))),
+        VersionLimitMode::Prune => {
+            prune_oldest_versions(conn, tool_id, max_versions.max(1) as usize).await
+        }
+    }
+}
+
+/// Sets or clears (`None` or blank) the note on one file of a version.
+pub async fn set_file_note(
+    pool: &SqlitePool,
+    version_id: &str,
+    file_id: &str,
+    note: Option<&str>,
+) -> ToolsResult<Option<String>> {
+    let note = normalize_file_note(note)?;
+    let result = sqlx::query(
+        "UPDATE custom_library_tool_files SET note = ?3 WHERE tool_version_id = ?1 AND id = ?2",
+    )
+    .bind(version_id)
+    .bind(file_id)
+    .bind(&note)
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ToolsError::NotFound(
+            "File not found in version.".to_string(),
+        ));
+    }
+    Ok(note)
+}
+
+pub async fn get_file_note(
+    pool: &SqlitePool,
+    version_id: &str,
+    file_id: &str,
+) -> ToolsResult<Option<String>> {
+    sqlx::query_scalar::<_, Option<String>>(
+        "SELECT note FROM custom_library_tool_files WHERE tool_version_id = ?1 AND id = ?2",
+    )
+    .bind(version_id)
+    .bind(file_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| ToolsError::NotFound("File not found in version.".to_string()))
+}
+
+pub async fn get_file_path_record(pool: &SqlitePool, file_id: &str) -> ToolsResult<FilePathRecord> {
+    let row = sqlx::query(
+        "SELECT f.id, v.tool_id, f.tool_version_id, f.original_name, f.stored_rel_path
+      FROM custom_library_tool_files f
+      JOIN custom_library_tool_versions v ON v.id = f.tool_version_id
+      WHERE f.id = ?1",
+    )
+    .bind(file_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| ToolsError::NotFound("File not found.".to_string()))?;
+
+    Ok(FilePathRecord {
+        file_id: row.get("id"),
+        tool_id: row.get("tool_id"),
+        tool_version_id: row.get("tool_version_id"),
+        original_name: row.get("original_name"),
+        stored_rel_path: row.get("stored_rel_path"),
+    })
+}
+
+/// Points a file row at a new (already sanitized) name and stored path; the hash, size and note
+/// are untouched. A name another file of the version already uses, ignoring case, is a conflict.
+/// `move_file` runs once the row is updated and before the commit; a failed move rolls the row
+/// back, and `restore_file` undoes the move when the commit itself fails.
+pub async fn rename_file(
+    pool: &SqlitePool,
+    file_id: &str,
+    original_name: &str,
+    stored_rel_path: &str,
+    move_file: impl FnOnce() -> ToolsResult<()>,
+    restore_file: impl FnOnce(),
+) -> ToolsResult<()> {
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+    update_file_path(&mut *tx, file_id, original_name, stored_rel_path).await?;
+    move_file()?;
+    if let Err(error) = tx.commit().await {
+        restore_file();
+        return Err(error.into());
+    }
+    Ok(())
+}
+
+async fn update_file_path<'e>(
+    executor: impl SqliteExecutor<'e>,
+    file_id: &str,
+    original_name: &str,
+    stored_rel_path: &str,
+) -> ToolsResult<()> {
+    let normalized_rel_path = normalize_stored_rel_path(stored_rel_path)?;
+    let result = sqlx::query(
+        "UPDATE custom_library_tool_files SET original_name = ?2, stored_rel_path = ?3 WHERE id = ?1",
+    )
+    .bind(file_id)
+    .bind(original_name)
+    .bind(normalized_rel_path)
+    .execute(executor)
+    .await
+    .map_err(|error| match &error {
+        sqlx::Error::Database(database_error) if database_error.is_unique_violation() => {
+            ToolsError::Conflict(format!("{original_name} is already a file in this version."))
+        }
+        _ => error.into(),
+    })?;
+    if result.rows_affected() == 0 {
+        return Err(ToolsError::NotFound("File not found.".to_string()));
+    }
+    Ok(())
+}
+
+pub async fn ensure_tool_exists(pool: &SqlitePool, tool_id: &str) -> ToolsResult<()> {
+    sqlx::query("SELECT 1 FROM custom_library_tools WHERE id = ?1")
+        .bind(tool_id)
+        .fetch_optional(pool)
+        .await?
+        .map(|_| ())
+        .ok_or_else(|| ToolsError::NotFound("Tool not found.".to_string()))
 }
 
 pub async fn delete_tool(pool: &SqlitePool, tool_id: &str) -> ToolsResult<()> {
@@ -375,52 +1230,471 @@ pub async fn delete_tool(pool: &SqlitePool, tool_id: &str) -> ToolsResult<()> {
     Ok(())
 }
 
-pub async fn delete_tool_version(pool: &SqlitePool, version_id: &str) -> ToolsResult<String> {
-    let row = sqlx::query("SELECT tool_id FROM custom_library_tool_versions WHERE id = ?1")
+/// Deletes one version of `tool_id` and its file rows. A version of another tool is not found;
+/// a tool's only version is refused, delete the tool instead.
+pub async fn delete_version(pool: &SqlitePool, tool_id: &str, version_id: &str) -> ToolsResult<()> {
+    let now = now_epoch_millis()?;
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+    let exists =
+        sqlx::query("SELECT 1 FROM custom_library_tool_versions WHERE id = ?1 AND tool_id = ?2")
+            .bind(version_id)
+            .bind(tool_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+    if !exists {
+        return Err(ToolsError::NotFound("Tool version not found.".to_string()));
+    }
+
+    let version_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM custom_library_tool_versions WHERE tool_id = ?1")
+            .bind(tool_id)
+            .fetch_one(&mut *tx)
+            .await?;
+    if version_count <= 1 {
+        return Err(ToolsError::Conflict(
+            "This is the tool's only version. Delete the tool instead.".to_string(),
+        ));
+    }
+
+    sqlx::query("DELETE FROM custom_library_tool_versions WHERE id = ?1 AND tool_id = ?2")
         .bind(version_id)
-        .fetch_optional(pool)
+        .bind(tool_id)
+        .execute(&mut *tx)
+        .await?;
+    touch_tool(&mut tx, tool_id, now).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Changes a version's label and returns the normalized label. Stored files are keyed by the
+/// version id, so nothing moves on disk. A label another version of the tool already uses is
+/// a conflict.
+pub async fn rename_version(
+    pool: &SqlitePool,
+    tool_id: &str,
+    version_id: &str,
+    new_version: &str,
+) -> ToolsResult<String> {
+    let label = validate_required("version", &normalize_version_label(new_version), 80)?;
+    let now = now_epoch_millis()?;
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+    let exists =
+        sqlx::query("SELECT 1 FROM custom_library_tool_versions WHERE id = ?1 AND tool_id = ?2")
+            .bind(version_id)
+            .bind(tool_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+    if !exists {
+        return Err(ToolsError::NotFound("Tool version not found.".to_string()));
+    }
+    if find_version_id(&mut *tx, tool_id, &label)
         .await?
-        .ok_or_else(|| ToolsError::NotFound("Tool version not found.".to_string()))?;
+        .is_some_and(|existing| existing != version_id)
+    {
+        return Err(ToolsError::Conflict(
+            "Version already exists for this tool.".to_string(),
+        ));
+    }
 
-    let tool_id: String = row.get("tool_id");
-    sqlx::query("DELETE FROM custom_library_tool_versions WHERE id = ?1")
+    sqlx::query("UPDATE custom_library_tool_versions SET version = ?2 WHERE id = ?1")
         .bind(version_id)
-        .execute(pool)
+        .bind(&label)
+        .execute(&mut *tx)
         .await?;
+    touch_tool(&mut tx, tool_id, now).await?;
+
+    tx.commit().await?;
+    Ok(label)
+}
+
+/// Deletes every version of a tool except the newest `keep`, using the same ordering as the
+/// latest version, and returns the pruned versions so their folders can be removed.
+pub async fn prune_versions(
+    pool: &SqlitePool,
+    tool_id: &str,
+    keep: usize,
+) -> ToolsResult<Vec<PrunedVersion>> {
+    if keep == 0 {
+        return Err(ToolsError::Validation(
+            "keep must be at least 1 so the latest version is retained.".to_string(),
+        ));
+    }
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+    let tool_exists = sqlx::query("SELECT 1 FROM custom_library_tools WHERE id = ?1")
+        .bind(tool_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .is_some();
+    if !tool_exists {
+        return Err(ToolsError::NotFound("Tool not found.".to_string()));
+    }
+
+    let pruned = prune_oldest_versions(&mut tx, tool_id, keep).await?;
+    if pruned.is_empty() {
+        return Ok(pruned);
+    }
+    touch_tool(&mut tx, tool_id, now_epoch_millis()?).await?;
+    tx.commit().await?;
+
+    Ok(pruned)
+}
+
+async fn prune_oldest_versions(
+    conn: &mut SqliteConnection,
+    tool_id: &str,
+    keep: usize,
+) -> ToolsResult<Vec<PrunedVersion>> {
+    let rows = sqlx::query(
+        "SELECT id, version
+      FROM custom_library_tool_versions
+      WHERE tool_id = ?1
+      ORDER BY created_at DESC, rowid DESC",
+    )
+    .bind(tool_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let pruned = rows
+        .into_iter()
+        .skip(keep)
+        .map(|row| PrunedVersion {
+            id: row.get("id"),
+            version: row.get("version"),
+        })
+        .collect::<Vec<_>>();
+    for version in &pruned {
+        sqlx::query("DELETE FROM custom_library_tool_files WHERE tool_version_id = ?1")
+            .bind(&version.id)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("DELETE FROM custom_library_tool_versions WHERE id = ?1")
+            .bind(&version.id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(pruned)
+}
+
+/// Points the tool at a new icon and returns the previous icon path, if any.
+pub async fn set_tool_icon(
+    pool: &SqlitePool,
+    tool_id: &str,
+    icon_rel_path: &str,
+) -> ToolsResult<Option<String>> {
+    let row = sqlx::query("SELECT icon_rel_path FROM custom_library_tools WHERE id = ?1")
+        .bind(tool_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| ToolsError::NotFound("Tool not found.".to_string()))?;
+    let previous: Option<String> = row.get("icon_rel_path");
 
     let now = now_epoch_millis()?;
-    sqlx::query("UPDATE custom_library_tools SET updated_at = ?2 WHERE id = ?1")
-        .bind(&tool_id)
-        .bind(now)
-        .execute(pool)
+    sqlx::query(
+        "UPDATE custom_library_tools SET icon_rel_path = ?2, updated_at = ?3 WHERE id = ?1",
+    )
+    .bind(tool_id)
+    .bind(icon_rel_path)
+    .bind(now)
+    .execute(pool)
+    .await?;
+
+    Ok(previous)
+}
+
+pub async fn find_tool_id_by_slug<'e, E: SqliteExecutor<'e>>(
+    executor: E,
+    slug: &str,
+) -> ToolsResult<Option<String>> {
+    Ok(find_tool_by_slug(executor, slug)
+        .await?
+        .map(|found| found.tool_id))
+}
+
+/// Looks a slug up as a current slug first, then as an alias left behind by a rename.
+pub async fn find_tool_by_slug<'e, E: SqliteExecutor<'e>>(
+    executor: E,
+    slug: &str,
+) -> ToolsResult<Option<SlugMatch>> {
+    let row = sqlx::query(
+        "SELECT id, 0 AS redirected FROM custom_library_tools WHERE slug = ?1
+      UNION ALL
+      SELECT t.id, 1 AS redirected
+      FROM custom_library_tool_slug_aliases a
+      JOIN custom_library_tools t ON t.id = a.tool_id
+      WHERE a.slug = ?1
+      ORDER BY redirected ASC
+      LIMIT 1",
+    )
+    .bind(slug)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(row.map(|value| SlugMatch {
+        tool_id: value.get("id"),
+        redirected: value.get::<i64, _>("redirected") == 1,
+    }))
+}
+
+/// Moves a tool to a new (uniquified) slug and keeps the old one as an alias.
+pub async fn rename_tool_slug(
+    pool: &SqlitePool,
+    tool_id: &str,
+    new_slug: &str,
+) -> ToolsResult<String> {
+    let requested_slug = validate_required("slug", new_slug, 120)?;
+    let now = now_epoch_millis()?;
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+    let current_slug: String =
+        sqlx::query_scalar("SELECT slug FROM custom_library_tools WHERE id = ?1")
+            .bind(tool_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| ToolsError::NotFound("Tool not found.".to_string()))?;
+
+    let slug = resolve_unique_slug(&mut tx, &requested_slug, Some(tool_id)).await?;
+    if slug == current_slug {
+        return Ok(slug);
+    }
+
+    move_tool_slug(&mut tx, tool_id, &current_slug, &slug, now).await?;
+    tx.commit().await?;
+    Ok(slug)
+}
+
+/// Replaces a tool's name, description, category and tags. A new slug is uniquified against the
+/// other tools and the old one kept as an alias, like `rename_tool_slug`; without a slug the
+/// current one stays. Creation defaults are not applied, so tags can be cleared.
+pub async fn update_tool_metadata(
+    pool: &SqlitePool,
+    tool_id: &str,
+    metadata: ToolMetadataInput,
+) -> ToolsResult<ToolDetail> {
+    let slug_given = metadata
+        .slug
+        .as_deref()
+        .is_some_and(|slug| !slug.trim().is_empty());
+    let metadata = validate_metadata(metadata)?;
+    let now = now_epoch_millis()?;
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+    let current_slug: String =
+        sqlx::query_scalar("SELECT slug FROM custom_library_tools WHERE id = ?1")
+            .bind(tool_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| ToolsError::NotFound("Tool not found.".to_string()))?;
+
+    if slug_given && slugify(&metadata.requested_slug) != current_slug {
+        let slug = resolve_unique_slug(&mut tx, &metadata.requested_slug, Some(tool_id)).await?;
+        if slug != current_slug {
+            move_tool_slug(&mut tx, tool_id, &current_slug, &slug, now).await?;
+        }
+    }
+
+    sqlx::query(
+        "UPDATE custom_library_tools
+      SET name = ?2, description = ?3, category = ?4, updated_at = ?5
+      WHERE id = ?1",
+    )
+    .bind(tool_id)
+    .bind(&metadata.name)
+    .bind(&metadata.description)
+    .bind(&metadata.category)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM custom_library_tool_tags WHERE tool_id = ?1")
+        .bind(tool_id)
+        .execute(&mut *tx)
         .await?;
+    for tag in &metadata.tags {
+        sqlx::query("INSERT INTO custom_library_tool_tags (tool_id, tag) VALUES (?1, ?2)")
+            .bind(tool_id)
+            .bind(tag)
+            .execute(&mut *tx)
+            .await?;
+    }
 
-    Ok(tool_id)
+    tx.commit().await?;
+    get_tool_detail(pool, tool_id).await
 }
 
-pub async fn find_tool_id_by_slug(pool: &SqlitePool, slug: &str) -> ToolsResult<Option<String>> {
-    let row = sqlx::query("SELECT id FROM custom_library_tools WHERE slug = ?1")
+async fn move_tool_slug(
+    conn: &mut SqliteConnection,
+    tool_id: &str,
+    current_slug: &str,
+    slug: &str,
+    now: i64,
+) -> ToolsResult<()> {
+    sqlx::query("UPDATE custom_library_tools SET slug = ?2, updated_at = ?3 WHERE id = ?1")
+        .bind(tool_id)
         .bind(slug)
-        .fetch_optional(pool)
+        .bind(now)
+        .execute(&mut *conn)
         .await?;
-
-    Ok(row.map(|value| value.get("id")))
+    // A slug that was reused by another tool and renamed away again points at the newest owner.
+    sqlx::query(
+        "INSERT OR REPLACE INTO custom_library_tool_slug_aliases (slug, tool_id, created_at)
+      VALUES (?1, ?2, ?3)",
+    )
+    .bind(current_slug)
+    .bind(tool_id)
+    .bind(now)
+    .execute(&mut *conn)
+    .await?;
+    // Renaming back to an earlier slug makes it canonical again.
+    sqlx::query("DELETE FROM custom_library_tool_slug_aliases WHERE slug = ?1")
+        .bind(slug)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
 }
 
-pub async fn find_version_id(
+/// Re-applies tag and slug normalization to every tool. Changes are computed inside one
+/// transaction and only committed when `apply` is set, so a dry run reports exactly what an
+/// applied run would do, including slug conflicts resolved against earlier renames.
+pub async fn normalize_all_tools(
     pool: &SqlitePool,
+    apply: bool,
+) -> ToolsResult<NormalizationReport> {
+    let now = now_epoch_millis()?;
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+    let tools =
+        sqlx::query("SELECT id, slug FROM custom_library_tools ORDER BY created_at ASC, rowid ASC")
+            .fetch_all(&mut *tx)
+            .await?;
+
+    let mut report = NormalizationReport {
+        applied: apply,
+        checked_tools: tools.len(),
+        slug_changes: Vec::new(),
+        tag_changes: Vec::new(),
+        skipped: Vec::new(),
+    };
+    for row in tools {
+        let tool_id: String = row.get("id");
+        let current_slug: String = row.get("slug");
+
+        if slugify(&current_slug) != current_slug {
+            let slug = resolve_unique_slug(&mut tx, &current_slug, Some(&tool_id)).await?;
+            move_tool_slug(&mut tx, &tool_id, &current_slug, &slug, now).await?;
+            report.slug_changes.push(SlugChange {
+                tool_id: tool_id.clone(),
+                from: current_slug,
+                to: slug,
+            });
+        }
+
+        let before = sqlx::query_scalar::<_, String>(
+            "SELECT tag FROM custom_library_tool_tags WHERE tool_id = ?1 ORDER BY tag COLLATE NOCASE ASC",
+        )
+        .bind(&tool_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let after = match normalize_tags(&before) {
+            Ok(after) => after,
+            Err(error) => {
+                report.skipped.push(NormalizationSkip {
+                    tool_id,
+                    reason: error.user_message(),
+                });
+                continue;
+            }
+        };
+        if after == before {
+            continue;
+        }
+        sqlx::query("DELETE FROM custom_library_tool_tags WHERE tool_id = ?1")
+            .bind(&tool_id)
+            .execute(&mut *tx)
+            .await?;
+        for tag in &after {
+            sqlx::query("INSERT INTO custom_library_tool_tags (tool_id, tag) VALUES (?1, ?2)")
+                .bind(&tool_id)
+                .bind(tag)
+                .execute(&mut *tx)
+                .await?;
+        }
+        touch_tool(&mut tx, &tool_id, now).await?;
+        report.tag_changes.push(TagChange {
+            tool_id,
+            before,
+            after,
+        });
+    }
+
+    if apply {
+        tx.commit().await?;
+    } else {
+        tx.rollback().await?;
+    }
+    Ok(report)
+}
+
+pub async fn find_version_id<'e, E: SqliteExecutor<'e>>(
+    executor: E,
     tool_id: &str,
     version: &str,
 ) -> ToolsResult<Option<String>> {
-    let row = sqlx::query(
-        "SELECT id FROM custom_library_tool_versions WHERE tool_id = ?1 AND version = ?2",
+    // Older rows may predate label normalization, so compare canonical forms in Rust.
+    let canonical = normalize_version_label(version);
+    let rows =
+        sqlx::query("SELECT id, version FROM custom_library_tool_versions WHERE tool_id = ?1")
+            .bind(tool_id)
+            .fetch_all(executor)
+            .await?;
+
+    Ok(rows
+        .into_iter()
+        .find(|row| normalize_version_label(&row.get::<String, _>("version")) == canonical)
+        .map(|row| row.get("id")))
+}
+
+/// Canonical version label: NFC-normalized, invisible characters dropped, and whitespace
+/// runs collapsed to a single space so visually identical labels compare equal.
+fn normalize_version_label(value: &str) -> String {
+    value
+        .nfc()
+        .filter(|character| !matches!(character, '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{FEFF}'))
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub async fn get_full_export_context(
+    pool: &SqlitePool,
+    tool_id: &str,
+) -> ToolsResult<FullExportContext> {
+    let version_ids: Vec<String> = sqlx::query_scalar(
+        "SELECT id
+      FROM custom_library_tool_versions
+      WHERE tool_id = ?1
+      ORDER BY created_at ASC, rowid ASC",
     )
     .bind(tool_id)
-    .bind(version)
-    .fetch_optional(pool)
+    .fetch_all(pool)
     .await?;
+    if version_ids.is_empty() {
+        return Err(ToolsError::NotFound(
+            "Tool not found or has no versions.".to_string(),
+        ));
+    }
 
-    Ok(row.map(|value| value.get("id")))
+    let mut versions = Vec::with_capacity(version_ids.len());
+    for version_id in &version_ids {
+        versions.push(get_export_context(pool, version_id).await?);
+    }
+    Ok(FullExportContext {
+        tool: versions[0].tool.clone(),
+        versions,
+    })
 }
 
 pub async fn get_export_context(
@@ -428,8 +1702,8 @@ pub async fn get_export_context(
     version_id: &str,
 ) -> ToolsResult<ExportVersionContext> {
     let version_row = sqlx::query(
-        "SELECT v.id as version_id, v.tool_id, v.version, v.changelog_md, v.instructions_md,
-            t.name, t.slug, t.description, t.category
+        "SELECT v.id as version_id, v.tool_id, v.version, v.created_at, v.changelog_md,
+            v.instructions_md, t.name, t.slug, t.description, t.category
       FROM custom_library_tool_versions v
       INNER JOIN custom_library_tools t ON t.id = v.tool_id
       WHERE v.id = ?1",
@@ -455,6 +1729,7 @@ pub async fn get_export_context(
             id: version_row.get("version_id"),
             tool_id,
             version: version_row.get("version"),
+            created_at: version_row.get("created_at"),
             changelog_md: version_row.get("changelog_md"),
             instructions_md: version_row.get("instructions_md"),
         },
@@ -462,8 +1737,324 @@ pub async fn get_export_context(
     })
 }
 
+pub async fn list_stored_files(pool: &SqlitePool) -> ToolsResult<Vec<StoredFileRecord>> {
+    let rows = sqlx::query(
+        "SELECT original_name, stored_rel_path, sha256, size_bytes
+      FROM custom_library_tool_files
+      ORDER BY stored_rel_path ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(map_stored_file_row).collect())
+}
+
+pub async fn list_file_paths(pool: &SqlitePool) -> ToolsResult<Vec<FilePathRecord>> {
+    let rows = sqlx::query(
+        "SELECT f.id, v.tool_id, f.tool_version_id, f.original_name, f.stored_rel_path
+      FROM custom_library_tool_files f
+      JOIN custom_library_tool_versions v ON v.id = f.tool_version_id
+      ORDER BY f.stored_rel_path ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| FilePathRecord {
+            file_id: row.get("id"),
+            tool_id: row.get("tool_id"),
+            tool_version_id: row.get("tool_version_id"),
+            original_name: row.get("original_name"),
+            stored_rel_path: row.get("stored_rel_path"),
+        })
+        .collect())
+}
+
+pub async fn list_stored_files_for_tool<'e, E: SqliteExecutor<'e>>(
+    executor: E,
+    tool_id: &str,
+) -> ToolsResult<Vec<StoredFileRecord>> {
+    let rows = sqlx::query(
+        "SELECT f.original_name, f.stored_rel_path, f.sha256, f.size_bytes
+      FROM custom_library_tool_files f
+      INNER JOIN custom_library_tool_versions v ON v.id = f.tool_version_id
+      WHERE v.tool_id = ?1
+      ORDER BY f.stored_rel_path ASC",
+    )
+    .bind(tool_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows.iter().map(map_stored_file_row).collect())
+}
+
+/// Groups a tool's files by content hash, listing the versions that reference each one.
+pub async fn list_distinct_files(
+    pool: &SqlitePool,
+    tool_id: &str,
+) -> ToolsResult<Vec<DistinctToolFile>> {
+    let rows = sqlx::query(
+        "SELECT lower(f.sha256) AS sha256, f.original_name, f.size_bytes, v.version
+      FROM custom_library_tool_files f
+      INNER JOIN custom_library_tool_versions v ON v.id = f.tool_version_id
+      WHERE v.tool_id = ?1
+      ORDER BY v.created_at ASC, f.original_name COLLATE NOCASE ASC",
+    )
+    .bind(tool_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut distinct: Vec<DistinctToolFile> = Vec::new();
+    for row in rows {
+        let sha256: String = row.get("sha256");
+        let version: String = row.get("version");
+        match distinct.iter_mut().find(|file| file.sha256 == sha256) {
+            Some(file) => {
+                if !file.versions.contains(&version) {
+                    file.versions.push(version);
+                }
+            }
+            None => distinct.push(DistinctToolFile {
+                sha256,
+                original_name: row.get("original_name"),
+                size_bytes: row.get("size_bytes"),
+                versions: vec![version],
+            }),
+        }
+    }
+
+    Ok(distinct)
+}
+
+pub async fn list_tool_ids(pool: &SqlitePool) -> ToolsResult<Vec<String>> {
+    let rows = sqlx::query("SELECT id FROM custom_library_tools ORDER BY name COLLATE NOCASE ASC")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.into_iter().map(|row| row.get("id")).collect())
+}
+
+pub async fn record_integrity_result(
+    pool: &SqlitePool,
+    tool_id: &str,
+    failed_files: usize,
+) -> ToolsResult<i64> {
+    let checked_at = now_epoch_millis()?;
+    let status = if failed_files == 0 { "ok" } else { "failed" };
+    sqlx::query(
+        "INSERT INTO custom_library_tool_integrity (tool_id, status, failed_files, checked_at)
+      VALUES (?1, ?2, ?3, ?4)
+      ON CONFLICT(tool_id) DO UPDATE SET
+        status = excluded.status,
+        failed_files = excluded.failed_files,
+        checked_at = excluded.checked_at",
+    )
+    .bind(tool_id)
+    .bind(status)
+    .bind(failed_files as i64)
+    .bind(checked_at)
+    .execute(pool)
+    .await?;
+
+    Ok(checked_at)
+}
+
+fn map_stored_file_row(row: &sqlx::sqlite::SqliteRow) -> StoredFileRecord {
+    StoredFileRecord {
+        original_name: row.get("original_name"),
+        stored_rel_path: row.get("stored_rel_path"),
+        sha256: row.get("sha256"),
+        size_bytes: row.get("size_bytes"),
+    }
+}
+
+pub async fn total_stored_bytes(pool: &SqlitePool) -> ToolsResult<u64> {
+    let total: i64 =
+        sqlx::query_scalar("SELECT COALESCE(SUM(size_bytes), 0) FROM custom_library_tool_files")
+            .fetch_one(pool)
+            .await?;
+    Ok(total.max(0) as u64)
+}
+
+pub async fn list_catalog_entries(pool: &SqlitePool) -> ToolsResult<Vec<CatalogEntry>> {
+    let rows = sqlx::query(
+        "SELECT t.id, t.name, t.slug, t.description, t.category, t.created_at, t.updated_at,
+            (SELECT COUNT(*) FROM custom_library_tool_versions v WHERE v.tool_id = t.id)
+              AS version_count,
+            (SELECT COALESCE(SUM(f.size_bytes), 0)
+               FROM custom_library_tool_files f
+               JOIN custom_library_tool_versions v ON v.id = f.tool_version_id
+              WHERE v.tool_id = t.id) AS total_size_bytes
+      FROM custom_library_tools t
+      ORDER BY t.name COLLATE NOCASE ASC, t.slug ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let tool_ids = rows
+        .iter()
+        .map(|row| row.get::<String, _>("id"))
+        .collect::<Vec<_>>();
+    let mut tags_by_tool = fetch_tags_for_tools(pool, &tool_ids).await?;
+    let mut latest_versions = fetch_latest_versions(pool, &tool_ids).await?;
+    let mut entries = Vec::with_capacity(rows.len());
+    for row in rows {
+        let tool_id: String = row.get("id");
+        entries.push(CatalogEntry {
+            name: row.get("name"),
+            slug: row.get("slug"),
+            description: row.get("description"),
+            category: row.get("category"),
+            tags: tags_by_tool.remove(&tool_id).unwrap_or_default(),
+            version_count: row.get("version_count"),
+            latest_version: latest_versions
+                .remove(&tool_id)
+                .map(|version| version.version),
+            total_size_bytes: row.get::<i64, _>("total_size_bytes").max(0) as u64,
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        });
+    }
+    Ok(entries)
+}
+
+/// Other tags found on tools tagged `tag`, most shared first. Tags compare case-insensitively.
+pub async fn tag_cooccurrence(
+    pool: &SqlitePool,
+    tag: &str,
+    limit: i64,
+) -> ToolsResult<Vec<TagCooccurrence>> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err(ToolsError::Validation("tag is required.".to_string()));
+    }
+    let rows = sqlx::query(
+        "SELECT MIN(other.tag) AS tag, COUNT(DISTINCT other.tool_id) AS tool_count
+      FROM custom_library_tool_tags base
+      JOIN custom_library_tool_tags other
+        ON other.tool_id = base.tool_id
+       AND other.tag <> base.tag COLLATE NOCASE
+      WHERE base.tag = ?1 COLLATE NOCASE
+      GROUP BY other.tag COLLATE NOCASE
+      ORDER BY tool_count DESC, MIN(other.tag) COLLATE NOCASE ASC
+      LIMIT ?2",
+    )
+    .bind(tag)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| TagCooccurrence {
+            tag: row.get("tag"),
+            tool_count: row.get("tool_count"),
+        })
+        .collect())
+}
+
+pub async fn list_icon_paths(pool: &SqlitePool) -> ToolsResult<Vec<String>> {
+    let rows = sqlx::query(
+        "SELECT icon_rel_path FROM custom_library_tools WHERE icon_rel_path IS NOT NULL",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| row.get("icon_rel_path"))
+        .collect())
+}
+
+async fn insert_tool(
+    conn: &mut SqliteConnection,
+    tool_id: &str,
+    metadata: &ValidatedMetadata,
+    now: i64,
+) -> ToolsResult<()> {
+    let slug = resolve_unique_slug(&mut *conn, &metadata.requested_slug, None).await?;
+    sqlx::query(
+        "INSERT INTO custom_library_tools (id, name, slug, description, category, created_at, updated_at)
+      VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )
+    .bind(tool_id)
+    .bind(&metadata.name)
+    .bind(&slug)
+    .bind(&metadata.description)
+    .bind(&metadata.category)
+    .bind(now)
+    .bind(now)
+    .execute(&mut *conn)
+    .await?;
+
+    for tag in &metadata.tags {
+        sqlx::query("INSERT INTO custom_library_tool_tags (tool_id, tag) VALUES (?1, ?2)")
+            .bind(tool_id)
+            .bind(tag)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn insert_version(
+    conn: &mut SqliteConnection,
+    tool_id: &str,
+    version_id: &str,
+    version: &ValidatedVersion,
+    files: &[FileRecordInsert],
+    now: i64,
+) -> ToolsResult<()> {
+    let version_hash = version_content_hash(
+        files.iter().map(|file| file.sha256.as_str()),
+        &version.instructions_md,
+        version.changelog_md.as_deref(),
+    );
+    sqlx::query(
+        "INSERT INTO custom_library_tool_versions (id, tool_id, version, changelog_md, instructions_md, version_hash, created_at)
+      VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )
+    .bind(version_id)
+    .bind(tool_id)
+    .bind(&version.version)
+    .bind(&version.changelog_md)
+    .bind(&version.instructions_md)
+    .bind(version_hash)
+    .bind(now)
+    .execute(conn)
+    .await
+    .map_err(map_version_insert_error)?;
+
+    Ok(())
+}
+
+/// The pre-insert checks run in the same transaction, so these only fire if a writer slips
+/// past them; either way the caller sees the same errors the checks would have returned.
+fn map_version_insert_error(error: sqlx::Error) -> ToolsError {
+    match &error {
+        sqlx::Error::Database(database_error) if database_error.is_unique_violation() => {
+            ToolsError::Conflict("Version already exists for this tool.".to_string())
+        }
+        sqlx::Error::Database(database_error) if database_error.is_foreign_key_violation() => {
+            ToolsError::NotFound("Tool not found.".to_string())
+        }
+        _ => error.into(),
+    }
+}
+
+async fn touch_tool(conn: &mut SqliteConnection, tool_id: &str, now: i64) -> ToolsResult<()> {
+    sqlx::query("UPDATE custom_library_tools SET updated_at = ?2 WHERE id = ?1")
+        .bind(tool_id)
+        .bind(now)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
 async fn insert_files(
-    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    conn: &mut SqliteConnection,
     version_id: &str,
     files: &[FileRecordInsert],
     created_at: i64,
@@ -474,8 +2065,8 @@ async fn insert_files(
         let file_id = Uuid::new_v4().to_string();
         sqlx::query(
             "INSERT INTO custom_library_tool_files
-        (id, tool_version_id, original_name, stored_rel_path, sha256, size_bytes, mime, created_at)
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        (id, tool_version_id, original_name, stored_rel_path, sha256, size_bytes, mime, created_at, note)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
         )
         .bind(file_id)
         .bind(version_id)
@@ -485,8 +2076,18 @@ async fn insert_files(
         .bind(file.size_bytes)
         .bind(&file.mime)
         .bind(created_at)
-        .execute(&mut **tx)
-        .await?;
+        .bind(normalize_file_note(file.note.as_deref())?)
+        .execute(&mut *conn)
+        .await
+        .map_err(|error| match &error {
+            sqlx::Error::Database(database_error) if database_error.is_unique_violation() => {
+                ToolsError::Conflict(format!(
+                    "{} is already a file in this version.",
+                    file.original_name
+                ))
+            }
+            _ => error.into(),
+        })?;
     }
 
     Ok(())
@@ -494,7 +2095,7 @@ async fn insert_files(
 
 async fn get_version_detail(pool: &SqlitePool, version_id: &str) -> ToolsResult<ToolVersionDetail> {
     let row = sqlx::query(
-        "SELECT id, tool_id, version, changelog_md, instructions_md, created_at
+        "SELECT id, tool_id, version, changelog_md, instructions_md, version_hash, created_at
       FROM custom_library_tool_versions
       WHERE id = ?1",
     )
@@ -511,17 +2112,30 @@ async fn get_version_detail(pool: &SqlitePool, version_id: &str) -> ToolsResult<
         version: row.get("version"),
         changelog_md: row.get("changelog_md"),
         instructions_md: row.get("instructions_md"),
+        version_hash: row.get("version_hash"),
         created_at: row.get("created_at"),
         files,
     })
 }
 
+pub async fn find_version_file(
+    pool: &SqlitePool,
+    version_id: &str,
+    original_name: &str,
+) -> ToolsResult<ToolFileDetail> {
+    fetch_files_for_version(pool, version_id)
+        .await?
+        .into_iter()
+        .find(|file| file.original_name == original_name)
+        .ok_or_else(|| ToolsError::NotFound(format!("File {original_name} not found in version.")))
+}
+
 async fn fetch_files_for_version(
     pool: &SqlitePool,
     version_id: &str,
 ) -> ToolsResult<Vec<ToolFileDetail>> {
     let rows = sqlx::query(
-        "SELECT id, original_name, stored_rel_path, sha256, size_bytes, mime, created_at
+        "SELECT id, original_name, stored_rel_path, sha256, size_bytes, mime, created_at, note
       FROM custom_library_tool_files
       WHERE tool_version_id = ?1
       ORDER BY original_name COLLATE NOCASE ASC",
@@ -540,6 +2154,7 @@ async fn fetch_files_for_version(
             size_bytes: row.get("size_bytes"),
             mime: row.get("mime"),
             created_at: row.get("created_at"),
+            note: row.get("note"),
         })
         .collect())
 }
@@ -561,105 +2176,289 @@ async fn fetch_tags(pool: &SqlitePool, tool_id: &str) -> ToolsResult<Vec<String>
         .collect())
 }
 
-async fn fetch_latest_version(
+/// Tags of each tool in `tool_ids`, keyed by tool id, in a single query. Tools without tags
+/// have no entry.
+async fn fetch_tags_for_tools(
     pool: &SqlitePool,
-    tool_id: &str,
-) -> ToolsResult<Option<VersionSummary>> {
-    let row = sqlx::query(
-        "SELECT id, version, created_at
+    tool_ids: &[String],
+) -> ToolsResult<HashMap<String, Vec<String>>> {
+    let rows = sqlx::query(
+        "SELECT tool_id, tag
+      FROM custom_library_tool_tags
+      WHERE tool_id IN (SELECT value FROM json_each(?1))
+      ORDER BY tag COLLATE NOCASE ASC",
+    )
+    .bind(serde_json::Value::from(tool_ids.to_vec()).to_string())
+    .fetch_all(pool)
+    .await?;
+
+    let mut tags = HashMap::<String, Vec<String>>::new();
+    for row in rows {
+        tags.entry(row.get("tool_id"))
+            .or_default()
+            .push(row.get("tag"));
+    }
+    Ok(tags)
+}
+
+/// Latest version of each tool in `tool_ids` with its file count, keyed by tool id, in a single
+/// query so listing N tools does not cost a round-trip per tool.
+async fn fetch_latest_versions(
+    pool: &SqlitePool,
+    tool_ids: &[String],
+) -> ToolsResult<HashMap<String, VersionSummary>> {
+    let rows = sqlx::query(
+        "SELECT ranked.tool_id, ranked.id, ranked.version, ranked.created_at,
+            COUNT(f.id) AS file_count
+      FROM (
+        SELECT tool_id, id, version, created_at,
+          ROW_NUMBER() OVER (
+            PARTITION BY tool_id ORDER BY created_at DESC, rowid DESC
+          ) AS recency
+        FROM custom_library_tool_versions
+        WHERE tool_id IN (SELECT value FROM json_each(?1))
+      ) ranked
+      LEFT JOIN custom_library_tool_files f ON f.tool_version_id = ranked.id
+      WHERE ranked.recency = 1
+      GROUP BY ranked.id",
+    )
+    .bind(serde_json::Value::from(tool_ids.to_vec()).to_string())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            (
+                row.get("tool_id"),
+                VersionSummary {
+                    id: row.get("id"),
+                    version: row.get("version"),
+                    file_count: row.get::<i64, _>("file_count") as usize,
+                    created_at: row.get("created_at"),
+                },
+            )
+        })
+        .collect())
+}
+
+fn quality_issues(tool: &ToolSummary) -> Vec<ToolQualityIssue> {
+    let mut issues = Vec::new();
+    if is_blank(&tool.description) {
+        issues.push(ToolQualityIssue::MissingDescription);
+    }
+    if tool.tags.iter().all(|tag| is_blank(tag)) {
+        issues.push(ToolQualityIssue::MissingTags);
+    }
+    let category = tool.category.trim_matches(BLANK_CHARS);
+    if category.is_empty()
+        || PLACEHOLDER_CATEGORIES
+            .iter()
+            .any(|placeholder| category.eq_ignore_ascii_case(placeholder))
+    {
+        issues.push(ToolQualityIssue::PlaceholderCategory);
+    }
+    issues
+}
+
+/// Files sharing a name within one version. The unique index on
+/// `(tool_version_id, lower(original_name))` prevents new ones; this finds rows that predate it
+/// or survived a dropped index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateFileName {
+    pub tool_version_id: String,
+    pub original_name: String,
+    pub file_count: i64,
+}
+
+pub async fn find_duplicate_file_names(pool: &SqlitePool) -> ToolsResult<Vec<DuplicateFileName>> {
+    let rows = sqlx::query(
+        "SELECT tool_version_id, MIN(original_name) AS original_name, COUNT(*) AS file_count
+      FROM custom_library_tool_files
+      GROUP BY tool_version_id, lower(original_name)
+      HAVING COUNT(*) > 1
+      ORDER BY tool_version_id, lower(original_name)",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| DuplicateFileName {
+            tool_version_id: row.get("tool_version_id"),
+            original_name: row.get("original_name"),
+            file_count: row.get("file_count"),
+        })
+        .collect())
+}
+
+/// Fills `version_hash` for versions created before the column existed.
+async fn backfill_version_hashes(conn: &mut SqliteConnection) -> ToolsResult<()> {
+    let rows = sqlx::query(
+        "SELECT id, instructions_md, changelog_md
       FROM custom_library_tool_versions
-      WHERE tool_id = ?1
-      ORDER BY created_at DESC
-      LIMIT 1",
+      WHERE version_hash IS NULL",
     )
-    .bind(tool_id)
-    .fetch_optional(pool)
+    .fetch_all(&mut *conn)
     .await?;
 
-    let Some(row) = row else {
-        return Ok(None);
-    };
+    for row in rows {
+        let version_id: String = row.get("id");
+        let file_hashes: Vec<String> = sqlx::query_scalar(
+            "SELECT sha256 FROM custom_library_tool_files WHERE tool_version_id = ?1",
+        )
+        .bind(&version_id)
+        .fetch_all(&mut *conn)
+        .await?;
+        let changelog_md: Option<String> = row.get("changelog_md");
+        let version_hash = version_content_hash(
+            file_hashes.iter().map(String::as_str),
+            &row.get::<String, _>("instructions_md"),
+            changelog_md.as_deref(),
+        );
+        sqlx::query("UPDATE custom_library_tool_versions SET version_hash = ?2 WHERE id = ?1")
+            .bind(&version_id)
+            .bind(version_hash)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
 
-    let version_id: String = row.get("id");
-    let file_count: i64 = sqlx::query_scalar(
-        "SELECT COUNT(*) FROM custom_library_tool_files WHERE tool_version_id = ?1",
+/// Applies each pending migration in its own transaction together with its tracking row, so a
+/// crash mid-migration rolls back to the last fully applied version.
+async fn apply_library_migrations(
+    pool: &SqlitePool,
+    migrations: &[LibraryMigration],
+) -> ToolsResult<()> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS custom_library_schema_migrations (
+        version INTEGER PRIMARY KEY,
+        description TEXT NOT NULL,
+        applied_at INTEGER NOT NULL
+      )",
     )
-    .bind(&version_id)
-    .fetch_one(pool)
+    .execute(pool)
     .await?;
 
-    Ok(Some(VersionSummary {
-        id: version_id,
-        version: row.get("version"),
-        file_count: file_count as usize,
-        created_at: row.get("created_at"),
-    }))
-}
+    for migration in migrations {
+        let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+        let applied =
+            sqlx::query("SELECT 1 FROM custom_library_schema_migrations WHERE version = ?1")
+                .bind(migration.version)
+                .fetch_optional(&mut *tx)
+                .await?
+                .is_some();
+        if applied {
+            continue;
+        }
 
-fn matches_filters(tool: &ToolSummary, filters: &ToolListFilters) -> bool {
-    let category_match = filters
-        .category
-        .as_ref()
-        .map(|category| {
-            category.trim().is_empty() || tool.category.eq_ignore_ascii_case(category.trim())
-        })
-        .unwrap_or(true);
-    if !category_match {
-        return false;
+        let failed = |error: &dyn std::fmt::Display| {
+            ToolsError::Database(format!(
+                "Migration {} ({}) failed: {error}",
+                migration.version, migration.description
+            ))
+        };
+        for sql in batch_statements(migration.sql) {
+            sqlx::query(&sql)
+                .execute(&mut *tx)
+                .await
+                .map_err(|error| failed(&error))?;
+        }
+        if let Some(LibraryBackfill::VersionHashes) = migration.backfill {
+            backfill_version_hashes(&mut tx)
+                .await
+                .map_err(|error| failed(&error.user_message()))?;
+        }
+        sqlx::query(
+            "INSERT INTO custom_library_schema_migrations (version, description, applied_at)
+          VALUES (?1, ?2, ?3)",
+        )
+        .bind(migration.version)
+        .bind(migration.description)
+        .bind(now_epoch_millis()?)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
     }
 
-    let tag_match = filters
-        .tag
-        .as_ref()
-        .map(|tag| {
-            let needle = tag.trim().to_ascii_lowercase();
-            needle.is_empty()
-                || tool
-                    .tags
-                    .iter()
-                    .any(|value| value.to_ascii_lowercase() == needle)
-        })
-        .unwrap_or(true);
-    if !tag_match {
-        return false;
-    }
+    Ok(())
+}
 
-    filters
-        .query
-        .as_ref()
-        .map(|query| {
-            let needle = query.trim().to_ascii_lowercase();
-            if needle.is_empty() {
-                return true;
-            }
+/// Splits a migration file into statements. Comment lines are dropped first so a `;` inside a
+/// comment does not cut a statement in half.
+fn batch_statements(sql_batch: &str) -> Vec<String> {
+    let without_comments = sql_batch
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("--"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    without_comments
+        .split(';')
+        .map(str::trim)
+        .filter(|sql| !sql.is_empty())
+        .map(str::to_string)
+        .collect()
+}
 
-            let mut haystack = vec![
-                tool.name.to_ascii_lowercase(),
-                tool.slug.to_ascii_lowercase(),
-                tool.description.to_ascii_lowercase(),
-                tool.category.to_ascii_lowercase(),
-            ];
-            haystack.extend(tool.tags.iter().map(|tag| tag.to_ascii_lowercase()));
-            if let Some(version) = &tool.latest_version {
-                haystack.push(version.version.to_ascii_lowercase());
-            }
+/// Fills an empty category or tag list from `defaults`; anything the caller supplied is kept.
+fn apply_metadata_defaults(
+    mut metadata: ToolMetadataInput,
+    defaults: &ToolMetadataDefaults,
+) -> ToolMetadataInput {
+    if metadata.category.trim().is_empty() {
+        if let Some(category) = &defaults.category {
+            metadata.category = category.clone();
+        }
+    }
+    if metadata.tags.iter().all(|tag| tag.trim().is_empty()) {
+        metadata.tags = defaults.tags.clone();
+    }
+    metadata
+}
 
-            haystack.iter().any(|value| value.contains(&needle))
-        })
-        .unwrap_or(true)
+/// Validates and stores the defaults for new tools; an empty category clears it.
+pub async fn set_metadata_defaults(
+    pool: &SqlitePool,
+    category: Option<String>,
+    tags: Vec<String>,
+) -> ToolsResult<ToolMetadataDefaults> {
+    let category = category
+        .filter(|value| !value.trim().is_empty())
+        .map(|value| validate_label("category", &value, 120))
+        .transpose()?;
+    let defaults = ToolMetadataDefaults {
+        category,
+        tags: normalize_tags(&tags)?,
+    };
+    settings::write_metadata_defaults(pool, &defaults).await?;
+    Ok(defaults)
 }
 
-async fn execute_batch(pool: &SqlitePool, sql_batch: &str) -> ToolsResult<()> {
-    for statement in sql_batch.split(';') {
-        let sql = statement.trim();
-        if sql.is_empty() {
-            continue;
-        }
+fn validate_metadata(metadata: ToolMetadataInput) -> ToolsResult<ValidatedMetadata> {
+    let name = validate_required("name", &metadata.name, 120)?;
+    let requested_slug = metadata
+        .slug
+        .as_ref()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| slugify(&name));
 
-        sqlx::query(sql).execute(pool).await?;
-    }
+    Ok(ValidatedMetadata {
+        description: validate_required("description", &metadata.description, 8_000)?,
+        category: validate_label("category", &metadata.category, 120)?,
+        tags: normalize_tags(&metadata.tags)?,
+        name,
+        requested_slug,
+    })
+}
 
-    Ok(())
+fn validate_version(version: VersionInsertInput) -> ToolsResult<ValidatedVersion> {
+    Ok(ValidatedVersion {
+        version: validate_required("version", &normalize_version_label(&version.version), 80)?,
+        instructions_md: validate_required("instructions", &version.instructions_md, 512 * 1024)?,
+        changelog_md: normalize_optional_text(version.changelog_md, 512 * 1024)?,
+    })
 }
 
 fn validate_required(field: &str, value: &str, max_len: usize) -> ToolsResult<String> {
@@ -694,6 +2493,44 @@ fn normalize_optional_text(value: Option<String>, max_len: usize) -> ToolsResult
     Ok(Some(trimmed.to_string()))
 }
 
+fn validate_label(field: &str, value: &str, max_len: usize) -> ToolsResult<String> {
+    let trimmed = validate_required(field, value, max_len)?;
+    reject_control_characters(field, &trimmed)?;
+    Ok(trimmed)
+}
+
+/// Tags and categories end up in filter chips and CSV exports, where newlines or NULs break rows.
+/// Trims a file note; blank notes clear it. Line breaks and tabs are allowed, other control
+/// characters are not.
+pub(crate) fn normalize_file_note(note: Option<&str>) -> ToolsResult<Option<String>> {
+    let Some(trimmed) = note.map(str::trim).filter(|note| !note.is_empty()) else {
+        return Ok(None);
+    };
+    if trimmed.chars().count() > MAX_FILE_NOTE_CHARS {
+        return Err(ToolsError::Validation(format!(
+            "File note exceeds {MAX_FILE_NOTE_CHARS} characters."
+        )));
+    }
+    if trimmed
+        .chars()
+        .any(|character| character.is_control() && !matches!(character, '\n' | '\r' | '\t'))
+    {
+        return Err(ToolsError::Validation(
+            "File note contains control characters.".to_string(),
+        ));
+    }
+    Ok(Some(trimmed.to_string()))
+}
+
+fn reject_control_characters(field: &str, value: &str) -> ToolsResult<()> {
+    if value.chars().any(char::is_control) {
+        return Err(ToolsError::Validation(format!(
+            "{field} {value:?} contains control characters."
+        )));
+    }
+    Ok(())
+}
+
 fn normalize_tags(tags: &[String]) -> ToolsResult<Vec<String>> {
     let mut seen = std::collections::HashSet::new();
     let mut normalized = Vec::new();
@@ -708,6 +2545,7 @@ fn normalize_tags(tags: &[String]) -> ToolsResult<Vec<String>> {
                 "Tag exceeds 48 characters.".to_string(),
             ));
         }
+        reject_control_characters("tag", trimmed)?;
 
         let lowered = trimmed.to_ascii_lowercase();
         if seen.insert(lowered) {
@@ -719,7 +2557,7 @@ fn normalize_tags(tags: &[String]) -> ToolsResult<Vec<String>> {
     Ok(normalized)
 }
 
-fn slugify(value: &str) -> String {
+pub(crate) fn slugify(value: &str) -> String {
     let lowered = value.to_ascii_lowercase();
     let mut slug = String::with_capacity(lowered.len());
     let mut previous_dash = false;
@@ -746,7 +2584,7 @@ fn slugify(value: &str) -> String {
 }
 
 async fn resolve_unique_slug(
-    pool: &SqlitePool,
+    conn: &mut SqliteConnection,
     requested_slug: &str,
     exclude_tool_id: Option<&str>,
 ) -> ToolsResult<String> {
@@ -759,13 +2597,13 @@ async fn resolve_unique_slug(
             sqlx::query("SELECT 1 FROM custom_library_tools WHERE slug = ?1 AND id <> ?2")
                 .bind(&candidate)
                 .bind(exclude_id)
-                .fetch_optional(pool)
+                .fetch_optional(&mut *conn)
                 .await?
                 .is_some()
         } else {
             sqlx::query("SELECT 1 FROM custom_library_tools WHERE slug = ?1")
                 .bind(&candidate)
-                .fetch_optional(pool)
+                .fetch_optional(&mut *conn)
                 .await?
                 .is_some()
         };
@@ -779,9 +2617,1739 @@ async fn resolve_unique_slug(
     }
 }
 
-fn now_epoch_millis() -> ToolsResult<i64> {
+pub(crate) fn now_epoch_millis() -> ToolsResult<i64> {
     let duration = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map_err(|error| ToolsError::Io(format!("System clock error: {error}")))?;
     Ok(duration.as_millis() as i64)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        apply_library_migrations(&pool, LIBRARY_MIGRATIONS)
+            .await
+            .unwrap();
+        pool
+    }
+
+    fn import_inputs(version: &str) -> (ToolMetadataInput, VersionInsertInput) {
+        (
+            ToolMetadataInput {
+                name: "CAD Toolset".to_string(),
+                slug: Some("cad-toolset".to_string()),
+                description: "CAD helpers".to_string(),
+                category: "cad".to_string(),
+                tags: vec!["autocad".to_string()],
+            },
+            VersionInsertInput {
+                version: version.to_string(),
+                changelog_md: None,
+                instructions_md: "# install".to_string(),
+            },
+        )
+    }
+
+    #[test]
+    fn checkpoint_truncates_the_wal_file() {
+        tauri::async_runtime::block_on(async {
+            let dir = std::env::temp_dir().join(format!("tools-wal-{}", Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let db_path = dir.join(DB_FILE_NAME);
+            let wal_path = dir.join(format!("{DB_FILE_NAME}-wal"));
+            let pool = sqlx::sqlite::SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect_with(
+                    sqlx::sqlite::SqliteConnectOptions::new()
+                        .filename(&db_path)
+                        .create_if_missing(true)
+                        .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal),
+                )
+                .await
+                .unwrap();
+            apply_library_migrations(&pool, LIBRARY_MIGRATIONS)
+                .await
+                .unwrap();
+            assert!(std::fs::metadata(&wal_path).unwrap().len() > 0);
+
+            let checkpoint = checkpoint_wal(&pool).await.unwrap();
+            assert!(!checkpoint.busy);
+            assert_eq!(checkpoint.wal_frames, checkpoint.checkpointed_frames);
+            assert_eq!(std::fs::metadata(&wal_path).unwrap().len(), 0);
+
+            let rollback = create_test_pool().await;
+            let checkpoint = checkpoint_wal(&rollback).await.unwrap();
+            assert_eq!(checkpoint.wal_frames, -1);
+
+            pool.close().await;
+            let _ = std::fs::remove_dir_all(dir);
+        });
+    }
+
+    #[test]
+    fn library_migrations_apply_atomically_and_only_once() {
+        tauri::async_runtime::block_on(async {
+            let pool = sqlx::sqlite::SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect("sqlite::memory:")
+                .await
+                .unwrap();
+            apply_library_migrations(&pool, LIBRARY_MIGRATIONS)
+                .await
+                .unwrap();
+            apply_library_migrations(&pool, LIBRARY_MIGRATIONS)
+                .await
+                .unwrap();
+            let applied: i64 =
+                sqlx::query_scalar("SELECT COUNT(*) FROM custom_library_schema_migrations")
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+            assert_eq!(applied, LIBRARY_MIGRATIONS.len() as i64);
+
+            let broken = [LibraryMigration {
+                version: 99,
+                description: "broken",
+                sql: "CREATE TABLE half_done (id TEXT); INSERT INTO missing_table VALUES (1)",
+                backfill: None,
+            }];
+            let error = apply_library_migrations(&pool, &broken).await.unwrap_err();
+            assert!(error.user_message().contains("Migration 99"));
+            let leftovers: i64 = sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'half_done'
+                 UNION ALL SELECT COUNT(*) FROM custom_library_schema_migrations WHERE version = 99",
+            )
+            .fetch_all(&pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .sum();
+            assert_eq!(leftovers, 0);
+        });
+    }
+
+    #[test]
+    fn version_labels_compare_after_normalization() {
+        assert_eq!(normalize_version_label(" 1.0.0\u{00A0}"), "1.0.0");
+        assert_eq!(normalize_version_label("1.0\u{200B}.0"), "1.0.0");
+        assert_eq!(normalize_version_label("beta\u{3000}\t 2"), "beta 2");
+        assert_eq!(
+            normalize_version_label("cafe\u{0301}"),
+            normalize_version_label("caf\u{00E9}")
+        );
+
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let (metadata, version) = import_inputs("caf\u{00E9} 1.0");
+            create_tool_with_version(&pool, "tool_1", "version_1", metadata, version, &[])
+                .await
+                .unwrap();
+
+            for variant in [
+                "cafe\u{0301} 1.0",
+                "caf\u{00E9}\u{00A0}1.0 ",
+                "caf\u{00E9}  1.0",
+            ] {
+                let (_, version) = import_inputs(variant);
+                let error = add_version_with_files(&pool, "tool_1", "version_2", version, &[])
+                    .await
+                    .unwrap_err();
+                assert!(matches!(error, ToolsError::Conflict(_)), "{variant:?}");
+            }
+        });
+    }
+
+    #[test]
+    fn metadata_defaults_fill_only_missing_category_and_tags() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+
+            let (mut metadata, version) = import_inputs("1.0.0");
+            metadata.category = String::new();
+            let error = create_tool_with_version(
+                &pool,
+                "tool_1",
+                "version_1",
+                metadata.clone(),
+                version.clone(),
+                &[],
+            )
+            .await
+            .unwrap_err();
+            assert!(error.user_message().contains("category is required"));
+
+            let defaults = set_metadata_defaults(
+                &pool,
+                Some(" Drafting ".to_string()),
+                vec!["Standards".to_string(), "standards".to_string()],
+            )
+            .await
+            .unwrap();
+            assert_eq!(defaults.category.as_deref(), Some("Drafting"));
+            assert_eq!(defaults.tags, vec!["Standards"]);
+            assert_eq!(
+                settings::load_metadata_defaults(&pool).await.unwrap(),
+                defaults
+            );
+
+            metadata.tags.clear();
+            create_tool_with_version(&pool, "tool_1", "version_1", metadata, version, &[])
+                .await
+                .unwrap();
+            let filled = get_tool_detail(&pool, "tool_1").await.unwrap();
+            assert_eq!(filled.category, "Drafting");
+            assert_eq!(filled.tags, vec!["Standards"]);
+
+            let (mut explicit, version) = import_inputs("1.0.0");
+            explicit.slug = Some("explicit".to_string());
+            create_tool_with_version(&pool, "tool_2", "version_2", explicit, version, &[])
+                .await
+                .unwrap();
+            let kept = get_tool_detail(&pool, "tool_2").await.unwrap();
+            assert_eq!(kept.category, "cad");
+            assert_eq!(kept.tags, vec!["autocad"]);
+
+            set_metadata_defaults(&pool, None, Vec::new())
+                .await
+                .unwrap();
+            assert_eq!(
+                settings::load_metadata_defaults(&pool).await.unwrap(),
+                ToolMetadataDefaults::default()
+            );
+        });
+    }
+
+    #[test]
+    fn recently_viewed_order_puts_unviewed_tools_last() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            for (tool_id, slug) in [("tool_1", "alpha"), ("tool_2", "beta"), ("tool_3", "gamma")] {
+                let (mut metadata, version) = import_inputs("1.0.0");
+                metadata.name = slug.to_string();
+                metadata.slug = Some(slug.to_string());
+                create_tool_with_version(
+                    &pool,
+                    tool_id,
+                    &format!("{tool_id}_v1"),
+                    metadata,
+                    version,
+                    &[],
+                )
+                .await
+                .unwrap();
+            }
+            record_tool_view(&pool, "tool_1").await.unwrap();
+            record_tool_view(&pool, "tool_3").await.unwrap();
+            sqlx::query(
+                "UPDATE custom_library_tool_views SET last_viewed_at = 1 WHERE tool_id = 'tool_1'",
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+            let error = record_tool_view(&pool, "missing").await.unwrap_err();
+            assert!(matches!(error, ToolsError::NotFound(_)));
+
+            let listed = list_tools(
+                &pool,
+                ToolListFilters {
+                    query: None,
+                    category: None,
+                    tag: None,
+                    order_by: ToolListOrder::RecentlyViewed,
+                    quality_issues: false,
+                },
+            )
+            .await
+            .unwrap();
+            let order = listed
+                .iter()
+                .map(|tool| (tool.id.as_str(), tool.last_viewed_at.is_some()))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                order,
+                vec![("tool_3", true), ("tool_1", true), ("tool_2", false)]
+            );
+        });
+    }
+
+    /// The in-memory filter `list_tools` used before filtering moved into SQL.
+    fn matches_filters(tool: &ToolSummary, filters: &ToolListFilters) -> bool {
+        if filters.quality_issues && tool.quality_issues.is_empty() {
+            return false;
+        }
+
+        let category_match = filters
+            .category
+            .as_ref()
+            .map(|category| {
+                category.trim().is_empty() || tool.category.eq_ignore_ascii_case(category.trim())
+            })
+            .unwrap_or(true);
+        if !category_match {
+            return false;
+        }
+
+        let tag_match = filters
+            .tag
+            .as_ref()
+            .map(|tag| {
+                let needle = tag.trim().to_ascii_lowercase();
+                needle.is_empty()
+                    || tool
+                        .tags
+                        .iter()
+                        .any(|value| value.to_ascii_lowercase() == needle)
+            })
+            .unwrap_or(true);
+        if !tag_match {
+            return false;
+        }
+
+        filters
+            .query
+            .as_ref()
+            .map(|query| {
+                let needle = query.trim().to_ascii_lowercase();
+                if needle.is_empty() {
+                    return true;
+                }
+
+                let mut haystack = vec![
+                    tool.name.to_ascii_lowercase(),
+                    tool.slug.to_ascii_lowercase(),
+                    tool.description.to_ascii_lowercase(),
+                    tool.category.to_ascii_lowercase(),
+                ];
+                haystack.extend(tool.tags.iter().map(|tag| tag.to_ascii_lowercase()));
+                if let Some(version) = &tool.latest_version {
+                    haystack.push(version.version.to_ascii_lowercase());
+                }
+
+                haystack.iter().any(|value| value.contains(&needle))
+            })
+            .unwrap_or(true)
+    }
+
+    #[test]
+    fn sql_filters_match_the_in_memory_filter() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let tools = [
+                (
+                    "Layer Fixer",
+                    "CAD",
+                    vec!["AutoCAD", "layers"],
+                    "Fixes 100% of layers",
+                ),
+                (
+                    "Plot Stamp",
+                    "cad",
+                    vec!["plotting"],
+                    "Adds a plot_stamp block",
+                ),
+                ("Notes", "general", vec![], "Annotation helpers"),
+                (
+                    "Dim Tools",
+                    "Annotation",
+                    vec!["autocad"],
+                    "Dimension styles",
+                ),
+                ("R\u{e9}sum\u{e9}", "Docs", vec!["Lisp"], "Caf\u{c9} menu"),
+            ];
+            for (index, (name, category, tags, description)) in tools.iter().enumerate() {
+                let (mut metadata, version) = import_inputs(&format!("{}.0.0", index + 1));
+                metadata.name = name.to_string();
+                metadata.slug = Some(format!("tool-{index}"));
+                metadata.category = category.to_string();
+                metadata.tags = tags.iter().map(|tag| tag.to_string()).collect();
+                metadata.description = description.to_string();
+                create_tool_with_version(
+                    &pool,
+                    &format!("tool_{index}"),
+                    &format!("version_{index}"),
+                    metadata,
+                    version,
+                    &[],
+                )
+                .await
+                .unwrap();
+            }
+            let (_, version) = import_inputs("7.2.0-beta");
+            add_version_with_files(&pool, "tool_3", "version_3b", version, &[])
+                .await
+                .unwrap();
+            // Tab and newline are blank to both filters; a no-break space is not.
+            sqlx::query(
+                "UPDATE custom_library_tools SET description = char(9, 32, 10) WHERE id = 'tool_4'",
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+            sqlx::query(
+                "UPDATE custom_library_tools SET description = char(160) WHERE id = 'tool_2'",
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+
+            let filter =
+                |query: Option<&str>, category: Option<&str>, tag: Option<&str>| ToolListFilters {
+                    query: query.map(str::to_string),
+                    category: category.map(str::to_string),
+                    tag: tag.map(str::to_string),
+                    ..ToolListFilters::default()
+                };
+            let mut cases = vec![
+                filter(None, None, None),
+                filter(Some("layer"), None, None),
+                filter(Some("  AUTOCAD "), None, None),
+                filter(Some("100%"), None, None),
+                filter(Some("%"), None, None),
+                filter(Some("plot_"), None, None),
+                filter(Some("t_stamp"), None, None),
+                filter(Some("7.2"), None, None),
+                filter(Some("4.0.0"), None, None),
+                filter(Some("caf\u{c9}"), None, None),
+                filter(Some("caf\u{e9}"), None, None),
+                filter(Some("tool-"), Some("cad"), None),
+                filter(None, Some(" CAD "), Some("autocad")),
+                filter(None, Some("  "), Some("  ")),
+                filter(None, None, Some("LISP")),
+                filter(Some("nothing matches"), None, None),
+            ];
+            cases.push(ToolListFilters {
+                quality_issues: true,
+                ..ToolListFilters::default()
+            });
+            cases.push(ToolListFilters {
+                quality_issues: true,
+                query: Some("notes".to_string()),
+                order_by: ToolListOrder::RecentlyViewed,
+                ..ToolListFilters::default()
+            });
+
+            for filters in cases {
+                let everything = list_tools(
+                    &pool,
+                    ToolListFilters {
+                        order_by: filters.order_by,
+                        ..ToolListFilters::default()
+                    },
+                )
+                .await
+                .unwrap();
+                let expected = everything
+                    .iter()
+                    .filter(|tool| matches_filters(tool, &filters))
+                    .map(|tool| tool.id.clone())
+                    .collect::<Vec<_>>();
+                let listed = list_tools(&pool, filters.clone())
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|tool| tool.id)
+                    .collect::<Vec<_>>();
+                assert_eq!(listed, expected, "filters {filters:?}");
+            }
+        });
+    }
+
+    #[test]
+    fn pages_partition_the_filtered_list_in_a_stable_order() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            for index in 0..7 {
+                let (mut metadata, version) = import_inputs("1.0.0");
+                metadata.slug = Some(format!("tool-{index}"));
+                metadata.category = if index == 3 { "other" } else { "cad" }.to_string();
+                create_tool_with_version(
+                    &pool,
+                    &format!("tool_{index}"),
+                    &format!("version_{index}"),
+                    metadata,
+                    version,
+                    &[],
+                )
+                .await
+                .unwrap();
+            }
+            // Identical names and timestamps leave only the id to order by.
+            sqlx::query("UPDATE custom_library_tools SET updated_at = 5")
+                .execute(&pool)
+                .await
+                .unwrap();
+            let cad = || ToolListFilters {
+                category: Some("cad".to_string()),
+                ..ToolListFilters::default()
+            };
+
+            let mut paged = Vec::new();
+            for page in 1..=3 {
+                let listed = list_tools_page(&pool, cad(), Some(page), Some(4))
+                    .await
+                    .unwrap();
+                assert_eq!(listed.total, 6);
+                assert_eq!(listed.page, page);
+                paged.extend(listed.tools.into_iter().map(|tool| tool.id));
+            }
+            assert_eq!(
+                paged,
+                vec!["tool_0", "tool_1", "tool_2", "tool_4", "tool_5", "tool_6"]
+            );
+            let again = list_tools_page(&pool, cad(), Some(2), Some(4))
+                .await
+                .unwrap();
+            let ids = again
+                .tools
+                .iter()
+                .map(|tool| tool.id.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(ids, vec!["tool_5", "tool_6"]);
+
+            let whole = list_tools_page(&pool, ToolListFilters::default(), None, None)
+                .await
+                .unwrap();
+            assert_eq!((whole.tools.len(), whole.total), (7, 7));
+            assert!(list_tools_page(&pool, cad(), Some(0), Some(4))
+                .await
+                .is_err());
+            assert!(list_tools_page(&pool, cad(), None, Some(0)).await.is_err());
+            assert!(
+                list_tools_page(&pool, cad(), None, Some(MAX_TOOL_PAGE_SIZE + 1))
+                    .await
+                    .is_err()
+            );
+        });
+    }
+
+    #[test]
+    fn grouped_list_merges_categories_case_insensitively_and_keeps_list_order() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            for (index, category) in ["cad", "Annotation", "CAD", "cad"].iter().enumerate() {
+                let (mut metadata, version) = import_inputs("1.0.0");
+                metadata.name = format!("Tool {index}");
+                metadata.slug = Some(format!("tool-{index}"));
+                metadata.category = category.to_string();
+                create_tool_with_version(
+                    &pool,
+                    &format!("tool_{index}"),
+                    &format!("version_{index}"),
+                    metadata,
+                    version,
+                    &[],
+                )
+                .await
+                .unwrap();
+            }
+
+            let listed = list_tools(&pool, ToolListFilters::default()).await.unwrap();
+            let groups = list_tools_grouped(&pool, ToolListFilters::default())
+                .await
+                .unwrap();
+
+            assert_eq!(groups.len(), 2);
+            assert_eq!(groups[0].category, "Annotation");
+            assert_eq!(groups[0].tools.len(), 1);
+            let cad_ids = groups[1]
+                .tools
+                .iter()
+                .map(|tool| tool.id.as_str())
+                .collect::<Vec<_>>();
+            let expected = listed
+                .iter()
+                .filter(|tool| tool.category.eq_ignore_ascii_case("cad"))
+                .map(|tool| tool.id.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(cad_ids, expected);
+            assert_eq!(
+                groups[1].category,
+                listed
+                    .iter()
+                    .find(|tool| tool.category.eq_ignore_ascii_case("cad"))
+                    .unwrap()
+                    .category
+            );
+        });
+    }
+
+    #[test]
+    fn list_tools_reports_latest_version_and_file_count_per_tool() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let file = |tool_id: &str, version_id: &str, name: &str| FileRecordInsert {
+                original_name: name.to_string(),
+                stored_rel_path: format!("tools/{tool_id}/{version_id}/files/{name}"),
+                sha256: "abc".to_string(),
+                size_bytes: 3,
+                mime: None,
+                note: None,
+            };
+            let (metadata, version) = import_inputs("1.0.0");
+            create_tool_with_version(&pool, "tool_1", "version_1", metadata, version, &[])
+                .await
+                .unwrap();
+            let (_, version) = import_inputs("1.1.0");
+            add_version_with_files(
+                &pool,
+                "tool_1",
+                "version_2",
+                version,
+                &[
+                    file("tool_1", "version_2", "a.lsp"),
+                    file("tool_1", "version_2", "b.lsp"),
+                ],
+            )
+            .await
+            .unwrap();
+            let (mut metadata, version) = import_inputs("3.0.0");
+            metadata.name = "Other".to_string();
+            metadata.slug = Some("other".to_string());
+            create_tool_with_version(
+                &pool,
+                "tool_2",
+                "version_3",
+                metadata,
+                version,
+                &[file("tool_2", "version_3", "c.lsp")],
+            )
+            .await
+            .unwrap();
+
+            let mut listed = list_tools(
+                &pool,
+                ToolListFilters {
+                    query: None,
+                    category: None,
+                    tag: None,
+                    order_by: ToolListOrder::Updated,
+                    quality_issues: false,
+                },
+            )
+            .await
+            .unwrap();
+            listed.sort_by(|left, right| left.id.cmp(&right.id));
+            let latest = listed
+                .iter()
+                .map(|tool| {
+                    let version = tool.latest_version.as_ref().unwrap();
+                    (
+                        version.id.as_str(),
+                        version.version.as_str(),
+                        version.file_count,
+                    )
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                latest,
+                vec![("version_2", "1.1.0", 2), ("version_3", "3.0.0", 1)]
+            );
+        });
+    }
+
+    #[test]
+    fn file_notes_round_trip_and_are_validated() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let (metadata, version) = import_inputs("1.0.0");
+            let file = FileRecordInsert {
+                original_name: "shim.lsp".to_string(),
+                stored_rel_path: "tools/tool_1/version_1/files/shim.lsp".to_string(),
+                sha256: "abc".to_string(),
+                size_bytes: 3,
+                mime: None,
+                note: Some("  legacy compat shim  ".to_string()),
+            };
+            create_tool_with_version(&pool, "tool_1", "version_1", metadata, version, &[file])
+                .await
+                .unwrap();
+            let detail = get_tool_detail(&pool, "tool_1").await.unwrap();
+            let file_id = detail.versions[0].files[0].id.clone();
+            assert_eq!(
+                detail.versions[0].files[0].note.as_deref(),
+                Some("legacy compat shim")
+            );
+
+            set_file_note(
+                &pool,
+                "version_1",
+                &file_id,
+                Some("remove in v3\nafter rollout"),
+            )
+            .await
+            .unwrap();
+            assert_eq!(
+                get_file_note(&pool, "version_1", &file_id).await.unwrap(),
+                Some("remove in v3\nafter rollout".to_string())
+            );
+            set_file_note(&pool, "version_1", &file_id, Some("   "))
+                .await
+                .unwrap();
+            assert_eq!(
+                get_file_note(&pool, "version_1", &file_id).await.unwrap(),
+                None
+            );
+
+            let too_long = "x".repeat(MAX_FILE_NOTE_CHARS + 1);
+            assert!(set_file_note(&pool, "version_1", &file_id, Some(&too_long))
+                .await
+                .is_err());
+            assert!(
+                set_file_note(&pool, "version_1", &file_id, Some("bell\u{7}"))
+                    .await
+                    .is_err()
+            );
+            let error = set_file_note(&pool, "version_2", &file_id, Some("note"))
+                .await
+                .unwrap_err();
+            assert!(matches!(error, ToolsError::NotFound(_)));
+        });
+    }
+
+    #[test]
+    fn quality_filter_reports_missing_metadata() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let (metadata, version) = import_inputs("1.0.0");
+            create_tool_with_version(&pool, "tool_1", "version_1", metadata, version, &[])
+                .await
+                .unwrap();
+            let (mut metadata, version) = import_inputs("1.0.0");
+            metadata.name = "Loose Ends".to_string();
+            metadata.slug = Some("loose-ends".to_string());
+            metadata.category = "General".to_string();
+            metadata.tags = Vec::new();
+            create_tool_with_version(&pool, "tool_2", "version_2", metadata, version, &[])
+                .await
+                .unwrap();
+
+            let listed = list_tools(
+                &pool,
+                ToolListFilters {
+                    query: None,
+                    category: None,
+                    tag: None,
+                    order_by: ToolListOrder::Updated,
+                    quality_issues: true,
+                },
+            )
+            .await
+            .unwrap();
+            assert_eq!(listed.len(), 1);
+            assert_eq!(listed[0].id, "tool_2");
+            assert_eq!(
+                listed[0].quality_issues,
+                vec![
+                    ToolQualityIssue::MissingTags,
+                    ToolQualityIssue::PlaceholderCategory
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn semver_order_ranks_versions_and_trails_unparseable_labels() {
+        let version = |label: &str| ToolVersionDetail {
+            id: label.to_string(),
+            tool_id: "tool_1".to_string(),
+            version: label.to_string(),
+            changelog_md: None,
+            instructions_md: String::new(),
+            version_hash: None,
+            created_at: 0,
+            files: Vec::new(),
+        };
+        // Newest upload first, as loaded from the database.
+        let mut versions = ["1.9.1", "beta", "2.0.0", "v1.10.0", "2.0.0-rc.1", "nightly"]
+            .map(version)
+            .to_vec();
+        sort_versions_semver(&mut versions);
+
+        let order = versions
+            .iter()
+            .map(|version| version.version.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            vec!["2.0.0", "2.0.0-rc.1", "v1.10.0", "1.9.1", "beta", "nightly"]
+        );
+    }
+
+    #[test]
+    fn concurrent_adds_of_the_same_version_conflict_cleanly() {
+        tauri::async_runtime::block_on(async {
+            let path =
+                std::env::temp_dir().join(format!("tools-concurrency-{}.db", Uuid::new_v4()));
+            let pool = sqlx::sqlite::SqlitePoolOptions::new()
+                .max_connections(4)
+                .connect_with(
+                    sqlx::sqlite::SqliteConnectOptions::new()
+                        .filename(&path)
+                        .create_if_missing(true)
+                        .foreign_keys(true),
+                )
+                .await
+                .unwrap();
+            apply_library_migrations(&pool, LIBRARY_MIGRATIONS)
+                .await
+                .unwrap();
+            let (metadata, version) = import_inputs("1.0.0");
+            create_tool_with_version(&pool, "tool_1", "version_1", metadata, version, &[])
+                .await
+                .unwrap();
+
+            let handles = (0..2)
+                .map(|index| {
+                    let pool = pool.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let (_, version) = import_inputs("2.0.0");
+                        add_version_with_files(
+                            &pool,
+                            "tool_1",
+                            &format!("version_2_{index}"),
+                            version,
+                            &[],
+                        )
+                        .await
+                    })
+                })
+                .collect::<Vec<_>>();
+            let mut results = Vec::new();
+            for handle in handles {
+                results.push(handle.await.unwrap());
+            }
+
+            assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+            assert!(results
+                .iter()
+                .any(|result| matches!(result, Err(ToolsError::Conflict(_)))));
+            let count: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM custom_library_tool_versions WHERE version = '2.0.0'",
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            assert_eq!(count, 1);
+
+            pool.close().await;
+            let _ = std::fs::remove_file(&path);
+        });
+    }
+
+    #[test]
+    fn tag_cooccurrence_counts_shared_tools() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            for (index, tags) in [
+                vec!["civil3d", "survey", "points"],
+                vec!["Civil3D", "Survey"],
+                vec!["civil3d", "grading"],
+                vec!["survey", "lisp"],
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                let (mut metadata, version) = import_inputs("1.0.0");
+                metadata.name = format!("Tool {index}");
+                metadata.slug = None;
+                metadata.tags = tags.into_iter().map(str::to_string).collect();
+                create_tool_with_version(
+                    &pool,
+                    &format!("tool_{index}"),
+                    &format!("version_{index}"),
+                    metadata,
+                    version,
+                    &[],
+                )
+                .await
+                .unwrap();
+            }
+
+            let related = tag_cooccurrence(&pool, "CIVIL3D", 2).await.unwrap();
+            assert_eq!(
+                related
+                    .iter()
+                    .map(|entry| (entry.tag.to_lowercase(), entry.tool_count))
+                    .collect::<Vec<_>>(),
+                vec![("survey".to_string(), 2), ("grading".to_string(), 1)]
+            );
+            assert!(tag_cooccurrence(&pool, "  ", 10).await.is_err());
+        });
+    }
+
+    #[test]
+    fn version_limit_rejects_or_prunes_by_mode() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let (metadata, version) = import_inputs("1.0.0");
+            create_tool_with_version(&pool, "tool_1", "version_1", metadata, version, &[])
+                .await
+                .unwrap();
+            assert!(
+                set_version_limit(&pool, "tool_1", Some(0), VersionLimitMode::Reject)
+                    .await
+                    .is_err()
+            );
+            set_version_limit(&pool, "tool_1", Some(2), VersionLimitMode::Reject)
+                .await
+                .unwrap();
+
+            let (_, version) = import_inputs("1.1.0");
+            add_version_with_files(&pool, "tool_1", "version_2", version, &[])
+                .await
+                .unwrap();
+            let (_, version) = import_inputs("1.2.0");
+            let error = add_version_with_files(&pool, "tool_1", "version_3", version, &[])
+                .await
+                .unwrap_err();
+            assert!(matches!(error, ToolsError::Conflict(_)));
+
+            set_version_limit(&pool, "tool_1", Some(2), VersionLimitMode::Prune)
+                .await
+                .unwrap();
+            let (_, version) = import_inputs("1.2.0");
+            let pruned = add_version_with_files(&pool, "tool_1", "version_3", version, &[])
+                .await
+                .unwrap();
+            assert_eq!(
+                pruned
+                    .iter()
+                    .map(|version| version.id.as_str())
+                    .collect::<Vec<_>>(),
+                vec!["version_1"]
+            );
+
+            let detail = get_tool_detail(&pool, "tool_1").await.unwrap();
+            assert_eq!(detail.max_versions, Some(2));
+            assert_eq!(detail.version_limit_mode, VersionLimitMode::Prune);
+            assert_eq!(detail.versions.len(), 2);
+        });
+    }
+
+    #[test]
+    fn latest_instructions_follow_the_newest_version() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let error = get_latest_instructions(&pool, "tool_1").await.unwrap_err();
+            assert!(matches!(error, ToolsError::NotFound(_)));
+
+            let (metadata, version) = import_inputs("1.0.0");
+            create_tool_with_version(&pool, "tool_1", "version_1", metadata, version, &[])
+                .await
+                .unwrap();
+            let (_, mut version) = import_inputs("1.1.0");
+            version.instructions_md = "# upgrade".to_string();
+            add_version_with_files(&pool, "tool_1", "version_2", version, &[])
+                .await
+                .unwrap();
+            sqlx::query("UPDATE custom_library_tool_versions SET created_at = created_at + 1 WHERE id = 'version_2'")
+                .execute(&pool)
+                .await
+                .unwrap();
+
+            let latest = get_latest_instructions(&pool, "tool_1").await.unwrap();
+            assert_eq!(latest.version, "1.1.0");
+            assert_eq!(latest.instructions_md, "# upgrade");
+        });
+    }
+
+    #[test]
+    fn prune_versions_keeps_the_newest_and_drops_file_rows() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let (metadata, version) = import_inputs("1.0.0");
+            create_tool_with_version(&pool, "tool_1", "version_1", metadata, version, &[])
+                .await
+                .unwrap();
+            for (index, label) in ["1.1.0", "1.2.0", "1.3.0"].into_iter().enumerate() {
+                let (_, version) = import_inputs(label);
+                let version_id = format!("version_{}", index + 2);
+                let file = FileRecordInsert {
+                    original_name: "setup.lsp".to_string(),
+                    stored_rel_path: format!("tools/tool_1/{version_id}/files/setup.lsp"),
+                    sha256: "abc".to_string(),
+                    size_bytes: 3,
+                    mime: None,
+                    note: None,
+                };
+                add_version_with_files(&pool, "tool_1", &version_id, version, &[file])
+                    .await
+                    .unwrap();
+            }
+            // Same timestamp everywhere: insertion order decides which versions are newest.
+            sqlx::query("UPDATE custom_library_tool_versions SET created_at = 1")
+                .execute(&pool)
+                .await
+                .unwrap();
+
+            assert!(prune_versions(&pool, "tool_1", 0).await.is_err());
+            let pruned = prune_versions(&pool, "tool_1", 2).await.unwrap();
+            let labels = pruned
+                .iter()
+                .map(|version| version.version.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(labels, vec!["1.1.0", "1.0.0"]);
+
+            let remaining: Vec<String> = sqlx::query_scalar(
+                "SELECT version FROM custom_library_tool_versions ORDER BY rowid DESC",
+            )
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+            assert_eq!(remaining, vec!["1.3.0", "1.2.0"]);
+            let orphaned: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM custom_library_tool_files WHERE tool_version_id = 'version_2'",
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            assert_eq!(orphaned, 0);
+            assert!(prune_versions(&pool, "tool_1", 2).await.unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn version_hash_matches_for_identical_content() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let file = |version_id: &str, name: &str, sha256: &str| FileRecordInsert {
+                original_name: name.to_string(),
+                stored_rel_path: format!("tools/tool_1/{version_id}/files/{name}"),
+                sha256: sha256.to_string(),
+                size_bytes: 3,
+                mime: None,
+                note: None,
+            };
+            let (metadata, version) = import_inputs("1.0.0");
+            create_tool_with_version(
+                &pool,
+                "tool_1",
+                "version_1",
+                metadata,
+                version,
+                &[
+                    file("version_1", "a.lsp", "aaa"),
+                    file("version_1", "b.lsp", "BBB"),
+                ],
+            )
+            .await
+            .unwrap();
+            let (_, version) = import_inputs("1.0.1");
+            add_version_with_files(
+                &pool,
+                "tool_1",
+                "version_2",
+                version,
+                &[
+                    file("version_2", "b.lsp", "bbb"),
+                    file("version_2", "a.lsp", "aaa"),
+                ],
+            )
+            .await
+            .unwrap();
+            let (_, mut version) = import_inputs("1.1.0");
+            version.changelog_md = Some("Changed".to_string());
+            add_version_with_files(
+                &pool,
+                "tool_1",
+                "version_3",
+                version,
+                &[
+                    file("version_3", "a.lsp", "aaa"),
+                    file("version_3", "b.lsp", "bbb"),
+                ],
+            )
+            .await
+            .unwrap();
+
+            let hash = |version_id: &'static str| {
+                let pool = pool.clone();
+                async move {
+                    get_version_detail(&pool, version_id)
+                        .await
+                        .unwrap()
+                        .version_hash
+                        .unwrap()
+                }
+            };
+            assert_eq!(hash("version_1").await, hash("version_2").await);
+            assert_ne!(hash("version_1").await, hash("version_3").await);
+
+            let original = hash("version_1").await;
+            sqlx::query("UPDATE custom_library_tool_versions SET version_hash = NULL")
+                .execute(&pool)
+                .await
+                .unwrap();
+            backfill_version_hashes(&mut pool.acquire().await.unwrap())
+                .await
+                .unwrap();
+            assert_eq!(hash("version_1").await, original);
+        });
+    }
+
+    #[test]
+    fn renamed_slugs_keep_resolving_through_aliases() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let (metadata, version) = import_inputs("1.0.0");
+            create_tool_with_version(&pool, "tool_1", "version_1", metadata, version, &[])
+                .await
+                .unwrap();
+            let (mut metadata, version) = import_inputs("1.0.0");
+            metadata.slug = Some("layer-tools".to_string());
+            create_tool_with_version(&pool, "tool_2", "version_2", metadata, version, &[])
+                .await
+                .unwrap();
+
+            let renamed = rename_tool_slug(&pool, "tool_1", "Layer Tools")
+                .await
+                .unwrap();
+            assert_eq!(renamed, "layer-tools-2");
+
+            let current = find_tool_by_slug(&pool, "layer-tools-2")
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(current.tool_id, "tool_1");
+            assert!(!current.redirected);
+            let old = find_tool_by_slug(&pool, "cad-toolset")
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(old.tool_id, "tool_1");
+            assert!(old.redirected);
+            assert_eq!(
+                find_tool_id_by_slug(&pool, "layer-tools").await.unwrap(),
+                Some("tool_2".to_string())
+            );
+
+            let restored = rename_tool_slug(&pool, "tool_1", "cad-toolset")
+                .await
+                .unwrap();
+            assert_eq!(restored, "cad-toolset");
+            assert!(
+                !find_tool_by_slug(&pool, "cad-toolset")
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .redirected
+            );
+            assert!(rename_tool_slug(&pool, "missing", "x").await.is_err());
+            assert!(rename_tool_slug(&pool, "tool_1", "  ").await.is_err());
+        });
+    }
+
+    #[test]
+    fn metadata_edits_replace_fields_and_suffix_taken_slugs() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let (metadata, version) = import_inputs("1.0.0");
+            create_tool_with_version(&pool, "tool_1", "version_1", metadata, version, &[])
+                .await
+                .unwrap();
+            let (mut metadata, version) = import_inputs("1.0.0");
+            metadata.slug = Some("layer-tools".to_string());
+            create_tool_with_version(&pool, "tool_2", "version_2", metadata, version, &[])
+                .await
+                .unwrap();
+            let created_at = get_tool_detail(&pool, "tool_1").await.unwrap().updated_at;
+
+            let edited = update_tool_metadata(
+                &pool,
+                "tool_1",
+                ToolMetadataInput {
+                    name: " CAD Toolbox ".to_string(),
+                    slug: None,
+                    description: "Drafting helpers".to_string(),
+                    category: "drafting".to_string(),
+                    tags: vec!["LISP".to_string(), "layers".to_string()],
+                },
+            )
+            .await
+            .unwrap();
+            assert_eq!(edited.name, "CAD Toolbox");
+            assert_eq!(edited.slug, "cad-toolset");
+            assert_eq!(edited.description, "Drafting helpers");
+            assert_eq!(edited.category, "drafting");
+            assert_eq!(edited.tags, vec!["layers".to_string(), "LISP".to_string()]);
+            assert!(edited.updated_at >= created_at);
+
+            let renamed = update_tool_metadata(
+                &pool,
+                "tool_1",
+                ToolMetadataInput {
+                    name: "CAD Toolbox".to_string(),
+                    slug: Some("Layer Tools".to_string()),
+                    description: "Drafting helpers".to_string(),
+                    category: "drafting".to_string(),
+                    tags: Vec::new(),
+                },
+            )
+            .await
+            .unwrap();
+            assert_eq!(renamed.slug, "layer-tools-2");
+            assert!(renamed.tags.is_empty());
+            assert!(
+                find_tool_by_slug(&pool, "cad-toolset")
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .redirected
+            );
+            assert_eq!(
+                get_tool_detail(&pool, "tool_2").await.unwrap().slug,
+                "layer-tools"
+            );
+
+            let (metadata, _) = import_inputs("1.0.0");
+            assert!(matches!(
+                update_tool_metadata(&pool, "missing", metadata).await,
+                Err(ToolsError::NotFound(_))
+            ));
+            let (mut metadata, _) = import_inputs("1.0.0");
+            metadata.description = "  ".to_string();
+            assert!(matches!(
+                update_tool_metadata(&pool, "tool_1", metadata).await,
+                Err(ToolsError::Validation(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn version_labels_can_be_corrected_but_not_duplicated() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let (metadata, version) = import_inputs("1.0");
+            create_tool_with_version(&pool, "tool_1", "version_1", metadata, version, &[])
+                .await
+                .unwrap();
+            let (_, version) = import_inputs("1.1.0");
+            add_version_with_files(&pool, "tool_1", "version_2", version, &[])
+                .await
+                .unwrap();
+
+            let label = rename_version(&pool, "tool_1", "version_1", "  1.0.0 ")
+                .await
+                .unwrap();
+            assert_eq!(label, "1.0.0");
+            assert_eq!(
+                find_version_id(&pool, "tool_1", "1.0.0").await.unwrap(),
+                Some("version_1".to_string())
+            );
+            assert_eq!(find_version_id(&pool, "tool_1", "1.0").await.unwrap(), None);
+            // Re-saving the current label is not a conflict with itself.
+            rename_version(&pool, "tool_1", "version_1", "1.0.0")
+                .await
+                .unwrap();
+
+            let error = rename_version(&pool, "tool_1", "version_1", "1.1.0")
+                .await
+                .unwrap_err();
+            assert!(matches!(error, ToolsError::Conflict(_)));
+            assert_eq!(
+                find_version_id(&pool, "tool_1", "1.0.0").await.unwrap(),
+                Some("version_1".to_string())
+            );
+            assert!(matches!(
+                rename_version(&pool, "tool_1", "version_1", "  ").await,
+                Err(ToolsError::Validation(_))
+            ));
+            assert!(matches!(
+                rename_version(&pool, "tool_2", "version_1", "2.0.0").await,
+                Err(ToolsError::NotFound(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn deleting_a_version_keeps_the_last_one() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let file = |version_id: &str| FileRecordInsert {
+                original_name: "layfix.lsp".to_string(),
+                stored_rel_path: format!("tools/tool_1/{version_id}/files/layfix.lsp"),
+                sha256: "aa".to_string(),
+                size_bytes: 3,
+                mime: None,
+                note: None,
+            };
+            let (metadata, version) = import_inputs("1.0.0");
+            create_tool_with_version(
+                &pool,
+                "tool_1",
+                "version_1",
+                metadata,
+                version,
+                &[file("version_1")],
+            )
+            .await
+            .unwrap();
+            let (_, version) = import_inputs("1.1.0");
+            add_version_with_files(&pool, "tool_1", "version_2", version, &[file("version_2")])
+                .await
+                .unwrap();
+            let before = get_tool_detail(&pool, "tool_1").await.unwrap().updated_at;
+
+            let (mut metadata, version) = import_inputs("1.0.0");
+            metadata.slug = Some("other-toolset".to_string());
+            create_tool_with_version(&pool, "tool_2", "version_3", metadata, version, &[])
+                .await
+                .unwrap();
+            assert!(matches!(
+                delete_version(&pool, "tool_2", "version_1").await,
+                Err(ToolsError::NotFound(_))
+            ));
+            assert_eq!(
+                get_tool_detail(&pool, "tool_1")
+                    .await
+                    .unwrap()
+                    .versions
+                    .len(),
+                2
+            );
+
+            delete_version(&pool, "tool_1", "version_1").await.unwrap();
+            let detail = get_tool_detail(&pool, "tool_1").await.unwrap();
+            assert_eq!(detail.versions.len(), 1);
+            assert_eq!(detail.versions[0].id, "version_2");
+            assert!(detail.updated_at >= before);
+            let file_rows: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM custom_library_tool_files WHERE tool_version_id = 'version_1'",
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            assert_eq!(file_rows, 0);
+
+            let error = delete_version(&pool, "tool_1", "version_2")
+                .await
+                .unwrap_err();
+            assert!(matches!(error, ToolsError::Conflict(_)));
+            assert_eq!(
+                get_tool_detail(&pool, "tool_1")
+                    .await
+                    .unwrap()
+                    .versions
+                    .len(),
+                1
+            );
+            assert!(matches!(
+                delete_version(&pool, "tool_1", "version_1").await,
+                Err(ToolsError::NotFound(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn duplicate_file_names_are_rejected_and_reported() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let file = |name: &str| FileRecordInsert {
+                original_name: name.to_string(),
+                stored_rel_path: format!("tools/tool_1/version_1/files/{name}"),
+                sha256: "aa".to_string(),
+                size_bytes: 3,
+                mime: None,
+                note: None,
+            };
+
+            let (metadata, version) = import_inputs("1.0.0");
+            let error = create_tool_with_version(
+                &pool,
+                "tool_1",
+                "version_1",
+                metadata.clone(),
+                version.clone(),
+                &[file("layfix.lsp"), file("LayFix.lsp")],
+            )
+            .await
+            .unwrap_err();
+            assert!(matches!(error, ToolsError::Conflict(_)));
+            assert_eq!(
+                error.user_message(),
+                "LayFix.lsp is already a file in this version."
+            );
+
+            create_tool_with_version(
+                &pool,
+                "tool_1",
+                "version_1",
+                metadata,
+                version,
+                &[file("layfix.lsp")],
+            )
+            .await
+            .unwrap();
+            assert!(find_duplicate_file_names(&pool).await.unwrap().is_empty());
+
+            sqlx::query("DROP INDEX idx_custom_library_tool_files_version_name_unique")
+                .execute(&pool)
+                .await
+                .unwrap();
+            sqlx::query(
+                "INSERT INTO custom_library_tool_files
+                (id, tool_version_id, original_name, stored_rel_path, sha256, size_bytes, created_at)
+                VALUES ('file_dup', 'version_1', 'LAYFIX.lsp', 'tools/tool_1/version_1/files/LAYFIX.lsp', 'bb', 3, 0)",
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+            assert_eq!(
+                find_duplicate_file_names(&pool).await.unwrap(),
+                vec![DuplicateFileName {
+                    tool_version_id: "version_1".to_string(),
+                    original_name: "LAYFIX.lsp".to_string(),
+                    file_count: 2,
+                }]
+            );
+        });
+    }
+
+    #[test]
+    fn renaming_a_file_keeps_its_hash_and_refuses_taken_names() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let file = |name: &str| FileRecordInsert {
+                original_name: name.to_string(),
+                stored_rel_path: format!("tools/tool_1/version_1/files/{name}"),
+                sha256: format!("hash-{name}"),
+                size_bytes: 3,
+                mime: None,
+                note: None,
+            };
+            let (metadata, version) = import_inputs("1.0.0");
+            create_tool_with_version(
+                &pool,
+                "tool_1",
+                "version_1",
+                metadata,
+                version,
+                &[file("layfx.lsp"), file("setup.scr")],
+            )
+            .await
+            .unwrap();
+            let detail = get_tool_detail(&pool, "tool_1").await.unwrap();
+            let file_id = |name: &str| {
+                detail.versions[0]
+                    .files
+                    .iter()
+                    .find(|file| file.original_name == name)
+                    .unwrap()
+                    .id
+                    .clone()
+            };
+            let typo_id = file_id("layfx.lsp");
+
+            let error = rename_file(
+                &pool,
+                &typo_id,
+                "layfix.lsp",
+                "tools/tool_1/version_1/files/layfix.lsp",
+                || Err(ToolsError::Io("disk full".to_string())),
+                || {},
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(error.user_message(), "disk full");
+            assert_eq!(
+                get_file_path_record(&pool, &typo_id)
+                    .await
+                    .unwrap()
+                    .original_name,
+                "layfx.lsp"
+            );
+
+            rename_file(
+                &pool,
+                &typo_id,
+                "layfix.lsp",
+                "tools/tool_1/version_1/files/layfix.lsp",
+                || Ok(()),
+                || {},
+            )
+            .await
+            .unwrap();
+            let renamed = get_file_path_record(&pool, &typo_id).await.unwrap();
+            assert_eq!(renamed.original_name, "layfix.lsp");
+            assert_eq!(
+                renamed.stored_rel_path,
+                "tools/tool_1/version_1/files/layfix.lsp"
+            );
+            assert_eq!(renamed.tool_id, "tool_1");
+            let detail = get_tool_detail(&pool, "tool_1").await.unwrap();
+            let file = detail.versions[0]
+                .files
+                .iter()
+                .find(|file| file.id == typo_id)
+                .unwrap();
+            assert_eq!(file.sha256, "hash-layfx.lsp");
+
+            let error = rename_file(
+                &pool,
+                &typo_id,
+                "Setup.scr",
+                "tools/tool_1/version_1/files/Setup.scr",
+                || panic!("the file must not move when the row cannot change"),
+                || {},
+            )
+            .await
+            .unwrap_err();
+            assert!(matches!(error, ToolsError::Conflict(_)));
+            assert_eq!(
+                error.user_message(),
+                "Setup.scr is already a file in this version."
+            );
+            assert_eq!(
+                get_file_path_record(&pool, &typo_id)
+                    .await
+                    .unwrap()
+                    .original_name,
+                "layfix.lsp"
+            );
+            assert!(matches!(
+                get_file_path_record(&pool, "missing").await,
+                Err(ToolsError::NotFound(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn full_export_context_lists_versions_oldest_first() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let (metadata, version) = import_inputs("1.0.0");
+            create_tool_with_version(&pool, "tool_1", "version_1", metadata, version, &[])
+                .await
+                .unwrap();
+            let (_, version) = import_inputs("1.1.0");
+            add_version_with_files(&pool, "tool_1", "version_2", version, &[])
+                .await
+                .unwrap();
+
+            let full = get_full_export_context(&pool, "tool_1").await.unwrap();
+            assert_eq!(full.tool.slug, "cad-toolset");
+            let versions = full
+                .versions
+                .iter()
+                .map(|context| context.version.version.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(versions, vec!["1.0.0", "1.1.0"]);
+
+            let missing = get_full_export_context(&pool, "missing").await.unwrap_err();
+            assert!(matches!(missing, ToolsError::NotFound(_)));
+        });
+    }
+
+    #[test]
+    fn distinct_files_group_shared_content_across_versions() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let file = |version_id: &str, name: &str, sha256: &str| FileRecordInsert {
+                original_name: name.to_string(),
+                stored_rel_path: format!("tools/tool_1/{version_id}/files/{name}"),
+                sha256: sha256.to_string(),
+                size_bytes: 3,
+                mime: None,
+                note: None,
+            };
+
+            let (metadata, version) = import_inputs("1.0.0");
+            create_tool_with_version(
+                &pool,
+                "tool_1",
+                "version_1",
+                metadata,
+                version,
+                &[
+                    file("version_1", "a.scr", "aa"),
+                    file("version_1", "b.lsp", "bb"),
+                ],
+            )
+            .await
+            .unwrap();
+            let (_, version) = import_inputs("1.1.0");
+            add_version_with_files(
+                &pool,
+                "tool_1",
+                "version_2",
+                version,
+                &[
+                    file("version_2", "a.scr", "AA"),
+                    file("version_2", "b.lsp", "cc"),
+                ],
+            )
+            .await
+            .unwrap();
+
+            let distinct = list_distinct_files(&pool, "tool_1").await.unwrap();
+            let summary = distinct
+                .iter()
+                .map(|file| (file.sha256.as_str(), file.versions.join(",")))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                summary,
+                vec![
+                    ("aa", "1.0.0,1.1.0".to_string()),
+                    ("bb", "1.0.0".to_string()),
+                    ("cc", "1.1.0".to_string()),
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn catalog_entries_aggregate_versions_and_sizes() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            let file = |version_id: &str, size_bytes: i64| FileRecordInsert {
+                original_name: "a.lsp".to_string(),
+                stored_rel_path: format!("tools/tool_1/{version_id}/files/a.lsp"),
+                sha256: version_id.to_string(),
+                size_bytes,
+                mime: None,
+                note: None,
+            };
+            let (metadata, version) = import_inputs("1.0.0");
+            create_tool_with_version(
+                &pool,
+                "tool_1",
+                "version_1",
+                metadata,
+                version,
+                &[file("version_1", 100)],
+            )
+            .await
+            .unwrap();
+            let (_, version) = import_inputs("1.1.0");
+            add_version_with_files(
+                &pool,
+                "tool_1",
+                "version_2",
+                version,
+                &[file("version_2", 50)],
+            )
+            .await
+            .unwrap();
+            sqlx::query("UPDATE custom_library_tool_versions SET created_at = created_at + 1 WHERE id = 'version_2'")
+                .execute(&pool)
+                .await
+                .unwrap();
+
+            let entries = list_catalog_entries(&pool).await.unwrap();
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].slug, "cad-toolset");
+            assert_eq!(entries[0].tags, vec!["autocad".to_string()]);
+            assert_eq!(entries[0].version_count, 2);
+            assert_eq!(entries[0].latest_version.as_deref(), Some("1.1.0"));
+            assert_eq!(entries[0].total_size_bytes, 150);
+        });
+    }
+
+    #[test]
+    fn normalize_all_dry_run_matches_apply() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+            for (tool_id, slug) in [("tool_1", "layer-tools"), ("tool_2", "Layer Tools")] {
+                let (mut metadata, version) = import_inputs("1.0.0");
+                metadata.slug = Some(slug.to_string());
+                create_tool_with_version(
+                    &pool,
+                    tool_id,
+                    &format!("{tool_id}_v1"),
+                    metadata,
+                    version,
+                    &[],
+                )
+                .await
+                .unwrap();
+            }
+            // Simulate rows written before slug and tag normalization existed.
+            for statement in [
+                "UPDATE custom_library_tools SET slug = 'Layer Tools' WHERE id = 'tool_2'",
+                "INSERT INTO custom_library_tool_tags (tool_id, tag) VALUES ('tool_2', 'AutoCAD'), ('tool_2', ' lisp ')",
+            ] {
+                sqlx::query(statement).execute(&pool).await.unwrap();
+            }
+
+            let dry_run = normalize_all_tools(&pool, false).await.unwrap();
+            assert!(!dry_run.applied);
+            assert_eq!(dry_run.slug_changes.len(), 1);
+            assert_eq!(dry_run.slug_changes[0].to, "layer-tools-2");
+            assert_eq!(dry_run.tag_changes.len(), 1);
+            let merged = &dry_run.tag_changes[0].after;
+            assert_eq!(merged.len(), 2);
+            assert!(merged[0].eq_ignore_ascii_case("autocad"));
+            assert_eq!(merged[1], "lisp");
+            let untouched: String =
+                sqlx::query_scalar("SELECT slug FROM custom_library_tools WHERE id = 'tool_2'")
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+            assert_eq!(untouched, "Layer Tools");
+
+            let applied = normalize_all_tools(&pool, true).await.unwrap();
+            assert_eq!(applied.slug_changes[0].to, "layer-tools-2");
+            assert_eq!(
+                find_tool_by_slug(&pool, "Layer Tools")
+                    .await
+                    .unwrap()
+                    .map(|found| found.tool_id),
+                Some("tool_2".to_string())
+            );
+            let again = normalize_all_tools(&pool, true).await.unwrap();
+            assert!(again.slug_changes.is_empty() && again.tag_changes.is_empty());
+        });
+    }
+
+    #[test]
+    fn tags_and_categories_reject_control_characters() {
+        assert_eq!(
+            normalize_tags(&["cad".to_string(), " lisp ".to_string()]).unwrap(),
+            vec!["cad".to_string(), "lisp".to_string()]
+        );
+        for bad in ["multi\nline", "nul\0byte", "bell\u{7}", "c1\u{85}tag"] {
+            let error = normalize_tags(&[bad.to_string()]).unwrap_err();
+            assert!(
+                error.user_message().contains("control characters"),
+                "{bad:?}"
+            );
+            assert!(validate_label("category", bad, 120).is_err(), "{bad:?}");
+        }
+        assert!(validate_label("category", "Drafting & Layout", 120).is_ok());
+    }
+
+    #[test]
+    fn import_reuses_existing_slug_and_rejects_duplicate_versions() {
+        tauri::async_runtime::block_on(async {
+            let pool = create_test_pool().await;
+
+            let (tx, first) = begin_import(&pool, "cad-toolset", "1.0.0").await.unwrap();
+            let (metadata, version) = import_inputs("1.0.0");
+            commit_import(tx, &first, metadata, version, &[])
+                .await
+                .unwrap();
+            assert!(first.created_tool);
+
+            let (tx, second) = begin_import(&pool, "cad-toolset", "1.1.0").await.unwrap();
+            let (metadata, version) = import_inputs("1.1.0");
+            commit_import(tx, &second, metadata, version, &[])
+                .await
+                .unwrap();
+            assert!(!second.created_tool);
+            assert_eq!(second.tool_id, first.tool_id);
+
+            let error = begin_import(&pool, "cad-toolset", "1.1.0")
+                .await
+                .map(|_| ())
+                .unwrap_err();
+            assert!(matches!(error, ToolsError::Conflict(_)));
+        });
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use super::error::{ToolsError, ToolsResult};
+
+/// Long enough for a confirmation dialog, short enough that a stale token is useless.
+pub const DELETE_TOKEN_TTL: Duration = Duration::from_secs(60);
+
+struct PendingDelete {
+    tool_id: String,
+    expires_at: Instant,
+}
+
+fn pending_deletes() -> &'static Mutex<HashMap<String, PendingDelete>> {
+    static PENDING: OnceLock<Mutex<HashMap<String, PendingDelete>>> = OnceLock::new();
+    PENDING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Issues a single-use token that authorizes deleting `tool_id` until it expires.
+pub fn issue_delete_token(tool_id: &str) -> String {
+    issue_delete_token_at(tool_id, Instant::now())
+}
+
+/// Consumes `token`, failing unless it was issued for `tool_id` and has not expired.
+pub fn redeem_delete_token(tool_id: &str, token: &str) -> ToolsResult<()> {
+    redeem_delete_token_at(tool_id, token, Instant::now())
+}
+
+fn issue_delete_token_at(tool_id: &str, now: Instant) -> String {
+    let token = Uuid::new_v4().simple().to_string();
+    let mut pending = lock_pending();
+    pending.retain(|_, entry| entry.expires_at > now);
+    pending.insert(
+        token.clone(),
+        PendingDelete {
+            tool_id: tool_id.to_string(),
+            expires_at: now + DELETE_TOKEN_TTL,
+        },
+    );
+    token
+}
+
+fn redeem_delete_token_at(tool_id: &str, token: &str, now: Instant) -> ToolsResult<()> {
+    let mut pending = lock_pending();
+    let entry = match pending.remove(token.trim()) {
+        Some(entry) if entry.tool_id == tool_id => entry,
+        other => {
+            // A token presented for the wrong tool stays valid for the tool it was issued for.
+            if let Some(entry) = other {
+                pending.insert(token.trim().to_string(), entry);
+            }
+            return Err(ToolsError::Validation(
+                "Delete confirmation token is not valid for this tool.".to_string(),
+            ));
+        }
+    };
+    if entry.expires_at <= now {
+        return Err(ToolsError::Validation(
+            "Delete confirmation token has expired. Request a new one.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn lock_pending() -> std::sync::MutexGuard<'static, HashMap<String, PendingDelete>> {
+    // A poisoned map only ever holds tokens; keep using it rather than blocking deletes.
+    pending_deletes()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_are_bound_to_one_tool_and_single_use() {
+        let now = Instant::now();
+        let token = issue_delete_token_at("tool_a", now);
+
+        assert!(redeem_delete_token_at("tool_b", &token, now).is_err());
+        assert!(redeem_delete_token_at("tool_a", &format!(" {token} "), now).is_ok());
+        assert!(redeem_delete_token_at("tool_a", &token, now).is_err());
+        assert!(redeem_delete_token_at("tool_a", "made-up", now).is_err());
+    }
+
+    #[test]
+    fn tokens_expire() {
+        let now = Instant::now();
+        let token = issue_delete_token_at("tool_a", now);
+
+        let error = redeem_delete_token_at("tool_a", &token, now + DELETE_TOKEN_TTL).unwrap_err();
+        assert!(error.user_message().contains("expired"));
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use tauri::AppHandle;
+
+use super::db::{FileRecordInsert, ToolFileDetail};
+use super::error::{ToolsError, ToolsResult};
+use super::storage::{
+    build_stored_rel_path, remove_written_files, resolve_stored_path, stage_inbound_files,
+    FileLimits, InboundToolFile, StagedToolFile,
+};
+
+/// A base-version file reused as-is; its stored bytes are copied rather than re-sent.
+#[derive(Debug, Clone)]
+pub struct CarriedToolFile {
+    pub source_rel_path: String,
+    pub row: FileRecordInsert,
+}
+
+/// File set for a version derived from an existing one.
+#[derive(Debug, Clone, Default)]
+pub struct DerivedVersionFiles {
+    /// New or changed uploads that still need writing.
+    pub staged: Vec<StagedToolFile>,
+    pub carried: Vec<CarriedToolFile>,
+}
+
+impl DerivedVersionFiles {
+    pub fn total_bytes(&self) -> u64 {
+        let staged = self.staged.iter().map(|file| file.size_bytes);
+        let carried = self
+            .carried
+            .iter()
+            .map(|file| file.row.size_bytes.max(0) as u64);
+        staged.chain(carried).sum()
+    }
+
+    /// DB rows for the whole set, uploads first, each group in base/upload order.
+    pub fn file_rows(&self) -> Vec<FileRecordInsert> {
+        let staged = self.staged.iter().map(|file| FileRecordInsert {
+            original_name: file.original_name.clone(),
+            stored_rel_path: file.stored_rel_path.clone(),
+            sha256: file.sha256.clone(),
+            size_bytes: file.size_bytes as i64,
+            mime: file.mime.clone(),
+            note: None,
+        });
+        let carried = self.carried.iter().map(|file| file.row.clone());
+        staged.chain(carried).collect()
+    }
+}
+
+/// Starts from `base_files`, drops `removed_names`, and applies `changed_files`, which replace a
+/// base file of the same (sanitized, case-insensitive) name or add a new one. An upload whose
+/// bytes match the base file it replaces is carried instead of re-staged. Names are matched
+/// without case, like uploads within a single version.
+pub fn plan_derived_files(
+    tool_id: &str,
+    version_id: &str,
+    base_files: &[ToolFileDetail],
+    changed_files: Vec<InboundToolFile>,
+    removed_names: &[String],
+    limits: &FileLimits,
+) -> ToolsResult<DerivedVersionFiles> {
+    let mut removed = HashSet::new();
+    for name in removed_names {
+        let key = name.trim().to_ascii_lowercase();
+        if !base_files
+            .iter()
+            .any(|file| file.original_name.to_ascii_lowercase() == key)
+        {
+            return Err(ToolsError::Validation(format!(
+                "{} is not a file in the base version.",
+                name.trim()
+            )));
+        }
+        removed.insert(key);
+    }
+
+    let staged = if changed_files.is_empty() {
+        Vec::new()
+    } else {
+        stage_inbound_files(tool_id, version_id, changed_files, limits)?
+    };
+
+    let mut planned = DerivedVersionFiles::default();
+    let mut replaced = HashSet::new();
+    for file in staged {
+        let key = file.original_name.to_ascii_lowercase();
+        if removed.contains(&key) {
+            return Err(ToolsError::Validation(format!(
+                "{} is both uploaded and removed.",
+                file.original_name
+            )));
+        }
+        let unchanged = base_files.iter().find(|base| {
+            base.original_name.to_ascii_lowercase() == key && base.sha256 == file.sha256
+        });
+        if unchanged.is_none() {
+            replaced.insert(key);
+            planned.staged.push(file);
+        }
+    }
+
+    for base in base_files {
+        let key = base.original_name.to_ascii_lowercase();
+        if removed.contains(&key) || replaced.contains(&key) {
+            continue;
+        }
+        planned.carried.push(CarriedToolFile {
+            source_rel_path: base.stored_rel_path.clone(),
+            row: FileRecordInsert {
+                original_name: base.original_name.clone(),
+                stored_rel_path: build_stored_rel_path(tool_id, version_id, &base.original_name)?,
+                sha256: base.sha256.clone(),
+                size_bytes: base.size_bytes,
+                mime: base.mime.clone(),
+                note: base.note.clone(),
+            },
+        });
+    }
+
+    if planned.staged.is_empty() && planned.carried.is_empty() {
+        return Err(ToolsError::Validation(
+            "At least one file is required.".to_string(),
+        ));
+    }
+    if planned.total_bytes() > limits.max_total_size_bytes {
+        return Err(ToolsError::Validation(format!(
+            "Combined file size exceeds {} bytes.",
+            limits.max_total_size_bytes
+        )));
+    }
+    Ok(planned)
+}
+
+/// Hardlinks carried files into the new version folder, copying them byte-for-byte where the
+/// volume cannot link. Either way encrypted files stay encrypted and no plaintext passes
+/// through memory.
+pub fn copy_carried_files(
+    app: &AppHandle,
+    carried: &[CarriedToolFile],
+) -> ToolsResult<Vec<PathBuf>> {
+    let mut written_paths = Vec::with_capacity(carried.len());
+    for file in carried {
+        let source = resolve_stored_path(app, &file.source_rel_path)?;
+        let destination = resolve_stored_path(app, &file.row.stored_rel_path)?;
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let linked_or_copied = fs::hard_link(&source, &destination)
+            .or_else(|_| fs::copy(&source, &destination).map(|_| ()));
+        if let Err(error) = linked_or_copied {
+            remove_written_files(&written_paths);
+            return Err(ToolsError::Io(format!(
+                "Failed to copy {} from the base version: {error}",
+                file.row.original_name
+            )));
+        }
+        written_paths.push(destination);
+    }
+    Ok(written_paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+
+    use super::super::db::test_export_file;
+    use super::*;
+
+    fn base_file(name: &str, bytes: &[u8]) -> ToolFileDetail {
+        ToolFileDetail {
+            note: Some(format!("{name} note")),
+            ..test_export_file("version-1", name, bytes)
+        }
+    }
+
+    fn upload(name: &str, bytes: &[u8]) -> InboundToolFile {
+        InboundToolFile {
+            original_name: name.to_string(),
+            mime: None,
+            data_base64: base64::engine::general_purpose::STANDARD.encode(bytes),
+        }
+    }
+
+    #[test]
+    fn plan_carries_unchanged_files_and_stages_only_new_bytes() {
+        let base = [
+            base_file("layfix.lsp", b"(princ)"),
+            base_file("readme.txt", b"old"),
+            base_file("legacy.scr", b"zoom e"),
+        ];
+
+        let planned = plan_derived_files(
+            "tool-1",
+            "version-2",
+            &base,
+            vec![
+                upload("readme.txt", b"new"),
+                upload("layfix.lsp", b"(princ)"),
+                upload("menu.json", b"{}"),
+            ],
+            &["Legacy.scr".to_string()],
+            &FileLimits::default(),
+        )
+        .unwrap();
+
+        let staged = planned
+            .staged
+            .iter()
+            .map(|file| file.original_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(staged, vec!["readme.txt", "menu.json"]);
+        assert_eq!(planned.carried.len(), 1);
+        let carried = &planned.carried[0];
+        assert_eq!(
+            carried.source_rel_path,
+            "tools/tool-1/version-1/files/layfix.lsp"
+        );
+        assert_eq!(
+            carried.row.stored_rel_path,
+            "tools/tool-1/version-2/files/layfix.lsp"
+        );
+        assert_eq!(carried.row.note.as_deref(), Some("layfix.lsp note"));
+        assert_eq!(planned.file_rows().len(), 3);
+        assert_eq!(planned.total_bytes(), 3 + 2 + 7);
+    }
+
+    #[test]
+    fn plan_rejects_unknown_removals_and_empty_or_oversized_sets() {
+        let base = [base_file("layfix.lsp", b"(princ)")];
+        let limits = FileLimits::default();
+
+        let unknown = plan_derived_files(
+            "tool-1",
+            "version-2",
+            &base,
+            Vec::new(),
+            &["missing.lsp".to_string()],
+            &limits,
+        );
+        assert!(unknown
+            .unwrap_err()
+            .user_message()
+            .contains("missing.lsp is not a file in the base version"));
+
+        let empty = plan_derived_files(
+            "tool-1",
+            "version-2",
+            &base,
+            Vec::new(),
+            &["layfix.lsp".to_string()],
+            &limits,
+        );
+        assert!(empty
+            .unwrap_err()
+            .user_message()
+            .contains("At least one file is required"));
+
+        let oversized = plan_derived_files(
+            "tool-1",
+            "version-2",
+            &base,
+            vec![upload("menu.json", b"{}")],
+            &[],
+            &FileLimits {
+                max_file_size_bytes: 100,
+                max_total_size_bytes: 8,
+                ..FileLimits::default()
+            },
+        );
+        assert!(oversized
+            .unwrap_err()
+            .user_message()
+            .contains("Combined file size exceeds 8 bytes"));
+    }
+}
@@ -0,0 +1,169 @@
+use std::sync::{Mutex, OnceLock};
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::Engine;
+
+use super::error::{ToolsError, ToolsResult};
+use crate::secrets;
+
+/// Marks files written with encryption at rest. Plaintext starting with it would read back as
+/// encrypted, so [`ensure_storable_as_plaintext`] refuses it.
+const ENCRYPTED_MAGIC: &[u8] = b"KTENC\x01";
+const NONCE_LEN: usize = 12;
+/// Bytes an encrypted file adds to its plaintext: the marker, the nonce and the GCM tag.
+pub const ENCRYPTION_OVERHEAD_BYTES: u64 = (ENCRYPTED_MAGIC.len() + NONCE_LEN + 16) as u64;
+const STORAGE_KEY_CREDENTIAL_ID: &str = "tools-storage-key";
+
+fn key_cache() -> &'static Mutex<Option<[u8; 32]>> {
+    static CACHE: OnceLock<Mutex<Option<[u8; 32]>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(None))
+}
+
+#[derive(Clone)]
+pub struct StorageCipher {
+    cipher: Aes256Gcm,
+}
+
+impl StorageCipher {
+    pub fn from_key(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        }
+    }
+
+    /// Loads the per-install key from the keyring, creating it on first use.
+    pub fn load_or_create() -> ToolsResult<Self> {
+        Ok(Self::from_key(&load_key(true)?))
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> ToolsResult<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| ToolsError::Io("Failed to encrypt stored file.".to_string()))?;
+
+        let mut output = Vec::with_capacity(ENCRYPTED_MAGIC.len() + NONCE_LEN + ciphertext.len());
+        output.extend_from_slice(ENCRYPTED_MAGIC);
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&ciphertext);
+        Ok(output)
+    }
+
+    pub fn decrypt(&self, stored: &[u8]) -> ToolsResult<Vec<u8>> {
+        let body = stored
+            .strip_prefix(ENCRYPTED_MAGIC)
+            .filter(|body| body.len() >= NONCE_LEN)
+            .ok_or_else(|| ToolsError::Io("Stored file is not encrypted.".to_string()))?;
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                ToolsError::Io(
+                    "Failed to decrypt stored file; the storage key may have changed.".to_string(),
+                )
+            })
+    }
+}
+
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(ENCRYPTED_MAGIC)
+}
+
+/// Refuses plaintext that starts with the encryption marker; stored unencrypted, it could not be
+/// told apart from an encrypted file.
+pub fn ensure_storable_as_plaintext(name: &str, bytes: &[u8]) -> ToolsResult<()> {
+    if is_encrypted(bytes) {
+        return Err(ToolsError::Validation(format!(
+            "{name} starts with the marker of an encrypted stored file and can only be stored \
+             with encryption at rest turned on."
+        )));
+    }
+    Ok(())
+}
+
+/// Returns plaintext for a stored file, decrypting with the keyring key only when needed.
+pub fn decrypt_if_encrypted(bytes: Vec<u8>) -> ToolsResult<Vec<u8>> {
+    if !is_encrypted(&bytes) {
+        return Ok(bytes);
+    }
+    StorageCipher::from_key(&load_key(false)?).decrypt(&bytes)
+}
+
+fn load_key(create: bool) -> ToolsResult<[u8; 32]> {
+    if let Ok(cache) = key_cache().lock() {
+        if let Some(key) = *cache {
+            return Ok(key);
+        }
+    }
+
+    let stored = secrets::read_internal_secret(STORAGE_KEY_CREDENTIAL_ID)
+        .map_err(|error| keyring_unavailable(&error))?;
+    let key = match stored {
+        Some(encoded) => decode_key(&encoded)?,
+        None if create => {
+            let key: [u8; 32] = Aes256Gcm::generate_key(&mut OsRng).into();
+            secrets::store_internal_secret(
+                STORAGE_KEY_CREDENTIAL_ID,
+                &base64::engine::general_purpose::STANDARD.encode(key),
+            )
+            .map_err(|error| keyring_unavailable(&error))?;
+            key
+        }
+        None => {
+            return Err(ToolsError::Io(
+                "Stored file is encrypted but no storage key exists in the keyring.".to_string(),
+            ))
+        }
+    };
+
+    if let Ok(mut cache) = key_cache().lock() {
+        *cache = Some(key);
+    }
+    Ok(key)
+}
+
+fn decode_key(encoded: &str) -> ToolsResult<[u8; 32]> {
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| ToolsError::Io("Storage key in the keyring is malformed.".to_string()))
+}
+
+fn keyring_unavailable(error: &str) -> ToolsError {
+    ToolsError::Validation(format!(
+        "Encryption at rest needs a working OS keyring: {error}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_and_rejects_tampering() {
+        let cipher = StorageCipher::from_key(&[7u8; 32]);
+        let stored = cipher.encrypt(b"(defun c:hello ())").unwrap();
+
+        assert!(is_encrypted(&stored));
+        assert!(!is_encrypted(b"(defun c:hello ())"));
+        assert_eq!(cipher.decrypt(&stored).unwrap(), b"(defun c:hello ())");
+
+        let mut tampered = stored.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(cipher.decrypt(&tampered).is_err());
+        assert!(StorageCipher::from_key(&[8u8; 32])
+            .decrypt(&stored)
+            .is_err());
+    }
+
+    #[test]
+    fn plaintext_passes_through_without_a_key() {
+        assert_eq!(
+            decrypt_if_encrypted(b"%PDF-1.7".to_vec()).unwrap(),
+            b"%PDF-1.7"
+        );
+    }
+}
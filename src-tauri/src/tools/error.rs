@@ -23,6 +23,18 @@ impl ToolsError {
             | Self::Zip(message) => message.clone(),
         }
     }
+
+    /// The same kind of error with `f` applied to its message.
+    pub fn map_message(self, f: impl FnOnce(String) -> String) -> Self {
+        match self {
+            Self::Validation(message) => Self::Validation(f(message)),
+            Self::NotFound(message) => Self::NotFound(f(message)),
+            Self::Conflict(message) => Self::Conflict(f(message)),
+            Self::Database(message) => Self::Database(f(message)),
+            Self::Io(message) => Self::Io(f(message)),
+            Self::Zip(message) => Self::Zip(f(message)),
+        }
+    }
 }
 
 impl Display for ToolsError {
@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::db::ExportVersionContext;
+use super::error::{ToolsError, ToolsResult};
+use super::zip::ParsedImportArchive;
+
+/// How long a previewed archive stays available for diffing.
+pub const IMPORT_PLAN_TTL: Duration = Duration::from_secs(10 * 60);
+/// Parsed archives hold every file in memory, so only the most recent few are kept.
+const MAX_IMPORT_PLANS: usize = 4;
+/// Above this many line pairs the instructions diff skips the LCS pass and reports the
+/// differing middle section as one removal and one addition.
+const MAX_LINE_DIFF_CELLS: usize = 4_000_000;
+
+struct ImportPlan {
+    parsed: ParsedImportArchive,
+    expires_at: Instant,
+}
+
+fn import_plans() -> &'static Mutex<HashMap<String, ImportPlan>> {
+    static PLANS: OnceLock<Mutex<HashMap<String, ImportPlan>>> = OnceLock::new();
+    PLANS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Keeps `parsed` for later inspection and returns the token that refers to it.
+pub fn store_import_plan(parsed: ParsedImportArchive) -> String {
+    store_import_plan_at(parsed, Instant::now())
+}
+
+/// Returns a copy of the archive prepared under `token`; the plan stays available until expiry.
+pub fn import_plan(token: &str) -> ToolsResult<ParsedImportArchive> {
+    import_plan_at(token, Instant::now())
+}
+
+fn store_import_plan_at(parsed: ParsedImportArchive, now: Instant) -> String {
+    let token = Uuid::new_v4().simple().to_string();
+    let mut plans = lock_plans();
+    plans.retain(|_, plan| plan.expires_at > now);
+    while plans.len() >= MAX_IMPORT_PLANS {
+        let Some(oldest) = plans
+            .iter()
+            .min_by_key(|(_, plan)| plan.expires_at)
+            .map(|(token, _)| token.clone())
+        else {
+            break;
+        };
+        plans.remove(&oldest);
+    }
+    plans.insert(
+        token.clone(),
+        ImportPlan {
+            parsed,
+            expires_at: now + IMPORT_PLAN_TTL,
+        },
+    );
+    token
+}
+
+fn import_plan_at(token: &str, now: Instant) -> ToolsResult<ParsedImportArchive> {
+    let mut plans = lock_plans();
+    plans.retain(|_, plan| plan.expires_at > now);
+    plans
+        .get(token.trim())
+        .map(|plan| plan.parsed.clone())
+        .ok_or_else(|| {
+            ToolsError::NotFound(
+                "Import preview has expired or does not exist. Preview the archive again."
+                    .to_string(),
+            )
+        })
+}
+
+fn lock_plans() -> std::sync::MutexGuard<'static, HashMap<String, ImportPlan>> {
+    import_plans()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolImportDiff {
+    pub tool_id: String,
+    pub version_id: String,
+    pub version: String,
+    pub incoming_version: String,
+    pub added: Vec<ToolImportDiffFile>,
+    pub removed: Vec<ToolImportDiffFile>,
+    pub changed: Vec<ToolImportChangedFile>,
+    pub unchanged_count: usize,
+    pub instructions_changed: bool,
+    pub instructions_diff: Vec<InstructionsDiffLine>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolImportDiffFile {
+    pub original_name: String,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolImportChangedFile {
+    pub original_name: String,
+    pub previous_size_bytes: u64,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffLineKind {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstructionsDiffLine {
+    pub kind: DiffLineKind,
+    pub text: String,
+}
+
+/// Compares an archive with an existing version. Files match by name without case, as
+/// storage does, and count as changed when their SHA-256 differs.
+pub fn diff_import(
+    parsed: &ParsedImportArchive,
+    existing: &ExportVersionContext,
+) -> ToolImportDiff {
+    let existing_files = existing
+        .files
+        .iter()
+        .map(|file| (file.original_name.to_ascii_lowercase(), file))
+        .collect::<HashMap<_, _>>();
+
+    let mut diff = ToolImportDiff {
+        tool_id: existing.version.tool_id.clone(),
+        version_id: existing.version.id.clone(),
+        version: existing.version.version.clone(),
+        incoming_version: parsed.version.version.clone(),
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+        unchanged_count: 0,
+        instructions_changed: parsed.version.instructions_md != existing.version.instructions_md,
+        instructions_diff: diff_lines(
+            &existing.version.instructions_md,
+            &parsed.version.instructions_md,
+        ),
+    };
+
+    let mut matched = std::collections::HashSet::new();
+    for file in &parsed.files {
+        let key = file.original_name.to_ascii_lowercase();
+        let size_bytes = file.bytes.len() as u64;
+        match existing_files.get(&key) {
+            Some(previous) => {
+                matched.insert(key);
+                if previous.sha256.eq_ignore_ascii_case(&file.sha256) {
+                    diff.unchanged_count += 1;
+                } else {
+                    diff.changed.push(ToolImportChangedFile {
+                        original_name: file.original_name.clone(),
+                        previous_size_bytes: previous.size_bytes.max(0) as u64,
+                        size_bytes,
+                    });
+                }
+            }
+            None => diff.added.push(ToolImportDiffFile {
+                original_name: file.original_name.clone(),
+                size_bytes,
+            }),
+        }
+    }
+    for file in &existing.files {
+        if !matched.contains(&file.original_name.to_ascii_lowercase()) {
+            diff.removed.push(ToolImportDiffFile {
+                original_name: file.original_name.clone(),
+                size_bytes: file.size_bytes.max(0) as u64,
+            });
+        }
+    }
+    diff
+}
+
+/// Line diff from `before` to `after`: common prefix and suffix, then an LCS over the rest.
+pub fn diff_lines(before: &str, after: &str) -> Vec<InstructionsDiffLine> {
+    let old = before.lines().collect::<Vec<_>>();
+    let new = after.lines().collect::<Vec<_>>();
+    let prefix = old
+        .iter()
+        .zip(&new)
+        .take_while(|(left, right)| left == right)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(left, right)| left == right)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let line = |kind, text: &str| InstructionsDiffLine {
+        kind,
+        text: text.to_string(),
+    };
+    let mut lines = old[..prefix]
+        .iter()
+        .map(|text| line(DiffLineKind::Unchanged, text))
+        .collect::<Vec<_>>();
+
+    if old_middle.len().saturating_mul(new_middle.len()) > MAX_LINE_DIFF_CELLS {
+        lines.extend(
+            old_middle
+                .iter()
+                .map(|text| line(DiffLineKind::Removed, text)),
+        );
+        lines.extend(
+            new_middle
+                .iter()
+                .map(|text| line(DiffLineKind::Added, text)),
+        );
+    } else {
+        // lengths[i][j] is the LCS length of old_middle[i..] and new_middle[j..].
+        let width = new_middle.len() + 1;
+        let mut lengths = vec![0u32; (old_middle.len() + 1) * width];
+        for i in (0..old_middle.len()).rev() {
+            for j in (0..new_middle.len()).rev() {
+                lengths[i * width + j] = if old_middle[i] == new_middle[j] {
+                    lengths[(i + 1) * width + j + 1] + 1
+                } else {
+                    lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < old_middle.len() || j < new_middle.len() {
+            if i < old_middle.len() && j < new_middle.len() && old_middle[i] == new_middle[j] {
+                lines.push(line(DiffLineKind::Unchanged, old_middle[i]));
+                i += 1;
+                j += 1;
+            } else if j < new_middle.len()
+                && (i == old_middle.len()
+                    || lengths[i * width + j + 1] >= lengths[(i + 1) * width + j])
+            {
+                lines.push(line(DiffLineKind::Added, new_middle[j]));
+                j += 1;
+            } else {
+                lines.push(line(DiffLineKind::Removed, old_middle[i]));
+                i += 1;
+            }
+        }
+    }
+
+    lines.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|text| line(DiffLineKind::Unchanged, text)),
+    );
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::db::{test_export_context, ToolMetadataInput, VersionInsertInput};
+    use super::super::storage::sha256_hex;
+    use super::super::zip::ImportFileBytes;
+    use super::*;
+
+    fn parsed(files: &[(&str, &[u8])], instructions_md: &str) -> ParsedImportArchive {
+        ParsedImportArchive {
+            metadata: ToolMetadataInput {
+                name: "CAD Toolset".to_string(),
+                slug: Some("cad-toolset".to_string()),
+                description: "CAD helpers".to_string(),
+                category: "cad".to_string(),
+                tags: Vec::new(),
+            },
+            version: VersionInsertInput {
+                version: "1.1.0".to_string(),
+                changelog_md: None,
+                instructions_md: instructions_md.to_string(),
+            },
+            files: files
+                .iter()
+                .map(|(name, bytes)| ImportFileBytes {
+                    original_name: name.to_string(),
+                    mime: None,
+                    bytes: bytes.to_vec(),
+                    sha256: sha256_hex(bytes),
+                    note: None,
+                })
+                .collect(),
+            warnings: Vec::new(),
+            exported_at: None,
+            file_problems: Vec::new(),
+            assets: Vec::new(),
+        }
+    }
+
+    fn existing(files: &[(&str, &[u8])], instructions_md: &str) -> ExportVersionContext {
+        let mut context = test_export_context(files);
+        context.version.instructions_md = instructions_md.to_string();
+        context
+    }
+
+    #[test]
+    fn diff_reports_added_removed_changed_files_and_instruction_lines() {
+        let diff = diff_import(
+            &parsed(
+                &[
+                    ("layfix.lsp", b"(princ)"),
+                    ("readme.txt", b"new text"),
+                    ("menu.json", b"{}"),
+                ],
+                "# Install\nLoad layfix.lsp\nRun LAYFIX\n",
+            ),
+            &existing(
+                &[
+                    ("LayFix.lsp", b"(princ)"),
+                    ("readme.txt", b"old"),
+                    ("legacy.scr", b"zoom e"),
+                ],
+                "# Install\nLoad legacy.scr\nRun LAYFIX\n",
+            ),
+        );
+
+        assert_eq!(diff.version, "1.0.0");
+        assert_eq!(diff.incoming_version, "1.1.0");
+        assert_eq!(diff.unchanged_count, 1);
+        assert_eq!(diff.added[0].original_name, "menu.json");
+        assert_eq!(diff.removed[0].original_name, "legacy.scr");
+        assert_eq!(diff.changed[0].original_name, "readme.txt");
+        assert_eq!(diff.changed[0].previous_size_bytes, 3);
+        assert_eq!(diff.changed[0].size_bytes, 8);
+        assert!(diff.instructions_changed);
+        let kinds = diff
+            .instructions_diff
+            .iter()
+            .map(|line| (line.kind, line.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (DiffLineKind::Unchanged, "# Install"),
+                (DiffLineKind::Added, "Load layfix.lsp"),
+                (DiffLineKind::Removed, "Load legacy.scr"),
+                (DiffLineKind::Unchanged, "Run LAYFIX"),
+            ]
+        );
+    }
+
+    #[test]
+    fn plans_expire_and_only_the_most_recent_are_kept() {
+        let now = Instant::now();
+        let token = store_import_plan_at(parsed(&[], "# a"), now);
+        assert!(import_plan_at(&token, now).is_ok());
+        assert!(import_plan_at(&format!(" {token} "), now).is_ok());
+        assert!(import_plan_at(&token, now + IMPORT_PLAN_TTL).is_err());
+
+        let tokens = (0..=MAX_IMPORT_PLANS)
+            .map(|offset| {
+                store_import_plan_at(parsed(&[], "# a"), now + Duration::from_secs(offset as u64))
+            })
+            .collect::<Vec<_>>();
+        assert!(import_plan_at(&tokens[0], now).is_err());
+        assert!(import_plan_at(&tokens[MAX_IMPORT_PLANS], now).is_ok());
+    }
+}
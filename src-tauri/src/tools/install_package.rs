@@ -0,0 +1,213 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use tauri::AppHandle;
+
+use super::cleanup_ledger::TrackedArtifacts;
+use super::db::ExportVersionContext;
+use super::error::ToolsResult;
+use super::storage::{ensure_free_space, sanitize_filename};
+use super::zip::{
+    compress_directory_to_zip, create_temp_dir_in, estimate_export_size_bytes,
+    normalize_destination, read_verified_export_file, resolve_staging_root, CompressionLevel,
+};
+
+pub const INSTALLER_SCRIPT_NAME: &str = "install.ps1";
+/// Written by the installer into the install folder; AutoCAD runs it with `SCRIPT`.
+const LOAD_SCRIPT_NAME: &str = "load.scr";
+const FILES_DIR: &str = "files";
+/// Extensions AutoCAD can `(load ...)` from a script.
+const LISP_EXTENSIONS: &[&str] = &["lsp", "fas", "vlx"];
+
+/// Writes a zip holding the version files, `instructions.md`, and an `install.ps1` that copies
+/// the files into a per-user support folder and generates a `load.scr` for the LISP files.
+/// Files are staged under `staging_root`; see `resolve_staging_root`.
+pub fn export_install_package(
+    app: &AppHandle,
+    context: &ExportVersionContext,
+    destination_path: &str,
+    staging_root: Option<&Path>,
+) -> ToolsResult<PathBuf> {
+    let required_bytes = estimate_export_size_bytes(context);
+    let destination = normalize_destination(destination_path)?;
+    let parent = destination
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    ensure_free_space(&parent, required_bytes, "install package export")?;
+    let staging_root = resolve_staging_root(staging_root);
+    ensure_free_space(&staging_root, required_bytes, "export staging")?;
+
+    let staging = create_temp_dir_in(&staging_root, "tool-install-package")?;
+    let cleanup = TrackedArtifacts::track("install package export", vec![staging.clone()]);
+    debug!(
+        "custom-tools: install package export start tool_id={} version_id={} destination={}",
+        context.version.tool_id,
+        context.version.id,
+        destination.to_string_lossy()
+    );
+
+    let result = (|| -> ToolsResult<()> {
+        let files_dir = staging.join(FILES_DIR);
+        fs::create_dir_all(&files_dir)?;
+
+        fs::write(
+            staging.join(INSTALLER_SCRIPT_NAME),
+            build_installer_script(context)?,
+        )?;
+        fs::write(
+            staging.join("instructions.md"),
+            context.version.instructions_md.as_bytes(),
+        )?;
+        for file in &context.files {
+            let (sanitized, bytes) = read_verified_export_file(app, context, file)?;
+            fs::write(files_dir.join(sanitized), bytes)?;
+        }
+
+        compress_directory_to_zip(&staging, &destination, CompressionLevel::Default)
+    })();
+
+    cleanup.settle(&result);
+    result?;
+    debug!(
+        "custom-tools: install package export success tool_id={} version_id={}",
+        context.version.tool_id, context.version.id
+    );
+    Ok(destination)
+}
+
+/// Builds the PowerShell installer. The instructions are repeated as a comment header so the
+/// script explains itself when opened, and every interpolated value is single-quote escaped.
+pub fn build_installer_script(context: &ExportVersionContext) -> ToolsResult<String> {
+    let tool = &context.tool;
+    let version = &context.version;
+
+    let mut lisp_files = Vec::new();
+    for file in &context.files {
+        let sanitized = sanitize_filename(&file.original_name)?;
+        let extension = Path::new(&sanitized)
+            .extension()
+            .and_then(|value| value.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if LISP_EXTENSIONS.contains(&extension.as_str()) {
+            lisp_files.push(ps_quote(&sanitized));
+        }
+    }
+
+    let instructions = version
+        .instructions_md
+        .lines()
+        .map(|line| format!("#   {}", single_line(line)))
+        .collect::<Vec<_>>()
+        .join("\r\n");
+
+    let name_literal = ps_quote(&single_line(&tool.name));
+    let version_literal = ps_quote(&single_line(&version.version));
+    let lines = [
+        format!(
+            "# Installer for {} {}",
+            single_line(&tool.name),
+            single_line(&version.version)
+        ),
+        "#".to_string(),
+        "# Instructions:".to_string(),
+        instructions,
+        String::new(),
+        "param(".to_string(),
+        format!(
+            "    [string]$Destination = (Join-Path $env:APPDATA (Join-Path 'Korda Tools' {}))",
+            ps_quote(&tool.slug)
+        ),
+        ")".to_string(),
+        String::new(),
+        "$ErrorActionPreference = 'Stop'".to_string(),
+        format!("$source = Join-Path $PSScriptRoot '{FILES_DIR}'"),
+        "New-Item -ItemType Directory -Path $Destination -Force | Out-Null".to_string(),
+        "Copy-Item -Path (Join-Path $source '*') -Destination $Destination -Force".to_string(),
+        String::new(),
+        format!("$lispFiles = @({})", lisp_files.join(", ")),
+        "$loadLines = foreach ($file in $lispFiles) {".to_string(),
+        "    $path = (Join-Path $Destination $file) -replace '\\\\', '/'".to_string(),
+        "    '(load \"' + $path + '\")'".to_string(),
+        "}".to_string(),
+        "if ($lispFiles.Count -gt 0) {".to_string(),
+        format!("    Set-Content -LiteralPath (Join-Path $Destination '{LOAD_SCRIPT_NAME}') -Value $loadLines -Encoding ASCII"),
+        "}".to_string(),
+        String::new(),
+        format!("Write-Host ('Installed ' + {name_literal} + ' ' + {version_literal} + ' to ' + $Destination)"),
+        "Write-Host 'Add this folder to the AutoCAD support file search path.'".to_string(),
+        "if ($lispFiles.Count -gt 0) {".to_string(),
+        format!("    Write-Host ('Then run SCRIPT in AutoCAD and pick ' + (Join-Path $Destination '{LOAD_SCRIPT_NAME}'))"),
+        "}".to_string(),
+        String::new(),
+    ];
+    let script = lines.join("\r\n");
+    Ok(script)
+}
+
+/// Characters PowerShell accepts as a single quote, typographic variants included.
+const PS_SINGLE_QUOTES: [char; 5] = ['\'', '\u{2018}', '\u{2019}', '\u{201A}', '\u{201B}'];
+
+/// PowerShell single-quoted literal; only single quotes need escaping, by doubling each one.
+fn ps_quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('\'');
+    for character in value.chars() {
+        if PS_SINGLE_QUOTES.contains(&character) {
+            quoted.push(character);
+        }
+        quoted.push(character);
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// Keeps free-text metadata from breaking out of a comment line.
+fn single_line(value: &str) -> String {
+    value
+        .chars()
+        .map(|character| {
+            if character.is_control() {
+                ' '
+            } else {
+                character
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::db::test_export_context;
+    use super::*;
+
+    #[test]
+    fn installer_loads_lisp_files_and_escapes_metadata() {
+        let mut context = test_export_context(&[
+            ("layfix.lsp", b""),
+            ("menu.cuix", b""),
+            ("helpers.FAS", b""),
+        ]);
+        context.tool.name = "O'Brien\nLayers \u{2019}; Remove-Item C:\\ \u{2018}".to_string();
+        context.tool.slug = "obrien-layers".to_string();
+        context.version.version = "1.2.0".to_string();
+        context.version.instructions_md = "# Install\nRun LAYFIX after loading.".to_string();
+
+        let script = build_installer_script(&context).unwrap();
+
+        assert!(script.starts_with(
+            "# Installer for O'Brien Layers \u{2019}; Remove-Item C:\\ \u{2018} 1.2.0\r\n"
+        ));
+        assert!(script.contains("#   Run LAYFIX after loading.\r\n"));
+        assert!(script.contains("$lispFiles = @('layfix.lsp', 'helpers.fas')"));
+        assert!(script.contains("Join-Path 'Korda Tools' 'obrien-layers'"));
+        assert!(script
+            .contains("'O''Brien Layers \u{2019}\u{2019}; Remove-Item C:\\ \u{2018}\u{2018}'"));
+        assert!(script
+            .contains("\r\n    $path = (Join-Path $Destination $file) -replace '\\\\', '/'\r\n"));
+        assert!(!script.contains("menu.cuix"));
+    }
+}
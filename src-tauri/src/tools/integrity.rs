@@ -0,0 +1,350 @@
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::db::{FilePathRecord, StoredFileRecord};
+use super::storage::{normalize_stored_rel_path, resolve_stored_path_in, stored_file_digest};
+
+pub const INTEGRITY_PROGRESS_EVENT: &str = "tools-integrity-progress";
+pub const STORAGE_SCAN_PROGRESS_EVENT: &str = "tools-storage-scan-progress";
+
+/// How many files a storage scan checks between progress events.
+const STORAGE_SCAN_PROGRESS_INTERVAL: usize = 25;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityFailure {
+    pub original_name: String,
+    pub stored_rel_path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    pub tool_id: String,
+    pub status: String,
+    pub checked_files: usize,
+    pub failures: Vec<IntegrityFailure>,
+    pub checked_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityProgress {
+    pub processed: usize,
+    pub total: usize,
+    pub tool_id: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IntegrityIssueKind {
+    Missing,
+    Unreadable,
+    SizeMismatch,
+    HashMismatch,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityIssue {
+    pub kind: IntegrityIssueKind,
+    pub original_name: String,
+    pub stored_rel_path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageScanProgress {
+    pub processed: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathAuditIssue {
+    pub file_id: String,
+    pub tool_version_id: String,
+    pub stored_rel_path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathAuditReport {
+    pub checked_files: usize,
+    pub issues: Vec<PathAuditIssue>,
+}
+
+/// Checks stored paths against the current normalization rules without touching the rows.
+pub fn audit_stored_paths(rows: &[FilePathRecord]) -> PathAuditReport {
+    let issues = rows
+        .iter()
+        .filter_map(|row| {
+            let reason = match normalize_stored_rel_path(&row.stored_rel_path) {
+                Err(error) => error.user_message(),
+                Ok(normalized) if normalized != row.stored_rel_path => {
+                    format!("Stored path is not in normalized form (expected {normalized}).")
+                }
+                Ok(_) => return None,
+            };
+            Some(PathAuditIssue {
+                file_id: row.file_id.clone(),
+                tool_version_id: row.tool_version_id.clone(),
+                stored_rel_path: row.stored_rel_path.clone(),
+                reason,
+            })
+        })
+        .collect();
+
+    PathAuditReport {
+        checked_files: rows.len(),
+        issues,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingStoredFile {
+    pub tool_id: String,
+    pub version_id: String,
+    pub original_name: String,
+    pub stored_rel_path: String,
+}
+
+/// Lists rows whose file is gone from disk. Paths that do not resolve at all are left to
+/// `audit_stored_paths`, which explains why they are invalid.
+pub fn find_missing_files(base_dir: &Path, rows: &[FilePathRecord]) -> Vec<MissingStoredFile> {
+    rows.iter()
+        .filter(|row| {
+            resolve_stored_path_in(base_dir, &row.stored_rel_path).is_ok_and(|path| !path.is_file())
+        })
+        .map(|row| MissingStoredFile {
+            tool_id: row.tool_id.clone(),
+            version_id: row.tool_version_id.clone(),
+            original_name: row.original_name.clone(),
+            stored_rel_path: row.stored_rel_path.clone(),
+        })
+        .collect()
+}
+
+pub fn verify_stored_files(base_dir: &Path, files: &[StoredFileRecord]) -> Vec<IntegrityFailure> {
+    files
+        .iter()
+        .filter_map(|file| {
+            let (_, reason) = check_stored_file(base_dir, file)?;
+            Some(IntegrityFailure {
+                original_name: file.original_name.clone(),
+                stored_rel_path: file.stored_rel_path.clone(),
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// Reads every stored file back (decrypting where needed) and compares its size and hash with
+/// the row. `progress` is called every few files and once at the end.
+pub fn scan_stored_files(
+    base_dir: &Path,
+    files: &[StoredFileRecord],
+    progress: &dyn Fn(StorageScanProgress),
+) -> Vec<IntegrityIssue> {
+    let mut issues = Vec::new();
+    for (index, file) in files.iter().enumerate() {
+        if let Some((kind, reason)) = check_stored_file(base_dir, file) {
+            issues.push(IntegrityIssue {
+                kind,
+                original_name: file.original_name.clone(),
+                stored_rel_path: file.stored_rel_path.clone(),
+                reason,
+            });
+        }
+        let processed = index + 1;
+        if processed % STORAGE_SCAN_PROGRESS_INTERVAL == 0 || processed == files.len() {
+            progress(StorageScanProgress {
+                processed,
+                total: files.len(),
+            });
+        }
+    }
+    issues
+}
+
+fn check_stored_file(
+    base_dir: &Path,
+    file: &StoredFileRecord,
+) -> Option<(IntegrityIssueKind, String)> {
+    let path = match resolve_stored_path_in(base_dir, &file.stored_rel_path) {
+        Ok(path) => path,
+        Err(error) => return Some((IntegrityIssueKind::Unreadable, error.user_message())),
+    };
+    if !path.is_file() {
+        return Some((
+            IntegrityIssueKind::Missing,
+            "Stored file is missing.".to_string(),
+        ));
+    }
+    match stored_file_digest(&path) {
+        Err(error) => Some((IntegrityIssueKind::Unreadable, error.user_message())),
+        Ok((size, _)) if size as i64 != file.size_bytes => Some((
+            IntegrityIssueKind::SizeMismatch,
+            format!(
+                "Size mismatch: expected {} bytes, found {size}.",
+                file.size_bytes
+            ),
+        )),
+        Ok((_, sha256)) if !sha256.eq_ignore_ascii_case(file.sha256.trim()) => Some((
+            IntegrityIssueKind::HashMismatch,
+            "SHA256 mismatch.".to_string(),
+        )),
+        Ok(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::super::storage::sha256_hex;
+    use super::*;
+
+    #[test]
+    fn path_audit_reports_rows_that_fail_normalization() {
+        let row = |file_id: &str, stored_rel_path: &str| FilePathRecord {
+            file_id: file_id.to_string(),
+            tool_id: "tool_1".to_string(),
+            tool_version_id: "version_1".to_string(),
+            original_name: "setup.lsp".to_string(),
+            stored_rel_path: stored_rel_path.to_string(),
+        };
+        let report = audit_stored_paths(&[
+            row("ok", "tools/tool_1/version_1/files/setup.lsp"),
+            row("escape", "tools/tool_1/../files/setup.lsp"),
+            row("layout", "tools/tool_1/version_1/setup.lsp"),
+            row("padded", " tools/tool_1/version_1/files/setup.lsp"),
+        ]);
+
+        assert_eq!(report.checked_files, 4);
+        let flagged = report
+            .issues
+            .iter()
+            .map(|issue| issue.file_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(flagged, vec!["escape", "layout", "padded"]);
+        assert!(report.issues[2].reason.contains("normalized form"));
+    }
+
+    #[test]
+    fn reports_missing_and_tampered_files() {
+        let base = std::env::temp_dir().join(format!("integrity-{}", uuid::Uuid::new_v4()));
+        let record = |name: &str, bytes: &[u8]| {
+            let stored_rel_path = format!("tools/tool_1/version_1/files/{name}");
+            let path = resolve_stored_path_in(&base, &stored_rel_path).unwrap();
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, bytes).unwrap();
+            StoredFileRecord {
+                original_name: name.to_string(),
+                stored_rel_path,
+                sha256: sha256_hex(bytes),
+                size_bytes: bytes.len() as i64,
+            }
+        };
+
+        let intact = record("ok.scr", b"intact");
+        let tampered = record("tampered.lsp", b"before");
+        let missing = record("missing.txt", b"gone");
+        fs::write(
+            resolve_stored_path_in(&base, &tampered.stored_rel_path).unwrap(),
+            b"after!",
+        )
+        .unwrap();
+        fs::remove_file(resolve_stored_path_in(&base, &missing.stored_rel_path).unwrap()).unwrap();
+
+        let failures = verify_stored_files(&base, &[intact, tampered, missing]);
+        let names = failures
+            .iter()
+            .map(|failure| failure.original_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["tampered.lsp", "missing.txt"]);
+        assert_eq!(failures[0].reason, "SHA256 mismatch.");
+
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn storage_scan_flags_corrupted_files_and_reports_progress() {
+        let base = std::env::temp_dir().join(format!("storage-scan-{}", uuid::Uuid::new_v4()));
+        let files = (0..30)
+            .map(|index| {
+                let bytes = format!("file {index}").into_bytes();
+                let stored_rel_path = format!("tools/tool_1/version_1/files/f{index}.lsp");
+                let path = resolve_stored_path_in(&base, &stored_rel_path).unwrap();
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(&path, &bytes).unwrap();
+                StoredFileRecord {
+                    original_name: format!("f{index}.lsp"),
+                    stored_rel_path,
+                    sha256: sha256_hex(&bytes),
+                    size_bytes: bytes.len() as i64,
+                }
+            })
+            .collect::<Vec<_>>();
+        let path = |index: usize| resolve_stored_path_in(&base, &files[index].stored_rel_path);
+        fs::write(path(3).unwrap(), b"file X").unwrap();
+        fs::write(path(7).unwrap(), b"truncated").unwrap();
+        fs::remove_file(path(12).unwrap()).unwrap();
+
+        let seen = std::cell::RefCell::new(Vec::new());
+        let issues = scan_stored_files(&base, &files, &|progress| {
+            seen.borrow_mut().push((progress.processed, progress.total));
+        });
+
+        let flagged = issues
+            .iter()
+            .map(|issue| (issue.original_name.as_str(), issue.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            flagged,
+            vec![
+                ("f3.lsp", IntegrityIssueKind::HashMismatch),
+                ("f7.lsp", IntegrityIssueKind::SizeMismatch),
+                ("f12.lsp", IntegrityIssueKind::Missing),
+            ]
+        );
+        assert_eq!(seen.into_inner(), vec![(25, 30), (30, 30)]);
+
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn missing_file_scan_skips_present_and_unresolvable_rows() {
+        let base = std::env::temp_dir().join(format!("missing-{}", uuid::Uuid::new_v4()));
+        let row = |name: &str, stored_rel_path: &str| FilePathRecord {
+            file_id: name.to_string(),
+            tool_id: "tool_1".to_string(),
+            tool_version_id: "version_1".to_string(),
+            original_name: name.to_string(),
+            stored_rel_path: stored_rel_path.to_string(),
+        };
+        let present = row("present.lsp", "tools/tool_1/version_1/files/present.lsp");
+        let present_path = resolve_stored_path_in(&base, &present.stored_rel_path).unwrap();
+        fs::create_dir_all(present_path.parent().unwrap()).unwrap();
+        fs::write(&present_path, b"here").unwrap();
+
+        let missing = find_missing_files(
+            &base,
+            &[
+                present,
+                row("gone.scr", "tools/tool_1/version_1/files/gone.scr"),
+                row("escape.scr", "tools/tool_1/../escape.scr"),
+            ],
+        );
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].original_name, "gone.scr");
+        assert_eq!(missing[0].version_id, "version_1");
+
+        let _ = fs::remove_dir_all(base);
+    }
+}
@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+use super::db::{now_epoch_millis, ToolMetadataInput, VersionInsertInput};
+use super::error::ToolsResult;
+
+const LEGACY_VERSION_LABEL: &str = "1.0.0";
+
+#[derive(Debug, Clone)]
+pub struct LegacyToolRow {
+    pub id: String,
+    pub category: String,
+    pub tags_json: String,
+    pub config_json: String,
+    pub run_count: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct LegacyToolImport {
+    pub metadata: ToolMetadataInput,
+    pub version: VersionInsertInput,
+    pub file_name: String,
+    pub file_bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyMigratedTool {
+    pub legacy_tool_id: String,
+    pub tool_id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacySkippedTool {
+    pub legacy_tool_id: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyMigrationReport {
+    pub migrated: Vec<LegacyMigratedTool>,
+    pub skipped: Vec<LegacySkippedTool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyToolConfig {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default, rename = "type")]
+    tool_type: String,
+    #[serde(default)]
+    endpoint: String,
+}
+
+/// Reads the frontend's `tools` registry rows that have not been migrated yet. The legacy
+/// tables are only read, never modified.
+pub async fn list_pending_legacy_tools(pool: &SqlitePool) -> ToolsResult<Vec<LegacyToolRow>> {
+    if !table_exists(pool, "tools").await? {
+        return Ok(Vec::new());
+    }
+
+    let run_count_sql = if table_exists(pool, "tool_run_logs").await? {
+        "(SELECT COUNT(*) FROM tool_run_logs l WHERE l.tool_id = t.id)"
+    } else {
+        "0"
+    };
+    let rows = sqlx::query(&format!(
+        "SELECT t.id, t.category, t.tags, t.config_json, {run_count_sql} AS run_count
+      FROM tools t
+      WHERE t.id NOT IN (SELECT legacy_tool_id FROM custom_library_legacy_imports)
+      ORDER BY t.created_at ASC"
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| LegacyToolRow {
+            id: row.get("id"),
+            category: row.get("category"),
+            tags_json: row.get("tags"),
+            config_json: row.get("config_json"),
+            run_count: row.get("run_count"),
+        })
+        .collect())
+}
+
+pub async fn record_legacy_import(
+    pool: &SqlitePool,
+    legacy_tool_id: &str,
+    tool_id: &str,
+) -> ToolsResult<()> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO custom_library_legacy_imports (legacy_tool_id, tool_id, imported_at)
+      VALUES (?1, ?2, ?3)",
+    )
+    .bind(legacy_tool_id)
+    .bind(tool_id)
+    .bind(now_epoch_millis()?)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Maps a legacy registry row onto library inputs; the tool's config becomes its single file.
+pub fn to_library_import(row: &LegacyToolRow) -> Result<LegacyToolImport, String> {
+    let config: LegacyToolConfig = serde_json::from_str(&row.config_json)
+        .map_err(|error| format!("config_json is not a valid tool definition: {error}"))?;
+    let name = config.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Tool has no name.".to_string());
+    }
+
+    let tags = serde_json::from_str::<Vec<String>>(&row.tags_json).unwrap_or_default();
+    let description = if config.description.trim().is_empty() {
+        "Migrated from the legacy tool registry.".to_string()
+    } else {
+        config.description.trim().to_string()
+    };
+    let pretty_config = serde_json::from_str::<serde_json::Value>(&row.config_json)
+        .and_then(|value| serde_json::to_vec_pretty(&value))
+        .map_err(|error| format!("config_json could not be re-encoded: {error}"))?;
+
+    let mut instructions_md = format!(
+        "# {name}\n\nMigrated from the legacy tool registry. The original definition is attached as `tool-config.json`.\n"
+    );
+    if !config.endpoint.trim().is_empty() {
+        instructions_md.push_str(&format!(
+            "\n- Type: {}\n- Endpoint: `{}`\n",
+            config.tool_type.trim(),
+            config.endpoint.trim()
+        ));
+    }
+    if row.run_count > 0 {
+        instructions_md.push_str(&format!("- Recorded runs: {}\n", row.run_count));
+    }
+
+    Ok(LegacyToolImport {
+        metadata: ToolMetadataInput {
+            name,
+            slug: None,
+            description,
+            category: row.category.clone(),
+            tags,
+        },
+        version: VersionInsertInput {
+            version: LEGACY_VERSION_LABEL.to_string(),
+            changelog_md: None,
+            instructions_md,
+        },
+        file_name: "tool-config.json".to_string(),
+        file_bytes: pretty_config,
+    })
+}
+
+async fn table_exists(pool: &SqlitePool, table: &str) -> ToolsResult<bool> {
+    let row = sqlx::query("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1")
+        .bind(table)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy_row(config_json: &str) -> LegacyToolRow {
+        LegacyToolRow {
+            id: "legacy-1".to_string(),
+            category: "general".to_string(),
+            tags_json: "[\"api\"]".to_string(),
+            config_json: config_json.to_string(),
+            run_count: 3,
+        }
+    }
+
+    #[test]
+    fn maps_legacy_rows_and_skips_unusable_ones() {
+        let import = to_library_import(&legacy_row(
+            r#"{"name":"Weather","description":"","type":"rest_api","endpoint":"https://example.com"}"#,
+        ))
+        .unwrap();
+        assert_eq!(import.metadata.name, "Weather");
+        assert_eq!(import.metadata.tags, vec!["api".to_string()]);
+        assert!(import.metadata.description.contains("legacy tool registry"));
+        assert!(import.version.instructions_md.contains("Recorded runs: 3"));
+        assert_eq!(import.file_name, "tool-config.json");
+
+        assert!(to_library_import(&legacy_row("not json")).is_err());
+        assert!(to_library_import(&legacy_row(r#"{"name":"  "}"#)).is_err());
+    }
+}
@@ -1,5 +1,19 @@
+pub mod archive_inspect;
+pub mod bundle;
+pub mod catalog;
+pub mod cleanup_ledger;
 pub mod commands;
 pub mod db;
+pub mod delete_tokens;
+pub mod derived_version;
+pub mod encryption;
 pub mod error;
+pub mod import_plans;
+pub mod install_package;
+pub mod integrity;
+pub mod legacy;
+pub mod relocation;
+pub mod settings;
 pub mod storage;
+pub mod usage;
 pub mod zip;
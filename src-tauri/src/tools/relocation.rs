@@ -0,0 +1,401 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use log::debug;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use super::db::StoredFileRecord;
+use super::error::{ToolsError, ToolsResult};
+use super::storage::{
+    ensure_free_space, resolve_icon_path_in, resolve_stored_path_in, set_storage_base_dir,
+    storage_base_dir, stored_file_digest,
+};
+
+pub const STORAGE_MIGRATION_PROGRESS_EVENT: &str = "tools-storage-migration-progress";
+
+static MIGRATION_RUNNING: AtomicBool = AtomicBool::new(false);
+static MIGRATION_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Files moved so far as `(old, new)` pairs, kept so a later failure can move them back.
+type MovedFiles = Vec<(PathBuf, PathBuf)>;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageMigrationProgress {
+    pub processed: usize,
+    pub total: usize,
+    pub current_file: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageMigrationReport {
+    pub previous_root: String,
+    pub new_root: String,
+    pub moved_files: usize,
+    pub moved_bytes: u64,
+}
+
+pub fn request_cancel() {
+    MIGRATION_CANCELLED.store(true, Ordering::SeqCst);
+}
+
+pub fn migrate_storage_root(
+    app: &AppHandle,
+    new_root: &str,
+    files: &[StoredFileRecord],
+    icon_paths: &[String],
+) -> ToolsResult<StorageMigrationReport> {
+    let old_base = storage_base_dir(app)?;
+    let new_base = validate_new_root(&old_base, new_root)?;
+
+    if MIGRATION_RUNNING
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        return Err(ToolsError::Conflict(
+            "A storage migration is already running.".to_string(),
+        ));
+    }
+    MIGRATION_CANCELLED.store(false, Ordering::SeqCst);
+
+    let result = (|| -> ToolsResult<StorageMigrationReport> {
+        let required_bytes = files
+            .iter()
+            .map(|file| file.size_bytes.max(0) as u64)
+            .sum::<u64>();
+        ensure_free_space(&new_base, required_bytes, "storage migration")?;
+        fs::create_dir_all(&new_base)?;
+
+        debug!(
+            "custom-tools: storage migration start from={} to={} file_count={}",
+            old_base.to_string_lossy(),
+            new_base.to_string_lossy(),
+            files.len()
+        );
+        let (mut moved, moved_bytes) = relocate_stored_files(
+            &old_base,
+            &new_base,
+            files,
+            &MIGRATION_CANCELLED,
+            |progress| {
+                let _ = app.emit(STORAGE_MIGRATION_PROGRESS_EVENT, &progress);
+            },
+        )?;
+
+        let moved_files = moved.len();
+        moved.extend(move_icons(&old_base, &new_base, icon_paths));
+        if let Err(error) = set_storage_base_dir(app, &new_base) {
+            return Err(roll_back(&moved, &new_base, error));
+        }
+        remove_empty_dirs(&old_base.join("tools"));
+        debug!(
+            "custom-tools: storage migration success moved_files={} moved_bytes={}",
+            moved_files, moved_bytes
+        );
+
+        Ok(StorageMigrationReport {
+            previous_root: old_base.to_string_lossy().to_string(),
+            new_root: new_base.to_string_lossy().to_string(),
+            moved_files,
+            moved_bytes,
+        })
+    })();
+
+    MIGRATION_RUNNING.store(false, Ordering::SeqCst);
+    result
+}
+
+pub fn relocate_stored_files(
+    old_base: &Path,
+    new_base: &Path,
+    files: &[StoredFileRecord],
+    cancelled: &AtomicBool,
+    mut on_progress: impl FnMut(StorageMigrationProgress),
+) -> ToolsResult<(MovedFiles, u64)> {
+    let mut moved = MovedFiles::new();
+    let mut seen_paths = HashSet::new();
+    let mut moved_bytes = 0u64;
+
+    let result = (|| -> ToolsResult<()> {
+        for (index, file) in files.iter().enumerate() {
+            if cancelled.load(Ordering::SeqCst) {
+                return Err(ToolsError::Validation(
+                    "Storage migration was cancelled. No files were moved.".to_string(),
+                ));
+            }
+            if !seen_paths.insert(file.stored_rel_path.clone()) {
+                continue;
+            }
+
+            let source = resolve_stored_path_in(old_base, &file.stored_rel_path)?;
+            let target = resolve_stored_path_in(new_base, &file.stored_rel_path)?;
+            if !source.is_file() {
+                return Err(ToolsError::Io(format!(
+                    "Stored file is missing: {}",
+                    file.stored_rel_path
+                )));
+            }
+            if target.exists() {
+                return Err(ToolsError::Conflict(format!(
+                    "Destination already contains {}",
+                    file.stored_rel_path
+                )));
+            }
+
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            move_file(&source, &target)?;
+            moved.push((source, target.clone()));
+
+            let (size_bytes, sha256) = stored_file_digest(&target)?;
+            if !sha256.eq_ignore_ascii_case(file.sha256.trim()) {
+                return Err(ToolsError::Io(format!(
+                    "Hash mismatch after moving {}.",
+                    file.stored_rel_path
+                )));
+            }
+            moved_bytes += size_bytes;
+
+            on_progress(StorageMigrationProgress {
+                processed: index + 1,
+                total: files.len(),
+                current_file: file.original_name.clone(),
+            });
+        }
+
+        Ok(())
+    })();
+
+    if let Err(error) = result {
+        return Err(roll_back(&moved, new_base, error));
+    }
+
+    Ok((moved, moved_bytes))
+}
+
+/// Moves `moved` back in reverse order and returns `error`, extended with every file that could
+/// not be moved back and so is left under the new root.
+fn roll_back(moved: &[(PathBuf, PathBuf)], new_base: &Path, error: ToolsError) -> ToolsError {
+    let stranded = moved
+        .iter()
+        .rev()
+        .filter_map(|(source, target)| {
+            move_file(target, source)
+                .err()
+                .map(|rollback_error| format!("{} ({rollback_error})", target.to_string_lossy()))
+        })
+        .collect::<Vec<_>>();
+    remove_empty_dirs(&new_base.join("tools"));
+    if stranded.is_empty() {
+        return error;
+    }
+    error.map_message(|message| {
+        format!(
+            "{message} Rolling back left {} file(s) under the new root: {}",
+            stranded.len(),
+            stranded.join(", ")
+        )
+    })
+}
+
+/// Returns the icons that moved.
+fn move_icons(old_base: &Path, new_base: &Path, icon_paths: &[String]) -> MovedFiles {
+    // Icons are cosmetic, so a failed move is logged rather than rolling back the migration.
+    let mut moved_icons = MovedFiles::new();
+    for icon_rel_path in icon_paths {
+        let (Ok(source), Ok(target)) = (
+            resolve_icon_path_in(old_base, icon_rel_path),
+            resolve_icon_path_in(new_base, icon_rel_path),
+        ) else {
+            continue;
+        };
+        if !source.is_file() {
+            continue;
+        }
+        let moved = target
+            .parent()
+            .map(fs::create_dir_all)
+            .unwrap_or(Ok(()))
+            .map_err(ToolsError::from)
+            .and_then(|_| move_file(&source, &target));
+        match moved {
+            Ok(()) => moved_icons.push((source, target)),
+            Err(error) => debug!(
+                "custom-tools: storage migration failed to move icon {}: {}",
+                icon_rel_path, error
+            ),
+        }
+    }
+    moved_icons
+}
+
+fn validate_new_root(old_base: &Path, raw: &str) -> ToolsResult<PathBuf> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Err(ToolsError::Validation("new_root is required.".to_string()));
+    }
+
+    let new_base = PathBuf::from(trimmed);
+    if !new_base.is_absolute() {
+        return Err(ToolsError::Validation(
+            "new_root must be an absolute path.".to_string(),
+        ));
+    }
+    if new_base == old_base {
+        return Err(ToolsError::Validation(
+            "new_root is already the current storage root.".to_string(),
+        ));
+    }
+    if new_base.starts_with(old_base.join("tools")) {
+        return Err(ToolsError::Validation(
+            "new_root cannot be inside the current tools folder.".to_string(),
+        ));
+    }
+
+    Ok(new_base)
+}
+
+fn move_file(source: &Path, target: &Path) -> ToolsResult<()> {
+    if fs::rename(source, target).is_ok() {
+        return Ok(());
+    }
+
+    // Renames fail across volumes, so fall back to copy + delete.
+    fs::copy(source, target)?;
+    fs::remove_file(source)?;
+    Ok(())
+}
+
+fn remove_empty_dirs(root: &Path) {
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            remove_empty_dirs(&path);
+        }
+    }
+    let _ = fs::remove_dir(root);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::storage::sha256_hex;
+    use super::*;
+
+    fn temp_base(label: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{label}-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    fn seed_file(base: &Path, name: &str, bytes: &[u8]) -> StoredFileRecord {
+        let stored_rel_path = format!("tools/tool_1/version_1/files/{name}");
+        let absolute = resolve_stored_path_in(base, &stored_rel_path).unwrap();
+        fs::create_dir_all(absolute.parent().unwrap()).unwrap();
+        fs::write(&absolute, bytes).unwrap();
+        StoredFileRecord {
+            original_name: name.to_string(),
+            stored_rel_path,
+            sha256: sha256_hex(bytes),
+            size_bytes: bytes.len() as i64,
+        }
+    }
+
+    #[test]
+    fn relocates_files_and_reports_progress() {
+        let old_base = temp_base("relocate-old");
+        let new_base = temp_base("relocate-new");
+        let files = vec![
+            seed_file(&old_base, "a.scr", b"alpha"),
+            seed_file(&old_base, "b.lsp", b"bravo"),
+        ];
+
+        let mut progress = Vec::new();
+        let (moved, bytes) = relocate_stored_files(
+            &old_base,
+            &new_base,
+            &files,
+            &AtomicBool::new(false),
+            |event| progress.push(event.processed),
+        )
+        .unwrap();
+
+        assert_eq!((moved.len(), bytes), (2, 10));
+        assert_eq!(progress, vec![1, 2]);
+        for file in &files {
+            assert!(!resolve_stored_path_in(&old_base, &file.stored_rel_path)
+                .unwrap()
+                .exists());
+            assert!(resolve_stored_path_in(&new_base, &file.stored_rel_path)
+                .unwrap()
+                .exists());
+        }
+
+        let _ = fs::remove_dir_all(old_base);
+        let _ = fs::remove_dir_all(new_base);
+    }
+
+    #[test]
+    fn rolls_back_moved_files_on_hash_mismatch() {
+        let old_base = temp_base("relocate-rollback-old");
+        let new_base = temp_base("relocate-rollback-new");
+        let good = seed_file(&old_base, "a.scr", b"alpha");
+        let mut corrupt = seed_file(&old_base, "b.lsp", b"bravo");
+        corrupt.sha256 = sha256_hex(b"something else");
+
+        let error = relocate_stored_files(
+            &old_base,
+            &new_base,
+            &[good.clone(), corrupt.clone()],
+            &AtomicBool::new(false),
+            |_| {},
+        )
+        .unwrap_err();
+        assert!(error.user_message().contains("Hash mismatch"));
+
+        for file in [&good, &corrupt] {
+            assert!(resolve_stored_path_in(&old_base, &file.stored_rel_path)
+                .unwrap()
+                .exists());
+            assert!(!resolve_stored_path_in(&new_base, &file.stored_rel_path)
+                .unwrap()
+                .exists());
+        }
+
+        let _ = fs::remove_dir_all(old_base);
+        let _ = fs::remove_dir_all(new_base);
+    }
+
+    #[test]
+    fn rollback_restores_icons_and_reports_files_left_behind() {
+        let old_base = temp_base("relocate-icons-old");
+        let new_base = temp_base("relocate-icons-new");
+        let icon_rel_path = "tools/tool_1/icon.png";
+        let icon = resolve_icon_path_in(&old_base, icon_rel_path).unwrap();
+        fs::create_dir_all(icon.parent().unwrap()).unwrap();
+        fs::write(&icon, b"png").unwrap();
+
+        let mut moved = move_icons(&old_base, &new_base, &[icon_rel_path.to_string()]);
+        assert_eq!(moved.len(), 1);
+        assert!(!icon.exists());
+        let lost = new_base.join("tools/tool_1/version_1/files/gone.lsp");
+        moved.insert(0, (old_base.join("gone.lsp"), lost.clone()));
+
+        let error = roll_back(&moved, &new_base, ToolsError::Io("Disk full.".to_string()));
+        assert!(icon.exists());
+        let message = error.user_message();
+        assert!(message.starts_with("Disk full."));
+        assert!(message.contains("left 1 file(s)"));
+        assert!(message.contains(&*lost.to_string_lossy()));
+
+        let _ = fs::remove_dir_all(old_base);
+        let _ = fs::remove_dir_all(new_base);
+    }
+}
@@ -0,0 +1,213 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+use super::error::{ToolsError, ToolsResult};
+
+pub const JSON_VALIDATION_KEY: &str = "tools.json_validation";
+pub const MAGIC_BYTE_VALIDATION_KEY: &str = "tools.magic_byte_validation";
+pub const MANIFEST_UNKNOWN_FIELDS_KEY: &str = "tools.manifest_unknown_fields";
+pub const ENCRYPT_AT_REST_KEY: &str = "tools.encrypt_at_rest";
+pub const LIBRARY_QUOTA_KEY: &str = "tools.library_quota_bytes";
+pub const MAX_COMPRESSION_RATIO_KEY: &str = "tools.max_compression_ratio";
+pub const STARTUP_CLEANUP_KEY: &str = "tools.startup_cleanup";
+pub const STAGING_ROOT_KEY: &str = "tools.staging_root";
+pub const DEFAULT_CATEGORY_KEY: &str = "tools.default_category";
+/// Stored as a JSON array of strings.
+pub const DEFAULT_TAGS_KEY: &str = "tools.default_tags";
+/// File extensions accepted on top of the built-in set, stored as a JSON array of strings.
+pub const EXTRA_EXTENSIONS_KEY: &str = "tools.extra_extensions";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContentCheckMode {
+    Off,
+    #[default]
+    Warn,
+    Reject,
+}
+
+impl ContentCheckMode {
+    fn parse(key: &str, raw: &str) -> ToolsResult<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "warn" => Ok(Self::Warn),
+            "reject" => Ok(Self::Reject),
+            other => Err(ToolsError::Validation(format!(
+                "Invalid value '{other}' for {key}. Expected off, warn, or reject."
+            ))),
+        }
+    }
+}
+
+/// What startup does with artifacts left behind by operations that never finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StartupCleanupMode {
+    /// Only list the leftovers in the cleanup report.
+    Report,
+    #[default]
+    Remove,
+}
+
+impl StartupCleanupMode {
+    fn parse(raw: &str) -> ToolsResult<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "report" => Ok(Self::Report),
+            "remove" => Ok(Self::Remove),
+            other => Err(ToolsError::Validation(format!(
+                "Invalid value '{other}' for {STARTUP_CLEANUP_KEY}. Expected report or remove."
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ToolsSettings {
+    pub json_validation: ContentCheckMode,
+    /// How files whose leading bytes contradict their extension are handled.
+    pub magic_byte_validation: ContentCheckMode,
+    /// How import manifests with keys outside the known schema are handled.
+    pub manifest_unknown_fields: ContentCheckMode,
+    /// Encrypt newly written tool files with the keyring storage key.
+    pub encrypt_at_rest: bool,
+    /// Maximum total bytes of stored tool files; `None` means unlimited.
+    pub library_quota_bytes: Option<u64>,
+    /// Zip bomb threshold for imports; `None` uses the built-in default.
+    pub max_compression_ratio: Option<u64>,
+    pub startup_cleanup: StartupCleanupMode,
+    /// Directory for import and export scratch folders; `None` uses the system temp dir.
+    pub staging_root: Option<PathBuf>,
+}
+
+pub async fn load_settings(pool: &SqlitePool) -> ToolsResult<ToolsSettings> {
+    let mut settings = ToolsSettings::default();
+
+    if let Some(raw) = read_setting(pool, JSON_VALIDATION_KEY).await? {
+        settings.json_validation = ContentCheckMode::parse(JSON_VALIDATION_KEY, &raw)?;
+    }
+
+    if let Some(raw) = read_setting(pool, MAGIC_BYTE_VALIDATION_KEY).await? {
+        settings.magic_byte_validation = ContentCheckMode::parse(MAGIC_BYTE_VALIDATION_KEY, &raw)?;
+    }
+
+    if let Some(raw) = read_setting(pool, MANIFEST_UNKNOWN_FIELDS_KEY).await? {
+        settings.manifest_unknown_fields =
+            ContentCheckMode::parse(MANIFEST_UNKNOWN_FIELDS_KEY, &raw)?;
+    }
+
+    if let Some(raw) = read_setting(pool, LIBRARY_QUOTA_KEY).await? {
+        let quota = raw.trim().parse::<u64>().map_err(|_| {
+            ToolsError::Validation(format!(
+                "Invalid value '{}' for {LIBRARY_QUOTA_KEY}. Expected a byte count.",
+                raw.trim()
+            ))
+        })?;
+        settings.library_quota_bytes = (quota > 0).then_some(quota);
+    }
+
+    if let Some(raw) = read_setting(pool, MAX_COMPRESSION_RATIO_KEY).await? {
+        let ratio = raw.trim().parse::<u64>().ok().filter(|ratio| *ratio > 0);
+        settings.max_compression_ratio = Some(ratio.ok_or_else(|| {
+            ToolsError::Validation(format!(
+                "Invalid value '{}' for {MAX_COMPRESSION_RATIO_KEY}. Expected a positive ratio.",
+                raw.trim()
+            ))
+        })?);
+    }
+
+    if let Some(raw) = read_setting(pool, STARTUP_CLEANUP_KEY).await? {
+        settings.startup_cleanup = StartupCleanupMode::parse(&raw)?;
+    }
+
+    if let Some(raw) = read_setting(pool, STAGING_ROOT_KEY).await? {
+        settings.staging_root = Some(PathBuf::from(raw.trim()));
+    }
+
+    if let Some(raw) = read_setting(pool, ENCRYPT_AT_REST_KEY).await? {
+        settings.encrypt_at_rest = match raw.trim().to_ascii_lowercase().as_str() {
+            "true" => true,
+            "false" => false,
+            other => {
+                return Err(ToolsError::Validation(format!(
+                    "Invalid value '{other}' for {ENCRYPT_AT_REST_KEY}. Expected true or false."
+                )))
+            }
+        };
+    }
+
+    Ok(settings)
+}
+
+/// Category and tags filled in for new tools whose request leaves them empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolMetadataDefaults {
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+}
+
+pub async fn load_metadata_defaults(pool: &SqlitePool) -> ToolsResult<ToolMetadataDefaults> {
+    let category = read_setting(pool, DEFAULT_CATEGORY_KEY)
+        .await?
+        .map(|value| value.trim().to_string());
+    let tags = match read_setting(pool, DEFAULT_TAGS_KEY).await? {
+        Some(raw) => serde_json::from_str::<Vec<String>>(&raw).map_err(|_| {
+            ToolsError::Validation(format!(
+                "Invalid value for {DEFAULT_TAGS_KEY}. Expected a JSON array of tags."
+            ))
+        })?,
+        None => Vec::new(),
+    };
+    Ok(ToolMetadataDefaults { category, tags })
+}
+
+pub async fn write_metadata_defaults(
+    pool: &SqlitePool,
+    defaults: &ToolMetadataDefaults,
+) -> ToolsResult<()> {
+    let tags = serde_json::to_string(&defaults.tags)
+        .map_err(|error| ToolsError::Validation(format!("Invalid default tags: {error}")))?;
+    write_setting(
+        pool,
+        DEFAULT_CATEGORY_KEY,
+        defaults.category.as_deref().unwrap_or_default(),
+    )
+    .await?;
+    write_setting(pool, DEFAULT_TAGS_KEY, &tags).await
+}
+
+pub async fn load_extra_extensions(pool: &SqlitePool) -> ToolsResult<Vec<String>> {
+    match read_setting(pool, EXTRA_EXTENSIONS_KEY).await? {
+        Some(raw) => serde_json::from_str::<Vec<String>>(&raw).map_err(|_| {
+            ToolsError::Validation(format!(
+                "Invalid value for {EXTRA_EXTENSIONS_KEY}. Expected a JSON array of extensions."
+            ))
+        }),
+        None => Ok(Vec::new()),
+    }
+}
+
+pub async fn write_setting(pool: &SqlitePool, key: &str, value: &str) -> ToolsResult<()> {
+    sqlx::query(
+        "INSERT INTO app_state (key, value, updated_at)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+    )
+    .bind(key)
+    .bind(value)
+    .bind(super::db::now_epoch_millis()?)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn read_setting(pool: &SqlitePool, key: &str) -> ToolsResult<Option<String>> {
+    let row = sqlx::query("SELECT value FROM app_state WHERE key = ?1")
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row
+        .and_then(|value| value.get::<Option<String>, _>("value"))
+        .filter(|value| !value.trim().is_empty()))
+}
@@ -1,27 +1,70 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io::{BufRead, BufReader, Cursor, Read, Write};
 use std::path::{Component, Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 
 use base64::Engine;
 use sha2::{Digest, Sha256};
 use tauri::{AppHandle, Manager};
+use unicode_normalization::UnicodeNormalization;
+use uuid::Uuid;
 
+use super::db::StoredFileRecord;
+use super::encryption::{
+    decrypt_if_encrypted, ensure_storable_as_plaintext, is_encrypted, StorageCipher,
+};
 use super::error::{ToolsError, ToolsResult};
+use super::settings::ContentCheckMode;
 
 pub const DEFAULT_MAX_FILE_SIZE_BYTES: u64 = 50 * 1024 * 1024;
 pub const DEFAULT_MAX_VERSION_SIZE_BYTES: u64 = 200 * 1024 * 1024;
+pub const FREE_SPACE_OVERHEAD_BYTES: u64 = 16 * 1024 * 1024;
+pub const MAX_ICON_SIZE_BYTES: u64 = 256 * 1024;
+pub const TEXT_PREVIEW_MAX_LINES: usize = 500;
+pub const TEXT_PREVIEW_MAX_LINE_CHARS: usize = 1_000;
+pub const TEXT_PREVIEW_MAX_TOTAL_CHARS: usize = 64 * 1024;
 const MAX_SANITIZED_FILENAME_LEN: usize = 120;
+/// Hex digits of the original name's hash appended to a stem that had to be truncated.
+const TRUNCATED_NAME_HASH_LEN: usize = 8;
+const DECODE_CHUNK_BYTES: usize = 64 * 1024;
 const STORAGE_ROOT_SEGMENT: &str = "tools";
+/// Sibling of the tools root holding extraction folders of failed imports.
+const QUARANTINE_SEGMENT: &str = "quarantine";
 const STORAGE_FILES_SEGMENT: &str = "files";
+const STORAGE_ROOT_OVERRIDE_FILE: &str = "tools-storage-root";
+const ICON_FILE_STEM: &str = "icon";
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
 
 const ALLOWED_EXTENSIONS: &[&str] = &[
     "lsp", "vlx", "fas", "scr", "dwg", "dxf", "cuix", "zip", "pdf", "txt", "md", "json",
 ];
+/// Executables and shell scripts; `set_allowed_extensions` refuses to add these.
+const BLOCKED_EXTENSIONS: &[&str] = &[
+    "exe", "bat", "cmd", "com", "ps1", "psm1", "msi", "dll", "vbs", "wsf", "hta",
+];
+const MAX_EXTENSION_LEN: usize = 16;
+const ICON_EXTENSIONS: &[&str] = &["png", "svg"];
+/// Folder for manifest-declared images such as `icon.png` or `cover.png`.
+pub const ASSETS_DIR: &str = "assets";
+const ASSET_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "svg"];
+/// Leading bytes expected for extensions with a well-known file signature.
+const MAGIC_SIGNATURES: &[(&str, &[&[u8]])] = &[
+    ("dwg", &[b"AC1"]),
+    ("pdf", &[b"%PDF"]),
+    ("zip", &[b"PK\x03\x04", b"PK\x05\x06"]),
+    ("cuix", &[b"PK\x03\x04"]),
+];
+/// Sniffed kinds that are native or bytecode executables; no library extension is one of these.
+const EXECUTABLE_KINDS: &[&str] = &["exe", "dll", "elf", "mach", "class", "dex", "dey", "wasm"];
 
 #[derive(Debug, Clone)]
 pub struct FileLimits {
     pub max_file_size_bytes: u64,
     pub max_total_size_bytes: u64,
+    /// Tighter per-file ceilings keyed by lowercase extension without the dot, e.g. `txt`.
+    /// Entries above `max_file_size_bytes` have no effect.
+    pub per_extension: HashMap<String, u64>,
 }
 
 impl Default for FileLimits {
@@ -29,6 +72,30 @@ impl Default for FileLimits {
         Self {
             max_file_size_bytes: DEFAULT_MAX_FILE_SIZE_BYTES,
             max_total_size_bytes: DEFAULT_MAX_VERSION_SIZE_BYTES,
+            per_extension: HashMap::new(),
+        }
+    }
+}
+
+impl FileLimits {
+    /// Checks one file against `max_file_size_bytes`, then against its extension's limit.
+    pub fn check_file_size(&self, file_name: &str, size_bytes: u64) -> ToolsResult<()> {
+        if size_bytes > self.max_file_size_bytes {
+            return Err(ToolsError::Validation(format!(
+                "{} exceeds max size of {} bytes.",
+                file_name, self.max_file_size_bytes
+            )));
+        }
+        let extension = Path::new(file_name)
+            .extension()
+            .and_then(|value| value.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match self.per_extension.get(&extension) {
+            Some(&limit) if size_bytes > limit => Err(ToolsError::Validation(format!(
+                "{file_name} exceeds the .{extension} size limit of {limit} bytes."
+            ))),
+            _ => Ok(()),
         }
     }
 }
@@ -41,6 +108,16 @@ pub struct InboundToolFile {
     pub data_base64: String,
 }
 
+/// A file whose bytes are already in memory, e.g. read from an import archive.
+#[derive(Debug, Clone)]
+pub struct DecodedToolFile {
+    pub original_name: String,
+    pub mime: Option<String>,
+    pub bytes: Vec<u8>,
+    /// Digest already verified by the caller; computed during staging when absent.
+    pub sha256: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct StagedToolFile {
     pub original_name: String,
@@ -56,6 +133,56 @@ pub fn stage_inbound_files(
     version_id: &str,
     files: Vec<InboundToolFile>,
     limits: &FileLimits,
+) -> ToolsResult<Vec<StagedToolFile>> {
+    let decoded = files
+        .into_iter()
+        .map(|file| decode_inbound_file(file, limits.max_file_size_bytes))
+        .collect::<ToolsResult<Vec<_>>>()?;
+
+    stage_decoded_files(tool_id, version_id, decoded, limits)
+}
+
+/// Decodes a payload in chunks, hashing as it goes, so no second full-size buffer is needed.
+/// Decoding stops one byte past `max_size_bytes`; staging then rejects the oversized file
+/// without the rest of the payload ever being decoded.
+fn decode_inbound_file(file: InboundToolFile, max_size_bytes: u64) -> ToolsResult<DecodedToolFile> {
+    let encoded = file.data_base64.trim().as_bytes();
+    let estimated_bytes = (encoded.len() / 4 * 3) as u64;
+    let reader =
+        base64::read::DecoderReader::new(encoded, &base64::engine::general_purpose::STANDARD);
+    let mut reader = reader.take(max_size_bytes.saturating_add(1));
+
+    let mut bytes =
+        Vec::with_capacity(estimated_bytes.min(max_size_bytes.saturating_add(1)) as usize);
+    let mut hasher = Sha256::new();
+    let mut chunk = vec![0u8; DECODE_CHUNK_BYTES];
+    loop {
+        let read = reader.read(&mut chunk).map_err(|error| {
+            ToolsError::Validation(format!(
+                "Invalid base64 file payload for {}: {error}",
+                file.original_name.trim()
+            ))
+        })?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&chunk[..read]);
+        bytes.extend_from_slice(&chunk[..read]);
+    }
+
+    Ok(DecodedToolFile {
+        original_name: file.original_name,
+        mime: file.mime,
+        bytes,
+        sha256: Some(format!("{:x}", hasher.finalize())),
+    })
+}
+
+pub fn stage_decoded_files(
+    tool_id: &str,
+    version_id: &str,
+    files: Vec<DecodedToolFile>,
+    limits: &FileLimits,
 ) -> ToolsResult<Vec<StagedToolFile>> {
     let normalized_tool_id = validate_storage_segment("tool_id", tool_id)?;
     let normalized_version_id = validate_storage_segment("version_id", version_id)?;
@@ -72,25 +199,13 @@ pub fn stage_inbound_files(
 
     for file in files {
         let sanitized = unique_sanitized_filename(&file.original_name, &mut used_names)?;
-        let bytes = base64::engine::general_purpose::STANDARD
-            .decode(file.data_base64.trim())
-            .map_err(|error| {
-                ToolsError::Validation(format!(
-                    "Invalid base64 file payload for {}: {error}",
-                    file.original_name.trim()
-                ))
-            })?;
+        let bytes = file.bytes;
 
         let size_bytes = bytes.len() as u64;
         if size_bytes == 0 {
             return Err(ToolsError::Validation(format!("{} is empty.", sanitized)));
         }
-        if size_bytes > limits.max_file_size_bytes {
-            return Err(ToolsError::Validation(format!(
-                "{} exceeds max size of {} bytes.",
-                sanitized, limits.max_file_size_bytes
-            )));
-        }
+        limits.check_file_size(&sanitized, size_bytes)?;
 
         total_bytes += size_bytes;
         if total_bytes > limits.max_total_size_bytes {
@@ -100,13 +215,14 @@ pub fn stage_inbound_files(
             )));
         }
 
-        let sha256 = sha256_hex(&bytes);
+        let sniffed_mime = sniff_content(&sanitized, &bytes)?;
+        let sha256 = file.sha256.unwrap_or_else(|| sha256_hex(&bytes));
         let stored_rel_path =
             build_stored_rel_path(&normalized_tool_id, &normalized_version_id, &sanitized)?;
 
         staged.push(StagedToolFile {
             original_name: sanitized,
-            mime: file.mime,
+            mime: file.mime.or(sniffed_mime),
             bytes,
             size_bytes,
             sha256,
@@ -117,8 +233,282 @@ pub fn stage_inbound_files(
     Ok(staged)
 }
 
+#[derive(Debug, Clone)]
+pub struct StagedToolIcon {
+    pub bytes: Vec<u8>,
+    pub stored_rel_path: String,
+}
+
+pub fn stage_tool_icon(tool_id: &str, file: InboundToolFile) -> ToolsResult<StagedToolIcon> {
+    let safe_tool_id = validate_storage_segment("tool_id", tool_id)?;
+    let extension = Path::new(file.original_name.trim())
+        .extension()
+        .and_then(|value| value.to_str())
+        .map(|value| value.to_ascii_lowercase())
+        .filter(|value| ICON_EXTENSIONS.contains(&value.as_str()))
+        .ok_or_else(|| {
+            ToolsError::Validation("Tool icons must be .png or .svg files.".to_string())
+        })?;
+
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(file.data_base64.trim())
+        .map_err(|error| ToolsError::Validation(format!("Invalid base64 icon payload: {error}")))?;
+    if bytes.is_empty() {
+        return Err(ToolsError::Validation("Icon file is empty.".to_string()));
+    }
+    if bytes.len() as u64 > MAX_ICON_SIZE_BYTES {
+        return Err(ToolsError::Validation(format!(
+            "Icon exceeds max size of {MAX_ICON_SIZE_BYTES} bytes."
+        )));
+    }
+    assert_icon_content(&extension, &bytes)?;
+
+    Ok(StagedToolIcon {
+        bytes,
+        stored_rel_path: format!(
+            "{STORAGE_ROOT_SEGMENT}/{safe_tool_id}/{ICON_FILE_STEM}.{extension}"
+        ),
+    })
+}
+
+pub fn resolve_icon_path(app: &AppHandle, icon_rel_path: &str) -> ToolsResult<PathBuf> {
+    resolve_icon_path_in(&storage_base_dir(app)?, icon_rel_path)
+}
+
+pub fn resolve_icon_path_in(base_dir: &Path, icon_rel_path: &str) -> ToolsResult<PathBuf> {
+    let segments = icon_rel_path.trim().split('/').collect::<Vec<_>>();
+    let is_icon_name = segments.len() == 3
+        && ICON_EXTENSIONS
+            .iter()
+            .any(|extension| segments[2] == format!("{ICON_FILE_STEM}.{extension}"));
+    if segments[0] != STORAGE_ROOT_SEGMENT || !is_icon_name {
+        return Err(ToolsError::Io(format!(
+            "Invalid icon path structure: {}",
+            icon_rel_path.trim()
+        )));
+    }
+
+    let safe_tool_id = validate_storage_segment("tool_id", segments[1])?;
+    Ok(base_dir
+        .join(STORAGE_ROOT_SEGMENT)
+        .join(safe_tool_id)
+        .join(segments[2]))
+}
+
+/// Elements that run script or embed another document; SVG icons are rendered inline.
+const UNSAFE_SVG_ELEMENTS: &[&str] = &[
+    "script",
+    "foreignobject",
+    "iframe",
+    "embed",
+    "object",
+    "handler",
+];
+
+fn assert_icon_content(extension: &str, bytes: &[u8]) -> ToolsResult<()> {
+    let is_valid = match extension {
+        "png" => bytes.starts_with(PNG_SIGNATURE),
+        _ => std::str::from_utf8(bytes)
+            .map(|text| {
+                let lowered = text
+                    .trim_start_matches('\u{feff}')
+                    .trim_start()
+                    .to_ascii_lowercase();
+                (lowered.starts_with("<?xml")
+                    || lowered.starts_with("<svg")
+                    || lowered.starts_with("<!--"))
+                    && lowered.contains("<svg")
+                    && is_inert_svg(&lowered)
+            })
+            .unwrap_or(false),
+    };
+
+    if !is_valid {
+        return Err(ToolsError::Validation(format!(
+            "Icon content is not a valid .{extension} image."
+        )));
+    }
+    Ok(())
+}
+
+/// True when a lowercased SVG holds nothing that can run script or load another document once
+/// rendered inline: no [`UNSAFE_SVG_ELEMENTS`], no `on*` handlers, no DTD that could declare
+/// entities, and no `href` other than a same-document fragment. Encoded values are refused, not
+/// decoded, so `javascript&#58;` fails like `javascript:`.
+fn is_inert_svg(lowered: &str) -> bool {
+    if lowered.contains("<!doctype") || lowered.contains("<!entity") {
+        return false;
+    }
+    let mut rest = lowered;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        if rest.starts_with(['!', '?', '/']) {
+            continue;
+        }
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .unwrap_or(rest.len());
+        if UNSAFE_SVG_ELEMENTS.contains(&local_name(&rest[..name_end])) {
+            return false;
+        }
+        rest = &rest[name_end..];
+
+        loop {
+            rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+            if rest.is_empty() || rest.starts_with('>') {
+                break;
+            }
+            let attribute_end = rest
+                .find(|c: char| c.is_whitespace() || c == '=' || c == '>' || c == '/')
+                .unwrap_or(rest.len());
+            let attribute = &rest[..attribute_end];
+            rest = rest[attribute_end..].trim_start();
+            let mut value = "";
+            if let Some(after) = rest.strip_prefix('=') {
+                let after = after.trim_start();
+                match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let Some(end) = after[1..].find(quote) else {
+                            return false;
+                        };
+                        value = &after[1..end + 1];
+                        rest = &after[end + 2..];
+                    }
+                    _ => {
+                        let end = after
+                            .find(|c: char| c.is_whitespace() || c == '>')
+                            .unwrap_or(after.len());
+                        value = &after[..end];
+                        rest = &after[end..];
+                    }
+                }
+            }
+            if !is_inert_svg_attribute(local_name(attribute), value.trim()) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+fn is_inert_svg_attribute(name: &str, value: &str) -> bool {
+    match name {
+        _ if name.starts_with("on") => false,
+        "href" => value.starts_with('#') && !value.contains('&'),
+        // `<set>` and `<animate>` could otherwise swap an href in after loading.
+        "attributename" => !value.contains('&') && local_name(value) != "href",
+        _ => true,
+    }
+}
+
+/// `xlink:href` -> `href`.
+fn local_name(qualified: &str) -> &str {
+    qualified.rsplit(':').next().unwrap_or(qualified)
+}
+
+pub fn check_json_content(
+    original_name: &str,
+    bytes: &[u8],
+    mode: ContentCheckMode,
+) -> ToolsResult<Option<String>> {
+    let is_json = Path::new(original_name)
+        .extension()
+        .and_then(|value| value.to_str())
+        .map(|value| value.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+    if mode == ContentCheckMode::Off || !is_json {
+        return Ok(None);
+    }
+
+    let content = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let Err(error) = serde_json::from_slice::<serde_json::Value>(content) else {
+        return Ok(None);
+    };
+
+    let message = format!("{original_name} is not valid JSON: {error}");
+    match mode {
+        ContentCheckMode::Reject => Err(ToolsError::Validation(message)),
+        _ => Ok(Some(message)),
+    }
+}
+
+/// Detects the content type from the leading bytes and returns its MIME type. Fails when the
+/// content is an executable, whatever the extension says. Text and unrecognized content pass
+/// with no MIME type, since LISP and script files have no signature.
+pub fn sniff_content(original_name: &str, bytes: &[u8]) -> ToolsResult<Option<String>> {
+    let Some(kind) = infer::get(bytes) else {
+        return Ok(None);
+    };
+    if matches!(kind.extension(), "exe" | "dll") && !is_portable_executable(bytes) {
+        // Only "MZ" was matched, which plain text can start with too.
+        return Ok(None);
+    }
+    if EXECUTABLE_KINDS.contains(&kind.extension()) {
+        return Err(ToolsError::Validation(format!(
+            "{original_name} is an executable ({}), not a file its extension allows.",
+            kind.mime_type()
+        )));
+    }
+    Ok(Some(kind.mime_type().to_string()))
+}
+
+/// True when the DOS header points at a `PE\0\0` signature.
+fn is_portable_executable(bytes: &[u8]) -> bool {
+    let Some(offset) = bytes
+        .get(0x3c..0x40)
+        .map(|raw| u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as usize)
+    else {
+        return false;
+    };
+    offset
+        .checked_add(4)
+        .and_then(|end| bytes.get(offset..end))
+        .is_some_and(|signature| signature == b"PE\0\0")
+}
+
+pub fn check_magic_bytes(
+    original_name: &str,
+    bytes: &[u8],
+    mode: ContentCheckMode,
+) -> ToolsResult<Option<String>> {
+    if mode == ContentCheckMode::Off {
+        return Ok(None);
+    }
+    let extension = Path::new(original_name)
+        .extension()
+        .and_then(|value| value.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let Some((_, signatures)) = MAGIC_SIGNATURES
+        .iter()
+        .find(|(candidate, _)| *candidate == extension)
+    else {
+        return Ok(None);
+    };
+    if signatures
+        .iter()
+        .any(|signature| bytes.starts_with(signature))
+    {
+        return Ok(None);
+    }
+
+    let message = format!(
+        "{original_name} does not look like a .{extension} file; its content does not match the expected header."
+    );
+    match mode {
+        ContentCheckMode::Reject => Err(ToolsError::Validation(message)),
+        _ => Ok(Some(message)),
+    }
+}
+
 pub fn sanitize_filename(original_name: &str) -> ToolsResult<String> {
-    let candidate = original_name.trim();
+    // macOS hands over decomposed (NFD) names; compose them so both platforms agree.
+    let normalized = original_name.nfc().collect::<String>();
+    let candidate = normalized.trim();
     if candidate.is_empty() {
         return Err(ToolsError::Validation("File name is required.".to_string()));
     }
@@ -147,11 +537,12 @@ pub fn sanitize_filename(original_name: &str) -> ToolsResult<String> {
         .and_then(|value| value.to_str())
         .map(|value| value.to_ascii_lowercase())
         .ok_or_else(|| ToolsError::Validation("File extension is required.".to_string()))?;
-    if !ALLOWED_EXTENSIONS.contains(&extension.as_str()) {
+    let allowed = allowed_extensions();
+    if !allowed.contains(&extension) {
         return Err(ToolsError::Validation(format!(
             "Unsupported file extension .{}. Allowed: {}",
             extension,
-            ALLOWED_EXTENSIONS
+            allowed
                 .iter()
                 .map(|item| format!(".{item}"))
                 .collect::<Vec<_>>()
@@ -187,12 +578,78 @@ pub fn sanitize_filename(original_name: &str) -> ToolsResult<String> {
 
     let max_stem_len = MAX_SANITIZED_FILENAME_LEN.saturating_sub(extension.len() + 1);
     if sanitized_stem.len() > max_stem_len {
-        sanitized_stem.truncate(max_stem_len.max(1));
+        // Names that only differ past the cut would collapse into one, so tag the truncated
+        // stem with a hash of the full name.
+        let tag = &sha256_hex(candidate.as_bytes())[..TRUNCATED_NAME_HASH_LEN];
+        sanitized_stem.truncate(
+            max_stem_len
+                .saturating_sub(TRUNCATED_NAME_HASH_LEN + 1)
+                .max(1),
+        );
+        sanitized_stem = format!("{sanitized_stem}_{tag}");
     }
 
     Ok(format!("{sanitized_stem}.{extension}"))
 }
 
+fn extra_extensions() -> &'static Mutex<Vec<String>> {
+    static EXTRA: OnceLock<Mutex<Vec<String>>> = OnceLock::new();
+    EXTRA.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+fn lock_extra_extensions() -> std::sync::MutexGuard<'static, Vec<String>> {
+    // The list is replaced whole, so a poisoned lock still holds a consistent value.
+    extra_extensions()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The built-in extensions followed by the ones added with `set_allowed_extensions`.
+pub fn allowed_extensions() -> Vec<String> {
+    ALLOWED_EXTENSIONS
+        .iter()
+        .map(|extension| extension.to_string())
+        .chain(lock_extra_extensions().iter().cloned())
+        .collect()
+}
+
+/// Lowercases `extensions`, strips leading dots, and drops duplicates and built-ins. Fails on
+/// anything that is not a short alphanumeric extension, and on executables or shell scripts.
+pub fn normalize_extra_extensions(extensions: &[String]) -> ToolsResult<Vec<String>> {
+    let mut normalized = Vec::new();
+    for raw in extensions {
+        let extension = raw.trim().trim_start_matches('.').to_ascii_lowercase();
+        if extension.is_empty()
+            || extension.len() > MAX_EXTENSION_LEN
+            || !extension
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric())
+        {
+            return Err(ToolsError::Validation(format!(
+                "Invalid file extension '{}'.",
+                raw.trim()
+            )));
+        }
+        if BLOCKED_EXTENSIONS.contains(&extension.as_str()) {
+            return Err(ToolsError::Validation(format!(
+                "Files with the .{extension} extension can never be stored in the library."
+            )));
+        }
+        if !ALLOWED_EXTENSIONS.contains(&extension.as_str()) && !normalized.contains(&extension) {
+            normalized.push(extension);
+        }
+    }
+    Ok(normalized)
+}
+
+/// Replaces the extensions accepted on top of the built-in set and returns them normalized.
+/// Nothing changes when any entry is rejected.
+pub fn set_allowed_extensions(extensions: &[String]) -> ToolsResult<Vec<String>> {
+    let normalized = normalize_extra_extensions(extensions)?;
+    *lock_extra_extensions() = normalized.clone();
+    Ok(normalized)
+}
+
 pub fn assert_safe_archive_path(path: &str) -> ToolsResult<()> {
     let trimmed = path.trim();
     if trimmed.is_empty() {
@@ -225,30 +682,135 @@ pub fn assert_safe_archive_path(path: &str) -> ToolsResult<()> {
         }
     }
 
+    assert_asset_entry(trimmed)
+}
+
+/// Entries under the top-level `assets/` folder must be images sitting directly in it. A folder
+/// of that name anywhere else, such as among a tool's files, is ordinary content.
+fn assert_asset_entry(path: &str) -> ToolsResult<()> {
+    let mut segments = path.split('/');
+    if segments.next() != Some(ASSETS_DIR) {
+        return Ok(());
+    }
+    let rest = segments.collect::<Vec<_>>();
+    match rest.as_slice() {
+        [] => Ok(()),
+        [name] if is_allowed_asset_name(name) => Ok(()),
+        [name] => Err(ToolsError::Zip(format!(
+            "Asset {name} is not an allowed image type ({}).",
+            ASSET_EXTENSIONS.join(", ")
+        ))),
+        _ => Err(ToolsError::Zip(format!(
+            "Assets must sit directly under {ASSETS_DIR}/: {path}"
+        ))),
+    }
+}
+
+fn is_allowed_asset_name(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|value| value.to_str())
+        .is_some_and(|extension| {
+            ASSET_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+        })
+}
+
+pub fn storage_base_dir(app: &AppHandle) -> ToolsResult<PathBuf> {
+    let override_path = storage_root_override_path(app)?;
+    if override_path.exists() {
+        let configured = fs::read_to_string(&override_path)?;
+        let configured = configured.trim();
+        if !configured.is_empty() {
+            return Ok(PathBuf::from(configured));
+        }
+    }
+
+    app.path()
+        .app_data_dir()
+        .map_err(|error| ToolsError::Io(format!("Failed to resolve app data directory: {error}")))
+}
+
+pub fn set_storage_base_dir(app: &AppHandle, base_dir: &Path) -> ToolsResult<()> {
+    let override_path = storage_root_override_path(app)?;
+    if let Some(parent) = override_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(override_path, base_dir.to_string_lossy().as_bytes())?;
     Ok(())
 }
 
 pub fn tools_root_dir(app: &AppHandle) -> ToolsResult<PathBuf> {
-    let app_data_dir = app.path().app_data_dir().map_err(|error| {
-        ToolsError::Io(format!("Failed to resolve app data directory: {error}"))
-    })?;
-    Ok(app_data_dir.join("tools"))
+    Ok(tools_root_in(&storage_base_dir(app)?))
+}
+
+pub fn tools_root_in(base_dir: &Path) -> PathBuf {
+    base_dir.join(STORAGE_ROOT_SEGMENT)
 }
 
+pub fn quarantine_root(app: &AppHandle) -> ToolsResult<PathBuf> {
+    Ok(storage_base_dir(app)?.join(QUARANTINE_SEGMENT))
+}
+
+/// Writes staged files under the storage root, encrypting them when a cipher is given. The DB
+/// keeps plaintext hashes and sizes either way. A file whose content is already stored, in
+/// `existing` or earlier in the batch, is hardlinked to that copy instead of written again. Both
+/// sides are hashed before linking, since a trusted manifest's digest was never checked.
 pub fn write_staged_files(
     app: &AppHandle,
     staged_files: &[StagedToolFile],
+    cipher: Option<&StorageCipher>,
+    existing: &[StoredFileRecord],
 ) -> ToolsResult<Vec<PathBuf>> {
-    let root = tools_root_dir(app)?;
+    write_staged_files_in(&storage_base_dir(app)?, staged_files, cipher, existing)
+}
+
+fn write_staged_files_in(
+    base_dir: &Path,
+    staged_files: &[StagedToolFile],
+    cipher: Option<&StorageCipher>,
+    existing: &[StoredFileRecord],
+) -> ToolsResult<Vec<PathBuf>> {
+    let root = tools_root_in(base_dir);
+    let mut stored_by_hash = HashMap::new();
+    for record in existing {
+        stored_by_hash
+            .entry(record.sha256.to_ascii_lowercase())
+            .or_insert_with(|| record.stored_rel_path.clone());
+    }
+    if cipher.is_none() {
+        for file in staged_files {
+            ensure_storable_as_plaintext(&file.original_name, &file.bytes)?;
+        }
+    }
     let mut written_paths = Vec::with_capacity(staged_files.len());
 
     for file in staged_files {
-        let absolute_path = resolve_stored_path(app, &file.stored_rel_path)?;
+        let absolute_path = resolve_stored_path_in(base_dir, &file.stored_rel_path)?;
         let parent = absolute_path
             .parent()
             .ok_or_else(|| ToolsError::Io("Invalid destination file path.".to_string()))?;
         fs::create_dir_all(parent)?;
-        fs::write(&absolute_path, &file.bytes)?;
+        let hash = file.sha256.to_ascii_lowercase();
+        let linked = match stored_by_hash.get(&hash) {
+            Some(stored_rel_path) if sha256_hex(&file.bytes) == hash => link_stored_blob(
+                base_dir,
+                stored_rel_path,
+                &absolute_path,
+                cipher.is_some(),
+                &hash,
+            ),
+            _ => false,
+        };
+        if !linked {
+            let encrypted = cipher
+                .map(|cipher| cipher.encrypt(&file.bytes))
+                .transpose()?;
+            let bytes = encrypted.as_deref().unwrap_or(&file.bytes);
+            write_file_atomically(&absolute_path, |target| target.write_all(bytes))?;
+        }
+        stored_by_hash
+            .entry(hash)
+            .or_insert_with(|| file.stored_rel_path.clone());
         written_paths.push(absolute_path);
     }
 
@@ -259,6 +821,57 @@ pub fn write_staged_files(
     Ok(written_paths)
 }
 
+/// Hardlinks `destination` to the stored file at `stored_rel_path`, which must be encrypted
+/// exactly when new files are and still hash to `sha256`. Returns false, leaving the caller to
+/// write a copy, when the file is gone, its content differs, or the volume cannot hardlink.
+fn link_stored_blob(
+    base_dir: &Path,
+    stored_rel_path: &str,
+    destination: &Path,
+    encrypted: bool,
+    sha256: &str,
+) -> bool {
+    let Ok(source) = resolve_stored_path_in(base_dir, stored_rel_path) else {
+        return false;
+    };
+    let mut header = Vec::new();
+    let readable = fs::File::open(&source)
+        .and_then(|file| file.take(16).read_to_end(&mut header))
+        .is_ok();
+    readable
+        && is_encrypted(&header) == encrypted
+        && stored_file_digest(&source).is_ok_and(|(_, digest)| digest == sha256)
+        && fs::hard_link(&source, destination).is_ok()
+}
+
+/// Runs `write` against a sibling `.tmp` file and renames it over `path` once the bytes are
+/// flushed, so a crash mid-write never leaves a truncated file under the final name. The temp
+/// file is removed when writing or renaming fails.
+pub(crate) fn write_file_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut fs::File) -> std::io::Result<()>,
+) -> ToolsResult<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| ToolsError::Io("Invalid destination file path.".to_string()))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        Uuid::new_v4()
+    ));
+    let result = fs::File::create(&temp_path)
+        .and_then(|mut target| {
+            write(&mut target)?;
+            target.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+    if let Err(error) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(error.into());
+    }
+    Ok(())
+}
+
 pub fn remove_written_files(paths: &[PathBuf]) {
     for path in paths {
         let _ = fs::remove_file(path);
@@ -274,6 +887,8 @@ pub fn delete_tool_folder(app: &AppHandle, tool_id: &str) -> ToolsResult<()> {
     Ok(())
 }
 
+/// Removes a version's folder. Files shared with other versions are hardlinks, so removing this
+/// folder's links leaves the content in place for the versions still referencing it.
 pub fn delete_version_folder(app: &AppHandle, tool_id: &str, version_id: &str) -> ToolsResult<()> {
     let safe_tool_id = validate_storage_segment("tool_id", tool_id)?;
     let safe_version_id = validate_storage_segment("version_id", version_id)?;
@@ -360,11 +975,15 @@ pub fn normalize_stored_rel_path(stored_rel_path: &str) -> ToolsResult<String> {
 }
 
 pub fn resolve_stored_path(app: &AppHandle, stored_rel_path: &str) -> ToolsResult<PathBuf> {
-    let normalized_rel_path = normalize_stored_rel_path(stored_rel_path)?;
+    resolve_stored_path_in(&storage_base_dir(app)?, stored_rel_path)
+}
 
-    let mut absolute = app.path().app_data_dir().map_err(|error| {
-        ToolsError::Io(format!("Failed to resolve app data directory: {error}"))
-    })?;
+/// Resolves a stored path under `base_dir`. Any existing entry below the tools root that is a
+/// symlink is refused, so a swapped-in link cannot point reads or writes outside the library.
+pub fn resolve_stored_path_in(base_dir: &Path, stored_rel_path: &str) -> ToolsResult<PathBuf> {
+    let normalized_rel_path = normalize_stored_rel_path(stored_rel_path)?;
+    let tools_root = tools_root_in(base_dir);
+    let mut absolute = base_dir.to_path_buf();
 
     for component in Path::new(&normalized_rel_path).components() {
         match component {
@@ -375,18 +994,183 @@ pub fn resolve_stored_path(app: &AppHandle, stored_rel_path: &str) -> ToolsResul
                 )))
             }
         }
+        if absolute != tools_root
+            && fs::symlink_metadata(&absolute).is_ok_and(|metadata| metadata.is_symlink())
+        {
+            return Err(ToolsError::Io(format!(
+                "Refusing to read symlinked stored file: {normalized_rel_path}"
+            )));
+        }
     }
 
     Ok(absolute)
 }
 
-pub fn read_stored_file_bytes(app: &AppHandle, stored_rel_path: &str) -> ToolsResult<Vec<u8>> {
-    let path = resolve_stored_path(app, stored_rel_path)?;
-    let bytes = fs::read(path)?;
-    Ok(bytes)
+/// Moves a stored file to a new stored path. An existing file at the destination is never
+/// replaced, except when only the case of the name changes.
+pub fn move_stored_file_in(
+    base_dir: &Path,
+    from_rel_path: &str,
+    to_rel_path: &str,
+) -> ToolsResult<()> {
+    let from = resolve_stored_path_in(base_dir, from_rel_path)?;
+    let to = resolve_stored_path_in(base_dir, to_rel_path)?;
+    if to.exists() && !from_rel_path.eq_ignore_ascii_case(to_rel_path) {
+        return Err(ToolsError::Conflict(format!(
+            "A stored file already exists at {to_rel_path}."
+        )));
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(&from, &to)?;
+    Ok(())
+}
+
+/// Reads a stored file as plaintext, decrypting it if it was written with encryption at rest.
+pub fn read_stored_path(path: &Path) -> ToolsResult<Vec<u8>> {
+    decrypt_if_encrypted(fs::read(path)?)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextHead {
+    pub lines: Vec<String>,
+    /// True when anything was left out: later lines, the tail of a long line, or text past the cap.
+    pub truncated: bool,
+}
+
+/// Reads at most `max_lines` lines from the start of a stored file without loading the rest.
+/// Encrypted files have to be decrypted whole, so only plaintext files are truly streamed.
+pub fn read_stored_text_head(path: &Path, max_lines: usize) -> ToolsResult<TextHead> {
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    if is_encrypted(reader.fill_buf()?) {
+        let mut stored = Vec::new();
+        reader.read_to_end(&mut stored)?;
+        return read_text_head(Cursor::new(decrypt_if_encrypted(stored)?), max_lines);
+    }
+    read_text_head(reader, max_lines)
+}
+
+pub fn read_text_head(mut reader: impl BufRead, max_lines: usize) -> ToolsResult<TextHead> {
+    // Enough bytes for the longest allowed line even if every character is 4 bytes of UTF-8.
+    let max_line_bytes = (TEXT_PREVIEW_MAX_LINE_CHARS * 4) as u64;
+    let mut lines = Vec::new();
+    let mut total_chars = 0usize;
+    let mut truncated = false;
+    let mut raw = Vec::new();
+
+    while lines.len() < max_lines.min(TEXT_PREVIEW_MAX_LINES) {
+        raw.clear();
+        let read = reader
+            .by_ref()
+            .take(max_line_bytes)
+            .read_until(b'\n', &mut raw)?;
+        if read == 0 {
+            break;
+        }
+        if raw.contains(&0) {
+            return Err(ToolsError::Validation(
+                "File does not look like text and cannot be previewed.".to_string(),
+            ));
+        }
+
+        let ended = raw.last() == Some(&b'\n');
+        if !ended && read as u64 == max_line_bytes {
+            truncated = true;
+            skip_rest_of_line(&mut reader)?;
+        }
+        let decoded = String::from_utf8_lossy(&raw);
+        let mut line = decoded.trim_end_matches(['\r', '\n']).to_string();
+
+        let allowed = TEXT_PREVIEW_MAX_LINE_CHARS.min(TEXT_PREVIEW_MAX_TOTAL_CHARS - total_chars);
+        if let Some((cut, _)) = line.char_indices().nth(allowed) {
+            line.truncate(cut);
+            truncated = true;
+        }
+        total_chars += line.chars().count();
+        lines.push(line);
+        if total_chars >= TEXT_PREVIEW_MAX_TOTAL_CHARS {
+            truncated = true;
+            break;
+        }
+    }
+
+    if !truncated && !reader.fill_buf()?.is_empty() {
+        truncated = true;
+    }
+    Ok(TextHead { lines, truncated })
+}
+
+fn skip_rest_of_line(reader: &mut impl BufRead) -> ToolsResult<()> {
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(());
+        }
+        if let Some(index) = buffer.iter().position(|byte| *byte == b'\n') {
+            reader.consume(index + 1);
+            return Ok(());
+        }
+        let length = buffer.len();
+        reader.consume(length);
+    }
+}
+
+pub fn ensure_free_space(target: &Path, required_bytes: u64, operation: &str) -> ToolsResult<()> {
+    // The target may not exist yet (e.g. a new export folder), so probe the nearest existing ancestor.
+    let probe = target
+        .ancestors()
+        .find(|candidate| !candidate.as_os_str().is_empty() && candidate.exists())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let available_bytes = fs2::available_space(&probe).map_err(|error| {
+        ToolsError::Io(format!(
+            "Failed to determine free disk space at {}: {error}",
+            probe.to_string_lossy()
+        ))
+    })?;
+
+    let needed_bytes = required_bytes.saturating_add(FREE_SPACE_OVERHEAD_BYTES);
+    if available_bytes < needed_bytes {
+        return Err(ToolsError::Io(format!(
+            "Not enough free disk space for {operation} at {}: {} bytes required, {} bytes available.",
+            probe.to_string_lossy(),
+            needed_bytes,
+            available_bytes
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn ensure_within_quota(
+    used_bytes: u64,
+    incoming_bytes: u64,
+    quota_bytes: Option<u64>,
+) -> ToolsResult<()> {
+    let Some(quota_bytes) = quota_bytes else {
+        return Ok(());
+    };
+
+    let projected_bytes = used_bytes.saturating_add(incoming_bytes);
+    if projected_bytes > quota_bytes {
+        return Err(ToolsError::Validation(format!(
+            "Library size quota exceeded: {incoming_bytes} bytes requested, {} of {quota_bytes} bytes available.",
+            quota_bytes.saturating_sub(used_bytes)
+        )));
+    }
+
+    Ok(())
+}
+
+fn storage_root_override_path(app: &AppHandle) -> ToolsResult<PathBuf> {
+    let app_config_dir = app.path().app_config_dir().map_err(|error| {
+        ToolsError::Io(format!("Failed to resolve app config directory: {error}"))
+    })?;
+    Ok(app_config_dir.join(STORAGE_ROOT_OVERRIDE_FILE))
 }
 
-fn validate_storage_segment(label: &str, value: &str) -> ToolsResult<String> {
+pub(crate) fn validate_storage_segment(label: &str, value: &str) -> ToolsResult<String> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
         return Err(ToolsError::Validation(format!("{label} is required.")));
@@ -485,12 +1269,72 @@ fn is_reserved_windows_name(stem: &str) -> bool {
     )
 }
 
+/// Hashes a version's sorted file hashes together with its instructions and changelog, so two
+/// versions with identical content share a hash regardless of file order or names.
+pub fn version_content_hash<'a>(
+    file_sha256s: impl IntoIterator<Item = &'a str>,
+    instructions_md: &str,
+    changelog_md: Option<&str>,
+) -> String {
+    let mut file_hashes = file_sha256s
+        .into_iter()
+        .map(|value| value.trim().to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    file_hashes.sort();
+
+    let mut hasher = Sha256::new();
+    hasher.update(b"version-hash-v1\n");
+    for file_hash in &file_hashes {
+        hasher.update(file_hash.as_bytes());
+        hasher.update(b"\n");
+    }
+    hasher.update(format!("instructions:{}\n", instructions_md.len()).as_bytes());
+    hasher.update(instructions_md.as_bytes());
+    match changelog_md {
+        Some(changelog) => {
+            hasher.update(format!("changelog:{}\n", changelog.len()).as_bytes());
+            hasher.update(changelog.as_bytes());
+        }
+        None => hasher.update(b"changelog:none"),
+    }
+    format!("{:x}", hasher.finalize())
+}
+
 pub fn sha256_hex(bytes: &[u8]) -> String {
     let mut hasher = Sha256::new();
     hasher.update(bytes);
     format!("{:x}", hasher.finalize())
 }
 
+/// `sha256_hex` of a file's raw contents, read in fixed-size chunks so memory stays flat.
+pub fn sha256_file(path: &Path) -> ToolsResult<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut chunk = vec![0u8; DECODE_CHUNK_BYTES];
+    loop {
+        let read = file.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&chunk[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Plaintext size and SHA-256 of a stored file. Plaintext files are hashed in chunks; encrypted
+/// files have to be decrypted whole first.
+pub fn stored_file_digest(path: &Path) -> ToolsResult<(u64, String)> {
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    if is_encrypted(reader.fill_buf()?) {
+        let mut stored = Vec::new();
+        reader.read_to_end(&mut stored)?;
+        let bytes = decrypt_if_encrypted(stored)?;
+        return Ok((bytes.len() as u64, sha256_hex(&bytes)));
+    }
+    drop(reader);
+    Ok((fs::metadata(path)?.len(), sha256_file(path)?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -507,6 +1351,92 @@ mod tests {
         assert!(invalid.is_err());
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_stored_files_are_refused() {
+        let base = std::env::temp_dir().join(format!("symlink-{}", Uuid::new_v4()));
+        let secret = base.join("secrets.db");
+        let files_dir = base.join("tools/tool_1/version_1/files");
+        fs::create_dir_all(&files_dir).unwrap();
+        fs::write(&secret, b"secret").unwrap();
+        std::os::unix::fs::symlink(&secret, files_dir.join("leak.txt")).unwrap();
+        std::os::unix::fs::symlink(&files_dir, base.join("tools/tool_1/version_2")).unwrap();
+        fs::write(files_dir.join("plain.txt"), b"plain").unwrap();
+
+        for rel_path in [
+            "tools/tool_1/version_1/files/leak.txt",
+            "tools/tool_1/version_2/files/plain.txt",
+        ] {
+            let error = resolve_stored_path_in(&base, rel_path).unwrap_err();
+            assert!(error
+                .user_message()
+                .starts_with("Refusing to read symlinked stored file"));
+        }
+        let plain = resolve_stored_path_in(&base, "tools/tool_1/version_1/files/plain.txt");
+        assert_eq!(read_stored_path(&plain.unwrap()).unwrap(), b"plain");
+
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn truncated_names_keep_a_hash_of_the_original() {
+        let prefix = "survey_points_".repeat(10);
+        let first = sanitize_filename(&format!("{prefix}north_block.csv.txt")).unwrap();
+        let second = sanitize_filename(&format!("{prefix}south_block.csv.txt")).unwrap();
+        assert_ne!(first, second);
+        for name in [&first, &second] {
+            assert!(name.len() <= MAX_SANITIZED_FILENAME_LEN);
+            assert!(name.starts_with("survey_points_"));
+            assert!(name.ends_with(".txt"));
+        }
+        let tag = &sha256_hex(format!("{prefix}north_block.csv.txt").as_bytes())[..8];
+        assert!(first.ends_with(&format!("_{tag}.txt")));
+
+        assert_eq!(sanitize_filename("short.txt").unwrap(), "short.txt");
+    }
+
+    #[test]
+    fn composed_and_decomposed_names_sanitize_alike() {
+        let composed = "r\u{e9}sum\u{e9}.txt";
+        let decomposed = "re\u{301}sume\u{301}.txt";
+        assert_eq!(
+            sanitize_filename(composed).unwrap(),
+            sanitize_filename(decomposed).unwrap()
+        );
+
+        let mut used = HashSet::new();
+        let first = unique_sanitized_filename(composed, &mut used).unwrap();
+        let second = unique_sanitized_filename(decomposed, &mut used).unwrap();
+        let third = unique_sanitized_filename("R\u{c9}SUM\u{c9}.TXT", &mut used).unwrap();
+        assert_eq!(first, "r_sum_.txt");
+        assert_eq!(second, "r_sum__2.txt");
+        assert_eq!(third, "R_SUM__3.txt");
+    }
+
+    #[test]
+    fn added_extensions_are_accepted_but_executables_never_are() {
+        assert!(sanitize_filename("macros.dvb").is_err());
+        let added =
+            set_allowed_extensions(&[" .DVB ".to_string(), "dvb".to_string(), "lsp".to_string()])
+                .unwrap();
+        assert_eq!(added, vec!["dvb".to_string()]);
+        assert_eq!(sanitize_filename("macros.DVB").unwrap(), "macros.dvb");
+        assert!(allowed_extensions().contains(&"lsp".to_string()));
+
+        for blocked in ["exe", ".BAT", "ps1"] {
+            let error =
+                set_allowed_extensions(&["dvb".to_string(), blocked.to_string()]).unwrap_err();
+            assert!(error.user_message().contains("can never be stored"));
+        }
+        assert!(set_allowed_extensions(&["d/vb".to_string()]).is_err());
+        // A rejected update leaves the previous list in place.
+        assert_eq!(sanitize_filename("macros.dvb").unwrap(), "macros.dvb");
+        assert!(sanitize_filename("payload.exe").is_err());
+
+        set_allowed_extensions(&[]).unwrap();
+        assert!(sanitize_filename("macros.dvb").is_err());
+    }
+
     #[test]
     fn rejects_traversal_and_absolute_paths() {
         assert!(assert_safe_archive_path("../file.txt").is_err());
@@ -516,6 +1446,11 @@ mod tests {
         assert!(assert_safe_archive_path("/absolute/file.txt").is_err());
         assert!(assert_safe_archive_path("\\\\server\\share\\file.txt").is_err());
         assert!(assert_safe_archive_path("files/good/file.txt").is_ok());
+        assert!(assert_safe_archive_path("assets/icon.png").is_ok());
+        assert!(assert_safe_archive_path("versions/1.0.0/assets/Cover.JPG").is_ok());
+        assert!(assert_safe_archive_path("assets/nested/icon.png").is_err());
+        assert!(assert_safe_archive_path("assets/setup.exe").is_err());
+        assert!(assert_safe_archive_path("files/assets/readme.txt").is_ok());
     }
 
     #[test]
@@ -582,6 +1517,7 @@ mod tests {
             &FileLimits {
                 max_file_size_bytes: 2,
                 max_total_size_bytes: 10,
+                ..FileLimits::default()
             },
         )
         .unwrap_err();
@@ -594,6 +1530,7 @@ mod tests {
             &FileLimits {
                 max_file_size_bytes: 10,
                 max_total_size_bytes: 5,
+                ..FileLimits::default()
             },
         )
         .unwrap_err();
@@ -602,6 +1539,289 @@ mod tests {
             .contains("Combined file size exceeds"));
     }
 
+    #[test]
+    fn per_extension_limits_tighten_the_global_cap() {
+        let inbound = |name: &str, size: usize| InboundToolFile {
+            original_name: name.to_string(),
+            mime: None,
+            data_base64: base64::engine::general_purpose::STANDARD.encode(vec![b'a'; size]),
+        };
+        let limits = FileLimits {
+            max_file_size_bytes: 1024,
+            max_total_size_bytes: 4096,
+            per_extension: HashMap::from([("txt".to_string(), 16), ("dwg".to_string(), 8192)]),
+        };
+
+        let error = stage_inbound_files(
+            "tool_1",
+            "version_1",
+            vec![inbound("NOTES.TXT", 17)],
+            &limits,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.user_message(),
+            "NOTES.txt exceeds the .txt size limit of 16 bytes."
+        );
+
+        let staged = stage_inbound_files(
+            "tool_1",
+            "version_1",
+            vec![inbound("notes.txt", 16), inbound("plan.dwg", 1000)],
+            &limits,
+        )
+        .unwrap();
+        assert_eq!(staged.len(), 2);
+
+        // A looser extension entry does not lift the global cap.
+        let error = stage_inbound_files(
+            "tool_1",
+            "version_1",
+            vec![inbound("plan.dwg", 1025)],
+            &limits,
+        )
+        .unwrap_err();
+        assert!(error
+            .user_message()
+            .contains("plan.dwg exceeds max size of 1024 bytes"));
+    }
+
+    #[test]
+    fn versions_sharing_a_file_store_one_physical_copy() {
+        let base_dir = std::env::temp_dir().join(format!("dedup-{}", Uuid::new_v4()));
+        let drawing = b"AC1032 title block".repeat(64);
+        let stage = |version_id: &str, files: &[(&str, &[u8])]| {
+            let inbound = files
+                .iter()
+                .map(|(name, bytes)| InboundToolFile {
+                    original_name: name.to_string(),
+                    mime: None,
+                    data_base64: base64::engine::general_purpose::STANDARD.encode(bytes),
+                })
+                .collect();
+            stage_inbound_files("tool_1", version_id, inbound, &FileLimits::default()).unwrap()
+        };
+
+        let first = stage("version_1", &[("plan.dwg", &drawing)]);
+        write_staged_files_in(&base_dir, &first, None, &[]).unwrap();
+        let existing = first
+            .iter()
+            .map(|file| StoredFileRecord {
+                original_name: file.original_name.clone(),
+                stored_rel_path: file.stored_rel_path.clone(),
+                sha256: file.sha256.to_ascii_uppercase(),
+                size_bytes: file.size_bytes as i64,
+            })
+            .collect::<Vec<_>>();
+        let second = stage(
+            "version_2",
+            &[("plan-rev.dwg", &drawing), ("notes.txt", b"changed")],
+        );
+        let written = write_staged_files_in(&base_dir, &second, None, &existing).unwrap();
+
+        let first_path = resolve_stored_path_in(&base_dir, &first[0].stored_rel_path).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let inode = |path: &Path| fs::metadata(path).unwrap().ino();
+            assert_eq!(inode(&written[0]), inode(&first_path));
+            assert_ne!(inode(&written[1]), inode(&first_path));
+            assert_eq!(fs::metadata(&first_path).unwrap().nlink(), 2);
+        }
+
+        // Dropping the first version's folder keeps the content the second one shares.
+        fs::remove_dir_all(first_path.parent().unwrap().parent().unwrap()).unwrap();
+        assert_eq!(read_stored_path(&written[0]).unwrap(), drawing);
+        assert_eq!(read_stored_path(&written[1]).unwrap(), b"changed");
+
+        let _ = fs::remove_dir_all(base_dir);
+    }
+
+    #[test]
+    fn unverified_digests_never_link_to_other_content() {
+        let base_dir = std::env::temp_dir().join(format!("dedup-trust-{}", Uuid::new_v4()));
+        let staged = |version_id: &str, name: &str, bytes: &[u8]| {
+            let decoded = DecodedToolFile {
+                original_name: name.to_string(),
+                mime: None,
+                bytes: bytes.to_vec(),
+                sha256: None,
+            };
+            stage_decoded_files("tool_1", version_id, vec![decoded], &FileLimits::default())
+                .unwrap()
+        };
+        let record = |file: &StagedToolFile, sha256: &str| StoredFileRecord {
+            original_name: file.original_name.clone(),
+            stored_rel_path: file.stored_rel_path.clone(),
+            sha256: sha256.to_string(),
+            size_bytes: file.size_bytes as i64,
+        };
+
+        let first = staged("version_1", "plan.dwg", b"AC1032 plan");
+        write_staged_files_in(&base_dir, &first, None, &[]).unwrap();
+
+        // A trusted manifest declares the stored plan's digest for different bytes.
+        let mut second = staged("version_2", "detail.dwg", b"AC1032 detail");
+        second[0].sha256 = first[0].sha256.clone();
+        let written = write_staged_files_in(
+            &base_dir,
+            &second,
+            None,
+            &[record(&first[0], &first[0].sha256)],
+        )
+        .unwrap();
+        assert_eq!(fs::read(&written[0]).unwrap(), b"AC1032 detail");
+
+        // A stored row whose digest does not match its blob is not a link source either.
+        let third = staged("version_3", "plan-rev.dwg", b"AC1032 detail");
+        let written = write_staged_files_in(
+            &base_dir,
+            &third,
+            None,
+            &[record(&first[0], &third[0].sha256)],
+        )
+        .unwrap();
+        assert_eq!(fs::read(&written[0]).unwrap(), b"AC1032 detail");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let first_path = resolve_stored_path_in(&base_dir, &first[0].stored_rel_path).unwrap();
+            assert_eq!(fs::metadata(first_path).unwrap().nlink(), 1);
+        }
+
+        let _ = fs::remove_dir_all(base_dir);
+    }
+
+    #[test]
+    fn plaintext_with_the_encryption_marker_is_only_stored_encrypted() {
+        let base_dir = std::env::temp_dir().join(format!("marker-{}", Uuid::new_v4()));
+        let bytes = b"KTENC\x01 looks encrypted".to_vec();
+        let decoded = DecodedToolFile {
+            original_name: "notes.txt".to_string(),
+            mime: None,
+            bytes: bytes.clone(),
+            sha256: None,
+        };
+        let staged =
+            stage_decoded_files("tool_1", "version_1", vec![decoded], &FileLimits::default())
+                .unwrap();
+
+        let error = write_staged_files_in(&base_dir, &staged, None, &[]).unwrap_err();
+        assert!(error
+            .user_message()
+            .contains("notes.txt starts with the marker"));
+        assert!(
+            !resolve_stored_path_in(&base_dir, &staged[0].stored_rel_path)
+                .unwrap()
+                .exists()
+        );
+
+        let cipher = StorageCipher::from_key(&[7u8; 32]);
+        let written = write_staged_files_in(&base_dir, &staged, Some(&cipher), &[]).unwrap();
+        assert_eq!(
+            cipher.decrypt(&fs::read(&written[0]).unwrap()).unwrap(),
+            bytes
+        );
+
+        let _ = fs::remove_dir_all(base_dir);
+    }
+
+    #[test]
+    fn atomic_writes_only_expose_complete_files() {
+        let dir = std::env::temp_dir().join(format!("atomic-write-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("layfix.lsp");
+        fs::write(&target, b"(princ \"old\")").unwrap();
+
+        // The write dies halfway: the old file stays intact and no temp file is left behind.
+        let error = write_file_atomically(&target, |file| {
+            file.write_all(b"(princ")?;
+            assert_eq!(fs::read(&target).unwrap(), b"(princ \"old\")");
+            Err(std::io::Error::other("disk full"))
+        })
+        .unwrap_err();
+        assert!(error.user_message().contains("disk full"));
+        assert_eq!(fs::read(&target).unwrap(), b"(princ \"old\")");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        let fresh = dir.join("fresh.scr");
+        write_file_atomically(&fresh, |file| {
+            file.write_all(b"_.LINE")?;
+            assert!(!fresh.exists());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(fs::read(&fresh).unwrap(), b"_.LINE");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn streaming_hash_matches_the_in_memory_hash() {
+        let dir = std::env::temp_dir().join(format!("sha256-file-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("drawing.dwg");
+        let bytes = (0..5 * 1024 * 1024 + 123)
+            .map(|index: usize| (index * 17 % 253) as u8)
+            .collect::<Vec<_>>();
+        fs::write(&path, &bytes).unwrap();
+
+        assert_eq!(sha256_file(&path).unwrap(), sha256_hex(&bytes));
+        assert_eq!(
+            stored_file_digest(&path).unwrap(),
+            (bytes.len() as u64, sha256_hex(&bytes))
+        );
+
+        fs::write(&path, b"").unwrap();
+        assert_eq!(sha256_file(&path).unwrap(), sha256_hex(b""));
+        assert!(sha256_file(&dir.join("missing.dwg")).is_err());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn chunked_decoding_preserves_multi_megabyte_payloads() {
+        let bytes = (0..6 * 1024 * 1024 + 17)
+            .map(|index: usize| (index * 31 % 251) as u8)
+            .collect::<Vec<_>>();
+        let file = InboundToolFile {
+            original_name: "drawing.dwg".to_string(),
+            mime: None,
+            data_base64: base64::engine::general_purpose::STANDARD.encode(&bytes),
+        };
+
+        let staged = stage_inbound_files(
+            "tool_1",
+            "version_1",
+            vec![file.clone()],
+            &FileLimits::default(),
+        )
+        .unwrap();
+        assert_eq!(staged[0].size_bytes, bytes.len() as u64);
+        assert_eq!(staged[0].sha256, sha256_hex(&bytes));
+        assert!(staged[0].bytes == bytes);
+
+        // Decoding stops just past the limit instead of materializing the whole payload.
+        let decoded = decode_inbound_file(file, 1024).unwrap();
+        assert_eq!(decoded.bytes.len(), 1025);
+
+        let error = stage_inbound_files(
+            "tool_1",
+            "version_1",
+            vec![InboundToolFile {
+                original_name: "bad.scr".to_string(),
+                mime: None,
+                data_base64: "YW*j".to_string(),
+            }],
+            &FileLimits::default(),
+        )
+        .unwrap_err();
+        assert!(error
+            .user_message()
+            .starts_with("Invalid base64 file payload for bad.scr"));
+    }
+
     #[test]
     fn stored_path_scope_checks_match_expected_version() {
         assert!(assert_stored_path_matches_version(
@@ -619,6 +1839,181 @@ mod tests {
         .is_err());
     }
 
+    #[test]
+    fn free_space_check_rejects_impossible_requirements() {
+        let missing_child = std::env::temp_dir().join("free-space-probe").join("nested");
+        assert!(ensure_free_space(&missing_child, 0, "export").is_ok());
+
+        let error = ensure_free_space(&missing_child, u64::MAX, "export").unwrap_err();
+        assert!(error.user_message().contains("Not enough free disk space"));
+    }
+
+    #[test]
+    fn icon_staging_checks_extension_size_and_magic_bytes() {
+        let encode = |bytes: &[u8]| base64::engine::general_purpose::STANDARD.encode(bytes);
+        let icon = |name: &str, bytes: &[u8]| InboundToolFile {
+            original_name: name.to_string(),
+            mime: None,
+            data_base64: encode(bytes),
+        };
+
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend_from_slice(b"rest");
+        let staged = stage_tool_icon("tool_1", icon("Logo.PNG", &png)).unwrap();
+        assert_eq!(staged.stored_rel_path, "tools/tool_1/icon.png");
+        assert!(resolve_icon_path_in(Path::new("/base"), &staged.stored_rel_path).is_ok());
+
+        assert!(stage_tool_icon("tool_1", icon("logo.svg", b"<svg xmlns=\"x\"></svg>")).is_ok());
+        assert!(stage_tool_icon("tool_1", icon("logo.png", b"<svg></svg>")).is_err());
+        assert!(stage_tool_icon("tool_1", icon("logo.svg", b"<svg><script/></svg>")).is_err());
+        assert!(stage_tool_icon(
+            "tool_1",
+            icon(
+                "logo.svg",
+                b"<svg><use xlink:href='#dot'/><circle id=dot r=1 offset=\"0\"/></svg>"
+            )
+        )
+        .is_ok());
+        for payload in [
+            "<svg onload=\"alert(1)\"></svg>",
+            "<svg><g ONCLICK='alert(1)'/></svg>",
+            "<svg><foreignObject><body/></foreignObject></svg>",
+            "<svg><iframe src=\"https://example.com\"/></svg>",
+            "<svg><a href=\"javascript:alert(1)\"><text>x</text></a></svg>",
+            "<svg><a xlink:href=\"javascript&#58;alert(1)\"/></svg>",
+            "<svg><image href=\"data:image/svg+xml;base64,PHN2Zz4=\"/></svg>",
+            "<svg><a><set attributeName=\"xlink:href\" to=\"javascript:alert(1)\"/></a></svg>",
+            "<?xml version=\"1.0\"?><!DOCTYPE svg [<!ENTITY x \"y\">]><svg>&x;</svg>",
+        ] {
+            assert!(
+                stage_tool_icon("tool_1", icon("logo.svg", payload.as_bytes())).is_err(),
+                "{payload}"
+            );
+        }
+        assert!(stage_tool_icon("tool_1", icon("logo.gif", b"GIF89a")).is_err());
+        assert!(stage_tool_icon(
+            "tool_1",
+            icon("logo.png", &vec![0u8; MAX_ICON_SIZE_BYTES as usize + 1])
+        )
+        .is_err());
+        assert!(resolve_icon_path_in(Path::new("/base"), "tools/../icon.png").is_err());
+    }
+
+    #[test]
+    fn quota_check_rejects_operations_past_the_limit() {
+        assert!(ensure_within_quota(900, 10_000, None).is_ok());
+        assert!(ensure_within_quota(900, 100, Some(1_000)).is_ok());
+
+        let error = ensure_within_quota(900, 101, Some(1_000)).unwrap_err();
+        assert!(error.user_message().contains("quota exceeded"));
+    }
+
+    #[test]
+    fn sniffing_rejects_disguised_executables_and_fills_in_mime() {
+        let encode = |bytes: &[u8]| base64::engine::general_purpose::STANDARD.encode(bytes);
+        let mut executable = vec![0u8; 0x90];
+        executable[..2].copy_from_slice(b"MZ");
+        executable[0x3c] = 0x80;
+        executable[0x80..0x84].copy_from_slice(b"PE\0\0");
+        let inbound = |name: &str, bytes: &[u8], mime: Option<&str>| InboundToolFile {
+            original_name: name.to_string(),
+            mime: mime.map(str::to_string),
+            data_base64: encode(bytes),
+        };
+
+        let error = stage_inbound_files(
+            "tool_1",
+            "version_1",
+            vec![inbound("startup.scr", &executable, Some("text/plain"))],
+            &FileLimits::default(),
+        )
+        .unwrap_err();
+        assert!(error
+            .user_message()
+            .contains("startup.scr is an executable"));
+
+        let staged = stage_inbound_files(
+            "tool_1",
+            "version_1",
+            vec![
+                inbound("MZ-notes.txt", b"MZ notes on the layer fixes", None),
+                inbound("layfix.lsp", b"(defun c:layfix () (princ))", None),
+                inbound("manual.pdf", b"%PDF-1.7 body", None),
+                inbound("guide.pdf", b"%PDF-1.7 body", Some("application/x-custom")),
+            ],
+            &FileLimits::default(),
+        )
+        .unwrap();
+        let mimes = staged
+            .iter()
+            .map(|file| file.mime.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            mimes,
+            vec![
+                None,
+                None,
+                Some("application/pdf"),
+                Some("application/x-custom")
+            ]
+        );
+    }
+
+    #[test]
+    fn magic_byte_check_flags_renamed_files() {
+        assert_eq!(
+            check_magic_bytes("plan.dwg", b"AC1032\0\0", ContentCheckMode::Reject).unwrap(),
+            None
+        );
+        assert_eq!(
+            check_magic_bytes("spec.PDF", b"%PDF-1.7", ContentCheckMode::Reject).unwrap(),
+            None
+        );
+        assert_eq!(
+            check_magic_bytes("notes.txt", b"anything", ContentCheckMode::Reject).unwrap(),
+            None
+        );
+
+        let warning = check_magic_bytes("plan.dwg", b"just text", ContentCheckMode::Warn)
+            .unwrap()
+            .unwrap();
+        assert!(warning.contains("plan.dwg"));
+        assert!(check_magic_bytes("bundle.zip", b"", ContentCheckMode::Reject).is_err());
+        assert_eq!(
+            check_magic_bytes("plan.dwg", b"just text", ContentCheckMode::Off).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn json_content_check_warns_or_rejects_by_mode() {
+        let broken = b"{\"name\": ";
+        assert!(
+            check_json_content("config.json", b"{\"ok\": true}", ContentCheckMode::Reject)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            check_json_content("notes.txt", broken, ContentCheckMode::Reject)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            check_json_content("config.json", broken, ContentCheckMode::Off)
+                .unwrap()
+                .is_none()
+        );
+
+        let warning = check_json_content("config.json", broken, ContentCheckMode::Warn)
+            .unwrap()
+            .unwrap();
+        assert!(warning.contains("config.json is not valid JSON"));
+
+        let error =
+            check_json_content("config.json", broken, ContentCheckMode::Reject).unwrap_err();
+        assert!(error.user_message().contains("not valid JSON"));
+    }
+
     #[test]
     fn rejects_allowlist_bypass_file_names() {
         assert!(sanitize_filename("payload.scr.exe").is_err());
@@ -647,4 +2042,30 @@ mod tests {
             assert!(unique.insert(file.original_name.to_ascii_lowercase()));
         }
     }
+
+    #[test]
+    fn text_head_stops_at_line_and_length_caps() {
+        let head = read_text_head(Cursor::new("one\r\ntwo\nthree\n"), 2).unwrap();
+        assert_eq!(head.lines, vec!["one", "two"]);
+        assert!(head.truncated);
+
+        let head = read_text_head(Cursor::new("one\ntwo\n"), 2).unwrap();
+        assert_eq!(head.lines, vec!["one", "two"]);
+        assert!(!head.truncated);
+
+        let long_line = format!("{}\nnext\n", "é".repeat(TEXT_PREVIEW_MAX_LINE_CHARS * 3));
+        let head = read_text_head(Cursor::new(long_line), 10).unwrap();
+        assert_eq!(head.lines.len(), 2);
+        assert_eq!(head.lines[0].chars().count(), TEXT_PREVIEW_MAX_LINE_CHARS);
+        assert_eq!(head.lines[1], "next");
+        assert!(head.truncated);
+
+        let many_lines = format!("{}\n", "x".repeat(900)).repeat(TEXT_PREVIEW_MAX_LINES);
+        let head = read_text_head(Cursor::new(many_lines), TEXT_PREVIEW_MAX_LINES).unwrap();
+        let total = head.lines.iter().map(|line| line.len()).sum::<usize>();
+        assert_eq!(total, TEXT_PREVIEW_MAX_TOTAL_CHARS);
+        assert!(head.truncated);
+
+        assert!(read_text_head(Cursor::new(b"AC1032\0\0".to_vec()), 5).is_err());
+    }
 }
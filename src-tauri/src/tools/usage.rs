@@ -0,0 +1,431 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use log::warn;
+use serde::Serialize;
+
+use super::db::StoredFileRecord;
+use super::encryption::{is_encrypted, ENCRYPTION_OVERHEAD_BYTES};
+use super::error::ToolsResult;
+use super::storage::{
+    normalize_stored_rel_path, resolve_icon_path_in, tools_root_in, validate_storage_segment,
+};
+
+/// Files younger than this are never collected; they may belong to an import or version add
+/// whose database commit has not happened yet. Age counts from the last content or inode change,
+/// since a hardlink keeps the mtime of the file it shares.
+pub const GC_MIN_AGE: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsage {
+    /// Bytes on disk. Hardlinked files are counted once on platforms that expose file identity.
+    pub total_bytes: u64,
+    pub tool_count: usize,
+    pub file_count: usize,
+    /// Files whose on-disk size disagrees with the library, sorted by path.
+    pub mismatches: Vec<StorageMismatch>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageMismatch {
+    pub stored_rel_path: String,
+    /// Plaintext size recorded in `custom_library_tool_files`; `None` for unrecorded files.
+    pub recorded_bytes: Option<u64>,
+    /// Plaintext size found on disk; `None` when the file is missing.
+    pub on_disk_bytes: Option<u64>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcReport {
+    pub dry_run: bool,
+    /// Unreferenced files, removed unless this was a dry run.
+    pub removed_paths: Vec<String>,
+    pub reclaimed_bytes: u64,
+}
+
+/// Walks the whole tools root and reconciles it against `records`, which should be every
+/// stored file in the library.
+pub fn measure_storage(base_dir: &Path, records: &[StoredFileRecord]) -> ToolsResult<StorageUsage> {
+    measure(base_dir, &tools_root_in(base_dir), records)
+}
+
+/// Like `measure_storage`, limited to one tool's folder and the records of that tool.
+pub fn measure_tool_storage(
+    base_dir: &Path,
+    tool_id: &str,
+    records: &[StoredFileRecord],
+) -> ToolsResult<StorageUsage> {
+    let safe_tool_id = validate_storage_segment("tool_id", tool_id)?;
+    measure(
+        base_dir,
+        &tools_root_in(base_dir).join(safe_tool_id),
+        records,
+    )
+}
+
+fn measure(
+    base_dir: &Path,
+    scan_root: &Path,
+    records: &[StoredFileRecord],
+) -> ToolsResult<StorageUsage> {
+    let mut recorded = records
+        .iter()
+        .map(|record| {
+            let path = normalize_stored_rel_path(&record.stored_rel_path)
+                .unwrap_or_else(|_| record.stored_rel_path.clone());
+            (path, record.size_bytes.max(0) as u64)
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut on_disk = Vec::new();
+    if scan_root.is_dir() {
+        collect_files(scan_root, &mut on_disk)?;
+    }
+    on_disk.sort();
+
+    let mut usage = StorageUsage {
+        total_bytes: 0,
+        tool_count: 0,
+        file_count: on_disk.len(),
+        mismatches: Vec::new(),
+    };
+    let mut tools = HashSet::new();
+    let mut seen_links = HashSet::new();
+    for path in on_disk {
+        let metadata = fs::metadata(&path)?;
+        if seen_links.insert(file_identity(&metadata, &path)) {
+            usage.total_bytes += metadata.len();
+        }
+        let rel_path = relative_storage_path(base_dir, &path);
+        if let Some(tool_id) = rel_path.split('/').nth(1) {
+            tools.insert(tool_id.to_string());
+        }
+
+        let on_disk_bytes = plaintext_size(&path, metadata.len())?;
+        match recorded.remove(&rel_path) {
+            Some(recorded_bytes) if recorded_bytes != on_disk_bytes => {
+                usage.mismatches.push(StorageMismatch {
+                    reason: format!(
+                        "Size mismatch: recorded {recorded_bytes} bytes, found {on_disk_bytes}."
+                    ),
+                    stored_rel_path: rel_path,
+                    recorded_bytes: Some(recorded_bytes),
+                    on_disk_bytes: Some(on_disk_bytes),
+                })
+            }
+            Some(_) => {}
+            None if resolve_icon_path_in(base_dir, &rel_path).is_ok() => {}
+            None => usage.mismatches.push(StorageMismatch {
+                stored_rel_path: rel_path,
+                recorded_bytes: None,
+                on_disk_bytes: Some(on_disk_bytes),
+                reason: "File is not recorded in the library.".to_string(),
+            }),
+        }
+    }
+
+    for (stored_rel_path, recorded_bytes) in recorded {
+        usage.mismatches.push(StorageMismatch {
+            stored_rel_path,
+            recorded_bytes: Some(recorded_bytes),
+            on_disk_bytes: None,
+            reason: "Recorded file is missing on disk.".to_string(),
+        });
+    }
+    usage
+        .mismatches
+        .sort_by(|left, right| left.stored_rel_path.cmp(&right.stored_rel_path));
+    usage.tool_count = tools.len();
+    Ok(usage)
+}
+
+/// Removes files under the tools root that no path in `referenced` (stored file and icon paths)
+/// points at and that are at least `min_age` old, then any folders left empty. With `dry_run`
+/// nothing is removed and the report lists what would be.
+pub fn collect_garbage(
+    base_dir: &Path,
+    referenced: &[String],
+    in_flight: &[PathBuf],
+    min_age: Duration,
+    dry_run: bool,
+) -> ToolsResult<GcReport> {
+    let referenced = referenced
+        .iter()
+        .map(|path| normalize_stored_rel_path(path).unwrap_or_else(|_| path.trim().to_string()))
+        .collect::<HashSet<_>>();
+    let root = tools_root_in(base_dir);
+    let mut on_disk = Vec::new();
+    if root.is_dir() {
+        collect_files(&root, &mut on_disk)?;
+    }
+    on_disk.sort();
+
+    let now = SystemTime::now();
+    let mut report = GcReport {
+        dry_run,
+        removed_paths: Vec::new(),
+        reclaimed_bytes: 0,
+    };
+    for path in on_disk {
+        let rel_path = relative_storage_path(base_dir, &path);
+        if referenced.contains(&rel_path) || in_flight.iter().any(|owned| path.starts_with(owned)) {
+            continue;
+        }
+        let metadata = fs::metadata(&path)?;
+        let age = last_changed(&metadata)
+            .and_then(|changed| now.duration_since(changed).ok())
+            .unwrap_or_default();
+        if age < min_age {
+            continue;
+        }
+        if !dry_run {
+            if let Err(error) = fs::remove_file(&path) {
+                warn!("custom-tools: could not remove orphaned file {rel_path}: {error}");
+                continue;
+            }
+            remove_empty_parents(&root, &path);
+        }
+        report.reclaimed_bytes += metadata.len();
+        report.removed_paths.push(rel_path);
+    }
+    Ok(report)
+}
+
+fn remove_empty_parents(root: &Path, path: &Path) {
+    let mut current = path.parent();
+    while let Some(dir) = current.filter(|dir| dir.starts_with(root) && *dir != root) {
+        if fs::remove_dir(dir).is_err() {
+            break;
+        }
+        current = dir.parent();
+    }
+}
+
+/// `tools/<tool_id>/...` with forward slashes, as stored in the library.
+fn relative_storage_path(base_dir: &Path, path: &Path) -> String {
+    path.strip_prefix(base_dir)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> ToolsResult<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Size of the file's content, leaving out what encryption at rest adds.
+fn plaintext_size(path: &Path, stored_bytes: u64) -> ToolsResult<u64> {
+    let mut header = Vec::new();
+    fs::File::open(path)?.take(16).read_to_end(&mut header)?;
+    Ok(if is_encrypted(&header) {
+        stored_bytes.saturating_sub(ENCRYPTION_OVERHEAD_BYTES)
+    } else {
+        stored_bytes
+    })
+}
+
+/// The later of the mtime and, where available, the inode change time, which `link` bumps.
+#[cfg(unix)]
+fn last_changed(metadata: &fs::Metadata) -> Option<SystemTime> {
+    use std::os::unix::fs::MetadataExt;
+    let changed = SystemTime::UNIX_EPOCH.checked_add(Duration::new(
+        u64::try_from(metadata.ctime()).ok()?,
+        u32::try_from(metadata.ctime_nsec()).ok()?,
+    ));
+    metadata.modified().ok().max(changed)
+}
+
+#[cfg(not(unix))]
+fn last_changed(metadata: &fs::Metadata) -> Option<SystemTime> {
+    metadata.modified().ok()
+}
+
+#[cfg(unix)]
+fn file_identity(metadata: &fs::Metadata, _path: &Path) -> String {
+    use std::os::unix::fs::MetadataExt;
+    format!("{}:{}", metadata.dev(), metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn file_identity(_metadata: &fs::Metadata, path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::encryption::StorageCipher;
+    use super::*;
+
+    fn record(stored_rel_path: &str, size_bytes: i64) -> StoredFileRecord {
+        StoredFileRecord {
+            original_name: stored_rel_path.rsplit('/').next().unwrap().to_string(),
+            stored_rel_path: stored_rel_path.to_string(),
+            sha256: String::new(),
+            size_bytes,
+        }
+    }
+
+    #[test]
+    fn totals_count_staged_files_and_flag_mismatches() {
+        let base = std::env::temp_dir().join(format!("usage-{}", uuid::Uuid::new_v4()));
+        let write = |rel: &str, bytes: &[u8]| {
+            let path = base.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, bytes).unwrap();
+        };
+        let cipher = StorageCipher::from_key(&[7u8; 32]);
+        write("tools/tool-a/v1/files/plan.dwg", &[b'a'; 100]);
+        write("tools/tool-a/v1/files/layfix.lsp", &[b'b'; 20]);
+        write("tools/tool-a/icon.png", &[b'c'; 5]);
+        write(
+            "tools/tool-b/v1/files/secret.txt",
+            &cipher.encrypt(&[b'd'; 40]).unwrap(),
+        );
+        write("tools/tool-b/v1/files/stray.txt", &[b'e'; 3]);
+        let records = vec![
+            record("tools/tool-a/v1/files/plan.dwg", 100),
+            record("tools/tool-a/v1/files/layfix.lsp", 21),
+            record("tools/tool-b/v1/files/secret.txt", 40),
+            record("tools/tool-b/v2/files/gone.txt", 9),
+        ];
+
+        let usage = measure_storage(&base, &records).unwrap();
+        assert_eq!(usage.tool_count, 2);
+        assert_eq!(usage.file_count, 5);
+        assert_eq!(
+            usage.total_bytes,
+            100 + 20 + 5 + 40 + ENCRYPTION_OVERHEAD_BYTES + 3
+        );
+        let flagged = usage
+            .mismatches
+            .iter()
+            .map(|mismatch| {
+                (
+                    mismatch.stored_rel_path.as_str(),
+                    mismatch.recorded_bytes,
+                    mismatch.on_disk_bytes,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            flagged,
+            vec![
+                ("tools/tool-a/v1/files/layfix.lsp", Some(21), Some(20)),
+                ("tools/tool-b/v1/files/stray.txt", None, Some(3)),
+                ("tools/tool-b/v2/files/gone.txt", Some(9), None),
+            ]
+        );
+
+        let tool = measure_tool_storage(&base, "tool-a", &records[..2]).unwrap();
+        assert_eq!(
+            (tool.tool_count, tool.file_count, tool.total_bytes),
+            (1, 3, 125)
+        );
+        assert_eq!(tool.mismatches.len(), 1);
+
+        #[cfg(unix)]
+        {
+            fs::create_dir_all(base.join("tools/tool-a/v2/files")).unwrap();
+            fs::hard_link(
+                base.join("tools/tool-a/v1/files/plan.dwg"),
+                base.join("tools/tool-a/v2/files/plan.dwg"),
+            )
+            .unwrap();
+            let mut linked = records[..2].to_vec();
+            linked.push(record("tools/tool-a/v2/files/plan.dwg", 100));
+            let tool = measure_tool_storage(&base, "tool-a", &linked).unwrap();
+            assert_eq!((tool.file_count, tool.total_bytes), (4, 125));
+        }
+
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn garbage_collection_reports_then_removes_orphans() {
+        let base = std::env::temp_dir().join(format!("usage-gc-{}", uuid::Uuid::new_v4()));
+        let write = |rel: &str, bytes: &[u8]| {
+            let path = base.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, bytes).unwrap();
+        };
+        write("tools/tool-a/v1/files/plan.dwg", b"drawing");
+        write("tools/tool-a/icon.png", b"png");
+        write("tools/tool-a/v1/files/.plan.dwg.1234.tmp", b"draw");
+        write("tools/tool-gone/v9/files/orphan.lsp", b"(princ)");
+        let referenced = vec![
+            "tools/tool-a/v1/files/plan.dwg".to_string(),
+            "tools/tool-a/icon.png".to_string(),
+        ];
+        let orphans = vec![
+            "tools/tool-a/v1/files/.plan.dwg.1234.tmp".to_string(),
+            "tools/tool-gone/v9/files/orphan.lsp".to_string(),
+        ];
+
+        let fresh = collect_garbage(&base, &referenced, &[], GC_MIN_AGE, false).unwrap();
+        assert!(fresh.removed_paths.is_empty());
+
+        let dry_run = collect_garbage(&base, &referenced, &[], Duration::ZERO, true).unwrap();
+        assert_eq!(dry_run.removed_paths, orphans);
+        assert_eq!(dry_run.reclaimed_bytes, 11);
+        assert!(base.join("tools/tool-gone/v9/files/orphan.lsp").is_file());
+
+        let removed = collect_garbage(&base, &referenced, &[], Duration::ZERO, false).unwrap();
+        assert_eq!(removed.removed_paths, orphans);
+        assert_eq!(removed.reclaimed_bytes, 11);
+        assert!(!base.join("tools/tool-gone").exists());
+        assert!(base.join("tools/tool-a/v1/files/plan.dwg").is_file());
+        assert!(base.join("tools/tool-a/icon.png").is_file());
+
+        let again = collect_garbage(&base, &referenced, &[], Duration::ZERO, false).unwrap();
+        assert!(again.removed_paths.is_empty());
+
+        // A version add that has written (or linked) its files but not committed its rows.
+        write("tools/tool-a/v2/files/plan.dwg", b"drawing");
+        let in_flight = vec![base.join("tools/tool-a/v2/files/plan.dwg")];
+        let pending =
+            collect_garbage(&base, &referenced, &in_flight, Duration::ZERO, false).unwrap();
+        assert!(pending.removed_paths.is_empty());
+        assert!(base.join("tools/tool-a/v2/files/plan.dwg").is_file());
+
+        // A hardlink made just now shares an hour-old mtime but is still too young to collect.
+        #[cfg(unix)]
+        {
+            let source = fs::File::options()
+                .write(true)
+                .open(base.join("tools/tool-a/v1/files/plan.dwg"))
+                .unwrap();
+            source
+                .set_modified(SystemTime::now() - Duration::from_secs(60 * 60))
+                .unwrap();
+            fs::create_dir_all(base.join("tools/tool-a/v3/files")).unwrap();
+            fs::hard_link(
+                base.join("tools/tool-a/v1/files/plan.dwg"),
+                base.join("tools/tool-a/v3/files/plan.dwg"),
+            )
+            .unwrap();
+            let linked = collect_garbage(&base, &referenced, &[], GC_MIN_AGE, false).unwrap();
+            assert!(!linked
+                .removed_paths
+                .contains(&"tools/tool-a/v3/files/plan.dwg".to_string()));
+        }
+
+        let _ = fs::remove_dir_all(base);
+    }
+}
@@ -1,29 +1,47 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use base64::Engine;
-use log::debug;
+use log::{debug, warn};
 use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
 use uuid::Uuid;
 
-use super::db::{ExportVersionContext, ToolMetadataInput, VersionInsertInput};
+use super::cleanup_ledger::TrackedArtifacts;
+use super::db::{
+    normalize_file_note, now_epoch_millis, slugify, ExportVersionContext, FullExportContext,
+    ToolFileDetail, ToolMetadataInput, VersionInsertInput,
+};
 use super::error::{ToolsError, ToolsResult};
+use super::settings::ContentCheckMode;
 use super::storage::{
     assert_safe_archive_path, assert_stored_path_matches_version, build_stored_rel_path,
-    read_stored_file_bytes, sanitize_filename, sha256_hex, DEFAULT_MAX_FILE_SIZE_BYTES,
-    DEFAULT_MAX_VERSION_SIZE_BYTES,
+    ensure_free_space, read_stored_path, resolve_stored_path, sanitize_filename, sha256_hex,
+    stored_file_digest, FileLimits, ASSETS_DIR,
 };
 
+/// Manifest format written by this build; manifests from newer builds are refused on import.
+pub const MANIFEST_SCHEMA_VERSION: u32 = 1;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ToolExportManifest {
+    /// Absent in manifests written before the field existed, which are version 1.
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
     pub tool: ManifestTool,
     pub version: ManifestVersion,
     pub files: Vec<ManifestFile>,
+    /// Images shipped next to the tool, such as `assets/icon.png`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assets: Vec<ManifestAsset>,
+}
+
+fn legacy_schema_version() -> u32 {
+    1
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +59,9 @@ pub struct ManifestTool {
 pub struct ManifestVersion {
     pub version: String,
     pub changelog_md: Option<String>,
+    /// Epoch millis when the archive was written; absent in archives from older builds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exported_at: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +71,16 @@ pub struct ManifestFile {
     pub sha256: String,
     pub size_bytes: u64,
     pub relative_path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestAsset {
+    pub relative_path: String,
+    pub sha256: String,
+    pub size_bytes: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -57,6 +88,64 @@ pub struct ParsedImportArchive {
     pub metadata: ToolMetadataInput,
     pub version: VersionInsertInput,
     pub files: Vec<ImportFileBytes>,
+    pub assets: Vec<ImportAssetBytes>,
+    /// Non-fatal findings about the archive, such as manifest fields this build does not know.
+    pub warnings: Vec<String>,
+    pub exported_at: Option<i64>,
+    /// Per-file problems found with `ImportOptions::collect_file_problems`; always empty
+    /// otherwise, because the first problem fails the parse.
+    pub file_problems: Vec<String>,
+}
+
+impl ParsedImportArchive {
+    /// Fails with one validation error listing every collected file problem.
+    pub fn ensure_no_file_problems(&self) -> ToolsResult<()> {
+        match self.file_problems.as_slice() {
+            [] => Ok(()),
+            [problem] => Err(ToolsError::Validation(problem.clone())),
+            problems => Err(ToolsError::Validation(format!(
+                "{} problems found in the archive: {}",
+                problems.len(),
+                problems.join(" ")
+            ))),
+        }
+    }
+}
+
+/// What an archive would import, produced without staging files or touching the database.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolImportPreview {
+    /// Refers to the parsed archive for `tool_import_diff` until it expires; only set by
+    /// payload previews, which keep the parsed archive around.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    pub tool_name: String,
+    pub slug: String,
+    pub version: String,
+    pub files: Vec<ToolImportPreviewFile>,
+    /// Images declared under `assets/`, listed like files.
+    pub assets: Vec<ToolImportPreviewFile>,
+    pub total_size_bytes: u64,
+    pub warnings: Vec<String>,
+    /// Epoch millis when the archive was exported; `None` for archives from older builds.
+    pub exported_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolImportPreviewFile {
+    pub original_name: String,
+    pub size_bytes: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportAssetBytes {
+    /// File name inside `assets/`, e.g. `icon.png`.
+    pub name: String,
+    pub bytes: Vec<u8>,
+    pub sha256: String,
 }
 
 #[derive(Debug, Clone)]
@@ -64,9 +153,305 @@ pub struct ImportFileBytes {
     pub original_name: String,
     pub mime: Option<String>,
     pub bytes: Vec<u8>,
+    pub sha256: String,
+    pub note: Option<String>,
+}
+
+/// Coreutils-style checksum list optionally written at the archive root.
+pub const CHECKSUMS_FILE_NAME: &str = "SHA256SUMS";
+/// Present at the root of whole-tool archives, which hold one folder per version.
+pub const TOOL_INDEX_FILE_NAME: &str = "tool.json";
+const VERSIONS_DIR: &str = "versions";
+/// Written beside `tool.json` by full history exports; import reads versions from `tool.json`.
+pub const LINEAGE_FILE_NAME: &str = "lineage.json";
+/// Present at the root of bundle archives, which hold one folder per tool as `tools/<slug>/`.
+pub const BUNDLE_TOOLS_DIR: &str = "tools";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolArchiveIndex {
+    pub tool: ManifestTool,
+    /// Oldest first; imports follow this order.
+    pub versions: Vec<ToolArchiveVersion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolArchiveVersion {
+    pub version: String,
+    /// Folder holding the version's single-version layout, e.g. `versions/1.2.0`.
+    pub path: String,
+}
+
+/// Version order and file changes of a whole-tool archive, for reviewing a tool's history
+/// without unpacking every version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolLineage {
+    pub tool_slug: String,
+    /// Oldest first, in `tool.json` order.
+    pub versions: Vec<LineageVersion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineageVersion {
+    pub version: String,
+    pub path: String,
+    pub created_at: i64,
+    pub files: Vec<LineageFile>,
+    /// File names compared by sha256 with the previous version; the first version adds all.
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineageFile {
+    pub name: String,
+    pub sha256: String,
+}
+
+/// One tool folder of a bundle archive. A folder that fails to parse keeps its error so the
+/// other tools can still be imported.
+#[derive(Debug)]
+pub struct BundledToolArchive {
+    /// Folder name under `tools/`.
+    pub folder: String,
+    pub versions: ToolsResult<Vec<ParsedImportArchive>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Skip re-hashing archive files and accept the manifest's declared sha256 values.
+    pub trust_manifest: bool,
+    /// Cross-check a bundled SHA256SUMS file against the manifest hashes.
+    pub verify_checksums: bool,
+    /// How manifest keys outside the known schema are handled; `Reject` is strict parsing.
+    pub unknown_fields: ContentCheckMode,
+    /// Overrides `DEFAULT_MAX_COMPRESSION_RATIO` for the zip bomb check.
+    pub max_compression_ratio: Option<u64>,
+    /// Overrides `DEFAULT_MAX_ARCHIVE_SIZE_BYTES` for the whole extracted archive.
+    pub max_archive_size_bytes: Option<u64>,
+    /// Decrypts password-protected zip entries; ignored for unencrypted archives.
+    pub password: Option<String>,
+    /// Size ceilings for each file and for the whole version, also applied while extracting.
+    pub limits: FileLimits,
+    /// Check every file instead of stopping at the first size, hash or missing-file problem.
+    /// Problems land in `ParsedImportArchive::file_problems` and the broken files are left out.
+    pub collect_file_problems: bool,
+    /// Accept a gzip file that wraps a single file instead of a tool archive. Such files carry no
+    /// manifest, so the import also needs `bare_file`.
+    pub allow_bare_file: bool,
+    /// Tool and version details for a bare gzip file; ignored for real archives.
+    pub bare_file: Option<BareFileDetails>,
+    /// Where extraction happens; see `resolve_staging_root`.
+    pub staging_root: Option<PathBuf>,
+    /// When set, a failed import moves its extraction folder into a fresh subfolder here instead
+    /// of deleting it, and the error names that folder. See `purge_quarantine`.
+    pub quarantine_root: Option<PathBuf>,
+}
+
+/// What a manifest would otherwise say about a bare gzip file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BareFileDetails {
+    pub tool: ToolMetadataInput,
+    pub version: String,
+    #[serde(default)]
+    pub changelog_md: Option<String>,
+    pub instructions_md: String,
+}
+
+/// Uncompressed-to-compressed ratio above which an archive entry is treated as a zip bomb.
+pub const DEFAULT_MAX_COMPRESSION_RATIO: u64 = 100;
+/// Uncompressed ceiling for a whole archive. Whole-tool archives and bundles hold many versions,
+/// so this sits well above the per-version limit, which each parsed version is still held to.
+pub const DEFAULT_MAX_ARCHIVE_SIZE_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+/// Entries below this size skip the ratio check; short repetitive text compresses very well.
+const COMPRESSION_RATIO_MIN_BYTES: u64 = 1024 * 1024;
+
+/// Bounds checked while extracting, before and during writes to the temp directory.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ExtractionLimits {
+    pub(crate) max_total_bytes: u64,
+    pub(crate) max_compression_ratio: u64,
+}
+
+impl ExtractionLimits {
+    pub(crate) fn for_import(options: &ImportOptions) -> Self {
+        Self {
+            max_total_bytes: options
+                .max_archive_size_bytes
+                .unwrap_or(DEFAULT_MAX_ARCHIVE_SIZE_BYTES)
+                .max(options.limits.max_total_size_bytes),
+            max_compression_ratio: options
+                .max_compression_ratio
+                .unwrap_or(DEFAULT_MAX_COMPRESSION_RATIO),
+        }
+    }
+
+    fn check_total(&self, total_bytes: u64) -> ToolsResult<()> {
+        if total_bytes > self.max_total_bytes {
+            return Err(self.too_large());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn too_large(&self) -> ToolsError {
+        ToolsError::Zip(format!(
+            "The archive expands to more than {} bytes; import stopped.",
+            self.max_total_bytes
+        ))
+    }
+
+    pub(crate) fn exceeds_ratio(&self, size: u64, compressed_size: u64) -> bool {
+        size >= COMPRESSION_RATIO_MIN_BYTES
+            && size / compressed_size.max(1) > self.max_compression_ratio
+    }
+
+    fn check_ratio(&self, name: &str, size: u64, compressed_size: u64) -> ToolsResult<()> {
+        if self.exceeds_ratio(size, compressed_size) {
+            return Err(ToolsError::Zip(format!(
+                "{name} expands to more than {} times its compressed size; import stopped.",
+                self.max_compression_ratio
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Deflate effort for exported archives. `Store` skips compression, which suits packages of
+/// already-compressed files such as `.dwg` and `.vlx`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CompressionLevel {
+    Store,
+    Fast,
+    #[default]
+    Default,
+    Best,
+}
+
+impl CompressionLevel {
+    fn file_options(self) -> zip::write::SimpleFileOptions {
+        let options = zip::write::SimpleFileOptions::default();
+        match self {
+            Self::Store => options.compression_method(zip::CompressionMethod::Stored),
+            Self::Fast => options
+                .compression_method(zip::CompressionMethod::Deflated)
+                .compression_level(Some(1)),
+            Self::Default => options.compression_method(zip::CompressionMethod::Deflated),
+            Self::Best => options
+                .compression_method(zip::CompressionMethod::Deflated)
+                .compression_level(Some(9)),
+        }
+    }
+}
+
+impl CompressionLevel {
+    fn gzip_compression(self) -> flate2::Compression {
+        match self {
+            Self::Store => flate2::Compression::none(),
+            Self::Fast => flate2::Compression::fast(),
+            Self::Default => flate2::Compression::default(),
+            Self::Best => flate2::Compression::best(),
+        }
+    }
+}
+
+/// Container for exported archives. Both hold the same `manifest.json`, `instructions.md`,
+/// and `files/` layout, and import detects the format from the file's leading bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ArchiveFormat {
+    #[default]
+    Zip,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::TarGz => "tar.gz",
+        }
+    }
+}
+
+pub const EXPORT_PROGRESS_EVENT: &str = "tool-export-progress";
+pub const IMPORT_PROGRESS_EVENT: &str = "tool-import-progress";
+
+/// Per-file progress for exports and imports. The last report has `done` set and carries the
+/// total byte count of the files in `bytes_processed`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferProgress {
+    pub processed: usize,
+    pub total: usize,
+    pub current_file: Option<String>,
+    pub bytes_processed: u64,
+    pub done: bool,
+}
+
+/// Receives progress reports; commands forward them to the calling window as events.
+pub type ProgressCallback<'a> = &'a dyn Fn(TransferProgress);
+
+/// Where a version export landed and how large the finished archive is.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSummary {
+    pub destination: String,
+    pub bytes_written: u64,
+    pub file_count: usize,
 }
 
-pub fn build_manifest(context: &ExportVersionContext) -> ToolsResult<ToolExportManifest> {
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    pub include_checksums: bool,
+    pub compression: CompressionLevel,
+    pub format: ArchiveFormat,
+    /// Encrypts every zip entry, manifest included, with AES-256. Zip format only.
+    pub password: Option<String>,
+    /// Export time recorded in manifests; `None` stamps the current time. Pin it when the
+    /// archive must be byte-identical across runs.
+    pub exported_at: Option<i64>,
+    /// Where files are staged before they are archived; see `resolve_staging_root`.
+    pub staging_root: Option<PathBuf>,
+}
+
+impl ExportOptions {
+    fn export_time(&self) -> ToolsResult<i64> {
+        match self.exported_at {
+            Some(exported_at) => Ok(exported_at),
+            None => now_epoch_millis(),
+        }
+    }
+
+    /// Rejects blank passwords and passwords on formats that cannot carry them.
+    fn validate(&self) -> ToolsResult<()> {
+        let Some(password) = &self.password else {
+            return Ok(());
+        };
+        if password.trim().is_empty() {
+            return Err(ToolsError::Validation(
+                "Archive password cannot be empty.".to_string(),
+            ));
+        }
+        if self.format != ArchiveFormat::Zip {
+            return Err(ToolsError::Validation(
+                "Password-protected exports must use the zip format.".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub fn build_manifest(
+    context: &ExportVersionContext,
+    exported_at: i64,
+) -> ToolsResult<ToolExportManifest> {
     let mut seen_names = HashSet::new();
     let mut files = Vec::with_capacity(context.files.len());
 
@@ -84,10 +469,15 @@ pub fn build_manifest(context: &ExportVersionContext) -> ToolsResult<ToolExportM
             sha256: file.sha256.clone(),
             size_bytes: file.size_bytes.max(0) as u64,
             relative_path: format!("files/{}", original_name),
+            note: file.note.clone(),
         });
     }
 
+    files.sort_by(|left, right| left.original_name.cmp(&right.original_name));
+
     Ok(ToolExportManifest {
+        schema_version: MANIFEST_SCHEMA_VERSION,
+        assets: Vec::new(),
         tool: ManifestTool {
             name: context.tool.name.clone(),
             slug: context.tool.slug.clone(),
@@ -98,18 +488,114 @@ pub fn build_manifest(context: &ExportVersionContext) -> ToolsResult<ToolExportM
         version: ManifestVersion {
             version: context.version.version.clone(),
             changelog_md: context.version.changelog_md.clone(),
+            exported_at: Some(exported_at),
         },
         files,
     })
 }
 
+pub fn build_checksums_file(manifest: &ToolExportManifest) -> String {
+    manifest
+        .files
+        .iter()
+        .map(|file| {
+            format!(
+                "{}  {}\n",
+                file.sha256.trim().to_ascii_lowercase(),
+                file.relative_path
+            )
+        })
+        .collect()
+}
+
+pub fn parse_checksums_file(raw: &str) -> ToolsResult<HashMap<String, String>> {
+    let mut checksums = HashMap::new();
+    for line in raw
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+    {
+        // `<hash>  <path>` for text mode, `<hash> *<path>` for binary mode.
+        let parsed = line
+            .split_once(' ')
+            .map(|(hash, rest)| (hash, rest.strip_prefix([' ', '*']).unwrap_or(rest)));
+        let Some((hash, path)) = parsed.filter(|(hash, path)| {
+            hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()) && !path.is_empty()
+        }) else {
+            return Err(ToolsError::Zip(format!(
+                "Malformed {CHECKSUMS_FILE_NAME} line: {line}"
+            )));
+        };
+        checksums.insert(path.to_string(), hash.to_ascii_lowercase());
+    }
+    Ok(checksums)
+}
+
+fn cross_check_checksums(
+    checksums: &HashMap<String, String>,
+    files: &[ManifestFile],
+) -> ToolsResult<()> {
+    for file in files {
+        match checksums.get(&file.relative_path) {
+            Some(hash) if hash.eq_ignore_ascii_case(file.sha256.trim()) => {}
+            Some(_) => {
+                return Err(ToolsError::Validation(format!(
+                    "{CHECKSUMS_FILE_NAME} disagrees with manifest.json for {}.",
+                    file.relative_path
+                )))
+            }
+            None => {
+                return Err(ToolsError::Validation(format!(
+                    "{CHECKSUMS_FILE_NAME} is missing {}.",
+                    file.relative_path
+                )))
+            }
+        }
+    }
+
+    if checksums.len() != files.len() {
+        return Err(ToolsError::Validation(format!(
+            "{CHECKSUMS_FILE_NAME} lists files that are not in manifest.json."
+        )));
+    }
+    Ok(())
+}
+
 pub fn export_tool_version_zip(
     app: &AppHandle,
     context: &ExportVersionContext,
     destination_path: &str,
-) -> ToolsResult<()> {
+    options: &ExportOptions,
+    progress: Option<ProgressCallback>,
+) -> ToolsResult<ExportSummary> {
+    export_version_archive(
+        context,
+        destination_path,
+        options,
+        &|context, file| read_verified_export_file(app, context, file),
+        progress,
+    )
+}
+
+fn export_version_archive(
+    context: &ExportVersionContext,
+    destination_path: &str,
+    options: &ExportOptions,
+    read_file: ExportFileReader,
+    progress: Option<ProgressCallback>,
+) -> ToolsResult<ExportSummary> {
+    options.validate()?;
+    let required_bytes = estimate_export_size_bytes(context);
+    let trimmed_destination = destination_path.trim();
+    if !trimmed_destination.is_empty() {
+        ensure_free_space(Path::new(trimmed_destination), required_bytes, "export")?;
+    }
+    let staging_root = resolve_staging_root(options.staging_root.as_deref());
+    ensure_free_space(&staging_root, required_bytes, "export staging")?;
+
     let destination = normalize_destination(destination_path)?;
-    let staging = create_temp_dir("tool-export")?;
+    let staging = create_temp_dir_in(&staging_root, "tool-export")?;
+    let cleanup = TrackedArtifacts::track("version export", vec![staging.clone()]);
     debug!(
         "custom-tools: zip export start tool_id={} version_id={} destination={}",
         context.version.tool_id,
@@ -117,235 +603,1155 @@ pub fn export_tool_version_zip(
         destination.to_string_lossy()
     );
 
-    let result = (|| -> ToolsResult<()> {
-        let manifest = build_manifest(context)?;
-        let manifest_json = serde_json::to_string_pretty(&manifest).map_err(|error| {
-            ToolsError::Zip(format!("Failed to serialize manifest.json: {error}"))
-        })?;
-
-        let files_dir = staging.join("files");
-        fs::create_dir_all(&files_dir)?;
-
-        fs::write(staging.join("manifest.json"), manifest_json)?;
-        fs::write(
-            staging.join("instructions.md"),
-            context.version.instructions_md.as_bytes(),
+    let result = (|| -> ToolsResult<ExportSummary> {
+        let bytes_processed = stage_version_export(
+            context,
+            &staging,
+            options.include_checksums,
+            options.export_time()?,
+            read_file,
+            progress,
         )?;
 
-        for file in &context.files {
-            let sanitized = sanitize_filename(&file.original_name)?;
-            assert_stored_path_matches_version(
-                &file.stored_rel_path,
-                &context.version.tool_id,
-                &context.version.id,
-            )?;
-            let expected_rel_path =
-                build_stored_rel_path(&context.version.tool_id, &context.version.id, &sanitized)?;
-            if file.stored_rel_path != expected_rel_path {
-                return Err(ToolsError::Zip(format!(
-                    "Stored path mismatch for {}.",
-                    sanitized
-                )));
-            }
-            let bytes = read_stored_file_bytes(app, &file.stored_rel_path)?;
-            let expected_size = file.size_bytes.max(0) as usize;
-            if bytes.len() != expected_size {
-                return Err(ToolsError::Zip(format!(
-                    "Stored file size mismatch for {}.",
-                    sanitized
-                )));
-            }
-            if !sha256_hex(&bytes).eq_ignore_ascii_case(file.sha256.trim()) {
-                return Err(ToolsError::Zip(format!(
-                    "Stored file hash mismatch for {}.",
-                    sanitized
-                )));
-            }
-            fs::write(files_dir.join(sanitized), bytes)?;
+        write_archive(&staging, &destination, options, context.version.created_at)?;
+        if let Some(report) = progress {
+            report(TransferProgress {
+                processed: context.files.len(),
+                total: context.files.len(),
+                current_file: None,
+                bytes_processed,
+                done: true,
+            });
         }
-
-        compress_directory_to_zip(&staging, &destination)
+        Ok(ExportSummary {
+            destination: destination.to_string_lossy().to_string(),
+            bytes_written: fs::metadata(&destination)?.len(),
+            file_count: context.files.len(),
+        })
     })();
 
-    let _ = fs::remove_dir_all(staging);
-    if result.is_ok() {
+    cleanup.settle(&result);
+    if let Ok(summary) = &result {
         debug!(
-            "custom-tools: zip export success tool_id={} version_id={}",
-            context.version.tool_id, context.version.id
+            "custom-tools: zip export success tool_id={} version_id={} bytes_written={}",
+            context.version.tool_id, context.version.id, summary.bytes_written
         );
     }
     result
 }
 
-pub fn import_tool_zip(zip_path: &str) -> ToolsResult<ParsedImportArchive> {
-    let zip_path = PathBuf::from(zip_path.trim());
-    if !zip_path.exists() || !zip_path.is_file() {
-        return Err(ToolsError::Zip("Import zip path is invalid.".to_string()));
+/// Writes every version under `versions/<version>/` in the single-version layout, plus a
+/// top-level `tool.json` listing the version folders oldest first.
+pub fn export_tool_zip(
+    app: &AppHandle,
+    context: &FullExportContext,
+    destination_path: &str,
+    options: &ExportOptions,
+    include_lineage: bool,
+) -> ToolsResult<()> {
+    options.validate()?;
+    let required_bytes = context
+        .versions
+        .iter()
+        .map(estimate_export_size_bytes)
+        .sum::<u64>();
+    let trimmed_destination = destination_path.trim();
+    if !trimmed_destination.is_empty() {
+        ensure_free_space(Path::new(trimmed_destination), required_bytes, "export")?;
     }
+    let staging_root = resolve_staging_root(options.staging_root.as_deref());
+    ensure_free_space(&staging_root, required_bytes, "export staging")?;
+
+    let destination = normalize_destination(destination_path)?;
+    let staging = create_temp_dir_in(&staging_root, "tool-export-full")?;
+    let cleanup = TrackedArtifacts::track("full tool export", vec![staging.clone()]);
     debug!(
-        "custom-tools: zip import start path={}",
-        zip_path.to_string_lossy()
+        "custom-tools: full tool export start slug={} versions={} destination={}",
+        context.tool.slug,
+        context.versions.len(),
+        destination.to_string_lossy()
     );
 
-    let extraction_dir = create_temp_dir("tool-import")?;
-    let result = (|| -> ToolsResult<ParsedImportArchive> {
-        extract_zip_safely(&zip_path, &extraction_dir)?;
+    let result = (|| -> ToolsResult<()> {
+        stage_full_export(
+            context,
+            &staging,
+            options.include_checksums,
+            options.export_time()?,
+            include_lineage,
+            &|version, file| read_verified_export_file(app, version, file),
+        )?;
+        let newest = context
+            .versions
+            .iter()
+            .map(|version| version.version.created_at)
+            .max()
+            .unwrap_or_default();
+        write_archive(&staging, &destination, options, newest)
+    })();
 
-        let manifest_path = extraction_dir.join("manifest.json");
-        let instructions_path = extraction_dir.join("instructions.md");
+    cleanup.settle(&result);
+    if result.is_ok() {
+        debug!(
+            "custom-tools: full tool export success slug={}",
+            context.tool.slug
+        );
+    }
+    result
+}
 
-        let manifest_raw = fs::read_to_string(&manifest_path)
-            .map_err(|error| ToolsError::Zip(format!("Failed to read manifest.json: {error}")))?;
-        let manifest: ToolExportManifest = serde_json::from_str(&manifest_raw)
-            .map_err(|error| ToolsError::Zip(format!("Failed to parse manifest.json: {error}")))?;
+/// Upper bound on files read and hashed at once during export; each worker holds one file in
+/// memory.
+const EXPORT_READ_WORKERS: usize = 4;
 
-        let instructions_md = fs::read_to_string(&instructions_path)
-            .map_err(|error| ToolsError::Zip(format!("Failed to read instructions.md: {error}")))?;
-        if instructions_md.trim().is_empty() {
-            return Err(ToolsError::Validation(
-                "instructions.md cannot be empty.".to_string(),
-            ));
-        }
+/// Returns a file's sanitized name and verified bytes for export.
+///
+/// Called from several worker threads at once, see [`stage_version_files`].
+type ExportFileReader<'a> =
+    &'a (dyn Fn(&ExportVersionContext, &ToolFileDetail) -> ToolsResult<(String, Vec<u8>)> + Sync);
 
-        let metadata = ToolMetadataInput {
-            name: validate_required("tool.name", &manifest.tool.name, 120)?,
-            slug: Some(validate_required("tool.slug", &manifest.tool.slug, 120)?),
-            description: validate_required("tool.description", &manifest.tool.description, 8_000)?,
-            category: validate_required("tool.category", &manifest.tool.category, 120)?,
-            tags: manifest.tool.tags,
-        };
+fn stage_full_export(
+    context: &FullExportContext,
+    staging: &Path,
+    include_checksums: bool,
+    exported_at: i64,
+    include_lineage: bool,
+    read_file: ExportFileReader,
+) -> ToolsResult<()> {
+    let mut used_directories = HashSet::new();
+    let mut versions = Vec::with_capacity(context.versions.len());
+    for version in &context.versions {
+        let directory = version_directory_name(&version.version.version, &mut used_directories);
+        let path = format!("{VERSIONS_DIR}/{directory}");
+        stage_version_export(
+            version,
+            &staging.join(&path),
+            include_checksums,
+            exported_at,
+            read_file,
+            None,
+        )?;
+        versions.push(ToolArchiveVersion {
+            version: version.version.version.clone(),
+            path,
+        });
+    }
 
-        let version = VersionInsertInput {
-            version: validate_required("version.version", &manifest.version.version, 80)?,
-            changelog_md: normalize_optional_text(manifest.version.changelog_md, 512 * 1024)?,
-            instructions_md,
-        };
+    let tool = &context.tool;
+    let index = ToolArchiveIndex {
+        tool: ManifestTool {
+            name: tool.name.clone(),
+            slug: tool.slug.clone(),
+            description: tool.description.clone(),
+            category: tool.category.clone(),
+            tags: tool.tags.clone(),
+        },
+        versions,
+    };
+    let index_json = serde_json::to_string_pretty(&index).map_err(|error| {
+        ToolsError::Zip(format!(
+            "Failed to serialize {TOOL_INDEX_FILE_NAME}: {error}"
+        ))
+    })?;
+    fs::write(staging.join(TOOL_INDEX_FILE_NAME), index_json)?;
 
-        let all_entries = collect_relative_files(&extraction_dir)?;
-        let mut expected_paths = HashSet::new();
-        expected_paths.insert("manifest.json".to_string());
-        expected_paths.insert("instructions.md".to_string());
+    if include_lineage {
+        let lineage = build_lineage(context, &index.versions)?;
+        let lineage_json = serde_json::to_string_pretty(&lineage).map_err(|error| {
+            ToolsError::Zip(format!("Failed to serialize {LINEAGE_FILE_NAME}: {error}"))
+        })?;
+        fs::write(staging.join(LINEAGE_FILE_NAME), lineage_json)?;
+    }
+    Ok(())
+}
 
-        let mut parsed_files = Vec::with_capacity(manifest.files.len());
-        let mut seen_names = HashSet::new();
-        let mut total_size = 0u64;
+/// Pairs each version with its archive folder and diffs its files against the version before.
+fn build_lineage(
+    context: &FullExportContext,
+    folders: &[ToolArchiveVersion],
+) -> ToolsResult<ToolLineage> {
+    let mut versions = Vec::with_capacity(context.versions.len());
+    let mut previous = HashMap::<String, String>::new();
+    for (version, folder) in context.versions.iter().zip(folders) {
+        let mut files = Vec::with_capacity(version.files.len());
+        for file in &version.files {
+            files.push(LineageFile {
+                name: sanitize_filename(&file.original_name)?,
+                sha256: file.sha256.clone(),
+            });
+        }
 
-        for file in manifest.files {
-            assert_safe_archive_path(&file.relative_path)?;
-            if !file.relative_path.starts_with("files/") {
-                return Err(ToolsError::Zip(format!(
-                    "Manifest file path must start with files/: {}",
-                    file.relative_path
-                )));
+        let current = files
+            .iter()
+            .map(|file| (file.name.to_ascii_lowercase(), file.sha256.clone()))
+            .collect::<HashMap<_, _>>();
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+        for file in &files {
+            match previous.get(&file.name.to_ascii_lowercase()) {
+                None => added.push(file.name.clone()),
+                Some(sha256) if *sha256 != file.sha256 => changed.push(file.name.clone()),
+                Some(_) => {}
             }
+        }
+        let mut removed = versions
+            .last()
+            .map(|last: &LineageVersion| {
+                last.files
+                    .iter()
+                    .filter(|file| !current.contains_key(&file.name.to_ascii_lowercase()))
+                    .map(|file| file.name.clone())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        added.sort();
+        changed.sort();
+        removed.sort();
 
-            let sanitized = sanitize_filename(&file.original_name)?;
-            if !seen_names.insert(sanitized.to_ascii_lowercase()) {
-                return Err(ToolsError::Validation(format!(
-                    "Duplicate file in manifest: {sanitized}"
-                )));
-            }
+        versions.push(LineageVersion {
+            version: version.version.version.clone(),
+            path: folder.path.clone(),
+            created_at: version.version.created_at,
+            files,
+            added,
+            removed,
+            changed,
+        });
+        previous = current;
+    }
+    Ok(ToolLineage {
+        tool_slug: context.tool.slug.clone(),
+        versions,
+    })
+}
 
-            let expected_rel = format!("files/{sanitized}");
-            if file.relative_path != expected_rel {
-                return Err(ToolsError::Validation(format!(
-                    "Manifest relative_path mismatch for {}. Expected {}.",
-                    sanitized, expected_rel
-                )));
+/// Version labels are free text, so folder names keep only portable characters and get a
+/// numeric suffix when two labels clean up to the same name.
+fn version_directory_name(version: &str, used: &mut HashSet<String>) -> String {
+    let mut cleaned = version
+        .trim()
+        .chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() || matches!(character, '-' | '_' | '.') {
+                character
+            } else {
+                '_'
             }
-            expected_paths.insert(expected_rel.clone());
+        })
+        .collect::<String>();
+    while cleaned.contains("..") {
+        cleaned = cleaned.replace("..", ".");
+    }
+    let cleaned = cleaned.trim_matches('.').to_string();
+    let base = if cleaned.is_empty() {
+        "version".to_string()
+    } else {
+        cleaned
+    };
 
-            let absolute = extraction_dir.join(&expected_rel);
-            if !absolute.exists() {
-                return Err(ToolsError::Zip(format!(
-                    "Missing archive file: {}",
-                    expected_rel
-                )));
-            }
+    let mut candidate = base.clone();
+    let mut suffix = 2;
+    while !used.insert(candidate.to_ascii_lowercase()) {
+        candidate = format!("{base}-{suffix}");
+        suffix += 1;
+    }
+    candidate
+}
 
-            let bytes = fs::read(&absolute).map_err(|error| {
-                ToolsError::Zip(format!("Failed to read {}: {error}", expected_rel))
-            })?;
-            let size_bytes = bytes.len() as u64;
-            if size_bytes != file.size_bytes {
-                return Err(ToolsError::Validation(format!(
-                    "File size mismatch for {}. Manifest: {}, archive: {}.",
-                    sanitized, file.size_bytes, size_bytes
-                )));
-            }
+/// Writes one version's `manifest.json`, `instructions.md`, optional checksums and files into
+/// `directory`, returning the bytes of file content written.
+fn stage_version_export(
+    context: &ExportVersionContext,
+    directory: &Path,
+    include_checksums: bool,
+    exported_at: i64,
+    read_file: ExportFileReader,
+    progress: Option<ProgressCallback>,
+) -> ToolsResult<u64> {
+    let manifest = build_manifest(context, exported_at)?;
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|error| ToolsError::Zip(format!("Failed to serialize manifest.json: {error}")))?;
 
-            if size_bytes == 0 || size_bytes > DEFAULT_MAX_FILE_SIZE_BYTES {
-                return Err(ToolsError::Validation(format!(
-                    "{} exceeds allowed size limits.",
-                    sanitized
-                )));
-            }
+    let files_dir = directory.join("files");
+    fs::create_dir_all(&files_dir)?;
 
-            total_size += size_bytes;
-            if total_size > DEFAULT_MAX_VERSION_SIZE_BYTES {
-                return Err(ToolsError::Validation(format!(
-                    "Import file total exceeds {} bytes.",
-                    DEFAULT_MAX_VERSION_SIZE_BYTES
-                )));
-            }
+    fs::write(directory.join("manifest.json"), manifest_json)?;
+    if include_checksums {
+        fs::write(
+            directory.join(CHECKSUMS_FILE_NAME),
+            build_checksums_file(&manifest),
+        )?;
+    }
+    fs::write(
+        directory.join("instructions.md"),
+        context.version.instructions_md.as_bytes(),
+    )?;
 
-            let hash = sha256_hex(&bytes);
-            if !hash.eq_ignore_ascii_case(file.sha256.trim()) {
-                return Err(ToolsError::Validation(format!(
-                    "SHA256 mismatch for {}.",
-                    sanitized
-                )));
-            }
+    stage_version_files(context, &files_dir, read_file, progress)
+}
+
+/// Reads, verifies and writes a version's files on up to [`EXPORT_READ_WORKERS`] threads.
+/// Progress is reported from the calling thread as files finish, so `processed` counts up
+/// while `current_file` follows completion order. Any failure stops workers from picking up
+/// further files and fails the whole export with the error of the earliest failing file.
+fn stage_version_files(
+    context: &ExportVersionContext,
+    files_dir: &Path,
+    read_file: ExportFileReader,
+    progress: Option<ProgressCallback>,
+) -> ToolsResult<u64> {
+    let total = context.files.len();
+    let workers = std::thread::available_parallelism()
+        .map(usize::from)
+        .unwrap_or(1)
+        .min(EXPORT_READ_WORKERS)
+        .min(total);
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel::<(usize, ToolsResult<(String, u64)>)>();
 
-            parsed_files.push(ImportFileBytes {
-                original_name: sanitized,
-                mime: None,
-                bytes,
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            let sender = sender.clone();
+            let (next, failed) = (&next, &failed);
+            scope.spawn(move || {
+                while !failed.load(Ordering::Relaxed) {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(file) = context.files.get(index) else {
+                        break;
+                    };
+                    let result = read_file(context, file).and_then(|(sanitized, bytes)| {
+                        fs::write(files_dir.join(&sanitized), &bytes)?;
+                        Ok((sanitized, bytes.len() as u64))
+                    });
+                    if result.is_err() {
+                        failed.store(true, Ordering::Relaxed);
+                    }
+                    if sender.send((index, result)).is_err() {
+                        break;
+                    }
+                }
             });
         }
+        drop(sender);
 
-        for entry in all_entries {
-            if !expected_paths.contains(&entry) {
-                return Err(ToolsError::Zip(format!(
-                    "Unexpected file in archive: {}",
-                    entry
-                )));
+        let mut processed = 0;
+        let mut bytes_processed = 0u64;
+        let mut first_error: Option<(usize, ToolsError)> = None;
+        for (index, result) in receiver {
+            match result {
+                Ok((sanitized, size)) => {
+                    processed += 1;
+                    bytes_processed += size;
+                    if let Some(report) = progress {
+                        report(TransferProgress {
+                            processed,
+                            total,
+                            current_file: Some(sanitized),
+                            bytes_processed,
+                            done: false,
+                        });
+                    }
+                }
+                Err(error) => {
+                    if first_error
+                        .as_ref()
+                        .map_or(true, |(first, _)| index < *first)
+                    {
+                        first_error = Some((index, error));
+                    }
+                }
             }
         }
+        match first_error {
+            Some((_, error)) => Err(error),
+            None => Ok(bytes_processed),
+        }
+    })
+}
 
-        let parsed = ParsedImportArchive {
-            metadata,
-            version,
-            files: parsed_files,
-        };
-        debug!(
-            "custom-tools: zip import parsed tool_slug={} version={} file_count={}",
-            parsed.metadata.slug.clone().unwrap_or_default(),
-            parsed.version.version,
-            parsed.files.len()
-        );
-        Ok(parsed)
+/// Reads a stored file for export, checking its path, size and hash against the version record.
+pub(crate) fn read_verified_export_file(
+    app: &AppHandle,
+    context: &ExportVersionContext,
+    file: &ToolFileDetail,
+) -> ToolsResult<(String, Vec<u8>)> {
+    let sanitized = sanitize_filename(&file.original_name)?;
+    assert_stored_path_matches_version(
+        &file.stored_rel_path,
+        &context.version.tool_id,
+        &context.version.id,
+    )?;
+    let expected_rel_path =
+        build_stored_rel_path(&context.version.tool_id, &context.version.id, &sanitized)?;
+    if file.stored_rel_path != expected_rel_path {
+        return Err(ToolsError::Zip(format!(
+            "Stored path mismatch for {}.",
+            sanitized
+        )));
+    }
+    // Verify before loading, so a damaged file is rejected without reading it into memory.
+    let path = resolve_stored_path(app, &file.stored_rel_path)?;
+    let (size, sha256) = stored_file_digest(&path)?;
+    if size != file.size_bytes.max(0) as u64 {
+        return Err(ToolsError::Zip(format!(
+            "Stored file size mismatch for {}.",
+            sanitized
+        )));
+    }
+    if !sha256.eq_ignore_ascii_case(file.sha256.trim()) {
+        return Err(ToolsError::Zip(format!(
+            "Stored file hash mismatch for {}.",
+            sanitized
+        )));
+    }
+    Ok((sanitized, read_stored_path(&path)?))
+}
+
+/// Unwraps the result of an import for callers that only handle single-version archives.
+pub fn single_version(mut versions: Vec<ParsedImportArchive>) -> ToolsResult<ParsedImportArchive> {
+    if versions.len() != 1 {
+        return Err(ToolsError::Validation(format!(
+            "This archive holds {} versions of a tool; expected a single-version archive.",
+            versions.len()
+        )));
+    }
+    Ok(versions.remove(0))
+}
+
+/// Parses either layout: a single-version archive yields one entry, a whole-tool archive
+/// (`tool.json` plus `versions/`) yields every version in the order `tool.json` lists them.
+/// Bundles of several tools are refused; they go through `import_tool_bundle_zip`. A gzip file
+/// that wraps a single file, such as `tool.lsp.gz`, is imported as one version when
+/// `ImportOptions::allow_bare_file` is set.
+pub fn import_tool_zip(
+    zip_path: &str,
+    options: &ImportOptions,
+    progress: Option<ProgressCallback>,
+) -> ToolsResult<Vec<ParsedImportArchive>> {
+    let archive_path = Path::new(zip_path.trim());
+    if is_bare_gzip(archive_path) {
+        return Ok(vec![parse_bare_gzip(archive_path, options)?]);
+    }
+    with_extracted_archive(zip_path, options, |archive_root| {
+        if is_bundle_root(archive_root) {
+            return Err(ToolsError::Validation(
+                "This archive bundles several tools; import it as a bundle.".to_string(),
+            ));
+        }
+        parse_tool_root(archive_root, options, progress)
+    })
+}
+
+/// Parses a bundle archive (`tools/<slug>/...`), where each tool folder holds either the
+/// single-version or the whole-tool layout. Tools are returned in folder-name order.
+pub fn import_tool_bundle_zip(
+    zip_path: &str,
+    options: &ImportOptions,
+) -> ToolsResult<Vec<BundledToolArchive>> {
+    with_extracted_archive(zip_path, options, |archive_root| {
+        if !is_bundle_root(archive_root) {
+            return Err(ToolsError::Validation(format!(
+                "This archive is not a tool bundle; it has no {BUNDLE_TOOLS_DIR}/ folder."
+            )));
+        }
+        parse_bundle_root(archive_root, options)
+    })
+}
+
+/// Extracts the archive into a temp directory, hands its root to `parse`, and removes the
+/// directory again whatever `parse` returns.
+fn with_extracted_archive<T>(
+    zip_path: &str,
+    options: &ImportOptions,
+    parse: impl FnOnce(&Path) -> ToolsResult<T>,
+) -> ToolsResult<T> {
+    let zip_path = PathBuf::from(zip_path.trim());
+    if !zip_path.exists() || !zip_path.is_file() {
+        return Err(ToolsError::Zip("Import zip path is invalid.".to_string()));
+    }
+    debug!(
+        "custom-tools: zip import start path={}",
+        zip_path.to_string_lossy()
+    );
+
+    let staging_root = resolve_staging_root(options.staging_root.as_deref());
+    let format = sniff_archive_format(&zip_path)?;
+
+    let limits = ExtractionLimits::for_import(options);
+    let extraction_dir = create_temp_dir_in(&staging_root, "tool-import")?;
+    let cleanup = TrackedArtifacts::track("archive import", vec![extraction_dir.clone()]);
+    let result = (|| -> ToolsResult<T> {
+        match format {
+            ArchiveFormat::Zip => extract_zip_safely(
+                &zip_path,
+                &extraction_dir,
+                &limits,
+                options
+                    .password
+                    .as_deref()
+                    .filter(|password| !password.trim().is_empty()),
+            ),
+            ArchiveFormat::TarGz => extract_tar_gz_safely(&zip_path, &extraction_dir, &limits),
+        }
+        .map_err(describe_extraction_error)?;
+        parse(&resolve_archive_root(&extraction_dir)?)
     })();
 
-    let _ = fs::remove_dir_all(extraction_dir);
+    if let (Err(_), Some(quarantine_root)) = (&result, options.quarantine_root.as_deref()) {
+        match quarantine_extraction(&extraction_dir, quarantine_root) {
+            Ok(location) => {
+                cleanup.keep();
+                return result.map_err(|error| {
+                    error.map_message(|message| {
+                        format!(
+                            "{message} The extracted files were kept in {}.",
+                            location.display()
+                        )
+                    })
+                });
+            }
+            Err(error) => warn!(
+                "custom-tools: failed import not quarantined: {}",
+                error.user_message()
+            ),
+        }
+    }
+    cleanup.settle(&result);
     result
 }
 
+fn quarantine_extraction(extraction_dir: &Path, quarantine_root: &Path) -> ToolsResult<PathBuf> {
+    fs::create_dir_all(quarantine_root)?;
+    let location = quarantine_root.join(Uuid::new_v4().to_string());
+    fs::rename(extraction_dir, &location)?;
+    Ok(location)
+}
+
+/// Removes quarantined import folders older than `max_age` and returns how many were removed.
+pub fn purge_quarantine(quarantine_root: &Path, max_age: Duration) -> ToolsResult<usize> {
+    if !quarantine_root.is_dir() {
+        return Ok(0);
+    }
+    let now = SystemTime::now();
+    let mut removed = 0;
+    for entry in fs::read_dir(quarantine_root)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let age = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .unwrap_or_default();
+        if !metadata.is_dir() || age < max_age {
+            continue;
+        }
+        fs::remove_dir_all(entry.path())?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// True for a gzip file whose content is not a tar stream. Unreadable files are left to the
+/// archive extractor, which reports them.
+fn is_bare_gzip(path: &Path) -> bool {
+    use std::io::Read;
+
+    if !path.is_file() || !matches!(sniff_archive_format(path), Ok(ArchiveFormat::TarGz)) {
+        return false;
+    }
+    let Ok(file) = fs::File::open(path) else {
+        return false;
+    };
+    // A tar stream starts with a 512-byte header carrying the ustar magic at offset 257.
+    let mut header = Vec::with_capacity(512);
+    if flate2::read::GzDecoder::new(file)
+        .take(512)
+        .read_to_end(&mut header)
+        .is_err()
+    {
+        return false;
+    }
+    header.get(257..262) != Some(b"ustar".as_slice())
+}
+
+/// Builds a single-file version from a bare gzip file and the caller's details. The file name
+/// comes from the gzip header, or from the archive name without `.gz`.
+fn parse_bare_gzip(
+    archive_path: &Path,
+    options: &ImportOptions,
+) -> ToolsResult<ParsedImportArchive> {
+    use std::io::Read;
+
+    if !options.allow_bare_file {
+        return Err(ToolsError::Validation(
+            "This gzip file holds a single file rather than a tool archive; import it as a bare file."
+                .to_string(),
+        ));
+    }
+    let details = options.bare_file.as_ref().ok_or_else(|| {
+        ToolsError::Validation(
+            "A bare gzip file has no manifest; tool name, version and instructions are required."
+                .to_string(),
+        )
+    })?;
+
+    let instructions_md = details.instructions_md.clone();
+    if instructions_md.trim().is_empty() {
+        return Err(ToolsError::Validation(
+            "instructions.md cannot be empty.".to_string(),
+        ));
+    }
+    let name = validate_required("tool.name", &details.tool.name, 120)?;
+    let slug = match details.tool.slug.as_deref().map(str::trim) {
+        Some(slug) if !slug.is_empty() => validate_required("tool.slug", slug, 120)?,
+        _ => slugify(&name),
+    };
+    let metadata = ToolMetadataInput {
+        name,
+        slug: Some(slug),
+        ..details.tool.clone()
+    };
+    let version = VersionInsertInput {
+        version: validate_required("version.version", &details.version, 80)?,
+        changelog_md: normalize_optional_text(details.changelog_md.clone(), 512 * 1024)?,
+        instructions_md,
+    };
+
+    let limit = options
+        .limits
+        .max_file_size_bytes
+        .min(options.limits.max_total_size_bytes);
+    let mut decoder = flate2::read::GzDecoder::new(fs::File::open(archive_path)?);
+    let mut bytes = Vec::new();
+    (&mut decoder)
+        .take(limit + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|error| {
+            ToolsError::Zip(format!(
+                "The gzip file could not be decompressed; it may be corrupt or incomplete. {error}"
+            ))
+        })?;
+    let header_name = decoder
+        .header()
+        .and_then(|header| header.filename())
+        .map(|name| String::from_utf8_lossy(name).to_string());
+    let fallback_name = || {
+        let name = archive_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match name.len().checked_sub(3) {
+            Some(end) if name[end..].eq_ignore_ascii_case(".gz") => name[..end].to_string(),
+            _ => name,
+        }
+    };
+    let original_name = sanitize_filename(&header_name.unwrap_or_else(fallback_name))?;
+
+    options
+        .limits
+        .check_file_size(&original_name, bytes.len() as u64)?;
+    if bytes.len() as u64 > options.limits.max_total_size_bytes {
+        return Err(ToolsError::Validation(format!(
+            "Import file total exceeds {} bytes.",
+            options.limits.max_total_size_bytes
+        )));
+    }
+    ExtractionLimits::for_import(options).check_ratio(
+        &original_name,
+        bytes.len() as u64,
+        fs::metadata(archive_path)?.len(),
+    )?;
+
+    let sha256 = sha256_hex(&bytes);
+    debug!(
+        "custom-tools: zip import parsed bare gzip file={} bytes={}",
+        original_name,
+        bytes.len()
+    );
+    Ok(ParsedImportArchive {
+        metadata,
+        version,
+        files: vec![ImportFileBytes {
+            original_name,
+            mime: None,
+            bytes,
+            sha256,
+            note: None,
+        }],
+        assets: Vec::new(),
+        warnings: Vec::new(),
+        exported_at: None,
+        file_problems: Vec::new(),
+    })
+}
+
+fn is_bundle_root(archive_root: &Path) -> bool {
+    !archive_root.join("manifest.json").is_file()
+        && !archive_root.join(TOOL_INDEX_FILE_NAME).is_file()
+        && archive_root.join(BUNDLE_TOOLS_DIR).is_dir()
+}
+
+fn parse_tool_root(
+    tool_root: &Path,
+    options: &ImportOptions,
+    progress: Option<ProgressCallback>,
+) -> ToolsResult<Vec<ParsedImportArchive>> {
+    if tool_root.join(TOOL_INDEX_FILE_NAME).is_file() {
+        parse_tool_archive(tool_root, options, progress)
+    } else {
+        Ok(vec![parse_version_root(tool_root, options, progress)?])
+    }
+}
+
+/// Parses every tool folder on its own, so a broken tool only fails itself. A slug and version
+/// already claimed by an earlier folder is a conflict, as it would be against the library.
+fn parse_bundle_root(
+    archive_root: &Path,
+    options: &ImportOptions,
+) -> ToolsResult<Vec<BundledToolArchive>> {
+    let mut unexpected = collect_relative_files(archive_root)?
+        .into_iter()
+        .filter(|entry| {
+            entry
+                .strip_prefix(&format!("{BUNDLE_TOOLS_DIR}/"))
+                .map_or(true, |rest| !rest.contains('/'))
+        })
+        .collect::<Vec<_>>();
+    if !unexpected.is_empty() {
+        unexpected.sort();
+        return Err(ToolsError::Zip(format!(
+            "Unexpected files in bundle: {}.",
+            unexpected.join(", ")
+        )));
+    }
+
+    let mut folders = fs::read_dir(archive_root.join(BUNDLE_TOOLS_DIR))?
+        .map(|entry| entry.map(|value| value.file_name().to_string_lossy().to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if folders.is_empty() {
+        return Err(ToolsError::Zip("The bundle holds no tools.".to_string()));
+    }
+    folders.sort();
+
+    let mut claimed = HashMap::<(String, String), String>::new();
+    let mut tools = Vec::with_capacity(folders.len());
+    for folder in folders {
+        let tool_root = archive_root.join(BUNDLE_TOOLS_DIR).join(&folder);
+        let mut versions = parse_tool_root(&tool_root, options, None);
+        if let Ok(parsed) = &versions {
+            let duplicate = parsed.iter().find_map(|archive| {
+                let slug = archive.metadata.slug.clone().unwrap_or_default();
+                let key = (slug.to_ascii_lowercase(), archive.version.version.clone());
+                claimed
+                    .get(&key)
+                    .map(|other| format!("{slug} {} is also in {BUNDLE_TOOLS_DIR}/{other}", key.1))
+            });
+            match duplicate {
+                Some(message) => {
+                    versions = Err(ToolsError::Conflict(format!(
+                        "{message}; import aborted for {BUNDLE_TOOLS_DIR}/{folder}."
+                    )))
+                }
+                None => {
+                    for archive in parsed {
+                        let slug = archive.metadata.slug.clone().unwrap_or_default();
+                        claimed.insert(
+                            (slug.to_ascii_lowercase(), archive.version.version.clone()),
+                            folder.clone(),
+                        );
+                    }
+                }
+            }
+        }
+        tools.push(BundledToolArchive { folder, versions });
+    }
+    debug!(
+        "custom-tools: zip import parsed bundle tools={} failed={}",
+        tools.len(),
+        tools.iter().filter(|tool| tool.versions.is_err()).count()
+    );
+    Ok(tools)
+}
+
+/// Checks `tool.json` against the extracted folders, then parses each listed version.
+fn parse_tool_archive(
+    archive_root: &Path,
+    options: &ImportOptions,
+    progress: Option<ProgressCallback>,
+) -> ToolsResult<Vec<ParsedImportArchive>> {
+    let index_bytes = fs::read(archive_root.join(TOOL_INDEX_FILE_NAME)).map_err(|error| {
+        ToolsError::Zip(format!("{TOOL_INDEX_FILE_NAME} could not be read: {error}"))
+    })?;
+    let index_raw = decode_manifest_bytes(index_bytes)?;
+    let index: ToolArchiveIndex = serde_json::from_str(&index_raw).map_err(|error| {
+        ToolsError::Zip(format!(
+            "{TOOL_INDEX_FILE_NAME} is not a valid Korda tool index: {error}"
+        ))
+    })?;
+    if index.versions.is_empty() {
+        return Err(ToolsError::Validation(format!(
+            "{TOOL_INDEX_FILE_NAME} lists no versions."
+        )));
+    }
+
+    let mut version_paths = HashSet::new();
+    for version in &index.versions {
+        assert_safe_archive_path(&version.path)?;
+        let is_version_folder = version
+            .path
+            .strip_prefix(&format!("{VERSIONS_DIR}/"))
+            .is_some_and(|name| !name.is_empty() && !name.contains('/'));
+        if !is_version_folder || !version_paths.insert(version.path.clone()) {
+            return Err(ToolsError::Zip(format!(
+                "{TOOL_INDEX_FILE_NAME} lists an invalid version folder: {}",
+                version.path
+            )));
+        }
+    }
+    let mut unexpected = collect_relative_files(archive_root)?
+        .into_iter()
+        .filter(|entry| {
+            entry != TOOL_INDEX_FILE_NAME
+                && entry != LINEAGE_FILE_NAME
+                && !version_paths
+                    .iter()
+                    .any(|path| entry.starts_with(&format!("{path}/")))
+        })
+        .collect::<Vec<_>>();
+    if !unexpected.is_empty() {
+        unexpected.sort();
+        return Err(ToolsError::Zip(format!(
+            "Unexpected files in archive: {}.",
+            unexpected.join(", ")
+        )));
+    }
+
+    let mut parsed = Vec::with_capacity(index.versions.len());
+    for version in &index.versions {
+        let root = archive_root.join(&version.path);
+        if !root.join("manifest.json").is_file() {
+            return Err(ToolsError::Zip(format!(
+                "{} has no manifest.json.",
+                version.path
+            )));
+        }
+        let archive = parse_version_root(&root, options, progress)?;
+        if archive.metadata.slug.as_deref() != Some(index.tool.slug.trim()) {
+            return Err(ToolsError::Validation(format!(
+                "{}/manifest.json belongs to a different tool than {TOOL_INDEX_FILE_NAME}.",
+                version.path
+            )));
+        }
+        parsed.push(archive);
+    }
+    debug!(
+        "custom-tools: zip import parsed whole-tool archive tool_slug={} versions={}",
+        index.tool.slug,
+        parsed.len()
+    );
+    Ok(parsed)
+}
+
+/// Parses the single-version layout rooted at `archive_root`.
+fn parse_version_root(
+    archive_root: &Path,
+    options: &ImportOptions,
+    progress: Option<ProgressCallback>,
+) -> ToolsResult<ParsedImportArchive> {
+    let manifest_path = archive_root.join("manifest.json");
+    let instructions_path = archive_root.join("instructions.md");
+
+    let manifest_raw = read_manifest_text(&manifest_path)?;
+    let (manifest, warnings) = parse_manifest(&manifest_raw, options.unknown_fields)?;
+
+    let instructions_md = fs::read_to_string(&instructions_path)
+        .map_err(|error| ToolsError::Zip(format!("Failed to read instructions.md: {error}")))?;
+    if instructions_md.trim().is_empty() {
+        return Err(ToolsError::Validation(
+            "instructions.md cannot be empty.".to_string(),
+        ));
+    }
+
+    let metadata = ToolMetadataInput {
+        name: validate_required("tool.name", &manifest.tool.name, 120)?,
+        slug: Some(validate_required("tool.slug", &manifest.tool.slug, 120)?),
+        description: validate_required("tool.description", &manifest.tool.description, 8_000)?,
+        category: validate_required("tool.category", &manifest.tool.category, 120)?,
+        tags: manifest.tool.tags,
+    };
+
+    let exported_at = manifest.version.exported_at;
+    let version = VersionInsertInput {
+        version: validate_required("version.version", &manifest.version.version, 80)?,
+        changelog_md: normalize_optional_text(manifest.version.changelog_md, 512 * 1024)?,
+        instructions_md,
+    };
+
+    let all_entries = collect_relative_files(archive_root)?;
+    ensure_files_extracted(manifest.files.len(), &all_entries)?;
+    let mut metadata_paths = HashSet::new();
+    metadata_paths.insert("manifest.json".to_string());
+    metadata_paths.insert("instructions.md".to_string());
+
+    let checksums_path = archive_root.join(CHECKSUMS_FILE_NAME);
+    if checksums_path.is_file() {
+        metadata_paths.insert(CHECKSUMS_FILE_NAME.to_string());
+        if options.verify_checksums {
+            let raw = fs::read_to_string(&checksums_path).map_err(|error| {
+                ToolsError::Zip(format!("Failed to read {CHECKSUMS_FILE_NAME}: {error}"))
+            })?;
+            cross_check_checksums(&parse_checksums_file(&raw)?, &manifest.files)?;
+        }
+    }
+
+    let mut declared_files = Vec::with_capacity(manifest.files.len());
+    let mut seen_names = HashSet::new();
+    for file in manifest.files {
+        assert_safe_archive_path(&file.relative_path)?;
+        if !file.relative_path.starts_with("files/") {
+            return Err(ToolsError::Zip(format!(
+                "Manifest file path must start with files/: {}",
+                file.relative_path
+            )));
+        }
+
+        let sanitized = sanitize_filename(&file.original_name)?;
+        if !seen_names.insert(sanitized.to_ascii_lowercase()) {
+            return Err(ToolsError::Validation(format!(
+                "Duplicate file in manifest: {sanitized}"
+            )));
+        }
+
+        let expected_rel = format!("files/{sanitized}");
+        if file.relative_path != expected_rel {
+            return Err(ToolsError::Validation(format!(
+                "Manifest relative_path mismatch for {}. Expected {}.",
+                sanitized, expected_rel
+            )));
+        }
+        declared_files.push((file, sanitized, expected_rel));
+    }
+
+    let mut asset_names = HashSet::new();
+    for asset in &manifest.assets {
+        assert_safe_archive_path(&asset.relative_path)?;
+        let name = asset
+            .relative_path
+            .strip_prefix(&format!("{ASSETS_DIR}/"))
+            .ok_or_else(|| {
+                ToolsError::Zip(format!(
+                    "Manifest asset path must start with {ASSETS_DIR}/: {}",
+                    asset.relative_path
+                ))
+            })?;
+        if !asset_names.insert(name.to_ascii_lowercase()) {
+            return Err(ToolsError::Validation(format!(
+                "Duplicate asset in manifest: {name}"
+            )));
+        }
+        metadata_paths.insert(asset.relative_path.clone());
+    }
+
+    let declared_paths = declared_files
+        .iter()
+        .map(|(_, _, expected_rel)| expected_rel.clone())
+        .collect::<HashSet<_>>();
+    let mut file_problems = Vec::new();
+    if let Some(mismatch) = reconcile_archive_files(&declared_paths, &metadata_paths, &all_entries)
+    {
+        if !options.collect_file_problems {
+            return Err(ToolsError::Zip(mismatch.message()));
+        }
+        file_problems.push(mismatch.message());
+    }
+
+    let file_count = declared_files.len();
+    let mut parsed_files = Vec::with_capacity(file_count);
+    let mut total_size = 0u64;
+    for (file, sanitized, expected_rel) in declared_files {
+        let absolute = archive_root.join(&expected_rel);
+        if options.collect_file_problems && !absolute.is_file() {
+            // Already reported by the reconciliation above.
+            continue;
+        }
+        let bytes = fs::read(&absolute).map_err(|error| {
+            ToolsError::Zip(format!("Failed to read {}: {error}", expected_rel))
+        })?;
+        let hash =
+            match verify_import_file(&file.sha256, file.size_bytes, &sanitized, &bytes, options) {
+                Ok(hash) => hash,
+                Err(error) if options.collect_file_problems => {
+                    file_problems.push(error.user_message());
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+
+        total_size += bytes.len() as u64;
+        if total_size > options.limits.max_total_size_bytes {
+            return Err(ToolsError::Validation(format!(
+                "Import file total exceeds {} bytes.",
+                options.limits.max_total_size_bytes
+            )));
+        }
+
+        let note = normalize_file_note(file.note.as_deref())?;
+        if let Some(report) = progress {
+            report(TransferProgress {
+                processed: parsed_files.len() + 1,
+                total: file_count,
+                current_file: Some(sanitized.clone()),
+                bytes_processed: total_size,
+                done: false,
+            });
+        }
+        parsed_files.push(ImportFileBytes {
+            original_name: sanitized,
+            mime: None,
+            bytes,
+            sha256: hash,
+            note,
+        });
+    }
+    if let Some(report) = progress {
+        report(TransferProgress {
+            processed: file_count,
+            total: file_count,
+            current_file: None,
+            bytes_processed: total_size,
+            done: true,
+        });
+    }
+
+    let mut assets = Vec::with_capacity(manifest.assets.len());
+    for asset in manifest.assets {
+        let bytes = fs::read(archive_root.join(&asset.relative_path)).map_err(|error| {
+            ToolsError::Zip(format!("Failed to read {}: {error}", asset.relative_path))
+        })?;
+        let name = asset.relative_path[ASSETS_DIR.len() + 1..].to_string();
+        let sha256 = verify_import_file(&asset.sha256, asset.size_bytes, &name, &bytes, options)?;
+        assets.push(ImportAssetBytes {
+            name,
+            bytes,
+            sha256,
+        });
+    }
+
+    let parsed = ParsedImportArchive {
+        metadata,
+        version,
+        files: parsed_files,
+        assets,
+        warnings,
+        exported_at,
+        file_problems,
+    };
+    debug!(
+        "custom-tools: zip import parsed tool_slug={} version={} file_count={}",
+        parsed.metadata.slug.clone().unwrap_or_default(),
+        parsed.version.version,
+        parsed.files.len()
+    );
+    Ok(parsed)
+}
+
+/// Runs every manifest, size and hash check `import_tool_zip` does, extracting only into a temp
+/// directory, and describes what would be imported.
+pub fn verify_tool_zip(zip_path: &str, options: &ImportOptions) -> ToolsResult<ToolImportPreview> {
+    verify_archive(zip_path, options).map(|(preview, _)| preview)
+}
+
+/// Payload counterpart of `verify_tool_zip` that also hands back the parsed archive.
+pub fn verify_tool_zip_payload(
+    file_name: &str,
+    data_base64: &str,
+    options: &ImportOptions,
+) -> ToolsResult<(ToolImportPreview, ParsedImportArchive)> {
+    with_decoded_payload(
+        file_name,
+        data_base64,
+        options.staging_root.as_deref(),
+        |zip_path| verify_archive(zip_path, options),
+    )
+}
+
+fn verify_archive(
+    zip_path: &str,
+    options: &ImportOptions,
+) -> ToolsResult<(ToolImportPreview, ParsedImportArchive)> {
+    let parsed = single_version(import_tool_zip(zip_path, options, None)?)?;
+    let slug = parsed
+        .metadata
+        .slug
+        .clone()
+        .ok_or_else(|| ToolsError::Validation("Manifest tool.slug is required.".to_string()))?;
+    let describe = |original_name: &str, bytes: &[u8], sha256: &str| ToolImportPreviewFile {
+        original_name: original_name.to_string(),
+        size_bytes: bytes.len() as u64,
+        sha256: sha256.to_string(),
+    };
+    let files = parsed
+        .files
+        .iter()
+        .map(|file| describe(&file.original_name, &file.bytes, &file.sha256))
+        .collect::<Vec<_>>();
+    let preview = ToolImportPreview {
+        token: None,
+        tool_name: parsed.metadata.name.clone(),
+        slug,
+        version: parsed.version.version.clone(),
+        total_size_bytes: files.iter().map(|file| file.size_bytes).sum(),
+        files,
+        assets: parsed
+            .assets
+            .iter()
+            .map(|asset| describe(&asset.name, &asset.bytes, &asset.sha256))
+            .collect(),
+        warnings: parsed
+            .warnings
+            .iter()
+            .chain(&parsed.file_problems)
+            .cloned()
+            .collect(),
+        exported_at: parsed.exported_at,
+    };
+    Ok((preview, parsed))
+}
+
+/// Payload counterpart of `import_tool_zip`.
 pub fn import_tool_zip_payload(
     file_name: &str,
     data_base64: &str,
-) -> ToolsResult<ParsedImportArchive> {
+    options: &ImportOptions,
+    progress: Option<ProgressCallback>,
+) -> ToolsResult<Vec<ParsedImportArchive>> {
+    with_decoded_payload(
+        file_name,
+        data_base64,
+        options.staging_root.as_deref(),
+        |zip_path| import_tool_zip(zip_path, options, progress),
+    )
+}
+
+/// Decodes a base64 archive payload into a temp file under `staging_root` and runs `read` on
+/// its path.
+fn with_decoded_payload<T>(
+    file_name: &str,
+    data_base64: &str,
+    staging_root: Option<&Path>,
+    read: impl FnOnce(&str) -> ToolsResult<T>,
+) -> ToolsResult<T> {
     debug!(
         "custom-tools: zip payload import start file_name={}",
         file_name.trim()
     );
-    let bytes = base64::engine::general_purpose::STANDARD
-        .decode(data_base64.trim())
-        .map_err(|error| {
-            ToolsError::Validation(format!("Invalid zip payload encoding: {error}"))
-        })?;
-
-    let staging = create_temp_dir("tool-import-payload")?;
+    let encoded = data_base64.trim();
+    let estimated_bytes = (encoded.len() as u64 / 4) * 3;
+    let staging_root = resolve_staging_root(staging_root);
+    ensure_free_space(&staging_root, estimated_bytes, "import staging")?;
+    let staging = create_temp_dir_in(&staging_root, "tool-import-payload")?;
+    let cleanup = TrackedArtifacts::track("payload import", vec![staging.clone()]);
     let suggested_name = file_name.trim();
     let zip_file_name = if suggested_name.is_empty() {
         "import.zip".to_string()
@@ -360,266 +1766,2434 @@ pub fn import_tool_zip_payload(
                 }
             })
             .collect::<String>();
-        if !sanitized.to_ascii_lowercase().ends_with(".zip") {
+        let lower = sanitized.to_ascii_lowercase();
+        if ![".zip", ".tar.gz", ".tgz"]
+            .iter()
+            .any(|extension| lower.ends_with(extension))
+        {
             sanitized.push_str(".zip");
         }
         sanitized
     };
 
-    let zip_path = staging.join(zip_file_name);
-    fs::write(&zip_path, bytes)?;
-    let result = import_tool_zip(zip_path.to_string_lossy().as_ref());
-    let _ = fs::remove_dir_all(staging);
-    result
-}
+    // Decode straight to disk so the decoded archive never sits in memory next to the payload.
+    let zip_path = staging.join(zip_file_name);
+    decode_base64_to_file(encoded, &zip_path)?;
+    let result = read(zip_path.to_string_lossy().as_ref());
+    cleanup.settle(&result);
+    result
+}
+
+/// A manifest that lists files while `files/` came out empty means the payload entries were
+/// lost, so report that once instead of naming the first missing file.
+fn ensure_files_extracted(manifest_file_count: usize, entries: &[String]) -> ToolsResult<()> {
+    let extracted = entries
+        .iter()
+        .filter(|entry| entry.starts_with("files/"))
+        .count();
+    if manifest_file_count > 0 && extracted == 0 {
+        return Err(ToolsError::Zip(format!(
+            "The archive appears to be metadata-only or corrupt: manifest.json lists {manifest_file_count} file(s) but no files were extracted."
+        )));
+    }
+    Ok(())
+}
+
+/// Paths that differ between manifest.json and the extracted archive, in both directions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveMismatch {
+    /// Declared in the manifest but absent from the archive.
+    pub missing: Vec<String>,
+    /// Present in the archive but neither declared nor a known metadata file.
+    pub unexpected: Vec<String>,
+}
+
+impl ArchiveMismatch {
+    pub fn message(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.missing.is_empty() {
+            let label = if self.missing.len() == 1 {
+                "Missing archive file"
+            } else {
+                "Missing archive files"
+            };
+            parts.push(format!("{label}: {}", self.missing.join(", ")));
+        }
+        if !self.unexpected.is_empty() {
+            let label = if self.unexpected.len() == 1 {
+                "Unexpected file in archive"
+            } else {
+                "Unexpected files in archive"
+            };
+            parts.push(format!("{label}: {}", self.unexpected.join(", ")));
+        }
+        format!(
+            "Archive contents do not match manifest.json. {}.",
+            parts.join(". ")
+        )
+    }
+}
+
+/// Compares both directions at once so one error names every missing and extra path.
+fn reconcile_archive_files(
+    declared: &HashSet<String>,
+    metadata_files: &HashSet<String>,
+    extracted: &[String],
+) -> Option<ArchiveMismatch> {
+    let extracted_set = extracted.iter().cloned().collect::<HashSet<_>>();
+    let mut missing = declared
+        .difference(&extracted_set)
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut unexpected = extracted_set
+        .into_iter()
+        .filter(|entry| !declared.contains(entry) && !metadata_files.contains(entry))
+        .collect::<Vec<_>>();
+    if missing.is_empty() && unexpected.is_empty() {
+        return None;
+    }
+    missing.sort();
+    unexpected.sort();
+    Some(ArchiveMismatch {
+        missing,
+        unexpected,
+    })
+}
+
+/// Checks one archive file against its manifest entry and the size limits, returning the hash
+/// to record for it.
+fn verify_import_file(
+    declared_sha256: &str,
+    declared_size: u64,
+    sanitized: &str,
+    bytes: &[u8],
+    options: &ImportOptions,
+) -> ToolsResult<String> {
+    let size_bytes = bytes.len() as u64;
+    if size_bytes != declared_size {
+        return Err(ToolsError::Validation(format!(
+            "File size mismatch for {}. Manifest: {}, archive: {}.",
+            sanitized, declared_size, size_bytes
+        )));
+    }
+
+    if size_bytes == 0 || size_bytes > options.limits.max_file_size_bytes {
+        return Err(ToolsError::Validation(format!(
+            "{} exceeds allowed size limits.",
+            sanitized
+        )));
+    }
+
+    if options.trust_manifest {
+        let declared = declared_sha256.trim().to_ascii_lowercase();
+        if declared.len() != 64 || !declared.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ToolsError::Validation(format!(
+                "Manifest sha256 for {} is not a valid SHA-256 digest.",
+                sanitized
+            )));
+        }
+        Ok(declared)
+    } else {
+        let hash = sha256_hex(bytes);
+        if !hash.eq_ignore_ascii_case(declared_sha256.trim()) {
+            return Err(ToolsError::Validation(format!(
+                "SHA256 mismatch for {}.",
+                sanitized
+            )));
+        }
+        Ok(hash)
+    }
+}
+
+/// Parses manifest.json and lists keys this build does not recognize. Unknown keys are dropped
+/// by the typed parse, so lenient mode reports them rather than losing them silently.
+fn parse_manifest(
+    raw: &str,
+    unknown_fields: ContentCheckMode,
+) -> ToolsResult<(ToolExportManifest, Vec<String>)> {
+    let invalid = |error: serde_json::Error| {
+        ToolsError::Zip(format!(
+            "manifest.json is not a valid Korda tool manifest: {error}"
+        ))
+    };
+    let value: serde_json::Value = serde_json::from_str(raw).map_err(invalid)?;
+    check_schema_version(&value)?;
+    let manifest: ToolExportManifest = serde_json::from_value(value.clone()).map_err(invalid)?;
+    if unknown_fields == ContentCheckMode::Off {
+        return Ok((manifest, Vec::new()));
+    }
+
+    let known = serde_json::to_value(&manifest).map_err(invalid)?;
+    let mut unknown = Vec::new();
+    collect_unknown_keys(&value, &known, "", &mut unknown);
+    unknown.sort();
+    if unknown.is_empty() {
+        return Ok((manifest, Vec::new()));
+    }
+    if unknown_fields == ContentCheckMode::Reject {
+        return Err(ToolsError::Validation(format!(
+            "manifest.json has unexpected fields: {}.",
+            unknown.join(", ")
+        )));
+    }
+    Ok((
+        manifest,
+        vec![format!(
+            "manifest.json has fields this version does not recognize and ignored: {}.",
+            unknown.join(", ")
+        )],
+    ))
+}
+
+/// Runs before the typed parse, since a newer format may not fit this build's structs and the
+/// parse error would hide the real cause.
+fn check_schema_version(value: &serde_json::Value) -> ToolsResult<()> {
+    let Some(raw) = value.get("schemaVersion") else {
+        return Ok(());
+    };
+    let schema_version = raw.as_u64().ok_or_else(|| {
+        ToolsError::Validation(format!(
+            "manifest.json has an invalid schemaVersion: {raw}."
+        ))
+    })?;
+    if schema_version > u64::from(MANIFEST_SCHEMA_VERSION) {
+        return Err(ToolsError::Validation(format!(
+            "manifest.json uses format version {schema_version}, but this version of Korda Tools reads up to version {MANIFEST_SCHEMA_VERSION}. Update the app to import it."
+        )));
+    }
+    Ok(())
+}
+
+fn collect_unknown_keys(
+    raw: &serde_json::Value,
+    known: &serde_json::Value,
+    prefix: &str,
+    unknown: &mut Vec<String>,
+) {
+    match (raw, known) {
+        (serde_json::Value::Object(raw_map), serde_json::Value::Object(known_map)) => {
+            for (key, raw_value) in raw_map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                match known_map.get(key) {
+                    Some(known_value) => {
+                        collect_unknown_keys(raw_value, known_value, &path, unknown)
+                    }
+                    None => unknown.push(path),
+                }
+            }
+        }
+        (serde_json::Value::Array(raw_items), serde_json::Value::Array(known_items)) => {
+            for (index, (raw_item, known_item)) in raw_items.iter().zip(known_items).enumerate() {
+                collect_unknown_keys(raw_item, known_item, &format!("{prefix}[{index}]"), unknown);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Picks the extractor from the leading bytes and rejects files that cannot be tool archives
+/// before extraction, whose errors for these cases are opaque.
+pub(crate) fn sniff_archive_format(archive_path: &Path) -> ToolsResult<ArchiveFormat> {
+    use std::io::Read;
+
+    let mut header = Vec::with_capacity(4);
+    fs::File::open(archive_path)?
+        .take(4)
+        .read_to_end(&mut header)?;
+    match header.as_slice() {
+        [] => Err(ToolsError::Zip("The selected file is empty.".to_string())),
+        b"PK\x03\x04" => Ok(ArchiveFormat::Zip),
+        [0x1f, 0x8b, ..] => Ok(ArchiveFormat::TarGz),
+        b"PK\x05\x06" => Err(ToolsError::Zip(
+            "The archive is empty; it contains no files.".to_string(),
+        )),
+        _ => Err(ToolsError::Zip(
+            "The selected file is not a zip or tar.gz archive, or it is corrupt.".to_string(),
+        )),
+    }
+}
+
+fn describe_extraction_error(error: ToolsError) -> ToolsError {
+    match error {
+        ToolsError::Zip(details)
+            if !details.contains("Unsafe zip entry path")
+                && !details.contains("import stopped") =>
+        {
+            ToolsError::Zip(format!(
+                "The archive could not be extracted; it may be corrupt or incomplete. {details}"
+            ))
+        }
+        other => other,
+    }
+}
+
+/// Reads `manifest.json` as UTF-8, dropping a leading byte order mark. Editors that save as
+/// UTF-16 or a legacy code page get a message naming the encoding problem rather than a
+/// generic read failure.
+fn read_manifest_text(manifest_path: &Path) -> ToolsResult<String> {
+    let bytes = fs::read(manifest_path)
+        .map_err(|error| ToolsError::Zip(format!("manifest.json could not be read: {error}")))?;
+    decode_manifest_bytes(bytes)
+}
+
+pub(crate) fn decode_manifest_bytes(mut bytes: Vec<u8>) -> ToolsResult<String> {
+    const UTF8_BOM: &[u8] = &[0xef, 0xbb, 0xbf];
+    if bytes.starts_with(&[0xff, 0xfe]) || bytes.starts_with(&[0xfe, 0xff]) {
+        return Err(ToolsError::Zip(
+            "manifest.json is saved as UTF-16; save it as UTF-8 and import again.".to_string(),
+        ));
+    }
+    if bytes.starts_with(UTF8_BOM) {
+        bytes.drain(..UTF8_BOM.len());
+    }
+    String::from_utf8(bytes).map_err(|error| {
+        ToolsError::Zip(format!(
+            "manifest.json must be UTF-8 text, but byte {} is not valid UTF-8; save it as UTF-8 and import again.",
+            error.utf8_error().valid_up_to()
+        ))
+    })
+}
+
+/// Archives built by general-purpose zip tools often wrap everything in one folder
+/// (`cad-toolset/manifest.json`); treat that folder as the root when it holds the manifest.
+pub(crate) fn resolve_archive_root(extraction_dir: &Path) -> ToolsResult<PathBuf> {
+    let is_root = |dir: &Path| {
+        dir.join("manifest.json").is_file()
+            || dir.join(TOOL_INDEX_FILE_NAME).is_file()
+            || dir.join(BUNDLE_TOOLS_DIR).is_dir()
+    };
+    if is_root(extraction_dir) {
+        return Ok(extraction_dir.to_path_buf());
+    }
+
+    let entries = fs::read_dir(extraction_dir)?
+        .map(|entry| entry.map(|value| value.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let [only_entry] = entries.as_slice() {
+        if only_entry.is_dir() && is_root(only_entry) {
+            debug!(
+                "custom-tools: zip import using wrapped root folder {}",
+                only_entry
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            );
+            return Ok(only_entry.clone());
+        }
+    }
+
+    if entries.is_empty() {
+        return Err(ToolsError::Zip(
+            "The archive is empty; it contains no files.".to_string(),
+        ));
+    }
+    Err(ToolsError::Zip(
+        "This doesn't look like a Korda tool archive: manifest.json was not found at the archive root."
+            .to_string(),
+    ))
+}
+
+fn decode_base64_to_file(encoded: &str, destination: &Path) -> ToolsResult<u64> {
+    let mut reader = base64::read::DecoderReader::new(
+        encoded.as_bytes(),
+        &base64::engine::general_purpose::STANDARD,
+    );
+    let mut writer = std::io::BufWriter::new(fs::File::create(destination)?);
+    let written = std::io::copy(&mut reader, &mut writer).map_err(|error| {
+        if error.kind() == std::io::ErrorKind::InvalidData {
+            ToolsError::Validation(format!("Invalid zip payload encoding: {error}"))
+        } else {
+            ToolsError::from(error)
+        }
+    })?;
+    std::io::Write::flush(&mut writer)?;
+    Ok(written)
+}
+
+pub(crate) fn estimate_export_size_bytes(context: &ExportVersionContext) -> u64 {
+    let file_bytes = context
+        .files
+        .iter()
+        .map(|file| file.size_bytes.max(0) as u64)
+        .sum::<u64>();
+    file_bytes + context.version.instructions_md.len() as u64
+}
+
+pub(crate) fn normalize_destination(raw: &str) -> ToolsResult<PathBuf> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Err(ToolsError::Validation(
+            "Destination path is required.".to_string(),
+        ));
+    }
+
+    let mut destination = PathBuf::from(trimmed);
+    if destination.extension().is_none() {
+        destination.set_extension("zip");
+    }
+
+    if let Some(parent) = destination.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    Ok(destination)
+}
+
+pub const DEFAULT_EXPORT_NAME_TEMPLATE: &str = "{slug}-{version}.zip";
+
+/// Resolves one file name inside an export directory from a template using the tokens
+/// `{slug}`, `{version}`, `{name}`, and `{date}` (UTC, `YYYY-MM-DD`).
+pub fn resolve_export_destination(
+    directory: &Path,
+    template: &str,
+    context: &ExportVersionContext,
+    date: &str,
+) -> ToolsResult<PathBuf> {
+    let template = template.trim();
+    if template.is_empty() {
+        return Err(ToolsError::Validation(
+            "Name template is required.".to_string(),
+        ));
+    }
+
+    let mut file_name = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        file_name.push_str(&rest[..start]);
+        let end = rest[start..].find('}').ok_or_else(|| {
+            ToolsError::Validation("Name template has an unclosed '{'.".to_string())
+        })?;
+        let value = match &rest[start + 1..start + end] {
+            "slug" => context.tool.slug.as_str(),
+            "version" => context.version.version.as_str(),
+            "name" => context.tool.name.as_str(),
+            "date" => date,
+            token => {
+                return Err(ToolsError::Validation(format!(
+                    "Unknown name template token {{{token}}}; use slug, version, name, or date."
+                )))
+            }
+        };
+        // Tool names are free text; keep them from introducing path structure.
+        file_name.extend(value.chars().map(|character| {
+            if matches!(character, '/' | '\\' | ':') || character.is_control() {
+                '-'
+            } else {
+                character
+            }
+        }));
+        rest = &rest[start + end + 1..];
+    }
+    file_name.push_str(rest);
+
+    let file_name = file_name.trim();
+    let mut components = Path::new(file_name).components();
+    let single_normal = matches!(components.next(), Some(std::path::Component::Normal(_)))
+        && components.next().is_none();
+    if !single_normal || file_name.contains(['/', '\\', ':']) || file_name.contains("..") {
+        return Err(ToolsError::Validation(format!(
+            "Name template must produce a plain file name, got \"{file_name}\"."
+        )));
+    }
+
+    // Appended rather than set, so a version like `1.2.0` is not read as the extension.
+    let has_zip_extension = Path::new(file_name)
+        .extension()
+        .is_some_and(|value| value.eq_ignore_ascii_case("zip"));
+    Ok(if has_zip_extension {
+        directory.join(file_name)
+    } else {
+        directory.join(format!("{file_name}.zip"))
+    })
+}
+
+/// Formats Unix seconds as a UTC `YYYY-MM-DD` date.
+pub fn utc_date(epoch_secs: u64) -> String {
+    // Civil-from-days conversion for the proleptic Gregorian calendar.
+    let days = (epoch_secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Packs `source_dir` into `destination` in the format and compression level from `options`.
+/// Every entry gets `modified_at` (epoch millis) as its timestamp and entries are written in
+/// sorted order, so the same files always produce the same bytes. Password-protected zips are
+/// the exception: AES encryption salts each entry randomly.
+pub(crate) fn write_archive(
+    source_dir: &Path,
+    destination: &Path,
+    options: &ExportOptions,
+    modified_at: i64,
+) -> ToolsResult<()> {
+    options.validate()?;
+    match options.format {
+        ArchiveFormat::Zip => write_zip(
+            source_dir,
+            destination,
+            options.compression,
+            options.password.as_deref(),
+            modified_at,
+        ),
+        ArchiveFormat::TarGz => {
+            compress_directory_to_tar_gz(source_dir, destination, options.compression, modified_at)
+        }
+    }
+}
+
+/// Converts epoch millis to a zip timestamp. Zip dates cover 1980 to 2107; anything outside
+/// falls back to the 1980-01-01 default.
+fn zip_timestamp(modified_at: i64) -> zip::DateTime {
+    let seconds = modified_at.div_euclid(1000);
+    let (days, time_of_day) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+    // Civil date from days since 1970-01-01, after Howard Hinnant's `civil_from_days`.
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    u16::try_from(year)
+        .ok()
+        .and_then(|year| {
+            zip::DateTime::from_date_and_time(
+                year,
+                month as u8,
+                day as u8,
+                (time_of_day / 3_600) as u8,
+                (time_of_day % 3_600 / 60) as u8,
+                (time_of_day % 60) as u8,
+            )
+            .ok()
+        })
+        .unwrap_or_default()
+}
+
+/// Tar.gz counterpart of `compress_directory_to_zip`, with the same sorted relative paths.
+fn compress_directory_to_tar_gz(
+    source_dir: &Path,
+    destination: &Path,
+    compression: CompressionLevel,
+    modified_at: i64,
+) -> ToolsResult<()> {
+    let mut relative_paths = collect_relative_files(source_dir)?;
+    relative_paths.sort();
+
+    let result = (|| -> ToolsResult<()> {
+        let encoder = flate2::write::GzEncoder::new(
+            fs::File::create(destination)?,
+            compression.gzip_compression(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        for relative in &relative_paths {
+            // Headers are built by hand so owner, mode and mtime never come from the staging
+            // files themselves.
+            let source = fs::File::open(source_dir.join(relative))?;
+            let mut header = tar::Header::new_gnu();
+            header.set_size(source.metadata()?.len());
+            header.set_mode(0o644);
+            header.set_mtime(modified_at.div_euclid(1000).max(0) as u64);
+            header.set_entry_type(tar::EntryType::Regular);
+            builder
+                .append_data(&mut header, relative, source)
+                .map_err(|error| tar_error("write", error))?;
+        }
+        builder
+            .into_inner()
+            .and_then(|encoder| encoder.finish())
+            .map_err(|error| tar_error("write", error))?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(destination);
+    }
+    result
+}
+
+/// Zips every file under `source_dir` with paths relative to it, replacing `destination_zip`.
+pub(crate) fn compress_directory_to_zip(
+    source_dir: &Path,
+    destination_zip: &Path,
+    compression: CompressionLevel,
+) -> ToolsResult<()> {
+    write_zip(source_dir, destination_zip, compression, None, 0)
+}
+
+fn write_zip(
+    source_dir: &Path,
+    destination_zip: &Path,
+    compression: CompressionLevel,
+    password: Option<&str>,
+    modified_at: i64,
+) -> ToolsResult<()> {
+    let mut relative_paths = collect_relative_files(source_dir)?;
+    relative_paths.sort();
+
+    let result = (|| -> ToolsResult<()> {
+        let mut writer = zip::ZipWriter::new(fs::File::create(destination_zip)?);
+        let mut options = compression
+            .file_options()
+            .last_modified_time(zip_timestamp(modified_at));
+        if let Some(password) = password {
+            options = options.with_aes_encryption(zip::AesMode::Aes256, password);
+        }
+        for relative in &relative_paths {
+            writer
+                .start_file(relative.as_str(), options)
+                .map_err(|error| zip_error("write", error))?;
+            let mut source = fs::File::open(source_dir.join(relative))?;
+            std::io::copy(&mut source, &mut writer)?;
+        }
+        writer.finish().map_err(|error| zip_error("write", error))?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(destination_zip);
+    }
+    result
+}
+
+/// Extracts into `destination_dir`, rejecting any entry that could land outside it: absolute
+/// paths, drive prefixes, `..` segments, and symlinks. Declared sizes and ratios are checked
+/// against `limits` and the free disk space before anything is written, and the bytes actually inflated are counted
+/// too, since declared sizes can lie.
+fn extract_zip_safely(
+    zip_path: &Path,
+    destination_dir: &Path,
+    limits: &ExtractionLimits,
+    password: Option<&str>,
+) -> ToolsResult<()> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(zip_path)?)
+        .map_err(|error| zip_error("read", error))?;
+
+    let mut declared_total = 0u64;
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index_raw(index)
+            .map_err(|error| zip_error("read", error))?;
+        if entry.encrypted() && password.is_none() {
+            return Err(ToolsError::Validation(
+                "Archive is encrypted; a password is required.".to_string(),
+            ));
+        }
+        declared_total = declared_total.saturating_add(entry.size());
+        limits.check_total(declared_total)?;
+        limits.check_ratio(entry.name(), entry.size(), entry.compressed_size())?;
+    }
+    // The entries land uncompressed, so their declared sizes are what has to fit.
+    ensure_free_space(destination_dir, declared_total, "import extraction")?;
+
+    fs::create_dir_all(destination_dir)?;
+    let mut remaining = limits.max_total_bytes;
+
+    for index in 0..archive.len() {
+        let entry = match password {
+            Some(password) => archive.by_index_decrypt(index, password.as_bytes()),
+            None => archive.by_index(index),
+        };
+        let mut entry = entry.map_err(|error| match error {
+            zip::result::ZipError::InvalidPassword => {
+                ToolsError::Validation("The archive password is incorrect.".to_string())
+            }
+            error => zip_error("read", error),
+        })?;
+        // Windows PowerShell's Compress-Archive writes `\` separators; older exports rely on it.
+        let name = entry.name().replace('\\', "/");
+        if entry.is_symlink() {
+            return Err(ToolsError::Zip(format!("Unsafe zip entry path: {name}")));
+        }
+        if entry.is_dir() {
+            let directory = name.trim_end_matches('/');
+            assert_safe_archive_path(directory)?;
+            fs::create_dir_all(destination_dir.join(directory))?;
+            continue;
+        }
+
+        assert_safe_archive_path(&name)?;
+        let target = destination_dir.join(&name);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        extract_entry_within_limit(&mut entry, &target, &name, limits, &mut remaining)?;
+    }
+    Ok(())
+}
+
+/// Streams one entry to `target`, failing as soon as more than `remaining` bytes come out.
+pub(crate) fn extract_entry_within_limit(
+    entry: &mut impl std::io::Read,
+    target: &Path,
+    name: &str,
+    limits: &ExtractionLimits,
+    remaining: &mut u64,
+) -> ToolsResult<()> {
+    use std::io::Read;
+
+    let mut output = fs::File::create(target)?;
+    let written = std::io::copy(&mut entry.take(remaining.saturating_add(1)), &mut output)
+        .map_err(|error| ToolsError::Zip(format!("Failed to extract {name}: {error}")))?;
+    if written > *remaining {
+        drop(output);
+        let _ = fs::remove_file(target);
+        return Err(limits.too_large());
+    }
+    *remaining -= written;
+    Ok(())
+}
+
+/// Tar.gz counterpart of `extract_zip_safely`; only regular files and directories are accepted,
+/// so links and device entries are rejected along with unsafe paths. Gzip has no per-entry
+/// compressed size, so the ratio check uses the whole archive.
+fn extract_tar_gz_safely(
+    archive_path: &Path,
+    destination_dir: &Path,
+    limits: &ExtractionLimits,
+) -> ToolsResult<()> {
+    let archive_bytes = fs::metadata(archive_path)?.len();
+    let open = || -> ToolsResult<tar::Archive<flate2::read::GzDecoder<fs::File>>> {
+        Ok(tar::Archive::new(flate2::read::GzDecoder::new(
+            fs::File::open(archive_path)?,
+        )))
+    };
+
+    // Headers only: this pass inflates the stream but writes nothing.
+    let mut declared_total = 0u64;
+    let mut archive = open()?;
+    for entry in archive
+        .entries()
+        .map_err(|error| tar_error("read", error))?
+    {
+        let entry = entry.map_err(|error| tar_error("read", error))?;
+        declared_total = declared_total.saturating_add(entry.header().size().unwrap_or(0));
+        limits.check_total(declared_total)?;
+        limits.check_ratio("The archive", declared_total, archive_bytes)?;
+    }
+    ensure_free_space(destination_dir, declared_total, "import extraction")?;
+
+    fs::create_dir_all(destination_dir)?;
+    let mut remaining = limits.max_total_bytes;
+    let mut archive = open()?;
+
+    for entry in archive
+        .entries()
+        .map_err(|error| tar_error("read", error))?
+    {
+        let mut entry = entry.map_err(|error| tar_error("read", error))?;
+        let name = String::from_utf8_lossy(&entry.path_bytes()).replace('\\', "/");
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => {
+                let directory = name.trim_end_matches('/');
+                if directory.is_empty() || directory == "." {
+                    continue;
+                }
+                assert_safe_archive_path(directory)?;
+                fs::create_dir_all(destination_dir.join(directory))?;
+            }
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                let name = name.trim_start_matches("./");
+                assert_safe_archive_path(name)?;
+                let target = destination_dir.join(name);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                extract_entry_within_limit(&mut entry, &target, name, limits, &mut remaining)?;
+            }
+            tar::EntryType::XGlobalHeader => {}
+            _ => return Err(ToolsError::Zip(format!("Unsafe zip entry path: {name}"))),
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn tar_error(action: &str, error: std::io::Error) -> ToolsError {
+    ToolsError::Zip(format!("Failed to {action} tar.gz archive: {error}"))
+}
+
+pub(crate) fn zip_error(action: &str, error: zip::result::ZipError) -> ToolsError {
+    ToolsError::Zip(format!("Failed to {action} zip archive: {error}"))
+}
+
+fn collect_relative_files(root: &Path) -> ToolsResult<Vec<String>> {
+    let mut entries = Vec::new();
+    collect_recursive(root, root, &mut entries)?;
+    Ok(entries)
+}
+
+fn collect_recursive(root: &Path, current: &Path, entries: &mut Vec<String>) -> ToolsResult<()> {
+    for entry in fs::read_dir(current)? {
+        let entry = entry.map_err(|error| {
+            ToolsError::Zip(format!("Failed to read archive extraction entry: {error}"))
+        })?;
+        let path = entry.path();
+
+        if path.is_dir() {
+            collect_recursive(root, &path, entries)?;
+            continue;
+        }
+
+        let relative = path
+            .strip_prefix(root)
+            .map_err(|error| {
+                ToolsError::Zip(format!(
+                    "Failed to compute relative extraction path: {error}"
+                ))
+            })?
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        entries.push(relative);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn create_temp_dir(prefix: &str) -> ToolsResult<PathBuf> {
+    create_temp_dir_in(&std::env::temp_dir(), prefix)
+}
+
+/// Returns `preferred` when it is an existing directory a probe file can be written to, and the
+/// system temp dir otherwise. Some locked-down machines keep the temp dir on a small volume.
+pub(crate) fn resolve_staging_root(preferred: Option<&Path>) -> PathBuf {
+    let Some(root) = preferred.filter(|root| !root.as_os_str().is_empty()) else {
+        return std::env::temp_dir();
+    };
+    let probe = root.join(format!(".staging-probe-{}", Uuid::new_v4()));
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            root.to_path_buf()
+        }
+        Err(error) => {
+            warn!(
+                "custom-tools: staging root {} is not writable, using the system temp dir: {error}",
+                root.to_string_lossy()
+            );
+            std::env::temp_dir()
+        }
+    }
+}
+
+pub(crate) fn create_temp_dir_in(temp_root: &Path, prefix: &str) -> ToolsResult<PathBuf> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|error| ToolsError::Io(format!("System clock error: {error}")))?
+        .as_millis();
+    let path = temp_root.join(format!("{prefix}-{now}-{}", Uuid::new_v4()));
+    fs::create_dir_all(&path)?;
+    Ok(path)
+}
+
+fn validate_required(field: &str, value: &str, max_len: usize) -> ToolsResult<String> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return Err(ToolsError::Validation(format!("{field} is required.")));
+    }
+    if trimmed.len() > max_len {
+        return Err(ToolsError::Validation(format!(
+            "{field} exceeds maximum length ({max_len})."
+        )));
+    }
+    Ok(trimmed.to_string())
+}
+
+fn normalize_optional_text(value: Option<String>, max_len: usize) -> ToolsResult<Option<String>> {
+    let Some(raw) = value else {
+        return Ok(None);
+    };
+
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+
+    if trimmed.len() > max_len {
+        return Err(ToolsError::Validation(format!(
+            "Text exceeds maximum length ({max_len})."
+        )));
+    }
+
+    Ok(Some(trimmed.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+
+    use super::super::db::{test_export_context, test_export_file};
+    use super::*;
+
+    fn import_single_zip(
+        zip_path: &str,
+        options: &ImportOptions,
+        progress: Option<ProgressCallback>,
+    ) -> ToolsResult<ParsedImportArchive> {
+        single_version(import_tool_zip(zip_path, options, progress)?)
+    }
+
+    #[test]
+    fn generates_manifest_from_export_context() {
+        let mut context = test_export_context(&[("install.scr", b"abc")]);
+        context.tool.tags = vec!["autocad".to_string()];
+        context.version.changelog_md = Some("Initial release".to_string());
+
+        let manifest = build_manifest(&context, 0).unwrap();
+        assert_eq!(manifest.tool.slug, "cad-toolset");
+        assert_eq!(manifest.files.len(), 1);
+        assert_eq!(manifest.files[0].relative_path, "files/install.scr");
+    }
+
+    #[test]
+    fn reconciliation_reports_missing_and_unexpected_files_together() {
+        let declared = ["files/layers.lsp", "files/menu.cuix"]
+            .map(str::to_string)
+            .into_iter()
+            .collect::<HashSet<_>>();
+        let metadata = ["manifest.json", "instructions.md"]
+            .map(str::to_string)
+            .into_iter()
+            .collect::<HashSet<_>>();
+        let extracted = [
+            "manifest.json",
+            "instructions.md",
+            "files/layers.lsp",
+            "files/purge.lsp",
+            "notes.txt",
+        ]
+        .map(str::to_string);
+
+        let mismatch = reconcile_archive_files(&declared, &metadata, &extracted).unwrap();
+        assert_eq!(mismatch.missing, vec!["files/menu.cuix"]);
+        assert_eq!(mismatch.unexpected, vec!["files/purge.lsp", "notes.txt"]);
+        assert_eq!(
+            mismatch.message(),
+            "Archive contents do not match manifest.json. Missing archive file: files/menu.cuix. Unexpected files in archive: files/purge.lsp, notes.txt."
+        );
+
+        let matching = [
+            "manifest.json",
+            "instructions.md",
+            "files/layers.lsp",
+            "files/menu.cuix",
+        ]
+        .map(str::to_string);
+        assert!(reconcile_archive_files(&declared, &metadata, &matching).is_none());
+    }
+
+    #[test]
+    fn export_names_resolve_from_templates() {
+        let mut context = test_export_context(&[]);
+        context.tool.name = "Layers/Purge".to_string();
+        context.tool.slug = "layers-purge".to_string();
+        context.version.version = "2.1.0".to_string();
+        let directory = Path::new("exports");
+        let resolve = |template: &str| {
+            resolve_export_destination(directory, template, &context, "2026-10-16")
+        };
+
+        assert_eq!(
+            resolve(DEFAULT_EXPORT_NAME_TEMPLATE).unwrap(),
+            directory.join("layers-purge-2.1.0.zip")
+        );
+        assert_eq!(
+            resolve("{date} {name}").unwrap(),
+            directory.join("2026-10-16 Layers-Purge.zip")
+        );
+        assert_eq!(
+            resolve("{slug}-{version}").unwrap(),
+            directory.join("layers-purge-2.1.0.zip")
+        );
+        assert_eq!(
+            resolve("{slug}.ZIP").unwrap(),
+            directory.join("layers-purge.ZIP")
+        );
+        assert!(resolve("../{slug}").is_err());
+        assert!(resolve("nested/{slug}").is_err());
+        assert!(resolve("{author}").is_err());
+        assert!(resolve("{slug").is_err());
+
+        assert_eq!(utc_date(0), "1970-01-01");
+        assert_eq!(utc_date(1_709_164_800), "2024-02-29");
+    }
+
+    #[test]
+    fn zip_round_trip_preserves_manifest_shape() {
+        let root = create_temp_dir("zip-roundtrip").unwrap();
+        let staging = root.join("src");
+        std::fs::create_dir_all(staging.join("files")).unwrap();
+
+        let manifest = ToolExportManifest {
+            schema_version: MANIFEST_SCHEMA_VERSION,
+            assets: Vec::new(),
+            tool: ManifestTool {
+                name: "CAD Toolset".to_string(),
+                slug: "cad-toolset".to_string(),
+                description: "CAD helpers".to_string(),
+                category: "cad".to_string(),
+                tags: vec!["autocad".to_string()],
+            },
+            version: ManifestVersion {
+                version: "1.0.0".to_string(),
+                changelog_md: None,
+                exported_at: None,
+            },
+            files: vec![ManifestFile {
+                original_name: "install.scr".to_string(),
+                sha256: sha256_hex(b"abc"),
+                size_bytes: 3,
+                relative_path: "files/install.scr".to_string(),
+                note: None,
+            }],
+        };
+
+        std::fs::write(
+            staging.join("manifest.json"),
+            serde_json::to_string_pretty(&manifest).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(staging.join("instructions.md"), "# install").unwrap();
+        std::fs::write(staging.join("files").join("install.scr"), b"abc").unwrap();
+
+        let zip_path = root.join("archive.zip");
+        compress_directory_to_zip(&staging, &zip_path, CompressionLevel::Default).unwrap();
+
+        let extracted = create_temp_dir("zip-roundtrip-extracted").unwrap();
+        extract_zip_safely(
+            &zip_path,
+            &extracted,
+            &ExtractionLimits::for_import(&ImportOptions::default()),
+            None,
+        )
+        .unwrap();
+
+        let parsed: ToolExportManifest = serde_json::from_str(
+            &std::fs::read_to_string(extracted.join("manifest.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(parsed.tool.slug, manifest.tool.slug);
+        assert_eq!(parsed.version.version, manifest.version.version);
+        assert_eq!(parsed.files[0].original_name, "install.scr");
+
+        let _ = std::fs::remove_dir_all(root);
+        let _ = std::fs::remove_dir_all(extracted);
+    }
+
+    #[test]
+    fn tar_gz_export_imports_like_zip() {
+        let mut context = test_export_context(&[("install.scr", b"abc")]);
+        context.tool.tags = vec!["autocad".to_string()];
+        context.version.changelog_md = Some("First release".to_string());
+        context.files[0].note = Some("Runs on startup".to_string());
+        let root = create_temp_dir("archive-formats").unwrap();
+        let import_options = ImportOptions {
+            verify_checksums: true,
+            ..ImportOptions::default()
+        };
+        let mut parsed = Vec::new();
+        for format in [ArchiveFormat::Zip, ArchiveFormat::TarGz] {
+            let archive_path = root.join(format!("export.{}", format.extension()));
+            let summary = export_version_archive(
+                &context,
+                archive_path.to_string_lossy().as_ref(),
+                &ExportOptions {
+                    include_checksums: true,
+                    format,
+                    ..ExportOptions::default()
+                },
+                &|_, _| Ok(("install.scr".to_string(), b"abc".to_vec())),
+                None,
+            )
+            .unwrap();
+            assert_eq!(summary.destination, archive_path.to_string_lossy());
+            assert_eq!(
+                summary.bytes_written,
+                fs::metadata(&archive_path).unwrap().len()
+            );
+            assert_eq!(summary.file_count, 1);
+            parsed.push(
+                import_single_zip(
+                    archive_path.to_string_lossy().as_ref(),
+                    &import_options,
+                    None,
+                )
+                .unwrap(),
+            );
+        }
+
+        let (zip, tar_gz) = (&parsed[0], &parsed[1]);
+        assert_eq!(tar_gz.metadata.slug, zip.metadata.slug);
+        assert_eq!(tar_gz.metadata.tags, zip.metadata.tags);
+        assert_eq!(tar_gz.version.version, zip.version.version);
+        assert_eq!(tar_gz.version.changelog_md, zip.version.changelog_md);
+        assert_eq!(tar_gz.version.instructions_md, zip.version.instructions_md);
+        assert_eq!(tar_gz.files.len(), 1);
+        assert_eq!(tar_gz.files[0].original_name, zip.files[0].original_name);
+        assert_eq!(tar_gz.files[0].bytes, zip.files[0].bytes);
+        assert_eq!(tar_gz.files[0].sha256, zip.files[0].sha256);
+        assert_eq!(tar_gz.files[0].note, zip.files[0].note);
+        assert_eq!(tar_gz.warnings, zip.warnings);
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn bare_gzip_file_imports_as_a_new_tool_only_when_allowed() {
+        use std::io::Write;
+
+        let root = create_temp_dir("bare-gzip").unwrap();
+        let script = b"(defun c:hello () (princ \"hello\"))".repeat(40);
+        let named = root.join("upload.gz");
+        let mut encoder = flate2::GzBuilder::new().filename("hello.lsp").write(
+            fs::File::create(&named).unwrap(),
+            flate2::Compression::best(),
+        );
+        encoder.write_all(&script).unwrap();
+        encoder.finish().unwrap();
+
+        let named_path = named.to_string_lossy().to_string();
+        let error = import_tool_zip(&named_path, &ImportOptions::default(), None).unwrap_err();
+        assert!(error.user_message().contains("import it as a bare file"));
+        let allowed = ImportOptions {
+            allow_bare_file: true,
+            ..ImportOptions::default()
+        };
+        let error = import_tool_zip(&named_path, &allowed, None).unwrap_err();
+        assert!(error
+            .user_message()
+            .contains("tool name, version and instructions"));
+
+        let options = ImportOptions {
+            bare_file: Some(BareFileDetails {
+                tool: ToolMetadataInput {
+                    name: "Hello Lisp".to_string(),
+                    slug: None,
+                    description: "Greets".to_string(),
+                    category: String::new(),
+                    tags: Vec::new(),
+                },
+                version: " 1.0.0 ".to_string(),
+                changelog_md: None,
+                instructions_md: "APPLOAD hello.lsp".to_string(),
+            }),
+            ..allowed
+        };
+        let parsed = single_version(import_tool_zip(&named_path, &options, None).unwrap()).unwrap();
+        assert_eq!(parsed.metadata.slug.as_deref(), Some("hello-lisp"));
+        assert_eq!(parsed.version.version, "1.0.0");
+        assert_eq!(parsed.files.len(), 1);
+        assert_eq!(parsed.files[0].original_name, "hello.lsp");
+        assert_eq!(parsed.files[0].bytes, script);
+        assert_eq!(parsed.files[0].sha256, sha256_hex(&script));
+
+        // Without a name in the gzip header the archive name minus `.gz` is used.
+        let unnamed = root.join("startup.scr.gz");
+        let mut encoder = flate2::write::GzEncoder::new(
+            fs::File::create(&unnamed).unwrap(),
+            flate2::Compression::default(),
+        );
+        encoder.write_all(b"_.LINE").unwrap();
+        encoder.finish().unwrap();
+        let parsed =
+            single_version(import_tool_zip(&unnamed.to_string_lossy(), &options, None).unwrap())
+                .unwrap();
+        assert_eq!(parsed.files[0].original_name, "startup.scr");
+
+        let error = import_tool_zip(
+            &named_path,
+            &ImportOptions {
+                limits: FileLimits {
+                    max_file_size_bytes: 64,
+                    max_total_size_bytes: 1024,
+                    ..FileLimits::default()
+                },
+                ..options.clone()
+            },
+            None,
+        )
+        .unwrap_err();
+        assert!(error
+            .user_message()
+            .contains("hello.lsp exceeds max size of 64 bytes"));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn compression_level_is_applied_and_round_trips() {
+        let root = create_temp_dir("zip-compression").unwrap();
+        let staging = root.join("src");
+        std::fs::create_dir_all(staging.join("files")).unwrap();
+        let payload = "(defun c:layfix () (princ))\n".repeat(200);
+        std::fs::write(staging.join("files").join("layfix.lsp"), &payload).unwrap();
+
+        let mut sizes = Vec::new();
+        for (level, method) in [
+            (CompressionLevel::Store, zip::CompressionMethod::Stored),
+            (CompressionLevel::Fast, zip::CompressionMethod::Deflated),
+            (CompressionLevel::Default, zip::CompressionMethod::Deflated),
+            (CompressionLevel::Best, zip::CompressionMethod::Deflated),
+        ] {
+            let zip_path = root.join(format!("{level:?}.zip"));
+            compress_directory_to_zip(&staging, &zip_path, level).unwrap();
+
+            let mut archive =
+                zip::ZipArchive::new(std::fs::File::open(&zip_path).unwrap()).unwrap();
+            let entry = archive.by_name("files/layfix.lsp").unwrap();
+            assert_eq!(entry.compression(), method);
+            sizes.push(entry.compressed_size());
+            drop(entry);
+
+            let extracted = root.join(format!("{level:?}-extracted"));
+            extract_zip_safely(
+                &zip_path,
+                &extracted,
+                &ExtractionLimits::for_import(&ImportOptions::default()),
+                None,
+            )
+            .unwrap();
+            assert_eq!(
+                std::fs::read_to_string(extracted.join("files").join("layfix.lsp")).unwrap(),
+                payload
+            );
+        }
+        assert_eq!(sizes[0], payload.len() as u64);
+        assert!(sizes[1..].iter().all(|size| *size < sizes[0]));
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn whole_tool_archive_round_trips_every_version_in_order() {
+        let mut tool = test_export_context(&[]).tool;
+        tool.tags = vec!["autocad".to_string()];
+        let version = |id: &str, label: &str, files: &[(&str, &[u8])]| {
+            let mut context = test_export_context(&[]);
+            context.tool = tool.clone();
+            context.version.id = id.to_string();
+            context.version.version = label.to_string();
+            context.version.created_at = if label == "1.0.0" { 1 } else { 2 };
+            context.version.instructions_md = format!("# install {label}");
+            context.files = files
+                .iter()
+                .map(|(name, bytes)| test_export_file(id, name, bytes))
+                .collect();
+            context
+        };
+        let context = FullExportContext {
+            tool: tool.clone(),
+            versions: vec![
+                version("version-1", "1.0.0", &[("install.scr", b"abc")]),
+                version(
+                    "version-2",
+                    "1.1.0",
+                    &[("install.scr", b"abcd"), ("menu.json", b"{}")],
+                ),
+            ],
+        };
+        let stored = HashMap::from([
+            ("tools/tool-1/version-1/files/install.scr", b"abc".to_vec()),
+            ("tools/tool-1/version-2/files/install.scr", b"abcd".to_vec()),
+            ("tools/tool-1/version-2/files/menu.json", b"{}".to_vec()),
+        ]);
+
+        let root = create_temp_dir("zip-full-tool").unwrap();
+        let staging = root.join("src");
+        stage_full_export(&context, &staging, true, 0, true, &|_, file| {
+            Ok((
+                file.original_name.clone(),
+                stored[file.stored_rel_path.as_str()].clone(),
+            ))
+        })
+        .unwrap();
+        assert!(staging.join("versions/1.0.0/manifest.json").is_file());
+        assert!(staging.join("versions/1.1.0/files/menu.json").is_file());
+        let lineage: ToolLineage =
+            serde_json::from_slice(&fs::read(staging.join(LINEAGE_FILE_NAME)).unwrap()).unwrap();
+        assert_eq!(lineage.tool_slug, "cad-toolset");
+        assert_eq!(lineage.versions[0].path, "versions/1.0.0");
+        assert_eq!(lineage.versions[0].added, vec!["install.scr"]);
+        assert_eq!(lineage.versions[1].created_at, 2);
+        assert_eq!(lineage.versions[1].added, vec!["menu.json"]);
+        assert_eq!(lineage.versions[1].changed, vec!["install.scr"]);
+        assert!(lineage.versions[1].removed.is_empty());
+        assert_eq!(lineage.versions[1].files[1].sha256, sha256_hex(b"{}"));
+
+        let zip_path = root.join("cad-toolset.zip");
+        write_archive(&staging, &zip_path, &ExportOptions::default(), 0).unwrap();
+        let options = ImportOptions {
+            verify_checksums: true,
+            ..ImportOptions::default()
+        };
+        let parsed = import_tool_zip(zip_path.to_string_lossy().as_ref(), &options, None).unwrap();
+
+        let versions = parsed
+            .iter()
+            .map(|archive| archive.version.version.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(versions, vec!["1.0.0", "1.1.0"]);
+        assert!(parsed
+            .iter()
+            .all(|archive| archive.metadata.slug.as_deref() == Some("cad-toolset")));
+        assert_eq!(parsed[0].files.len(), 1);
+        assert_eq!(parsed[1].files.len(), 2);
+        assert_eq!(parsed[1].files[0].bytes, b"abcd");
+        assert_eq!(parsed[1].version.instructions_md, "# install 1.1.0");
+
+        // Each version fits the per-version limit even though the archive as a whole does not.
+        let tight = ImportOptions {
+            limits: FileLimits {
+                max_total_size_bytes: 8,
+                ..FileLimits::default()
+            },
+            ..options.clone()
+        };
+        assert_eq!(
+            import_tool_zip(zip_path.to_string_lossy().as_ref(), &tight, None)
+                .unwrap()
+                .len(),
+            2
+        );
+
+        assert!(
+            import_single_zip(zip_path.to_string_lossy().as_ref(), &options, None)
+                .unwrap_err()
+                .user_message()
+                .contains("holds 2 versions")
+        );
+
+        let mut used = HashSet::new();
+        assert_eq!(version_directory_name("1.0.0", &mut used), "1.0.0");
+        assert_eq!(version_directory_name("1.0.0 ", &mut used), "1.0.0-2");
+        assert_eq!(version_directory_name("../v2 beta", &mut used), "_v2_beta");
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn bundle_parses_each_tool_and_isolates_duplicate_slug_versions() {
+        let tool_entries = |folder: &str, slug: &str, version: &str| {
+            let manifest = ToolExportManifest {
+                schema_version: MANIFEST_SCHEMA_VERSION,
+                assets: Vec::new(),
+                tool: ManifestTool {
+                    name: slug.to_string(),
+                    slug: slug.to_string(),
+                    description: "CAD helpers".to_string(),
+                    category: "cad".to_string(),
+                    tags: Vec::new(),
+                },
+                version: ManifestVersion {
+                    version: version.to_string(),
+                    changelog_md: None,
+                    exported_at: None,
+                },
+                files: vec![ManifestFile {
+                    original_name: "install.scr".to_string(),
+                    sha256: sha256_hex(b"abc"),
+                    size_bytes: 3,
+                    relative_path: "files/install.scr".to_string(),
+                    note: None,
+                }],
+            };
+            vec![
+                (
+                    format!("tools/{folder}/manifest.json"),
+                    serde_json::to_vec(&manifest).unwrap(),
+                ),
+                (
+                    format!("tools/{folder}/instructions.md"),
+                    b"# install".to_vec(),
+                ),
+                (format!("tools/{folder}/files/install.scr"), b"abc".to_vec()),
+            ]
+        };
+        let root = create_temp_dir("zip-bundle").unwrap();
+        let zip_path = root.join("starter-pack.zip");
+        write_zip_with_entries(
+            &zip_path,
+            [
+                tool_entries("cad-toolset", "cad-toolset", "1.0.0"),
+                tool_entries("layer-tools", "layer-tools", "2.0.0"),
+                tool_entries("cad-toolset-copy", "cad-toolset", "1.0.0"),
+            ]
+            .concat(),
+        );
+        let zip_path = zip_path.to_string_lossy().to_string();
+
+        let tools = import_tool_bundle_zip(&zip_path, &ImportOptions::default()).unwrap();
+        let folders = tools
+            .iter()
+            .map(|tool| tool.folder.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            folders,
+            vec!["cad-toolset", "cad-toolset-copy", "layer-tools"]
+        );
+        let imported = tools[0].versions.as_ref().unwrap();
+        assert_eq!(imported[0].metadata.slug.as_deref(), Some("cad-toolset"));
+        assert_eq!(imported[0].files[0].bytes, b"abc");
+        let duplicate = tools[1].versions.as_ref().unwrap_err();
+        assert!(matches!(duplicate, ToolsError::Conflict(_)));
+        assert_eq!(
+            duplicate.user_message(),
+            "cad-toolset 1.0.0 is also in tools/cad-toolset; import aborted for tools/cad-toolset-copy."
+        );
+        assert_eq!(
+            tools[2].versions.as_ref().unwrap()[0].version.version,
+            "2.0.0"
+        );
+
+        let single = import_tool_zip(&zip_path, &ImportOptions::default(), None).unwrap_err();
+        assert!(single.user_message().contains("import it as a bundle"));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn file_limit_overrides_admit_files_above_the_default_ceiling() {
+        use std::io::Write;
+
+        let large = vec![b'x'; FileLimits::default().max_file_size_bytes as usize + 1];
+        let manifest = ToolExportManifest {
+            schema_version: MANIFEST_SCHEMA_VERSION,
+            assets: Vec::new(),
+            tool: ManifestTool {
+                name: "CAD Toolset".to_string(),
+                slug: "cad-toolset".to_string(),
+                description: "CAD helpers".to_string(),
+                category: "cad".to_string(),
+                tags: Vec::new(),
+            },
+            version: ManifestVersion {
+                version: "1.0.0".to_string(),
+                changelog_md: None,
+                exported_at: None,
+            },
+            files: vec![ManifestFile {
+                original_name: "sheets.txt".to_string(),
+                sha256: sha256_hex(&large),
+                size_bytes: large.len() as u64,
+                relative_path: "files/sheets.txt".to_string(),
+                note: None,
+            }],
+        };
+        let root = create_temp_dir("zip-file-limits").unwrap();
+        let zip_path = root.join("large.zip");
+        // Stored, so the ratio check does not mistake the repetitive payload for a zip bomb.
+        let mut writer = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        let stored = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored)
+            .large_file(true);
+        for (name, bytes) in [
+            ("manifest.json", serde_json::to_vec(&manifest).unwrap()),
+            ("instructions.md", b"# install".to_vec()),
+            ("files/sheets.txt", large.clone()),
+        ] {
+            writer.start_file(name, stored).unwrap();
+            writer.write_all(&bytes).unwrap();
+        }
+        writer.finish().unwrap();
+        let zip_path = zip_path.to_string_lossy().to_string();
+
+        let error = import_single_zip(&zip_path, &ImportOptions::default(), None).unwrap_err();
+        assert!(error.user_message().contains("exceeds allowed size limits"));
+
+        let options = ImportOptions {
+            limits: FileLimits {
+                max_file_size_bytes: 64 * 1024 * 1024,
+                ..FileLimits::default()
+            },
+            ..ImportOptions::default()
+        };
+        let parsed = import_single_zip(&zip_path, &options, None).unwrap();
+        assert_eq!(parsed.files[0].bytes.len(), large.len());
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn repeated_exports_of_a_version_are_byte_identical() {
+        let stored = HashMap::from([
+            ("tools/tool-1/version-1/files/menu.json", b"{}".to_vec()),
+            ("tools/tool-1/version-1/files/install.scr", b"abc".to_vec()),
+        ]);
+        let mut context = test_export_context(&[("menu.json", b"{}"), ("install.scr", b"abc")]);
+        context.tool.tags = vec!["autocad".to_string()];
+        // 2024-03-05 14:30:10 UTC
+        context.version.created_at = 1_709_649_010_000;
+        let manifest = build_manifest(&context, 0).unwrap();
+        assert_eq!(manifest.files[0].original_name, "install.scr");
+
+        let root = create_temp_dir("zip-deterministic").unwrap();
+        for format in [ArchiveFormat::Zip, ArchiveFormat::TarGz] {
+            let export = |run: u64| {
+                let staging = root.join(format!("staging-{run}"));
+                stage_version_export(
+                    &context,
+                    &staging,
+                    true,
+                    context.version.created_at,
+                    &|_, file| {
+                        Ok((
+                            file.original_name.clone(),
+                            stored[file.stored_rel_path.as_str()].clone(),
+                        ))
+                    },
+                    None,
+                )
+                .unwrap();
+                // Staged files from different runs carry different timestamps on disk.
+                for relative in collect_relative_files(&staging).unwrap() {
+                    fs::File::options()
+                        .write(true)
+                        .open(staging.join(relative))
+                        .unwrap()
+                        .set_modified(UNIX_EPOCH + std::time::Duration::from_secs(run * 86_400))
+                        .unwrap();
+                }
+                let archive_path = root.join(format!("export-{run}.{}", format.extension()));
+                write_archive(
+                    &staging,
+                    &archive_path,
+                    &ExportOptions {
+                        format,
+                        ..ExportOptions::default()
+                    },
+                    context.version.created_at,
+                )
+                .unwrap();
+                archive_path
+            };
+            let first = export(1);
+            let second = export(2);
+            assert_eq!(
+                sha256_hex(&fs::read(&first).unwrap()),
+                sha256_hex(&fs::read(&second).unwrap())
+            );
+
+            if format == ArchiveFormat::Zip {
+                let mut archive = zip::ZipArchive::new(fs::File::open(&first).unwrap()).unwrap();
+                let modified = archive.by_index(0).unwrap().last_modified().unwrap();
+                assert_eq!(
+                    (modified.year(), modified.month(), modified.day()),
+                    (2024, 3, 5)
+                );
+                assert_eq!(
+                    (modified.hour(), modified.minute(), modified.second()),
+                    (14, 30, 10)
+                );
+            }
+            let _ = fs::remove_dir_all(root.join("staging-1"));
+            let _ = fs::remove_dir_all(root.join("staging-2"));
+        }
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn parallel_export_stages_sixteen_files_and_aborts_on_any_hash_mismatch() {
+        let stored = (0..16)
+            .map(|index| {
+                (
+                    format!("tools/tool-1/version-1/files/part-{index:02}.lsp"),
+                    format!("(princ {index})")
+                        .repeat(4096 * (index + 1))
+                        .into_bytes(),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        let mut context = test_export_context(&[]);
+        context.files = stored
+            .iter()
+            .map(|(stored_rel_path, bytes)| {
+                test_export_file(
+                    "version-1",
+                    stored_rel_path.rsplit('/').next().unwrap(),
+                    bytes,
+                )
+            })
+            .collect();
+        context
+            .files
+            .sort_by(|left, right| left.original_name.cmp(&right.original_name));
+        // Mirrors read_verified_export_file without the app handle.
+        let read_file = |_: &ExportVersionContext, file: &ToolFileDetail| {
+            let bytes = stored[&file.stored_rel_path].clone();
+            if sha256_hex(&bytes) != file.sha256 {
+                return Err(ToolsError::Zip(format!(
+                    "Stored file hash mismatch for {}.",
+                    file.original_name
+                )));
+            }
+            Ok((file.original_name.clone(), bytes))
+        };
+
+        let root = create_temp_dir("zip-parallel-export").unwrap();
+        let reports = std::sync::Mutex::new(Vec::new());
+        let started = std::time::Instant::now();
+        let bytes_processed = stage_version_export(
+            &context,
+            &root.join("ok"),
+            true,
+            0,
+            &read_file,
+            Some(&|progress| reports.lock().unwrap().push(progress)),
+        )
+        .unwrap();
+        debug!(
+            "custom-tools: staged 16 files in {} ms",
+            started.elapsed().as_millis()
+        );
+
+        let total_bytes = stored.values().map(|bytes| bytes.len() as u64).sum::<u64>();
+        assert_eq!(bytes_processed, total_bytes);
+        for file in &context.files {
+            let written = fs::read(root.join("ok/files").join(&file.original_name)).unwrap();
+            assert_eq!(sha256_hex(&written), file.sha256);
+        }
+        let reports = reports.into_inner().unwrap();
+        assert_eq!(
+            reports
+                .iter()
+                .map(|report| report.processed)
+                .collect::<Vec<_>>(),
+            (1..=16).collect::<Vec<_>>()
+        );
+        assert_eq!(reports.last().unwrap().bytes_processed, total_bytes);
+        let checksums = fs::read_to_string(root.join("ok").join(CHECKSUMS_FILE_NAME)).unwrap();
+        assert_eq!(checksums.lines().count(), 16);
+
+        context.files[3].sha256 = sha256_hex(b"tampered");
+        context.files[11].sha256 = sha256_hex(b"tampered");
+        let error = stage_version_export(&context, &root.join("bad"), true, 0, &read_file, None)
+            .unwrap_err()
+            .user_message();
+        assert_eq!(error, "Stored file hash mismatch for part-03.lsp.");
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn password_export_encrypts_every_entry() {
+        let root = create_temp_dir("zip-password").unwrap();
+        let staging = root.join("src");
+        std::fs::create_dir_all(staging.join("files")).unwrap();
+        std::fs::write(staging.join("manifest.json"), "{}").unwrap();
+        std::fs::write(staging.join("instructions.md"), "# install").unwrap();
+        std::fs::write(staging.join("files").join("layfix.lsp"), "(princ)").unwrap();
+
+        let zip_path = root.join("export.zip");
+        let options = ExportOptions {
+            password: Some("s3cret routines".to_string()),
+            ..ExportOptions::default()
+        };
+        write_archive(&staging, &zip_path, &options, 0).unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&zip_path).unwrap()).unwrap();
+        for index in 0..archive.len() {
+            assert!(archive.by_index_raw(index).unwrap().encrypted());
+        }
+        drop(archive);
+        let raw = std::fs::read(&zip_path).unwrap();
+        assert!(!raw.windows(9).any(|window| window == b"# install"));
+
+        assert!(import_single_zip(
+            zip_path.to_string_lossy().as_ref(),
+            &ImportOptions::default(),
+            None,
+        )
+        .unwrap_err()
+        .user_message()
+        .contains("a password is required"));
+
+        for (password, format, message) in [
+            ("  ", ArchiveFormat::Zip, "password cannot be empty"),
+            ("s3cret", ArchiveFormat::TarGz, "must use the zip format"),
+        ] {
+            let error = write_archive(
+                &staging,
+                &root.join("rejected"),
+                &ExportOptions {
+                    format,
+                    password: Some(password.to_string()),
+                    ..ExportOptions::default()
+                },
+                0,
+            )
+            .unwrap_err();
+            assert!(matches!(error, ToolsError::Validation(_)));
+            assert!(error.user_message().contains(message));
+        }
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn password_import_round_trips_and_rejects_wrong_or_missing_password() {
+        let root = create_temp_dir("zip-password-import").unwrap();
+        let staging = root.join("src");
+        std::fs::create_dir_all(staging.join("files")).unwrap();
+        let manifest = ToolExportManifest {
+            schema_version: MANIFEST_SCHEMA_VERSION,
+            assets: Vec::new(),
+            tool: ManifestTool {
+                name: "CAD Toolset".to_string(),
+                slug: "cad-toolset".to_string(),
+                description: "CAD helpers".to_string(),
+                category: "cad".to_string(),
+                tags: Vec::new(),
+            },
+            version: ManifestVersion {
+                version: "1.0.0".to_string(),
+                changelog_md: None,
+                exported_at: None,
+            },
+            files: vec![ManifestFile {
+                original_name: "layfix.lsp".to_string(),
+                sha256: sha256_hex(b"(princ)"),
+                size_bytes: 7,
+                relative_path: "files/layfix.lsp".to_string(),
+                note: None,
+            }],
+        };
+        std::fs::write(
+            staging.join("manifest.json"),
+            serde_json::to_string_pretty(&manifest).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(staging.join("instructions.md"), "# install").unwrap();
+        std::fs::write(staging.join("files").join("layfix.lsp"), "(princ)").unwrap();
+
+        let zip_path = root.join("export.zip");
+        write_archive(
+            &staging,
+            &zip_path,
+            &ExportOptions {
+                password: Some("s3cret routines".to_string()),
+                ..ExportOptions::default()
+            },
+            0,
+        )
+        .unwrap();
+
+        let import = |password: Option<&str>| {
+            import_single_zip(
+                zip_path.to_string_lossy().as_ref(),
+                &ImportOptions {
+                    password: password.map(str::to_string),
+                    ..ImportOptions::default()
+                },
+                None,
+            )
+        };
+
+        let parsed = import(Some("s3cret routines")).unwrap();
+        assert_eq!(parsed.metadata.slug.as_deref(), Some("cad-toolset"));
+        assert_eq!(parsed.files[0].bytes, b"(princ)");
+
+        for (password, message) in [
+            (None, "Archive is encrypted; a password is required."),
+            (Some(" "), "Archive is encrypted; a password is required."),
+            (Some("wrong"), "The archive password is incorrect."),
+        ] {
+            let error = import(password).unwrap_err();
+            assert!(matches!(error, ToolsError::Validation(_)), "{password:?}");
+            assert_eq!(error.user_message(), message);
+        }
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn manifest_integrity_rejects_sha_mismatch() {
+        let root = create_temp_dir("manifest-integrity").unwrap();
+        let staging = root.join("src");
+        std::fs::create_dir_all(staging.join("files")).unwrap();
+
+        let manifest = ToolExportManifest {
+            schema_version: MANIFEST_SCHEMA_VERSION,
+            assets: Vec::new(),
+            tool: ManifestTool {
+                name: "CAD Toolset".to_string(),
+                slug: "cad-toolset".to_string(),
+                description: "CAD helpers".to_string(),
+                category: "cad".to_string(),
+                tags: vec!["autocad".to_string()],
+            },
+            version: ManifestVersion {
+                version: "1.0.0".to_string(),
+                changelog_md: None,
+                exported_at: None,
+            },
+            files: vec![ManifestFile {
+                original_name: "install.scr".to_string(),
+                sha256: "deadbeef".to_string(),
+                size_bytes: 3,
+                relative_path: "files/install.scr".to_string(),
+                note: None,
+            }],
+        };
+
+        std::fs::write(
+            staging.join("manifest.json"),
+            serde_json::to_string_pretty(&manifest).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(staging.join("instructions.md"), "# install").unwrap();
+        std::fs::write(staging.join("files").join("install.scr"), b"abc").unwrap();
+
+        let zip_path = root.join("archive.zip");
+        compress_directory_to_zip(&staging, &zip_path, CompressionLevel::Default).unwrap();
+
+        let error = import_single_zip(
+            zip_path.to_string_lossy().as_ref(),
+            &ImportOptions::default(),
+            None,
+        )
+        .unwrap_err();
+        assert!(error.user_message().contains("SHA256 mismatch"));
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn collected_file_problems_report_every_broken_file() {
+        let root = create_temp_dir("manifest-problems").unwrap();
+        let manifest_file = |name: &str, sha256: String, size_bytes: u64| ManifestFile {
+            original_name: name.to_string(),
+            sha256,
+            size_bytes,
+            relative_path: format!("files/{name}"),
+            note: None,
+        };
+        let manifest = ToolExportManifest {
+            schema_version: MANIFEST_SCHEMA_VERSION,
+            assets: Vec::new(),
+            tool: ManifestTool {
+                name: "CAD Toolset".to_string(),
+                slug: "cad-toolset".to_string(),
+                description: "CAD helpers".to_string(),
+                category: "cad".to_string(),
+                tags: Vec::new(),
+            },
+            version: ManifestVersion {
+                version: "1.0.0".to_string(),
+                changelog_md: None,
+                exported_at: None,
+            },
+            files: vec![
+                manifest_file("install.scr", sha256_hex(b"zoom e"), 3),
+                manifest_file("layfix.lsp", sha256_hex(b"(princ)"), 7),
+                manifest_file("menu.json", sha256_hex(b"{}"), 2),
+            ],
+        };
+        let zip_path = root.join("broken.zip");
+        write_zip_with_entries(
+            &zip_path,
+            vec![
+                (
+                    "manifest.json".to_string(),
+                    serde_json::to_vec(&manifest).unwrap(),
+                ),
+                ("instructions.md".to_string(), b"# install".to_vec()),
+                ("files/install.scr".to_string(), b"abc".to_vec()),
+                ("files/layfix.lsp".to_string(), b"(PRINC)".to_vec()),
+                ("files/menu.json".to_string(), b"{}".to_vec()),
+            ],
+        );
+        let zip_path = zip_path.to_string_lossy().to_string();
+
+        let fast_fail = import_single_zip(&zip_path, &ImportOptions::default(), None).unwrap_err();
+        assert_eq!(fast_fail.user_message(), "SHA256 mismatch for install.scr.");
+
+        let options = ImportOptions {
+            collect_file_problems: true,
+            ..ImportOptions::default()
+        };
+        let parsed = import_single_zip(&zip_path, &options, None).unwrap();
+        assert_eq!(
+            parsed.file_problems,
+            vec![
+                "SHA256 mismatch for install.scr.".to_string(),
+                "SHA256 mismatch for layfix.lsp.".to_string(),
+            ]
+        );
+        assert_eq!(parsed.files.len(), 1);
+        let error = parsed.ensure_no_file_problems().unwrap_err();
+        assert!(matches!(error, ToolsError::Validation(_)));
+        assert_eq!(
+            error.user_message(),
+            "2 problems found in the archive: SHA256 mismatch for install.scr. \
+             SHA256 mismatch for layfix.lsp."
+        );
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn manifest_assets_round_trip_and_reject_non_images() {
+        let icon = b"\x89PNG\r\n\x1a\nicon".to_vec();
+        let context = test_export_context(&[]);
+        let mut manifest = build_manifest(&context, 0).unwrap();
+        manifest.assets.push(ManifestAsset {
+            relative_path: "assets/icon.png".to_string(),
+            sha256: sha256_hex(&icon),
+            size_bytes: icon.len() as u64,
+        });
+
+        let root = create_temp_dir("zip-assets").unwrap();
+        let archive = |name: &str, manifest: &ToolExportManifest, asset_path: &str| {
+            let zip_path = root.join(name);
+            write_zip_with_entries(
+                &zip_path,
+                vec![
+                    (
+                        "manifest.json".to_string(),
+                        serde_json::to_vec(manifest).unwrap(),
+                    ),
+                    ("instructions.md".to_string(), b"# install".to_vec()),
+                    ("files/".to_string(), Vec::new()),
+                    (asset_path.to_string(), icon.clone()),
+                ],
+            );
+            import_single_zip(
+                zip_path.to_string_lossy().as_ref(),
+                &ImportOptions::default(),
+                None,
+            )
+        };
+
+        let parsed = archive("icon.zip", &manifest, "assets/icon.png").unwrap();
+        assert_eq!(parsed.assets.len(), 1);
+        assert_eq!(parsed.assets[0].name, "icon.png");
+        assert_eq!(parsed.assets[0].bytes, icon);
+        assert_eq!(parsed.assets[0].sha256, sha256_hex(&icon));
+
+        manifest.assets[0].relative_path = "assets/setup.exe".to_string();
+        let error = archive("exe.zip", &manifest, "assets/setup.exe").unwrap_err();
+        assert!(error
+            .user_message()
+            .contains("Asset setup.exe is not an allowed image type"));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn verify_reports_tampered_files_exactly_like_import() {
+        let manifest = ToolExportManifest {
+            schema_version: MANIFEST_SCHEMA_VERSION,
+            assets: Vec::new(),
+            tool: ManifestTool {
+                name: "CAD Toolset".to_string(),
+                slug: "cad-toolset".to_string(),
+                description: "CAD helpers".to_string(),
+                category: "cad".to_string(),
+                tags: Vec::new(),
+            },
+            version: ManifestVersion {
+                version: "1.0.0".to_string(),
+                changelog_md: None,
+                exported_at: Some(1_709_649_010_000),
+            },
+            files: vec![ManifestFile {
+                original_name: "layfix.lsp".to_string(),
+                sha256: sha256_hex(b"(princ)"),
+                size_bytes: 7,
+                relative_path: "files/layfix.lsp".to_string(),
+                note: None,
+            }],
+        };
+        let root = create_temp_dir("zip-verify").unwrap();
+        let archive = |name: &str, contents: &[u8]| {
+            let zip_path = root.join(name);
+            write_zip_with_entries(
+                &zip_path,
+                vec![
+                    (
+                        "manifest.json".to_string(),
+                        serde_json::to_vec(&manifest).unwrap(),
+                    ),
+                    ("instructions.md".to_string(), b"# install".to_vec()),
+                    ("files/layfix.lsp".to_string(), contents.to_vec()),
+                ],
+            );
+            zip_path.to_string_lossy().to_string()
+        };
+        let options = ImportOptions::default();
+
+        let preview = verify_tool_zip(&archive("good.zip", b"(princ)"), &options).unwrap();
+        assert_eq!(preview.token, None);
+        assert_eq!(preview.slug, "cad-toolset");
+        assert_eq!(preview.files[0].sha256, sha256_hex(b"(princ)"));
+        assert_eq!(preview.total_size_bytes, 7);
+        assert_eq!(preview.exported_at, Some(1_709_649_010_000));
+
+        let tampered = archive("tampered.zip", b"(PRINC)");
+        let verify_error = verify_tool_zip(&tampered, &options).unwrap_err();
+        let import_error = import_single_zip(&tampered, &options, None).unwrap_err();
+        assert!(matches!(verify_error, ToolsError::Validation(_)));
+        assert_eq!(verify_error.user_message(), import_error.user_message());
+        assert_eq!(
+            verify_error.user_message(),
+            "SHA256 mismatch for layfix.lsp."
+        );
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn explicit_staging_root_holds_import_scratch_space() {
+        let root = create_temp_dir("staging-root").unwrap();
+        let staging_root = root.join("staging");
+        fs::create_dir_all(&staging_root).unwrap();
+        assert_eq!(resolve_staging_root(Some(&staging_root)), staging_root);
+        assert_eq!(
+            resolve_staging_root(Some(&root.join("missing"))),
+            std::env::temp_dir()
+        );
+        assert_eq!(resolve_staging_root(None), std::env::temp_dir());
+
+        let manifest = ToolExportManifest {
+            schema_version: MANIFEST_SCHEMA_VERSION,
+            assets: Vec::new(),
+            tool: ManifestTool {
+                name: "CAD Toolset".to_string(),
+                slug: "cad-toolset".to_string(),
+                description: "CAD helpers".to_string(),
+                category: "cad".to_string(),
+                tags: Vec::new(),
+            },
+            version: ManifestVersion {
+                version: "1.0.0".to_string(),
+                changelog_md: None,
+                exported_at: None,
+            },
+            files: vec![ManifestFile {
+                original_name: "layfix.lsp".to_string(),
+                sha256: sha256_hex(b"(princ)"),
+                size_bytes: 7,
+                relative_path: "files/layfix.lsp".to_string(),
+                note: None,
+            }],
+        };
+        let zip_path = root.join("tool.zip");
+        write_zip_with_entries(
+            &zip_path,
+            vec![
+                (
+                    "manifest.json".to_string(),
+                    serde_json::to_vec(&manifest).unwrap(),
+                ),
+                ("instructions.md".to_string(), b"# install".to_vec()),
+                ("files/layfix.lsp".to_string(), b"(princ)".to_vec()),
+            ],
+        );
+
+        let staged = std::cell::RefCell::new(Vec::new());
+        let record = |_: TransferProgress| {
+            for entry in fs::read_dir(&staging_root).unwrap() {
+                staged
+                    .borrow_mut()
+                    .push(entry.unwrap().file_name().to_string_lossy().to_string());
+            }
+        };
+        let options = ImportOptions {
+            staging_root: Some(staging_root.clone()),
+            ..ImportOptions::default()
+        };
+        import_single_zip(&zip_path.to_string_lossy(), &options, Some(&record)).unwrap();
+        assert!(staged
+            .borrow()
+            .iter()
+            .any(|name| name.starts_with("tool-import-")));
+        assert_eq!(fs::read_dir(&staging_root).unwrap().count(), 0);
 
-fn normalize_destination(raw: &str) -> ToolsResult<PathBuf> {
-    let trimmed = raw.trim();
-    if trimmed.is_empty() {
-        return Err(ToolsError::Validation(
-            "Destination path is required.".to_string(),
-        ));
+        let _ = fs::remove_dir_all(root);
     }
 
-    let mut destination = PathBuf::from(trimmed);
-    if destination.extension().is_none() {
-        destination.set_extension("zip");
-    }
+    #[test]
+    fn failed_import_is_quarantined_only_when_asked() {
+        let root = create_temp_dir("quarantine").unwrap();
+        let staging_root = root.join("staging");
+        let quarantine_root = root.join("quarantine");
+        fs::create_dir_all(&staging_root).unwrap();
+        let zip_path = root.join("broken.zip");
+        write_zip_with_entries(
+            &zip_path,
+            vec![
+                ("manifest.json".to_string(), b"{}".to_vec()),
+                ("files/layfix.lsp".to_string(), b"(princ)".to_vec()),
+            ],
+        );
+        let zip_path = zip_path.to_string_lossy().to_string();
 
-    if let Some(parent) = destination.parent() {
-        if !parent.as_os_str().is_empty() {
-            fs::create_dir_all(parent)?;
-        }
-    }
+        let options = ImportOptions {
+            staging_root: Some(staging_root.clone()),
+            ..ImportOptions::default()
+        };
+        import_tool_zip(&zip_path, &options, None).unwrap_err();
+        assert_eq!(fs::read_dir(&staging_root).unwrap().count(), 0);
+        assert!(!quarantine_root.exists());
 
-    Ok(destination)
-}
+        let options = ImportOptions {
+            quarantine_root: Some(quarantine_root.clone()),
+            ..options
+        };
+        let error = import_tool_zip(&zip_path, &options, None).unwrap_err();
+        assert_eq!(fs::read_dir(&staging_root).unwrap().count(), 0);
+        let kept = fs::read_dir(&quarantine_root)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        assert_eq!(kept.len(), 1);
+        assert!(kept[0].join("files/layfix.lsp").is_file());
+        assert!(error.user_message().ends_with(&format!(
+            "The extracted files were kept in {}.",
+            kept[0].display()
+        )));
 
-fn compress_directory_to_zip(source_dir: &Path, destination_zip: &Path) -> ToolsResult<()> {
-    let source = ps_quote(source_dir.to_string_lossy().as_ref());
-    let destination = ps_quote(destination_zip.to_string_lossy().as_ref());
-    let script = format!(
-    "$ErrorActionPreference='Stop'; $src={source}; $dest={destination}; if (Test-Path -LiteralPath $dest) {{ Remove-Item -LiteralPath $dest -Force }}; Compress-Archive -Path (Join-Path $src '*') -DestinationPath $dest -CompressionLevel Optimal"
-  );
+        assert_eq!(
+            purge_quarantine(&quarantine_root, Duration::from_secs(3600)).unwrap(),
+            0
+        );
+        assert_eq!(
+            purge_quarantine(&quarantine_root, Duration::ZERO).unwrap(),
+            1
+        );
+        assert_eq!(fs::read_dir(&quarantine_root).unwrap().count(), 0);
+        assert_eq!(
+            purge_quarantine(&root.join("missing"), Duration::ZERO).unwrap(),
+            0
+        );
 
-    run_powershell(&script).map(|_| ())
-}
+        let _ = fs::remove_dir_all(root);
+    }
 
-fn extract_zip_safely(zip_path: &Path, destination_dir: &Path) -> ToolsResult<()> {
-    let zip = ps_quote(zip_path.to_string_lossy().as_ref());
-    let destination = ps_quote(destination_dir.to_string_lossy().as_ref());
-    let script = format!(
-    "$ErrorActionPreference='Stop'; Add-Type -AssemblyName System.IO.Compression.FileSystem; $zipPath={zip}; $dest={destination}; if (Test-Path -LiteralPath $dest) {{ Remove-Item -LiteralPath $dest -Recurse -Force }}; New-Item -ItemType Directory -Path $dest | Out-Null; $archive=[System.IO.Compression.ZipFile]::OpenRead($zipPath); try {{ $destRoot=[System.IO.Path]::GetFullPath($dest + [System.IO.Path]::DirectorySeparatorChar); foreach ($entry in $archive.Entries) {{ $name=$entry.FullName; if ([string]::IsNullOrWhiteSpace($name)) {{ throw ('Unsafe zip entry path: ' + $name) }}; if ($name.StartsWith('/') -or $name.StartsWith('\\')) {{ throw ('Unsafe zip entry path: ' + $name) }}; if ($name.Contains('..')) {{ throw ('Unsafe zip entry path: ' + $name) }}; if ($name.Contains(':')) {{ throw ('Unsafe zip entry path: ' + $name) }}; $target=[System.IO.Path]::GetFullPath((Join-Path $dest $name)); if (-not $target.StartsWith($destRoot, [System.StringComparison]::OrdinalIgnoreCase)) {{ throw ('Unsafe zip entry path: ' + $name) }}; if ($name.EndsWith('/') -or $name.EndsWith('\\')) {{ [System.IO.Directory]::CreateDirectory($target) | Out-Null; continue }}; $parent=[System.IO.Path]::GetDirectoryName($target); if ($parent) {{ [System.IO.Directory]::CreateDirectory($parent) | Out-Null }}; [System.IO.Compression.ZipFileExtensions]::ExtractToFile($entry, $target, $true) }} }} finally {{ $archive.Dispose() }}"
-  );
+    #[test]
+    fn archive_root_unwraps_a_single_top_level_folder() {
+        let root = create_temp_dir("archive-root").unwrap();
 
-    run_powershell(&script).map(|_| ())
-}
+        let flat = root.join("flat");
+        fs::create_dir_all(&flat).unwrap();
+        fs::write(flat.join("manifest.json"), "{}").unwrap();
+        assert_eq!(resolve_archive_root(&flat).unwrap(), flat);
 
-fn collect_relative_files(root: &Path) -> ToolsResult<Vec<String>> {
-    let mut entries = Vec::new();
-    collect_recursive(root, root, &mut entries)?;
-    Ok(entries)
-}
+        let wrapped = root.join("wrapped");
+        fs::create_dir_all(wrapped.join("cad-toolset")).unwrap();
+        fs::write(wrapped.join("cad-toolset").join("manifest.json"), "{}").unwrap();
+        assert_eq!(
+            resolve_archive_root(&wrapped).unwrap(),
+            wrapped.join("cad-toolset")
+        );
 
-fn collect_recursive(root: &Path, current: &Path, entries: &mut Vec<String>) -> ToolsResult<()> {
-    for entry in fs::read_dir(current)? {
-        let entry = entry.map_err(|error| {
-            ToolsError::Zip(format!("Failed to read archive extraction entry: {error}"))
-        })?;
-        let path = entry.path();
+        let ambiguous = root.join("ambiguous");
+        fs::create_dir_all(ambiguous.join("cad-toolset")).unwrap();
+        fs::write(ambiguous.join("cad-toolset").join("manifest.json"), "{}").unwrap();
+        fs::write(ambiguous.join("stray.txt"), "x").unwrap();
+        assert!(resolve_archive_root(&ambiguous)
+            .unwrap_err()
+            .user_message()
+            .contains("doesn't look like a Korda tool archive"));
 
-        if path.is_dir() {
-            collect_recursive(root, &path, entries)?;
-            continue;
-        }
+        let empty = root.join("empty");
+        fs::create_dir_all(&empty).unwrap();
+        assert!(resolve_archive_root(&empty)
+            .unwrap_err()
+            .user_message()
+            .contains("archive is empty"));
 
-        let relative = path
-            .strip_prefix(root)
-            .map_err(|error| {
-                ToolsError::Zip(format!(
-                    "Failed to compute relative extraction path: {error}"
-                ))
-            })?
-            .to_string_lossy()
-            .replace('\\', "/");
+        let _ = fs::remove_dir_all(root);
+    }
 
-        entries.push(relative);
+    #[test]
+    fn empty_files_folder_is_reported_as_a_whole() {
+        let metadata_only = vec!["manifest.json".to_string(), "instructions.md".to_string()];
+        let error = ensure_files_extracted(2, &metadata_only).unwrap_err();
+        assert!(error.user_message().contains("metadata-only or corrupt"));
+        assert!(error.user_message().contains("lists 2 file(s)"));
+
+        assert!(ensure_files_extracted(0, &metadata_only).is_ok());
+        let partial = vec!["manifest.json".to_string(), "files/a.lsp".to_string()];
+        assert!(ensure_files_extracted(2, &partial).is_ok());
     }
 
-    Ok(())
-}
+    #[test]
+    fn unknown_manifest_fields_warn_or_reject_by_mode() {
+        let raw = r#"{
+            "tool": {"name": "CAD", "slug": "cad", "description": "d", "category": "cad",
+                     "tags": [], "color": "red"},
+            "version": {"version": "1.0.0", "changelogMd": null},
+            "files": [{"originalName": "a.lsp", "sha256": "aa", "sizeBytes": 1,
+                       "relativePath": "files/a.lsp", "signature": "x"}],
+            "generator": "korda 9"
+        }"#;
 
-fn create_temp_dir(prefix: &str) -> ToolsResult<PathBuf> {
-    let temp_root = std::env::temp_dir();
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_err(|error| ToolsError::Io(format!("System clock error: {error}")))?
-        .as_millis();
-    let path = temp_root.join(format!("{prefix}-{now}-{}", Uuid::new_v4()));
-    fs::create_dir_all(&path)?;
-    Ok(path)
-}
+        let (manifest, warnings) = parse_manifest(raw, ContentCheckMode::Warn).unwrap();
+        assert_eq!(manifest.tool.slug, "cad");
+        assert_eq!(warnings.len(), 1);
+        assert!(
+            warnings[0].contains("files[0].signature, generator, tool.color"),
+            "{}",
+            warnings[0]
+        );
 
-fn run_powershell(script: &str) -> ToolsResult<String> {
-    let output = Command::new("powershell")
-        .args([
-            "-NoProfile",
-            "-NonInteractive",
-            "-ExecutionPolicy",
-            "Bypass",
-            "-Command",
-            script,
-        ])
-        .output()
-        .map_err(|error| ToolsError::Zip(format!("Failed to execute archive command: {error}")))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        let details = if !stderr.is_empty() { stderr } else { stdout };
-        return Err(ToolsError::Zip(if details.is_empty() {
-            "Archive command failed.".to_string()
-        } else {
-            details
-        }));
+        assert!(parse_manifest(raw, ContentCheckMode::Off)
+            .unwrap()
+            .1
+            .is_empty());
+        let error = parse_manifest(raw, ContentCheckMode::Reject).unwrap_err();
+        assert!(error.user_message().contains("unexpected fields"));
     }
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
-}
+    #[test]
+    fn manifest_schema_version_defaults_to_one_and_rejects_newer_formats() {
+        let body = r#""tool": {"name": "CAD", "slug": "cad", "description": "d", "category": "cad", "tags": []},
+            "version": {"version": "1.0.0", "changelogMd": null},
+            "files": []"#;
 
-fn ps_quote(value: &str) -> String {
-    format!("'{}'", value.replace('\'', "''"))
-}
+        let (current, warnings) = parse_manifest(
+            &format!("{{\"schemaVersion\": 1, {body}}}"),
+            ContentCheckMode::Reject,
+        )
+        .unwrap();
+        assert_eq!(current.schema_version, MANIFEST_SCHEMA_VERSION);
+        assert!(warnings.is_empty());
 
-fn validate_required(field: &str, value: &str, max_len: usize) -> ToolsResult<String> {
-    let trimmed = value.trim();
-    if trimmed.is_empty() {
-        return Err(ToolsError::Validation(format!("{field} is required.")));
-    }
-    if trimmed.len() > max_len {
-        return Err(ToolsError::Validation(format!(
-            "{field} exceeds maximum length ({max_len})."
-        )));
-    }
-    Ok(trimmed.to_string())
-}
+        let (absent, _) = parse_manifest(&format!("{{{body}}}"), ContentCheckMode::Reject).unwrap();
+        assert_eq!(absent.schema_version, 1);
 
-fn normalize_optional_text(value: Option<String>, max_len: usize) -> ToolsResult<Option<String>> {
-    let Some(raw) = value else {
-        return Ok(None);
-    };
+        let future = format!("{{\"schemaVersion\": 2, {body}, \"signatures\": []}}");
+        let error = parse_manifest(&future, ContentCheckMode::Off).unwrap_err();
+        assert!(matches!(error, ToolsError::Validation(_)));
+        assert!(error.user_message().contains(
+            "uses format version 2, but this version of Korda Tools reads up to version 1"
+        ));
 
-    let trimmed = raw.trim();
-    if trimmed.is_empty() {
-        return Ok(None);
+        let root = create_temp_dir("zip-schema-version").unwrap();
+        let zip_path = root.join("future.zip");
+        write_zip_with_entries(
+            &zip_path,
+            vec![
+                ("manifest.json".to_string(), future.into_bytes()),
+                ("instructions.md".to_string(), b"# install".to_vec()),
+            ],
+        );
+        let error = import_single_zip(
+            zip_path.to_string_lossy().as_ref(),
+            &ImportOptions::default(),
+            None,
+        )
+        .unwrap_err();
+        assert!(error.user_message().contains("uses format version 2"));
+        let _ = fs::remove_dir_all(root);
     }
 
-    if trimmed.len() > max_len {
-        return Err(ToolsError::Validation(format!(
-            "Text exceeds maximum length ({max_len})."
-        )));
-    }
+    #[test]
+    fn exported_at_round_trips_and_is_optional_on_import() {
+        let context = test_export_context(&[]);
+        let manifest = build_manifest(&context, 1_709_649_010_000).unwrap();
+        let root = create_temp_dir("zip-exported-at").unwrap();
 
-    Ok(Some(trimmed.to_string()))
-}
+        let mut imported = Vec::new();
+        let legacy = r#"{"tool": {"name": "CAD Toolset", "slug": "cad-toolset", "description": "CAD helpers", "category": "cad", "tags": []},
+            "version": {"version": "1.0.0", "changelogMd": null},
+            "files": []}"#;
+        for (name, manifest_json) in [
+            ("current", serde_json::to_string(&manifest).unwrap()),
+            ("legacy", legacy.to_string()),
+        ] {
+            assert_eq!(
+                manifest_json.contains("\"exportedAt\":1709649010000"),
+                name == "current"
+            );
+            let zip_path = root.join(format!("{name}.zip"));
+            write_zip_with_entries(
+                &zip_path,
+                vec![
+                    ("manifest.json".to_string(), manifest_json.into_bytes()),
+                    ("instructions.md".to_string(), b"# install".to_vec()),
+                    ("files/".to_string(), Vec::new()),
+                ],
+            );
+            let options = ImportOptions {
+                unknown_fields: ContentCheckMode::Reject,
+                ..ImportOptions::default()
+            };
+            imported.push(
+                import_single_zip(zip_path.to_string_lossy().as_ref(), &options, None)
+                    .unwrap()
+                    .exported_at,
+            );
+        }
+        assert_eq!(imported, vec![Some(1_709_649_010_000), None]);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let _ = fs::remove_dir_all(root);
+    }
 
     #[test]
-    fn generates_manifest_from_export_context() {
-        let context = ExportVersionContext {
-            tool: super::super::db::ToolMetadataExport {
-                name: "CAD Toolset".to_string(),
-                slug: "cad-toolset".to_string(),
-                description: "CAD helpers".to_string(),
-                category: "cad".to_string(),
-                tags: vec!["autocad".to_string()],
-            },
-            version: super::super::db::VersionExport {
-                id: "version-1".to_string(),
-                tool_id: "tool-1".to_string(),
-                version: "1.0.0".to_string(),
-                changelog_md: Some("Initial release".to_string()),
-                instructions_md: "# install".to_string(),
-            },
-            files: vec![super::super::db::ToolFileDetail {
-                id: "file-1".to_string(),
-                original_name: "install.scr".to_string(),
-                stored_rel_path: "tools/tool-1/version-1/files/install.scr".to_string(),
-                sha256: "abc".to_string(),
-                size_bytes: 100,
-                mime: None,
-                created_at: 0,
-            }],
-        };
+    fn empty_and_foreign_files_are_rejected_before_extraction() {
+        let root = create_temp_dir("zip-signature").unwrap();
+        let cases: [(&str, &[u8], Result<ArchiveFormat, &str>); 5] = [
+            ("empty.zip", b"", Err("file is empty")),
+            (
+                "no-entries.zip",
+                b"PK\x05\x06\0\0\0\0",
+                Err("contains no files"),
+            ),
+            (
+                "notes.zip",
+                b"just some text",
+                Err("not a zip or tar.gz archive"),
+            ),
+            ("tool.zip", b"PK\x03\x04rest", Ok(ArchiveFormat::Zip)),
+            (
+                "tool.tar.gz",
+                b"\x1f\x8b\x08\0rest",
+                Ok(ArchiveFormat::TarGz),
+            ),
+        ];
+        for (name, bytes, expected) in cases {
+            let path = root.join(name);
+            fs::write(&path, bytes).unwrap();
+            let result = sniff_archive_format(&path);
+            match expected {
+                Err(message) => assert!(
+                    result.unwrap_err().user_message().contains(message),
+                    "{name}"
+                ),
+                Ok(format) => assert_eq!(result.unwrap(), format, "{name}"),
+            }
+        }
 
-        let manifest = build_manifest(&context).unwrap();
-        assert_eq!(manifest.tool.slug, "cad-toolset");
-        assert_eq!(manifest.files.len(), 1);
-        assert_eq!(manifest.files[0].relative_path, "files/install.scr");
+        fs::write(root.join("manifest.json"), [0xff, 0xfe, b'{']).unwrap();
+        assert!(read_manifest_text(&root.join("manifest.json"))
+            .unwrap_err()
+            .user_message()
+            .contains("saved as UTF-16"));
+        assert!(decode_manifest_bytes(b"{\"name\": \"Caf\xe9\"}".to_vec())
+            .unwrap_err()
+            .user_message()
+            .contains("byte 13 is not valid UTF-8"));
+        assert_eq!(
+            decode_manifest_bytes(b"\xef\xbb\xbf{}".to_vec()).unwrap(),
+            "{}"
+        );
+
+        let _ = fs::remove_dir_all(root);
     }
 
     #[test]
-    fn zip_round_trip_preserves_manifest_shape() {
-        let root = create_temp_dir("zip-roundtrip").unwrap();
-        let staging = root.join("src");
-        std::fs::create_dir_all(staging.join("files")).unwrap();
+    fn payload_decodes_to_file_and_rejects_bad_encoding() {
+        let root = create_temp_dir("payload-decode").unwrap();
+        let bytes = (0..=255u8)
+            .cycle()
+            .take(4 * 1024 * 1024 + 3)
+            .collect::<Vec<_>>();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&bytes);
+
+        let target = root.join("payload.zip");
+        assert_eq!(
+            decode_base64_to_file(&encoded, &target).unwrap(),
+            bytes.len() as u64
+        );
+        assert_eq!(fs::read(&target).unwrap(), bytes);
 
+        let error = decode_base64_to_file("not base64!", &root.join("bad.zip")).unwrap_err();
+        assert!(error
+            .user_message()
+            .contains("Invalid zip payload encoding"));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn checksums_file_round_trips_and_cross_checks_manifest() {
+        let hash = sha256_hex(b"abc");
         let manifest = ToolExportManifest {
+            schema_version: MANIFEST_SCHEMA_VERSION,
+            assets: Vec::new(),
             tool: ManifestTool {
                 name: "CAD Toolset".to_string(),
                 slug: "cad-toolset".to_string(),
                 description: "CAD helpers".to_string(),
                 category: "cad".to_string(),
-                tags: vec!["autocad".to_string()],
+                tags: Vec::new(),
             },
             version: ManifestVersion {
                 version: "1.0.0".to_string(),
                 changelog_md: None,
+                exported_at: None,
             },
             files: vec![ManifestFile {
                 original_name: "install.scr".to_string(),
-                sha256: sha256_hex(b"abc"),
+                sha256: hash.clone(),
                 size_bytes: 3,
                 relative_path: "files/install.scr".to_string(),
+                note: None,
             }],
         };
 
-        std::fs::write(
-            staging.join("manifest.json"),
-            serde_json::to_string_pretty(&manifest).unwrap(),
-        )
-        .unwrap();
-        std::fs::write(staging.join("instructions.md"), "# install").unwrap();
-        std::fs::write(staging.join("files").join("install.scr"), b"abc").unwrap();
-
-        let zip_path = root.join("archive.zip");
-        compress_directory_to_zip(&staging, &zip_path).unwrap();
-
-        let extracted = create_temp_dir("zip-roundtrip-extracted").unwrap();
-        extract_zip_safely(&zip_path, &extracted).unwrap();
+        let raw = build_checksums_file(&manifest);
+        assert_eq!(raw, format!("{hash}  files/install.scr\n"));
+        assert!(
+            cross_check_checksums(&parse_checksums_file(&raw).unwrap(), &manifest.files).is_ok()
+        );
 
-        let parsed: ToolExportManifest = serde_json::from_str(
-            &std::fs::read_to_string(extracted.join("manifest.json")).unwrap(),
+        let binary_mode = format!("{hash} *files/install.scr\n");
+        assert!(cross_check_checksums(
+            &parse_checksums_file(&binary_mode).unwrap(),
+            &manifest.files
         )
-        .unwrap();
-        assert_eq!(parsed.tool.slug, manifest.tool.slug);
-        assert_eq!(parsed.version.version, manifest.version.version);
-        assert_eq!(parsed.files[0].original_name, "install.scr");
+        .is_ok());
 
-        let _ = std::fs::remove_dir_all(root);
-        let _ = std::fs::remove_dir_all(extracted);
+        let wrong = format!("{}  files/install.scr\n", sha256_hex(b"other"));
+        assert!(
+            cross_check_checksums(&parse_checksums_file(&wrong).unwrap(), &manifest.files).is_err()
+        );
+        let extra = format!("{raw}{hash}  files/extra.scr\n");
+        assert!(
+            cross_check_checksums(&parse_checksums_file(&extra).unwrap(), &manifest.files).is_err()
+        );
+        assert!(parse_checksums_file("not-a-hash  files/install.scr").is_err());
     }
 
     #[test]
-    fn manifest_integrity_rejects_sha_mismatch() {
-        let root = create_temp_dir("manifest-integrity").unwrap();
+    fn trusted_manifest_import_skips_rehash() {
+        let root = create_temp_dir("manifest-trusted").unwrap();
         let staging = root.join("src");
         std::fs::create_dir_all(staging.join("files")).unwrap();
 
+        let declared_hash = sha256_hex(b"declared");
         let manifest = ToolExportManifest {
+            schema_version: MANIFEST_SCHEMA_VERSION,
+            assets: Vec::new(),
             tool: ManifestTool {
                 name: "CAD Toolset".to_string(),
                 slug: "cad-toolset".to_string(),
@@ -630,12 +4204,14 @@ mod tests {
             version: ManifestVersion {
                 version: "1.0.0".to_string(),
                 changelog_md: None,
+                exported_at: None,
             },
             files: vec![ManifestFile {
                 original_name: "install.scr".to_string(),
-                sha256: "deadbeef".to_string(),
+                sha256: declared_hash.clone(),
                 size_bytes: 3,
                 relative_path: "files/install.scr".to_string(),
+                note: None,
             }],
         };
 
@@ -648,9 +4224,25 @@ mod tests {
         std::fs::write(staging.join("files").join("install.scr"), b"abc").unwrap();
 
         let zip_path = root.join("archive.zip");
-        compress_directory_to_zip(&staging, &zip_path).unwrap();
+        compress_directory_to_zip(&staging, &zip_path, CompressionLevel::Default).unwrap();
+
+        let parsed = import_single_zip(
+            zip_path.to_string_lossy().as_ref(),
+            &ImportOptions {
+                trust_manifest: true,
+                ..ImportOptions::default()
+            },
+            None,
+        )
+        .unwrap();
+        assert_eq!(parsed.files[0].sha256, declared_hash);
 
-        let error = import_tool_zip(zip_path.to_string_lossy().as_ref()).unwrap_err();
+        let error = import_single_zip(
+            zip_path.to_string_lossy().as_ref(),
+            &ImportOptions::default(),
+            None,
+        )
+        .unwrap_err();
         assert!(error.user_message().contains("SHA256 mismatch"));
 
         let _ = std::fs::remove_dir_all(root);
@@ -663,6 +4255,8 @@ mod tests {
         std::fs::create_dir_all(staging.join("files")).unwrap();
 
         let manifest = ToolExportManifest {
+            schema_version: MANIFEST_SCHEMA_VERSION,
+            assets: Vec::new(),
             tool: ManifestTool {
                 name: "CAD Toolset".to_string(),
                 slug: "cad-toolset".to_string(),
@@ -673,6 +4267,7 @@ mod tests {
             version: ManifestVersion {
                 version: "1.0.0".to_string(),
                 changelog_md: None,
+                exported_at: None,
             },
             files: vec![
                 ManifestFile {
@@ -680,12 +4275,14 @@ mod tests {
                     sha256: sha256_hex(b"def"),
                     size_bytes: 3,
                     relative_path: "files/install.scr".to_string(),
+                    note: None,
                 },
                 ManifestFile {
                     original_name: "install.scr".to_string(),
                     sha256: sha256_hex(b"def"),
                     size_bytes: 3,
                     relative_path: "files/install.scr".to_string(),
+                    note: None,
                 },
             ],
         };
@@ -699,9 +4296,14 @@ mod tests {
         std::fs::write(staging.join("files").join("install.scr"), b"def").unwrap();
 
         let zip_path = root.join("archive.zip");
-        compress_directory_to_zip(&staging, &zip_path).unwrap();
+        compress_directory_to_zip(&staging, &zip_path, CompressionLevel::Default).unwrap();
 
-        let error = import_tool_zip(zip_path.to_string_lossy().as_ref()).unwrap_err();
+        let error = import_single_zip(
+            zip_path.to_string_lossy().as_ref(),
+            &ImportOptions::default(),
+            None,
+        )
+        .unwrap_err();
         assert!(
             error.user_message().contains("Duplicate file in manifest"),
             "unexpected error: {}",
@@ -725,6 +4327,8 @@ mod tests {
             let root = create_temp_dir("zip-slip").unwrap();
             let zip_path = root.join("payload.zip");
             let manifest_json = serde_json::to_string_pretty(&ToolExportManifest {
+                schema_version: MANIFEST_SCHEMA_VERSION,
+                assets: Vec::new(),
                 tool: ManifestTool {
                     name: "CAD Toolset".to_string(),
                     slug: "cad-toolset".to_string(),
@@ -735,12 +4339,14 @@ mod tests {
                 version: ManifestVersion {
                     version: "1.0.0".to_string(),
                     changelog_md: None,
+                    exported_at: None,
                 },
                 files: vec![ManifestFile {
                     original_name: "install.scr".to_string(),
                     sha256: sha256_hex(b"abc"),
                     size_bytes: 3,
                     relative_path: "files/install.scr".to_string(),
+                    note: None,
                 }],
             })
             .unwrap();
@@ -754,19 +4360,98 @@ mod tests {
                 ],
             );
 
-            let error = import_tool_zip(zip_path.to_string_lossy().as_ref()).unwrap_err();
+            let error = import_single_zip(
+                zip_path.to_string_lossy().as_ref(),
+                &ImportOptions::default(),
+                None,
+            )
+            .unwrap_err();
             assert!(
                 error.user_message().contains("Unsafe zip entry path")
                     || error.user_message().contains("Unexpected file in archive")
-                    || error
-                        .user_message()
-                        .contains("Failed to execute archive command")
             );
 
             let _ = std::fs::remove_dir_all(root);
         }
     }
 
+    #[test]
+    fn import_reports_progress_per_file_and_on_completion() {
+        let root = create_temp_dir("zip-progress").unwrap();
+        let staging = root.join("src");
+        std::fs::create_dir_all(staging.join("files")).unwrap();
+
+        let files = [
+            ("install.scr", b"zoom e".as_slice()),
+            ("layfix.lsp", b"(princ)"),
+        ];
+        let manifest = ToolExportManifest {
+            schema_version: MANIFEST_SCHEMA_VERSION,
+            assets: Vec::new(),
+            tool: ManifestTool {
+                name: "CAD Toolset".to_string(),
+                slug: "cad-toolset".to_string(),
+                description: "CAD helpers".to_string(),
+                category: "cad".to_string(),
+                tags: Vec::new(),
+            },
+            version: ManifestVersion {
+                version: "1.0.0".to_string(),
+                changelog_md: None,
+                exported_at: None,
+            },
+            files: files
+                .iter()
+                .map(|(name, bytes)| ManifestFile {
+                    original_name: name.to_string(),
+                    sha256: sha256_hex(bytes),
+                    size_bytes: bytes.len() as u64,
+                    relative_path: format!("files/{name}"),
+                    note: None,
+                })
+                .collect(),
+        };
+        std::fs::write(
+            staging.join("manifest.json"),
+            serde_json::to_string_pretty(&manifest).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(staging.join("instructions.md"), "# install").unwrap();
+        for (name, bytes) in files {
+            std::fs::write(staging.join("files").join(name), bytes).unwrap();
+        }
+        let zip_path = root.join("archive.zip");
+        compress_directory_to_zip(&staging, &zip_path, CompressionLevel::Default).unwrap();
+
+        let reports = std::cell::RefCell::new(Vec::new());
+        import_single_zip(
+            zip_path.to_string_lossy().as_ref(),
+            &ImportOptions::default(),
+            Some(&|progress| reports.borrow_mut().push(progress)),
+        )
+        .unwrap();
+
+        let reports = reports.into_inner();
+        assert_eq!(reports.len(), 3);
+        assert_eq!(reports[0].processed, 1);
+        assert_eq!(reports[0].total, 2);
+        assert_eq!(reports[0].current_file.as_deref(), Some("install.scr"));
+        assert_eq!(reports[0].bytes_processed, 6);
+        assert_eq!(reports[1].current_file.as_deref(), Some("layfix.lsp"));
+        assert_eq!(
+            reports[2],
+            TransferProgress {
+                processed: 2,
+                total: 2,
+                current_file: None,
+                bytes_processed: 13,
+                done: true,
+            }
+        );
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
     #[test]
     fn stress_repeated_import_round_trips() {
         let root = create_temp_dir("zip-stress").unwrap();
@@ -777,6 +4462,8 @@ mod tests {
 
             let bytes = format!("payload-{index}").into_bytes();
             let manifest = ToolExportManifest {
+                schema_version: MANIFEST_SCHEMA_VERSION,
+                assets: Vec::new(),
                 tool: ManifestTool {
                     name: format!("CAD Toolset {index}"),
                     slug: format!("cad-toolset-{index}"),
@@ -787,12 +4474,14 @@ mod tests {
                 version: ManifestVersion {
                     version: "1.0.0".to_string(),
                     changelog_md: None,
+                    exported_at: None,
                 },
                 files: vec![ManifestFile {
                     original_name: "install.scr".to_string(),
                     sha256: sha256_hex(&bytes),
                     size_bytes: bytes.len() as u64,
                     relative_path: "files/install.scr".to_string(),
+                    note: None,
                 }],
             };
 
@@ -805,8 +4494,13 @@ mod tests {
             std::fs::write(staging.join("files").join("install.scr"), bytes).unwrap();
 
             let zip_path = root.join(format!("archive-{index}.zip"));
-            compress_directory_to_zip(&staging, &zip_path).unwrap();
-            let parsed = import_tool_zip(zip_path.to_string_lossy().as_ref()).unwrap();
+            compress_directory_to_zip(&staging, &zip_path, CompressionLevel::Default).unwrap();
+            let parsed = import_single_zip(
+                zip_path.to_string_lossy().as_ref(),
+                &ImportOptions::default(),
+                None,
+            )
+            .unwrap();
             assert_eq!(parsed.files.len(), 1);
             assert_eq!(parsed.version.version, "1.0.0");
         }
@@ -814,24 +4508,73 @@ mod tests {
         let _ = std::fs::remove_dir_all(root);
     }
 
-    fn write_zip_with_entries(path: &Path, entries: Vec<(String, Vec<u8>)>) {
-        let zip_path = ps_quote(path.to_string_lossy().as_ref());
-        let entry_objects = entries
-            .into_iter()
-            .map(|(entry_name, content)| {
-                format!(
-                    "@{{ Name='{}'; Data='{}' }}",
-                    entry_name.replace('\'', "''"),
-                    base64::engine::general_purpose::STANDARD.encode(content)
-                )
-            })
-            .collect::<Vec<_>>()
-            .join(",");
-
-        let script = format!(
-            "$ErrorActionPreference='Stop'; Add-Type -AssemblyName System.IO.Compression; Add-Type -AssemblyName System.IO.Compression.FileSystem; $zipPath={zip_path}; if (Test-Path -LiteralPath $zipPath) {{ Remove-Item -LiteralPath $zipPath -Force }}; $entries=@({entry_objects}); $archive=[System.IO.Compression.ZipFile]::Open($zipPath, [System.IO.Compression.ZipArchiveMode]::Create); try {{ foreach ($entry in $entries) {{ $zipEntry=$archive.CreateEntry($entry.Name); $stream=$zipEntry.Open(); try {{ $bytes=[System.Convert]::FromBase64String($entry.Data); $stream.Write($bytes, 0, $bytes.Length) }} finally {{ $stream.Dispose() }} }} }} finally {{ $archive.Dispose() }}"
+    #[test]
+    fn extraction_stops_on_highly_compressible_payloads() {
+        let root = create_temp_dir("zip-bomb").unwrap();
+        let zip_path = root.join("bomb.zip");
+        write_zip_with_entries(
+            &zip_path,
+            vec![("files/padding.txt".to_string(), vec![0u8; 4 * 1024 * 1024])],
         );
 
-        run_powershell(&script).unwrap();
+        let extracted = root.join("ratio");
+        let error = extract_zip_safely(
+            &zip_path,
+            &extracted,
+            &ExtractionLimits::for_import(&ImportOptions::default()),
+            None,
+        )
+        .unwrap_err();
+        assert!(error.user_message().contains("times its compressed size"));
+        assert!(!extracted.exists());
+
+        let capped = ExtractionLimits {
+            max_total_bytes: 1024 * 1024,
+            max_compression_ratio: u64::MAX,
+        };
+        let extracted = root.join("total");
+        let error = extract_zip_safely(&zip_path, &extracted, &capped, None).unwrap_err();
+        assert!(error
+            .user_message()
+            .contains("expands to more than 1048576 bytes"));
+        assert!(!extracted.exists());
+
+        let staging = root.join("src");
+        std::fs::create_dir_all(&staging).unwrap();
+        std::fs::write(staging.join("padding.txt"), vec![0u8; 4 * 1024 * 1024]).unwrap();
+        let tar_path = root.join("bomb.tar.gz");
+        compress_directory_to_tar_gz(&staging, &tar_path, CompressionLevel::Best, 0).unwrap();
+        let extracted = root.join("tar");
+        let error = extract_tar_gz_safely(
+            &tar_path,
+            &extracted,
+            &ExtractionLimits::for_import(&ImportOptions::default()),
+        )
+        .unwrap_err();
+        assert!(error.user_message().contains("times its compressed size"));
+        assert!(!extracted.exists());
+
+        let error = import_single_zip(
+            zip_path.to_string_lossy().as_ref(),
+            &ImportOptions::default(),
+            None,
+        )
+        .unwrap_err();
+        assert!(!error.user_message().contains("may be corrupt"));
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    fn write_zip_with_entries(path: &Path, entries: Vec<(String, Vec<u8>)>) {
+        use std::io::Write;
+
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        for (entry_name, content) in entries {
+            writer
+                .start_file(entry_name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(&content).unwrap();
+        }
+        writer.finish().unwrap();
     }
 }
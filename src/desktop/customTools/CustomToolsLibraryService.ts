@@ -5,6 +5,12 @@ export type CustomToolVersionSummary = {
   createdAt: number;
 };
 
+export type CustomToolIntegrityStatus = {
+  status: 'ok' | 'failed';
+  failedFiles: number;
+  checkedAt: number;
+};
+
 export type CustomToolSummary = {
   id: string;
   name: string;
@@ -12,11 +18,18 @@ export type CustomToolSummary = {
   description: string;
   category: string;
   tags: string[];
+  iconRelPath?: string | null;
+  iconDataBase64?: string | null;
+  integrity?: CustomToolIntegrityStatus | null;
   createdAt: number;
   updatedAt: number;
+  lastViewedAt: number | null;
   latestVersion: CustomToolVersionSummary | null;
+  qualityIssues?: CustomToolQualityIssue[];
 };
 
+export type CustomToolQualityIssue = 'missingDescription' | 'missingTags' | 'placeholderCategory';
+
 export type CustomToolFile = {
   id: string;
   originalName: string;
@@ -25,6 +38,7 @@ export type CustomToolFile = {
   sizeBytes: number;
   mime: string | null;
   createdAt: number;
+  note?: string | null;
 };
 
 export type CustomToolVersion = {
@@ -33,6 +47,7 @@ export type CustomToolVersion = {
   version: string;
   changelogMd: string | null;
   instructionsMd: string;
+  versionHash?: string | null;
   createdAt: number;
   files: CustomToolFile[];
 };
@@ -44,11 +59,33 @@ export type CustomToolDetail = {
   description: string;
   category: string;
   tags: string[];
+  iconRelPath?: string | null;
+  iconDataBase64?: string | null;
   createdAt: number;
   updatedAt: number;
+  maxVersions?: number | null;
+  versionLimitMode?: CustomToolVersionLimitMode;
   versions: CustomToolVersion[];
 };
 
+export type CustomToolVersionLimitMode = 'reject' | 'prune';
+
+export type CustomToolPrunedVersion = {
+  id: string;
+  version: string;
+};
+
+export type CustomToolBySlug = CustomToolDetail & {
+  redirectedFrom?: string | null;
+};
+
+export type CustomToolLatestInstructions = {
+  versionId: string;
+  version: string;
+  instructionsMd: string;
+  changelogMd: string | null;
+};
+
 export type CustomToolFileInput = {
   originalName: string;
   mime?: string;
@@ -59,7 +96,13 @@ export type CustomToolMetadataInput = {
   name: string;
   slug?: string;
   description: string;
-  category: string;
+  /** Filled from the configured defaults when omitted or empty. */
+  category?: string;
+  tags?: string[];
+};
+
+export type CustomToolMetadataDefaults = {
+  category: string | null;
   tags: string[];
 };
 
@@ -79,20 +122,41 @@ export type AddCustomToolVersionInput = {
   files: CustomToolFileInput[];
 };
 
+export type AddCustomToolVersionFromInput = {
+  toolId: string;
+  baseVersionId: string;
+  version: string;
+  instructionsMd: string;
+  changedFiles: CustomToolFileInput[];
+  removedNames: string[];
+};
+
 export type CreateCustomToolResult = {
   toolId: string;
   versionId: string;
+  prunedVersions?: CustomToolPrunedVersion[];
 };
 
 export type ImportZipPayloadInput = {
   fileName: string;
   dataBase64: string;
+  trustManifest?: boolean;
+  verifyChecksums?: boolean;
+  password?: string;
+  maxFileSizeBytes?: number;
+  maxTotalSizeBytes?: number;
 };
 
 export type ImportZipResult = {
   toolId: string;
   versionId: string;
   createdTool: boolean;
+  durationMs?: number | null;
+  totalBytes?: number | null;
+  fileCount?: number | null;
+  prunedVersions?: CustomToolPrunedVersion[];
+  importedVersions?: string[];
+  skippedVersions?: string[];
 };
 
 export type ImportZipPreviewFile = {
@@ -102,12 +166,44 @@ export type ImportZipPreviewFile = {
 };
 
 export type ImportZipPreview = {
+  token: string;
   toolName: string;
   slug: string;
   version: string;
   files: ImportZipPreviewFile[];
+  assets: ImportZipPreviewFile[];
   totalSizeBytes: number;
   warnings: string[];
+  exportedAt: number | null;
+};
+
+export type ImportDiffFile = {
+  originalName: string;
+  sizeBytes: number;
+};
+
+export type ImportDiffChangedFile = {
+  originalName: string;
+  previousSizeBytes: number;
+  sizeBytes: number;
+};
+
+export type ImportDiffLine = {
+  kind: 'unchanged' | 'added' | 'removed';
+  text: string;
+};
+
+export type ImportZipDiff = {
+  toolId: string;
+  versionId: string;
+  version: string;
+  incomingVersion: string;
+  added: ImportDiffFile[];
+  removed: ImportDiffFile[];
+  changed: ImportDiffChangedFile[];
+  unchangedCount: number;
+  instructionsChanged: boolean;
+  instructionsDiff: ImportDiffLine[];
 };
 
 export type ExportZipPayload = {
@@ -115,19 +211,74 @@ export type ExportZipPayload = {
   dataBase64: string;
 };
 
+export type CustomToolTagCooccurrence = {
+  tag: string;
+  toolCount: number;
+};
+
+export type CustomToolListOrder = 'updated' | 'recentlyViewed';
+
+export type ExportCompressionLevel = 'store' | 'fast' | 'default' | 'best';
+
+export type ExportArchiveFormat = 'zip' | 'tarGz';
+
 export type CustomToolListFilters = {
   query?: string;
   category?: string;
   tag?: string;
+  orderBy?: CustomToolListOrder;
+  qualityIssues?: boolean;
+};
+
+export type CustomToolListPaging = {
+  page?: number;
+  pageSize?: number;
+};
+
+export type CustomToolListPage = {
+  tools: CustomToolSummary[];
+  total: number;
+  page: number;
+  pageSize: number | null;
+};
+
+export type CustomToolCategoryGroup = {
+  category: string;
+  tools: CustomToolSummary[];
 };
 
 export interface CustomToolsLibraryService {
   listTools: (filters?: CustomToolListFilters) => Promise<CustomToolSummary[]>;
+  listToolsPage: (
+    filters?: CustomToolListFilters,
+    paging?: CustomToolListPaging,
+  ) => Promise<CustomToolListPage>;
+  listToolsGrouped: (filters?: CustomToolListFilters) => Promise<CustomToolCategoryGroup[]>;
   getTool: (toolId: string) => Promise<CustomToolDetail>;
+  getTagCooccurrence: (tag: string) => Promise<CustomToolTagCooccurrence[]>;
+  getMetadataDefaults: () => Promise<CustomToolMetadataDefaults>;
+  recordToolView: (toolId: string) => Promise<number>;
+  getToolBySlug: (slug: string) => Promise<CustomToolBySlug>;
+  renameToolSlug: (toolId: string, newSlug: string) => Promise<string>;
+  getLatestInstructions: (toolId: string) => Promise<CustomToolLatestInstructions>;
   createTool: (input: CreateCustomToolInput) => Promise<CreateCustomToolResult>;
   addToolVersion: (input: AddCustomToolVersionInput) => Promise<CreateCustomToolResult>;
-  deleteTool: (toolId: string) => Promise<void>;
-  exportToolVersionZipPayload: (toolVersionId: string) => Promise<ExportZipPayload>;
+  addToolVersionFrom: (input: AddCustomToolVersionFromInput) => Promise<CreateCustomToolResult>;
+  setVersionLimit: (
+    toolId: string,
+    maxVersions: number | null,
+    mode?: CustomToolVersionLimitMode,
+  ) => Promise<void>;
+  setFileNote: (toolVersionId: string, fileId: string, note: string | null) => Promise<string | null>;
+  requestToolDelete: (toolId: string) => Promise<string>;
+  deleteTool: (toolId: string, confirmToken: string) => Promise<void>;
+  exportToolVersionZipPayload: (
+    toolVersionId: string,
+    compression?: ExportCompressionLevel,
+    format?: ExportArchiveFormat,
+    password?: string,
+  ) => Promise<ExportZipPayload>;
   previewImportZipPayload: (payload: ImportZipPayloadInput) => Promise<ImportZipPreview>;
+  importDiff: (token: string, versionId?: string) => Promise<ImportZipDiff>;
   importZipPayload: (payload: ImportZipPayloadInput) => Promise<ImportZipResult>;
 }
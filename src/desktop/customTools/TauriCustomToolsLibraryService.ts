@@ -1,13 +1,25 @@
 import { tauriInvoke } from '../../lib/tauri';
 import type {
+  AddCustomToolVersionFromInput,
   AddCustomToolVersionInput,
   CreateCustomToolInput,
   CreateCustomToolResult,
+  CustomToolBySlug,
+  CustomToolCategoryGroup,
   CustomToolDetail,
+  CustomToolLatestInstructions,
   CustomToolListFilters,
+  CustomToolListPage,
+  CustomToolListPaging,
+  CustomToolMetadataDefaults,
   CustomToolSummary,
+  CustomToolTagCooccurrence,
+  CustomToolVersionLimitMode,
   CustomToolsLibraryService,
+  ExportArchiveFormat,
+  ExportCompressionLevel,
   ExportZipPayload,
+  ImportZipDiff,
   ImportZipPayloadInput,
   ImportZipPreview,
   ImportZipResult,
@@ -15,13 +27,31 @@ import type {
 
 export class TauriCustomToolsLibraryService implements CustomToolsLibraryService {
   listTools(filters: CustomToolListFilters = {}): Promise<CustomToolSummary[]> {
-    return tauriInvoke<CustomToolSummary[]>('tools_list', {
+    return this.listToolsPage(filters).then((listed) => listed.tools);
+  }
+
+  listToolsPage(
+    filters: CustomToolListFilters = {},
+    paging: CustomToolListPaging = {},
+  ): Promise<CustomToolListPage> {
+    return tauriInvoke<CustomToolListPage>('tools_list', {
       query: filters.query,
       category: filters.category,
       tag: filters.tag,
+      order_by: filters.orderBy,
+      orderBy: filters.orderBy,
+      quality_issues: filters.qualityIssues,
+      qualityIssues: filters.qualityIssues,
+      page: paging.page,
+      page_size: paging.pageSize,
+      pageSize: paging.pageSize,
     });
   }
 
+  listToolsGrouped(filters: CustomToolListFilters = {}): Promise<CustomToolCategoryGroup[]> {
+    return tauriInvoke<CustomToolCategoryGroup[]>('tools_list_grouped', { filters });
+  }
+
   getTool(toolId: string): Promise<CustomToolDetail> {
     return tauriInvoke<CustomToolDetail>('tool_get', {
       tool_id: toolId,
@@ -29,6 +59,45 @@ export class TauriCustomToolsLibraryService implements CustomToolsLibraryService
     });
   }
 
+  getTagCooccurrence(tag: string): Promise<CustomToolTagCooccurrence[]> {
+    return tauriInvoke<CustomToolTagCooccurrence[]>('tools_tag_cooccurrence', {
+      tag,
+    });
+  }
+
+  getMetadataDefaults(): Promise<CustomToolMetadataDefaults> {
+    return tauriInvoke<CustomToolMetadataDefaults>('tools_get_metadata_defaults');
+  }
+
+  recordToolView(toolId: string): Promise<number> {
+    return tauriInvoke<number>('tool_record_view', {
+      tool_id: toolId,
+      toolId,
+    });
+  }
+
+  getToolBySlug(slug: string): Promise<CustomToolBySlug> {
+    return tauriInvoke<CustomToolBySlug>('tool_get_by_slug', {
+      slug,
+    });
+  }
+
+  renameToolSlug(toolId: string, newSlug: string): Promise<string> {
+    return tauriInvoke<string>('tool_rename_slug', {
+      tool_id: toolId,
+      toolId,
+      new_slug: newSlug,
+      newSlug,
+    });
+  }
+
+  getLatestInstructions(toolId: string): Promise<CustomToolLatestInstructions> {
+    return tauriInvoke<CustomToolLatestInstructions>('tool_get_latest_instructions', {
+      tool_id: toolId,
+      toolId,
+    });
+  }
+
   createTool(input: CreateCustomToolInput): Promise<CreateCustomToolResult> {
     return tauriInvoke<CreateCustomToolResult>('tool_create', {
       request: input,
@@ -41,17 +110,73 @@ export class TauriCustomToolsLibraryService implements CustomToolsLibraryService
     });
   }
 
-  deleteTool(toolId: string): Promise<void> {
+  addToolVersionFrom(input: AddCustomToolVersionFromInput): Promise<CreateCustomToolResult> {
+    return tauriInvoke<CreateCustomToolResult>('tool_add_version_from', {
+      tool_id: input.toolId,
+      toolId: input.toolId,
+      base_version_id: input.baseVersionId,
+      baseVersionId: input.baseVersionId,
+      changed_files: input.changedFiles,
+      changedFiles: input.changedFiles,
+      removed_names: input.removedNames,
+      removedNames: input.removedNames,
+      instructions: input.instructionsMd,
+      version: input.version,
+    });
+  }
+
+  setVersionLimit(
+    toolId: string,
+    maxVersions: number | null,
+    mode?: CustomToolVersionLimitMode,
+  ): Promise<void> {
+    return tauriInvoke<void>('tool_set_version_limit', {
+      tool_id: toolId,
+      toolId,
+      max_versions: maxVersions,
+      maxVersions,
+      mode,
+    });
+  }
+
+  setFileNote(toolVersionId: string, fileId: string, note: string | null): Promise<string | null> {
+    return tauriInvoke<string | null>('tool_file_set_note', {
+      tool_version_id: toolVersionId,
+      toolVersionId,
+      file_id: fileId,
+      fileId,
+      note,
+    });
+  }
+
+  requestToolDelete(toolId: string): Promise<string> {
+    return tauriInvoke<string>('tool_request_delete', {
+      tool_id: toolId,
+      toolId,
+    });
+  }
+
+  deleteTool(toolId: string, confirmToken: string): Promise<void> {
     return tauriInvoke<void>('tool_delete', {
       tool_id: toolId,
       toolId,
+      confirm_token: confirmToken,
+      confirmToken,
     });
   }
 
-  exportToolVersionZipPayload(toolVersionId: string): Promise<ExportZipPayload> {
+  exportToolVersionZipPayload(
+    toolVersionId: string,
+    compression?: ExportCompressionLevel,
+    format?: ExportArchiveFormat,
+    password?: string,
+  ): Promise<ExportZipPayload> {
     return tauriInvoke<ExportZipPayload>('tool_export_zip_payload', {
       tool_version_id: toolVersionId,
       toolVersionId,
+      compression,
+      format,
+      password,
     });
   }
 
@@ -61,6 +186,14 @@ export class TauriCustomToolsLibraryService implements CustomToolsLibraryService
     });
   }
 
+  importDiff(token: string, versionId?: string): Promise<ImportZipDiff> {
+    return tauriInvoke<ImportZipDiff>('tool_import_diff', {
+      token,
+      version_id: versionId,
+      versionId,
+    });
+  }
+
   importZipPayload(payload: ImportZipPayloadInput): Promise<ImportZipResult> {
     return tauriInvoke<ImportZipResult>('tool_import_zip_payload', {
       payload,
@@ -1,12 +1,24 @@
 import type {
+  AddCustomToolVersionFromInput,
   AddCustomToolVersionInput,
   CreateCustomToolInput,
   CreateCustomToolResult,
+  CustomToolBySlug,
+  CustomToolCategoryGroup,
   CustomToolDetail,
+  CustomToolLatestInstructions,
   CustomToolListFilters,
+  CustomToolListPage,
+  CustomToolListPaging,
+  CustomToolMetadataDefaults,
   CustomToolSummary,
+  CustomToolTagCooccurrence,
+  CustomToolVersionLimitMode,
   CustomToolsLibraryService,
+  ExportArchiveFormat,
+  ExportCompressionLevel,
   ExportZipPayload,
+  ImportZipDiff,
   ImportZipPayloadInput,
   ImportZipPreview,
   ImportZipResult,
@@ -21,10 +33,45 @@ export class WebCustomToolsLibraryService implements CustomToolsLibraryService {
     return Promise.resolve(unsupported());
   }
 
+  listToolsPage(
+    _filters?: CustomToolListFilters,
+    _paging?: CustomToolListPaging,
+  ): Promise<CustomToolListPage> {
+    return Promise.resolve(unsupported());
+  }
+
+  listToolsGrouped(_filters?: CustomToolListFilters): Promise<CustomToolCategoryGroup[]> {
+    return Promise.resolve(unsupported());
+  }
+
   getTool(_toolId: string): Promise<CustomToolDetail> {
     return Promise.resolve(unsupported());
   }
 
+  getToolBySlug(_slug: string): Promise<CustomToolBySlug> {
+    return Promise.resolve(unsupported());
+  }
+
+  getTagCooccurrence(_tag: string): Promise<CustomToolTagCooccurrence[]> {
+    return Promise.resolve(unsupported());
+  }
+
+  getMetadataDefaults(): Promise<CustomToolMetadataDefaults> {
+    return Promise.resolve(unsupported());
+  }
+
+  recordToolView(_toolId: string): Promise<number> {
+    return Promise.resolve(unsupported());
+  }
+
+  renameToolSlug(_toolId: string, _newSlug: string): Promise<string> {
+    return Promise.resolve(unsupported());
+  }
+
+  getLatestInstructions(_toolId: string): Promise<CustomToolLatestInstructions> {
+    return Promise.resolve(unsupported());
+  }
+
   createTool(_input: CreateCustomToolInput): Promise<CreateCustomToolResult> {
     return Promise.resolve(unsupported());
   }
@@ -33,11 +80,36 @@ export class WebCustomToolsLibraryService implements CustomToolsLibraryService {
     return Promise.resolve(unsupported());
   }
 
-  deleteTool(_toolId: string): Promise<void> {
+  addToolVersionFrom(_input: AddCustomToolVersionFromInput): Promise<CreateCustomToolResult> {
+    return Promise.resolve(unsupported());
+  }
+
+  setVersionLimit(
+    _toolId: string,
+    _maxVersions: number | null,
+    _mode?: CustomToolVersionLimitMode,
+  ): Promise<void> {
+    return Promise.resolve(unsupported());
+  }
+
+  setFileNote(_toolVersionId: string, _fileId: string, _note: string | null): Promise<string | null> {
     return Promise.resolve(unsupported());
   }
 
-  exportToolVersionZipPayload(_toolVersionId: string): Promise<ExportZipPayload> {
+  requestToolDelete(_toolId: string): Promise<string> {
+    return Promise.resolve(unsupported());
+  }
+
+  deleteTool(_toolId: string, _confirmToken: string): Promise<void> {
+    return Promise.resolve(unsupported());
+  }
+
+  exportToolVersionZipPayload(
+    _toolVersionId: string,
+    _compression?: ExportCompressionLevel,
+    _format?: ExportArchiveFormat,
+    _password?: string,
+  ): Promise<ExportZipPayload> {
     return Promise.resolve(unsupported());
   }
 
@@ -45,6 +117,10 @@ export class WebCustomToolsLibraryService implements CustomToolsLibraryService {
     return Promise.resolve(unsupported());
   }
 
+  importDiff(_token: string, _versionId?: string): Promise<ImportZipDiff> {
+    return Promise.resolve(unsupported());
+  }
+
   importZipPayload(_payload: ImportZipPayloadInput): Promise<ImportZipResult> {
     return Promise.resolve(unsupported());
   }
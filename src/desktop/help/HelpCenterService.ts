@@ -35,12 +35,18 @@ export type HelpUpdatePageInput = {
   contentMd?: string;
 };
 
+export type HelpBrokenLink = {
+  sourceSlug: string;
+  brokenTarget: string;
+};
+
 export interface HelpCenterService {
   listPages: () => Promise<HelpPageSummary[]>;
   getPage: (slug: string) => Promise<HelpPageRecord>;
   createPage: (input: HelpCreatePageInput) => Promise<HelpPageRecord>;
   updatePage: (slug: string, input: HelpUpdatePageInput) => Promise<HelpPageRecord>;
   deletePage: (slug: string) => Promise<void>;
+  checkLinks: () => Promise<HelpBrokenLink[]>;
   getAppState: (key: string) => Promise<string | null>;
   setAppState: (key: string, value: string) => Promise<void>;
 }
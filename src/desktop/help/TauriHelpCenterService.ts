@@ -1,5 +1,6 @@
 import { tauriInvoke } from '../../lib/tauri';
 import type {
+  HelpBrokenLink,
   HelpCenterService,
   HelpCreatePageInput,
   HelpPageRecord,
@@ -37,6 +38,10 @@ export class TauriHelpCenterService implements HelpCenterService {
     });
   }
 
+  checkLinks(): Promise<HelpBrokenLink[]> {
+    return tauriInvoke<HelpBrokenLink[]>('help_check_links');
+  }
+
   getAppState(key: string): Promise<string | null> {
     return tauriInvoke<string | null>('app_state_get', {
       key,
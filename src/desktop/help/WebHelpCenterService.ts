@@ -1,4 +1,5 @@
 import type {
+  HelpBrokenLink,
   HelpCenterService,
   HelpCreatePageInput,
   HelpPageRecord,
@@ -31,6 +32,10 @@ export class WebHelpCenterService implements HelpCenterService {
     return unsupportedPromise();
   }
 
+  checkLinks(): Promise<HelpBrokenLink[]> {
+    return unsupportedPromise();
+  }
+
   getAppState(_key: string): Promise<string | null> {
     return unsupportedPromise();
   }
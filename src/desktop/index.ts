@@ -17,6 +17,7 @@ export type {
   CustomToolDetail,
   CustomToolFileInput,
   CustomToolListFilters,
+  CustomToolListOrder,
   CustomToolSummary,
   CustomToolsLibraryService,
   ExportZipPayload,
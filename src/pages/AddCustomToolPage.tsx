@@ -1,4 +1,4 @@
-import { useMemo, useState } from 'react';
+import { useEffect, useMemo, useState } from 'react';
 import { Link, useNavigate } from 'react-router-dom';
 import { customToolsLibraryService } from '../features/customToolsLibrary/service';
 import { CUSTOM_TOOL_ALLOWED_EXTENSIONS, MAX_FILE_SIZE_BYTES, MAX_TOTAL_VERSION_SIZE_BYTES } from '../features/customToolsLibrary/constants';
@@ -30,6 +30,30 @@ export function AddCustomToolPage() {
   const [validationErrors, setValidationErrors] = useState<string[]>([]);
   const [isSubmitting, setIsSubmitting] = useState(false);
 
+  useEffect(() => {
+    let cancelled = false;
+    const loadDefaults = async (): Promise<void> => {
+      try {
+        const defaults = await customToolsLibraryService.getMetadataDefaults();
+        if (cancelled) {
+          return;
+        }
+        if (defaults.category) {
+          setCategory(defaults.category);
+        }
+        if (defaults.tags.length > 0) {
+          setTagsText(defaults.tags.join(', '));
+        }
+      } catch {
+        // Keep the built-in defaults when library settings are unavailable.
+      }
+    };
+    void loadDefaults();
+    return () => {
+      cancelled = true;
+    };
+  }, []);
+
   const parsedTags = useMemo(() => {
     return tagsText
       .split(',')
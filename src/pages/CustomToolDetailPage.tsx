@@ -106,7 +106,8 @@ export function CustomToolDetailPage() {
     setMessage('');
 
     try {
-      await customToolsLibraryService.deleteTool(tool.id);
+      const confirmToken = await customToolsLibraryService.requestToolDelete(tool.id);
+      await customToolsLibraryService.deleteTool(tool.id, confirmToken);
       navigate('/tools');
     } catch (deleteError) {
       setError(deleteError instanceof Error ? deleteError.message : 'Failed to delete tool.');